@@ -57,6 +57,7 @@ fn create_sample_submit_sm() -> SubmitSm {
         its_reply_type: None,
         its_session_info: None,
         ussd_service_op: None,
+        network_routing: NetworkRouting::default(),
     }
 }
 
@@ -125,6 +126,7 @@ fn create_sample_deliver_sm() -> DeliverSm {
         ms_availability_status: None,
         receipted_message_id: None,
         message_state: None,
+        network_routing: NetworkRouting::default(),
     }
 }
 
@@ -0,0 +1,110 @@
+// ABOUTME: The pcap command of the smpp CLI: per-session SMPP timelines from tcpdump captures
+// ABOUTME: Pairs requests with responses, showing latencies, errors and unanswered requests
+
+use crate::output::{self, Output};
+use argh::FromArgs;
+use serde_json::{Value, json};
+use smpp::pcap::{self, Direction, Session};
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Show the SMPP sessions in a pcap or pcapng capture as timelines
+#[derive(FromArgs)]
+#[argh(subcommand, name = "pcap")]
+pub struct PcapArgs {
+    /// the capture file
+    #[argh(positional)]
+    file: PathBuf,
+
+    /// only read connections with this TCP port at one end
+    #[argh(option)]
+    port: Option<u16>,
+}
+
+pub fn pcap(args: PcapArgs, output: Output) -> Result<(), Box<dyn Error>> {
+    let capture = std::fs::read(&args.file)
+        .map_err(|e| format!("cannot read {}: {e}", args.file.display()))?;
+    let sessions = pcap::read_sessions(&capture, args.port)?;
+    if sessions.is_empty() {
+        return Err(format!("no SMPP sessions found in {}", args.file.display()).into());
+    }
+
+    for (index, session) in sessions.iter().enumerate() {
+        for pdu in &session.pdus {
+            let header = &pdu.header;
+            let latency = pdu
+                .partner
+                .filter(|_| header.command_id.is_response())
+                .map(|request| pdu.timestamp.saturating_sub(session.pdus[request].timestamp))
+                .map(millis);
+            output.record(
+                "pdu",
+                json!({
+                    "session": index,
+                    "timestamp": pdu.timestamp.as_secs_f64(),
+                    "direction": direction(pdu.direction),
+                    "command": header.command_id.name(),
+                    "sequence_number": header.sequence_number,
+                    "command_status": format!("{:?}", header.command_status),
+                    "latency_ms": latency,
+                    "unanswered": pdu.expects_response() && pdu.partner.is_none(),
+                    "unmatched": header.command_id.is_response() && pdu.partner.is_none(),
+                    "hex": output::hex(&pdu.bytes),
+                }),
+            );
+        }
+        for problem in &session.problems {
+            output.record(
+                "problem",
+                json!({
+                    "session": index,
+                    "timestamp": problem.timestamp.as_secs_f64(),
+                    "direction": direction(problem.direction),
+                    "message": problem.message,
+                }),
+            );
+        }
+        let text = if index + 1 < sessions.len() {
+            format!("{session}\n")
+        } else {
+            session.to_string()
+        };
+        output.emit("session", text, record(index, session));
+    }
+    Ok(())
+}
+
+fn record(index: usize, session: &Session) -> Value {
+    let summary = session.summary();
+    json!({
+        "session": index,
+        "esme": session.esme.to_string(),
+        "smsc": session.smsc.to_string(),
+        "system_id": session.system_id,
+        "bind": session.bind.map(|bind| bind.name()),
+        "start": session.start().as_secs_f64(),
+        "pdus": summary.pdus,
+        "exchanges": summary.exchanges,
+        "error_responses": summary.error_responses,
+        "unanswered": summary.unanswered,
+        "unmatched": summary.unmatched,
+        "problems": summary.problems,
+        "latency_ms": {
+            "min": summary.min_latency.map(millis),
+            "avg": summary.avg_latency.map(millis),
+            "max": summary.max_latency.map(millis),
+        },
+    })
+}
+
+fn direction(direction: Direction) -> &'static str {
+    match direction {
+        Direction::EsmeToSmsc => "esme_to_smsc",
+        Direction::SmscToEsme => "smsc_to_esme",
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_micros() as f64 / 1000.0
+}
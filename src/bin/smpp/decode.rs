@@ -0,0 +1,99 @@
+// ABOUTME: The decode command of the smpp CLI: explains a captured PDU field by field
+// ABOUTME: Takes hex from the command line, a file or stdin, and needs no SMSC or profile
+
+use crate::output::{self, Output};
+use crate::profile;
+use argh::FromArgs;
+use serde_json::{Value, json};
+use smpp::inspect::{self, Inspection, Issue};
+use std::error::Error;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Decode a PDU given as hex or raw octets and show each field
+#[derive(FromArgs)]
+#[argh(subcommand, name = "decode")]
+pub struct DecodeArgs {
+    /// the PDU as hex; spaces, colons and 0x prefixes are ignored
+    #[argh(positional)]
+    hex: Vec<String>,
+
+    /// read the PDU from a file of raw octets or hex text instead
+    #[argh(option)]
+    file: Option<PathBuf>,
+}
+
+/// Decode as `version`, or as SMPP v5.0 (which knows every command) when not given
+pub fn decode(
+    args: DecodeArgs,
+    version: Option<&str>,
+    output: Output,
+) -> Result<(), Box<dyn Error>> {
+    let pdu = if !args.hex.is_empty() {
+        inspect::parse_hex(&args.hex.join(" "))?
+    } else {
+        let data = match &args.file {
+            Some(path) => std::fs::read(path)
+                .map_err(|e| format!("cannot read {}: {e}", path.display()))?,
+            None => {
+                let mut data = Vec::new();
+                std::io::stdin().read_to_end(&mut data)?;
+                data
+            }
+        };
+        // Hex text is taken as such; anything else is the PDU itself
+        match std::str::from_utf8(&data).ok().map(inspect::parse_hex) {
+            Some(Ok(pdu)) if !pdu.is_empty() => pdu,
+            _ => data,
+        }
+    };
+    if pdu.is_empty() {
+        return Err("no PDU given".into());
+    }
+
+    let inspection = match version {
+        Some(version) => inspect::inspect_as(&pdu, profile::parse_version(version)?),
+        None => inspect::inspect(&pdu),
+    };
+    output.emit("pdu", inspection.to_string().trim_end(), record(&inspection));
+
+    match &inspection.failure {
+        Some(failure) => Err(format!(
+            "decoding failed at offset {:#06x} in {}",
+            failure.offset, failure.field
+        )
+        .into()),
+        None => Ok(()),
+    }
+}
+
+fn record(inspection: &Inspection) -> Value {
+    let fields: Vec<_> = inspection
+        .fields
+        .iter()
+        .map(|field| {
+            json!({
+                "offset": field.offset,
+                "hex": output::hex(&field.bytes),
+                "name": field.name,
+                "value": field.value,
+            })
+        })
+        .collect();
+    let warnings: Vec<_> = inspection.warnings.iter().map(issue).collect();
+    json!({
+        "command": inspection.command_id.map(|command_id| command_id.name()),
+        "length": inspection.fields.iter().map(|field| field.bytes.len()).sum::<usize>(),
+        "fields": fields,
+        "warnings": warnings,
+        "error": inspection.failure.as_ref().map(issue),
+    })
+}
+
+fn issue(issue: &Issue) -> Value {
+    json!({
+        "offset": issue.offset,
+        "field": issue.field,
+        "message": issue.message,
+    })
+}
@@ -0,0 +1,140 @@
+// ABOUTME: The listen command of the smpp CLI: binds as a receiver and prints what arrives
+// ABOUTME: MO messages, delivery receipts, data_sm and alerts are printed and acknowledged
+
+use crate::message;
+use crate::output::Output;
+use crate::profile::Target;
+use argh::FromArgs;
+use serde_json::json;
+use smpp::client::{BindType, InboundConfig, InboundEvent, KeepAliveConfig, SmppClient};
+use smpp::datatypes::DeliverSm;
+use std::error::Error;
+
+/// Bind as a receiver and print MO messages and delivery receipts
+#[derive(FromArgs)]
+#[argh(subcommand, name = "listen")]
+pub struct ListenArgs {
+    /// bind as a transceiver instead of a receiver
+    #[argh(switch)]
+    transceiver: bool,
+
+    /// stop after this many messages, receipts and alerts
+    #[argh(option, short = 'n')]
+    count: Option<u64>,
+}
+
+pub async fn listen(
+    target: &Target,
+    args: ListenArgs,
+    output: Output,
+) -> Result<(), Box<dyn Error>> {
+    let bind_type = if args.transceiver {
+        BindType::Transceiver
+    } else {
+        BindType::Receiver
+    };
+    let mut client = target.bind(bind_type).await?;
+    client.start_keep_alive(KeepAliveConfig::default()).await?;
+    let mut session = client.into_inbound(InboundConfig::new())?;
+    tracing::info!("bound to {} as {bind_type:?}, listening", target.addr);
+
+    let mut seen = 0;
+    while args.count.is_none_or(|count| seen < count) {
+        let Some(event) = session.next().await else {
+            return Err("the SMSC closed the session".into());
+        };
+        match event? {
+            InboundEvent::Message { pdu, ack } => {
+                print_message(&pdu, output);
+                ack.ack()?;
+            }
+            InboundEvent::Receipt { receipt, pdu, ack } => {
+                let error = receipt.error.clone().unwrap_or_default();
+                let done = receipt.done_date.as_ref().map(ToString::to_string);
+                output.emit(
+                    "receipt",
+                    format!(
+                        "receipt for {} from {}: {:?}{}",
+                        receipt.message_id,
+                        address(pdu.source_addr.as_str()),
+                        receipt.state,
+                        if error.is_empty() { String::new() } else { format!(" (err {error})") }
+                    ),
+                    json!({
+                        "message_id": receipt.message_id,
+                        "from": address(pdu.source_addr.as_str()),
+                        "state": format!("{:?}", receipt.state),
+                        "error": error,
+                        "done_date": done,
+                    }),
+                );
+                ack.ack()?;
+            }
+            InboundEvent::Data { pdu, ack } => {
+                let body = pdu.message_payload().map(|b| b.to_vec()).unwrap_or_default();
+                let text = message::decode_text(&body, pdu.data_coding, pdu.esm_class.has_udhi());
+                let from = address(pdu.source_addr.as_str());
+                let to = address(pdu.destination_addr.as_str());
+                output.emit(
+                    "data",
+                    format!("data_sm from {from} to {to}: {text}"),
+                    json!({
+                        "from": from,
+                        "to": to,
+                        "data_coding": pdu.data_coding.to_byte(),
+                        "text": text,
+                        "hex": crate::output::hex(&body),
+                    }),
+                );
+                ack.ack()?;
+            }
+            InboundEvent::Alert { alert, .. } => {
+                let availability = alert.availability.map(|status| format!("{status:?}"));
+                output.emit(
+                    "alert",
+                    format!(
+                        "alert: {} is {}",
+                        alert.subscriber,
+                        availability.as_deref().unwrap_or("unknown")
+                    ),
+                    json!({
+                        "subscriber": alert.subscriber,
+                        "esme_addr": alert.esme_addr,
+                        "availability": availability,
+                    }),
+                );
+            }
+        }
+        seen += 1;
+    }
+
+    session.close().await?;
+    Ok(())
+}
+
+fn print_message(pdu: &DeliverSm, output: Output) {
+    let body = match &pdu.message_payload {
+        Some(payload) => payload.value.to_vec(),
+        None => pdu.short_message.as_bytes().to_vec(),
+    };
+    let udhi = pdu.esm_class.has_udhi() && pdu.message_payload.is_none();
+    let text = message::decode_text(&body, pdu.data_coding, udhi);
+    let from = address(pdu.source_addr.as_str());
+    let to = address(pdu.destination_addr.as_str());
+    output.emit(
+        "message",
+        format!("message from {from} to {to}: {text}"),
+        json!({
+            "from": from,
+            "to": to,
+            "data_coding": pdu.data_coding.to_byte(),
+            "udhi": pdu.esm_class.has_udhi(),
+            "text": text,
+            "hex": crate::output::hex(&body),
+        }),
+    );
+}
+
+fn address(value: Result<&str, std::str::Utf8Error>) -> &str {
+    value.unwrap_or("?")
+}
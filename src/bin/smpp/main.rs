@@ -0,0 +1,152 @@
+// ABOUTME: The smpp command line tool: send, query, cancel and replace messages, listen for MO,
+// ABOUTME: decode PDUs and read captures; settings come from profiles and flags, --json for JSON
+
+mod capture;
+mod decode;
+mod listen;
+mod manage;
+mod message;
+mod output;
+mod profile;
+mod send;
+
+use argh::FromArgs;
+use output::Output;
+use profile::Profile;
+use std::error::Error;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tracing::Level;
+use tracing_subscriber::FmtSubscriber;
+
+/// Send and manage SMS over SMPP, listen for MO messages and receipts, and decode PDUs and
+/// captures
+#[derive(FromArgs)]
+struct Cli {
+    /// the config file with connection profiles (default: $SMPP_CONFIG or
+    /// ~/.config/smpp/config.toml)
+    #[argh(option, short = 'c')]
+    config: Option<PathBuf>,
+
+    /// the profile to use (default: the config file's default)
+    #[argh(option, short = 'p')]
+    profile: Option<String>,
+
+    /// the SMSC host (default: localhost)
+    #[argh(option)]
+    host: Option<String>,
+
+    /// the SMSC port (default: 2775)
+    #[argh(option)]
+    port: Option<u16>,
+
+    /// the system_id to bind with
+    #[argh(option, short = 'u')]
+    system_id: Option<String>,
+
+    /// the password to bind with
+    #[argh(option, short = 'P')]
+    password: Option<String>,
+
+    /// a file to read the password from instead
+    #[argh(option)]
+    password_file: Option<PathBuf>,
+
+    /// the system_type to bind with
+    #[argh(option)]
+    system_type: Option<String>,
+
+    /// the interface version: 3.3, 3.4 or 5.0 (default: 3.4)
+    #[argh(option, short = 'v')]
+    version: Option<String>,
+
+    /// print one JSON object per line instead of text
+    #[argh(switch, short = 'j')]
+    json: bool,
+
+    /// whether or not to enable debugging
+    #[argh(switch, short = 'd')]
+    debugging: bool,
+
+    #[argh(subcommand)]
+    command: Command,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Send(send::SendArgs),
+    SubmitMulti(send::SubmitMultiArgs),
+    Query(manage::QueryArgs),
+    Cancel(manage::CancelArgs),
+    Replace(manage::ReplaceArgs),
+    Listen(listen::ListenArgs),
+    Decode(decode::DecodeArgs),
+    Pcap(capture::PcapArgs),
+}
+
+impl Cli {
+    /// Connection settings given as flags, overriding the profile's
+    fn overrides(&self) -> Profile {
+        Profile {
+            host: self.host.clone(),
+            port: self.port,
+            system_id: self.system_id.clone(),
+            password: self.password.clone(),
+            password_file: self.password_file.clone(),
+            system_type: self.system_type.clone(),
+            version: self.version.clone(),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli: Cli = argh::from_env();
+
+    // Logs go to stderr so they never mix with results on stdout
+    let level = if cli.debugging {
+        Level::DEBUG
+    } else {
+        Level::WARN
+    };
+    let subscriber = FmtSubscriber::builder()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .finish();
+    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+
+    let output = Output::new(cli.json);
+    match run(cli, output).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            output.error(e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli, output: Output) -> Result<(), Box<dyn Error>> {
+    let overrides = cli.overrides();
+    let command = match cli.command {
+        // Decoding needs no SMSC, so no profile either
+        Command::Decode(args) => return decode::decode(args, cli.version.as_deref(), output),
+        Command::Pcap(args) => return capture::pcap(args, output),
+        command => command,
+    };
+    let target = profile::load(cli.config.as_deref(), cli.profile.as_deref())?
+        .merge(overrides)
+        .into_target()?;
+
+    match command {
+        Command::Send(args) => send::send(&target, args, output).await,
+        Command::SubmitMulti(args) => send::submit_multi(&target, args, output).await,
+        Command::Query(args) => manage::query(&target, args, output).await,
+        Command::Cancel(args) => manage::cancel(&target, args, output).await,
+        Command::Replace(args) => manage::replace(&target, args, output).await,
+        Command::Listen(args) => listen::listen(&target, args, output).await,
+        Command::Decode(_) | Command::Pcap(_) => {
+            unreachable!("decode and pcap are handled before connecting")
+        }
+    }
+}
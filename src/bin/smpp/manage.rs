@@ -0,0 +1,164 @@
+// ABOUTME: The query, cancel and replace commands of the smpp CLI
+// ABOUTME: Each binds as a transmitter, acts on one previously submitted message and unbinds
+
+use crate::message::{self, Coding};
+use crate::output::Output;
+use crate::profile::Target;
+use argh::FromArgs;
+use serde_json::json;
+use smpp::client::{BindType, DefaultClient, SmppClient, SmppConnection};
+use smpp::datatypes::{
+    CancelSm, MessageId, NumericPlanIndicator, QuerySm, ReplaceSm, ScheduleDeliveryTime,
+    ServiceType, ShortMessage, SourceAddr, TypeOfNumber, ValidityPeriod,
+};
+use std::error::Error;
+
+/// Ask the SMSC for the state of a submitted message
+#[derive(FromArgs)]
+#[argh(subcommand, name = "query")]
+pub struct QueryArgs {
+    /// the message_id the SMSC returned on submission
+    #[argh(option, short = 'i')]
+    id: String,
+
+    /// the sender address the message was submitted with
+    #[argh(option, short = 'f')]
+    from: String,
+}
+
+/// Cancel a message the SMSC has not delivered yet
+#[derive(FromArgs)]
+#[argh(subcommand, name = "cancel")]
+pub struct CancelArgs {
+    /// the message_id the SMSC returned on submission
+    #[argh(option, short = 'i')]
+    id: String,
+
+    /// the sender address the message was submitted with
+    #[argh(option, short = 'f')]
+    from: String,
+
+    /// the recipient address the message was submitted with
+    #[argh(option, short = 't', default = "String::new()")]
+    to: String,
+
+    /// the service_type the message was submitted with
+    #[argh(option, default = "String::new()")]
+    service_type: String,
+}
+
+/// Replace the text of a message the SMSC has not delivered yet
+#[derive(FromArgs)]
+#[argh(subcommand, name = "replace")]
+pub struct ReplaceArgs {
+    /// the message_id the SMSC returned on submission
+    #[argh(option, short = 'i')]
+    id: String,
+
+    /// the sender address the message was submitted with
+    #[argh(option, short = 'f')]
+    from: String,
+
+    /// the new message text
+    #[argh(option, short = 'm')]
+    text: Option<String>,
+
+    /// the new message body as hex
+    #[argh(option)]
+    hex: Option<String>,
+
+    /// request a delivery receipt for the replacement
+    #[argh(switch, short = 'r')]
+    receipt: bool,
+}
+
+pub async fn query(target: &Target, args: QueryArgs, output: Output) -> Result<(), Box<dyn Error>> {
+    let mut client = target.bind(BindType::Transmitter).await?;
+    let query = QuerySm::new(
+        // The client allocates the sequence number when it sends the PDU
+        0,
+        MessageId::from(args.id.as_str()),
+        TypeOfNumber::Unknown,
+        NumericPlanIndicator::Unknown,
+        SourceAddr::new(&args.from, TypeOfNumber::Unknown)?,
+    )?;
+    let response = client.query_sm(&query).await?;
+
+    let state = format!("{:?}", response.message_state);
+    let final_date = response.final_date.unwrap_or_default();
+    let mut text = format!("{}: {state}", args.id);
+    if !final_date.is_empty() {
+        text.push_str(&format!(" at {final_date}"));
+    }
+    if response.error_code != 0 {
+        text.push_str(&format!(" (error {})", response.error_code));
+    }
+    output.emit(
+        "query",
+        text,
+        json!({
+            "message_id": args.id,
+            "state": state,
+            "final_date": final_date,
+            "error_code": response.error_code,
+        }),
+    );
+    finish(client).await
+}
+
+pub async fn cancel(
+    target: &Target,
+    args: CancelArgs,
+    output: Output,
+) -> Result<(), Box<dyn Error>> {
+    let mut client = target.bind(BindType::Transmitter).await?;
+    let cancel = CancelSm::new(
+        // The client allocates the sequence number when it sends the PDU
+        0,
+        ServiceType::from(args.service_type.as_str()),
+        MessageId::from(args.id.as_str()),
+        TypeOfNumber::Unknown,
+        NumericPlanIndicator::Unknown,
+        SourceAddr::new(&args.from, TypeOfNumber::Unknown)?,
+        TypeOfNumber::Unknown,
+        NumericPlanIndicator::Unknown,
+        SourceAddr::new(&args.to, TypeOfNumber::Unknown)?,
+    )?;
+    client.cancel_sm(&cancel).await?;
+
+    output.emit("cancelled", format!("cancelled {}", args.id), json!({ "message_id": args.id }));
+    finish(client).await
+}
+
+pub async fn replace(
+    target: &Target,
+    args: ReplaceArgs,
+    output: Output,
+) -> Result<(), Box<dyn Error>> {
+    // replace_sm has no data_coding, the replacement keeps the original one
+    let (_, body) = message::encode(args.text.as_deref(), args.hex.as_deref(), Coding::Auto)?;
+    let mut client = target.bind(BindType::Transmitter).await?;
+    let replace = ReplaceSm::new(
+        // The client allocates the sequence number when it sends the PDU
+        0,
+        MessageId::from(args.id.as_str()),
+        TypeOfNumber::Unknown,
+        NumericPlanIndicator::Unknown,
+        SourceAddr::new(&args.from, TypeOfNumber::Unknown)?,
+        ScheduleDeliveryTime::default(),
+        ValidityPeriod::default(),
+        u8::from(args.receipt),
+        0,
+        ShortMessage::new(&body)?,
+    )?;
+    client.replace_sm(&replace).await?;
+
+    output.emit("replaced", format!("replaced {}", args.id), json!({ "message_id": args.id }));
+    finish(client).await
+}
+
+async fn finish(mut client: DefaultClient) -> Result<(), Box<dyn Error>> {
+    client.unbind().await?;
+    client.disconnect().await?;
+    Ok(())
+}
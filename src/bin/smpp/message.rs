@@ -0,0 +1,451 @@
+// ABOUTME: Message bodies for the smpp CLI: data coding, long message splitting and TLV arguments
+// ABOUTME: Encodes text or hex for submission and decodes received short messages for display
+
+use smpp::datatypes::{DataCoding, SubmitSm, Tlv, tags};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Octets a single short message carries in a 7-bit (unpacked) coding
+const SINGLE_7BIT: usize = 160;
+/// Octets a single short message carries in 8-bit and 16-bit codings
+const SINGLE_OCTETS: usize = 140;
+/// Length of the concatenation user data header written by [`split`]
+const UDH_LEN: usize = 6;
+
+/// The data coding chosen on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coding {
+    /// The SMSC default alphabet for ASCII text, UCS-2 for anything else
+    Auto,
+    /// A specific data_coding
+    Fixed(DataCoding),
+}
+
+impl FromStr for Coding {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let coding = match value {
+            "auto" => return Ok(Coding::Auto),
+            "default" | "gsm" => DataCoding::SmscDefault,
+            "ascii" => DataCoding::Ascii,
+            "latin1" => DataCoding::Latin1,
+            "binary" => DataCoding::Binary,
+            "ucs2" => DataCoding::Ucs2,
+            other => match parse_number(other) {
+                Some(byte) => DataCoding::from_byte(byte),
+                None => {
+                    return Err(format!(
+                        "unknown data coding '{other}', expected auto, default, ascii, latin1, \
+                         binary, ucs2 or a number"
+                    ));
+                }
+            },
+        };
+        Ok(Coding::Fixed(coding))
+    }
+}
+
+/// How messages too long for one short_message are sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    /// Concatenated parts with a user data header
+    Udh,
+    /// Concatenated parts with sar_* TLVs
+    Sar,
+    /// One submission carrying the whole message in message_payload
+    Payload,
+}
+
+impl FromStr for Split {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "udh" => Ok(Split::Udh),
+            "sar" => Ok(Split::Sar),
+            "payload" => Ok(Split::Payload),
+            other => Err(format!("unknown split method '{other}', expected udh, sar or payload")),
+        }
+    }
+}
+
+/// One submission of a possibly concatenated message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    /// The short_message, including any user data header
+    pub short_message: Vec<u8>,
+    /// Whether `short_message` starts with a user data header
+    pub udhi: bool,
+    /// The message body in message_payload instead of short_message
+    pub payload: Option<Vec<u8>>,
+    /// sar_msg_ref_num, sar_total_segments and sar_segment_seqnum
+    pub sar: Option<(u16, u8, u8)>,
+}
+
+impl Part {
+    fn single(body: &[u8]) -> Self {
+        Self {
+            short_message: body.to_vec(),
+            udhi: false,
+            payload: None,
+            sar: None,
+        }
+    }
+}
+
+/// The data coding and encoded body of a message given as `text` or `hex`
+pub fn encode(
+    text: Option<&str>,
+    hex: Option<&str>,
+    coding: Coding,
+) -> Result<(DataCoding, Vec<u8>), String> {
+    match (text, hex) {
+        (Some(_), Some(_)) => Err("give either --text or --hex, not both".to_string()),
+        (None, None) => Err("give the message with --text or --hex".to_string()),
+        (None, Some(hex)) => {
+            let coding = match coding {
+                Coding::Auto => DataCoding::Binary,
+                Coding::Fixed(coding) => coding,
+            };
+            Ok((coding, parse_hex(hex)?))
+        }
+        (Some(text), None) => {
+            let coding = match coding {
+                Coding::Auto if is_default_alphabet(text) => DataCoding::SmscDefault,
+                Coding::Auto => DataCoding::Ucs2,
+                Coding::Fixed(coding) => coding,
+            };
+            Ok((coding, encode_text(text, coding)?))
+        }
+    }
+}
+
+/// Whether `text` can be sent as is in the SMSC default alphabet
+fn is_default_alphabet(text: &str) -> bool {
+    text.is_ascii() && DataCoding::SmscDefault.validate_text(text).is_ok()
+}
+
+fn encode_text(text: &str, coding: DataCoding) -> Result<Vec<u8>, String> {
+    if is_ucs2(coding) {
+        return Ok(text.encode_utf16().flat_map(u16::to_be_bytes).collect());
+    }
+    match coding {
+        DataCoding::Binary => Err("binary data coding needs the message as --hex".to_string()),
+        DataCoding::Latin1 => text
+            .chars()
+            .map(|c| u8::try_from(u32::from(c)).map_err(|_| format!("'{c}' is not in Latin-1")))
+            .collect(),
+        DataCoding::SmscDefault | DataCoding::Ascii | DataCoding::Gsm7BitWithClass(_) => {
+            coding.validate_text(text).map_err(|e| e.to_string())?;
+            if !text.is_ascii() {
+                return Err(format!(
+                    "{} text must be ASCII here; use --coding ucs2 or latin1",
+                    coding.charset_name()
+                ));
+            }
+            Ok(text.as_bytes().to_vec())
+        }
+        _ => Ok(text.as_bytes().to_vec()),
+    }
+}
+
+/// A reference for the parts of one concatenated message
+///
+/// Taken from the clock and process id rather than a session's sequence
+/// numbers, so separate runs of the CLI are unlikely to pick the same one.
+pub fn concat_reference() -> u16 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or(0);
+    ((nanos >> 10) ^ std::process::id()) as u16
+}
+
+/// Split `body` into the submissions needed to send it with `method`
+///
+/// `reference` identifies the parts of one concatenated message; only its
+/// low byte is used in a user data header.
+pub fn split(
+    body: &[u8],
+    coding: DataCoding,
+    method: Split,
+    reference: u16,
+) -> Result<Vec<Part>, String> {
+    let single = if coding.is_7bit() {
+        SINGLE_7BIT
+    } else {
+        SINGLE_OCTETS
+    };
+    if body.len() <= single {
+        return Ok(vec![Part::single(body)]);
+    }
+
+    let capacity = match method {
+        Split::Payload if body.len() > usize::from(u16::MAX) => {
+            return Err(format!(
+                "message is {} octets, more than the {} message_payload can carry",
+                body.len(),
+                u16::MAX
+            ));
+        }
+        Split::Payload => {
+            return Ok(vec![Part {
+                short_message: Vec::new(),
+                udhi: false,
+                payload: Some(body.to_vec()),
+                sar: None,
+            }]);
+        }
+        Split::Udh if coding.is_7bit() => SINGLE_7BIT - 7,
+        Split::Udh => SINGLE_OCTETS - UDH_LEN,
+        Split::Sar => single,
+    };
+    let chunks = chunks(body, capacity, is_ucs2(coding));
+    let total = u8::try_from(chunks.len())
+        .map_err(|_| format!("message needs {} parts, at most 255 are possible", chunks.len()))?;
+
+    Ok(chunks
+        .into_iter()
+        .zip(1..=total)
+        .map(|(chunk, seqnum)| match method {
+            Split::Udh => {
+                let mut short_message = vec![0x05, 0x00, 0x03, reference as u8, total, seqnum];
+                short_message.extend_from_slice(chunk);
+                Part {
+                    short_message,
+                    udhi: true,
+                    payload: None,
+                    sar: None,
+                }
+            }
+            _ => Part {
+                sar: Some((reference, total, seqnum)),
+                ..Part::single(chunk)
+            },
+        })
+        .collect())
+}
+
+/// Cut `body` into pieces of at most `capacity` octets, keeping UTF-16 units and pairs whole
+fn chunks(body: &[u8], capacity: usize, utf16: bool) -> Vec<&[u8]> {
+    let mut chunks = Vec::new();
+    let mut rest = body;
+    while !rest.is_empty() {
+        let mut end = capacity.min(rest.len());
+        if utf16 && end < rest.len() {
+            end -= end % 2;
+            // Leave a high surrogate with its low surrogate in the next part
+            if end >= 2 && (0xD8..=0xDB).contains(&rest[end - 2]) {
+                end -= 2;
+            }
+        }
+        let (chunk, remainder) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = remainder;
+    }
+    chunks
+}
+
+/// Parse a TLV argument written as `tag=hexvalue`, the tag as a name or number
+pub fn parse_tlv(value: &str) -> Result<Tlv, String> {
+    let (tag, data) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected tag=hexvalue, got '{value}'"))?;
+    let tag = match SUBMIT_SM_TAGS.iter().find(|(name, _)| *name == tag) {
+        Some((_, tag)) => *tag,
+        None => parse_number(tag)
+            .ok_or_else(|| format!("unknown TLV tag '{tag}'"))?,
+    };
+    Tlv::new(tag, parse_hex(data)?).map_err(|error| error.to_string())
+}
+
+/// Set the submit_sm parameter a TLV carries
+pub fn set_tlv(submit: &mut SubmitSm, tlv: Tlv) -> Result<(), String> {
+    let field = match tlv.tag {
+        tags::USER_MESSAGE_REFERENCE => &mut submit.user_message_reference,
+        tags::SOURCE_PORT => &mut submit.source_port,
+        tags::SOURCE_ADDR_SUBMIT => &mut submit.source_addr_submit,
+        tags::DESTINATION_PORT => &mut submit.destination_port,
+        tags::DEST_ADDR_SUBMIT => &mut submit.dest_addr_submit,
+        tags::SAR_MSG_REF_NUM => &mut submit.sar_msg_ref_num,
+        tags::SAR_TOTAL_SEGMENTS => &mut submit.sar_total_segments,
+        tags::SAR_SEGMENT_SEQNUM => &mut submit.sar_segment_seqnum,
+        tags::MORE_MESSAGES_TO_SEND => &mut submit.more_messages_to_send,
+        tags::PAYLOAD_TYPE => &mut submit.payload_type,
+        tags::MESSAGE_PAYLOAD => &mut submit.message_payload,
+        tags::PRIVACY_INDICATOR => &mut submit.privacy_indicator,
+        tags::CALLBACK_NUM => &mut submit.callback_num,
+        tags::CALLBACK_NUM_PRES_IND => &mut submit.callback_num_pres_ind,
+        tags::CALLBACK_NUM_ATAG => &mut submit.callback_num_atag,
+        tags::SOURCE_SUBADDRESS => &mut submit.source_subaddress,
+        tags::DEST_SUBADDRESS => &mut submit.dest_subaddress,
+        tags::DISPLAY_TIME => &mut submit.display_time,
+        tags::SMS_SIGNAL => &mut submit.sms_signal,
+        tags::MS_VALIDITY => &mut submit.ms_validity,
+        tags::MS_MSG_WAIT_FACILITIES => &mut submit.ms_msg_wait_facilities,
+        tags::NUMBER_OF_MESSAGES => &mut submit.number_of_messages,
+        tags::ALERT_ON_MSG_DELIVERY => &mut submit.alert_on_msg_delivery,
+        tags::LANGUAGE_INDICATOR => &mut submit.language_indicator,
+        tags::ITS_REPLY_TYPE => &mut submit.its_reply_type,
+        tags::ITS_SESSION_INFO => &mut submit.its_session_info,
+        tags::USSD_SERVICE_OP => &mut submit.ussd_service_op,
+        other => return Err(format!("TLV 0x{other:04X} is not a submit_sm parameter")),
+    };
+    *field = Some(tlv);
+    Ok(())
+}
+
+/// Names accepted for the submit_sm TLVs in `--tlv`
+const SUBMIT_SM_TAGS: &[(&str, u16)] = &[
+    ("user_message_reference", tags::USER_MESSAGE_REFERENCE),
+    ("source_port", tags::SOURCE_PORT),
+    ("source_addr_submit", tags::SOURCE_ADDR_SUBMIT),
+    ("destination_port", tags::DESTINATION_PORT),
+    ("dest_addr_submit", tags::DEST_ADDR_SUBMIT),
+    ("sar_msg_ref_num", tags::SAR_MSG_REF_NUM),
+    ("sar_total_segments", tags::SAR_TOTAL_SEGMENTS),
+    ("sar_segment_seqnum", tags::SAR_SEGMENT_SEQNUM),
+    ("more_messages_to_send", tags::MORE_MESSAGES_TO_SEND),
+    ("payload_type", tags::PAYLOAD_TYPE),
+    ("message_payload", tags::MESSAGE_PAYLOAD),
+    ("privacy_indicator", tags::PRIVACY_INDICATOR),
+    ("callback_num", tags::CALLBACK_NUM),
+    ("callback_num_pres_ind", tags::CALLBACK_NUM_PRES_IND),
+    ("callback_num_atag", tags::CALLBACK_NUM_ATAG),
+    ("source_subaddress", tags::SOURCE_SUBADDRESS),
+    ("dest_subaddress", tags::DEST_SUBADDRESS),
+    ("display_time", tags::DISPLAY_TIME),
+    ("sms_signal", tags::SMS_SIGNAL),
+    ("ms_validity", tags::MS_VALIDITY),
+    ("ms_msg_wait_facilities", tags::MS_MSG_WAIT_FACILITIES),
+    ("number_of_messages", tags::NUMBER_OF_MESSAGES),
+    ("alert_on_msg_delivery", tags::ALERT_ON_MSG_DELIVERY),
+    ("language_indicator", tags::LANGUAGE_INDICATOR),
+    ("its_reply_type", tags::ITS_REPLY_TYPE),
+    ("its_session_info", tags::ITS_SESSION_INFO),
+    ("ussd_service_op", tags::USSD_SERVICE_OP),
+];
+
+/// Text of a received short message, without any user data header
+pub fn decode_text(short_message: &[u8], coding: DataCoding, udhi: bool) -> String {
+    let body = match short_message.first() {
+        Some(&udh_len) if udhi => short_message.get(usize::from(udh_len) + 1..).unwrap_or(&[]),
+        _ => short_message,
+    };
+    if is_ucs2(coding) {
+        let units: Vec<u16> = body
+            .chunks(2)
+            .map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else if coding == DataCoding::Latin1 {
+        body.iter().map(|&b| char::from(b)).collect()
+    } else if coding.is_binary() {
+        crate::output::hex(body)
+    } else {
+        String::from_utf8_lossy(body).into_owned()
+    }
+}
+
+fn is_ucs2(coding: DataCoding) -> bool {
+    matches!(coding, DataCoding::Ucs2 | DataCoding::Ucs2WithClass(_))
+}
+
+fn parse_hex(value: &str) -> Result<Vec<u8>, String> {
+    let digits: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    let digits = digits.strip_prefix("0x").unwrap_or(&digits);
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("odd number of hex digits in '{value}'"));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| format!("invalid hex in '{value}'"))
+        })
+        .collect()
+}
+
+/// A number written in decimal or with a `0x` prefix in hex
+fn parse_number<T: TryFrom<u32>>(value: &str) -> Option<T> {
+    let number = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => value.parse().ok()?,
+    };
+    T::try_from(number).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_coding_picks_default_or_ucs2() {
+        let (coding, body) = encode(Some("Hello"), None, Coding::Auto).unwrap();
+        assert_eq!(coding, DataCoding::SmscDefault);
+        assert_eq!(body, b"Hello");
+
+        let (coding, body) = encode(Some("Привет"), None, Coding::Auto).unwrap();
+        assert_eq!(coding, DataCoding::Ucs2);
+        assert_eq!(&body[..2], &[0x04, 0x1F]);
+
+        let (coding, body) = encode(None, Some("0102ff"), Coding::Auto).unwrap();
+        assert_eq!((coding, body), (DataCoding::Binary, vec![0x01, 0x02, 0xFF]));
+
+        assert!(encode(Some("café"), None, "latin1".parse().unwrap()).is_ok());
+        assert!(encode(Some("日本"), None, "latin1".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_long_messages_split_with_udh_or_sar() {
+        let body = vec![b'a'; 300];
+        let parts = split(&body, DataCoding::SmscDefault, Split::Udh, 0x1234).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].udhi);
+        assert_eq!(&parts[0].short_message[..6], &[0x05, 0x00, 0x03, 0x34, 2, 1]);
+        assert_eq!(parts[0].short_message.len(), 6 + 153);
+        assert_eq!(parts[1].short_message.len(), 6 + 147);
+
+        let parts = split(&body, DataCoding::SmscDefault, Split::Sar, 7).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[1].sar, Some((7, 2, 2)));
+        assert_eq!(parts[0].short_message.len(), 160);
+
+        let parts = split(&body, DataCoding::SmscDefault, Split::Payload, 7).unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].payload.as_deref(), Some(&body[..]));
+
+        // message_payload's length is two octets
+        let oversize = vec![b'a'; 65536];
+        assert!(split(&oversize, DataCoding::SmscDefault, Split::Payload, 7).is_err());
+
+        let short = split(b"Hi", DataCoding::SmscDefault, Split::Udh, 1).unwrap();
+        assert_eq!(short, vec![Part::single(b"Hi")]);
+    }
+
+    #[test]
+    fn test_ucs2_parts_keep_surrogate_pairs_whole() {
+        // 66 BMP characters then an emoji straddling the 134 octet boundary
+        let text = format!("{}😀{}", "a".repeat(66), "b".repeat(10));
+        let (coding, body) = encode(Some(&text), None, Coding::Auto).unwrap();
+        let parts = split(&body, coding, Split::Udh, 1).unwrap();
+        assert_eq!(parts[0].short_message.len(), 6 + 132);
+        let first = decode_text(&parts[0].short_message, coding, true);
+        let second = decode_text(&parts[1].short_message, coding, true);
+        assert_eq!(first + &second, text);
+    }
+
+    #[test]
+    fn test_tlv_arguments() {
+        let tlv = parse_tlv("its_session_info=0102").unwrap();
+        assert_eq!((tlv.tag, &tlv.value[..]), (tags::ITS_SESSION_INFO, &[1u8, 2][..]));
+        assert_eq!(parse_tlv("0x1383=00").unwrap().tag, 0x1383);
+        assert!(parse_tlv("nonsense=00").is_err());
+
+        let mut submit = SubmitSm::builder().build().unwrap();
+        set_tlv(&mut submit, tlv).unwrap();
+        assert!(submit.its_session_info.is_some());
+        assert!(set_tlv(&mut submit, Tlv::new(0x1400, vec![0]).unwrap()).is_err());
+    }
+}
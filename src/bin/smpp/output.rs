@@ -0,0 +1,71 @@
+// ABOUTME: Output for the smpp CLI: readable lines for people or one JSON object per line
+// ABOUTME: Every record carries a "type" so scripts can dispatch on it
+
+use serde_json::{Value, json};
+use std::fmt::Display;
+
+/// Where and how command results are printed
+#[derive(Debug, Clone, Copy)]
+pub struct Output {
+    json: bool,
+}
+
+impl Output {
+    pub fn new(json: bool) -> Self {
+        Self { json }
+    }
+
+    /// Print a result: `text` for people, or `record` as a JSON line
+    ///
+    /// `record` should be a JSON object; its `type` comes first when printed.
+    pub fn emit(&self, kind: &str, text: impl Display, record: Value) {
+        if self.json {
+            println!("{}", with_type(kind, record));
+        } else {
+            println!("{text}");
+        }
+    }
+
+    /// Print a record in JSON mode only, for detail the text already shows
+    pub fn record(&self, kind: &str, record: Value) {
+        if self.json {
+            println!("{}", with_type(kind, record));
+        }
+    }
+
+    /// Report a failure: on stderr for people, or as an `error` record on stdout
+    pub fn error(&self, error: impl Display) {
+        if self.json {
+            println!("{}", with_type("error", json!({ "message": error.to_string() })));
+        } else {
+            eprintln!("error: {error}");
+        }
+    }
+}
+
+/// `record` with `"type": kind` as its first field
+fn with_type(kind: &str, record: Value) -> Value {
+    let mut object = serde_json::Map::new();
+    object.insert("type".to_string(), Value::from(kind));
+    if let Value::Object(fields) = record {
+        object.extend(fields);
+    }
+    Value::Object(object)
+}
+
+/// Lower-case hex of `bytes`
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_start_with_their_type() {
+        let record = with_type("submitted", json!({ "message_id": "abc" }));
+        assert_eq!(record.to_string(), r#"{"type":"submitted","message_id":"abc"}"#);
+        assert_eq!(hex(&[0x00, 0xAB]), "00ab");
+    }
+}
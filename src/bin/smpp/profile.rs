@@ -0,0 +1,251 @@
+// ABOUTME: Connection profiles for the smpp CLI, read from a TOML config file
+// ABOUTME: Merges a named profile with command line overrides and binds a client with it
+
+use serde::Deserialize;
+use smpp::client::{BindCredentials, BindType, DefaultClient, Secret, SmppClient, SmppConnection};
+use smpp::datatypes::InterfaceVersion;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming the config file
+pub const CONFIG_ENV: &str = "SMPP_CONFIG";
+
+/// A config file: named profiles and which one to use by default
+///
+/// ```toml
+/// default = "staging"
+///
+/// [profiles.staging]
+/// host = "smsc.staging.example.com"
+/// port = 2775
+/// system_id = "ops"
+/// password = "secret"
+/// version = "3.4"
+/// ```
+///
+/// Instead of `password`, a profile may give a `password_file` to read the
+/// password from, e.g. one mounted by a secret store.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    default: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// Connection settings; anything not set falls back to the built-in defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub system_id: Option<String>,
+    pub password: Option<String>,
+    pub password_file: Option<PathBuf>,
+    pub system_type: Option<String>,
+    pub version: Option<String>,
+}
+
+impl Profile {
+    /// Settings from `overrides` where given, else from this profile
+    pub fn merge(self, overrides: Profile) -> Profile {
+        // A password given either way overrides both of the profile's
+        let (password, password_file) =
+            if overrides.password.is_some() || overrides.password_file.is_some() {
+                (overrides.password, overrides.password_file)
+            } else {
+                (self.password, self.password_file)
+            };
+        Profile {
+            host: overrides.host.or(self.host),
+            port: overrides.port.or(self.port),
+            system_id: overrides.system_id.or(self.system_id),
+            password,
+            password_file,
+            system_type: overrides.system_type.or(self.system_type),
+            version: overrides.version.or(self.version),
+        }
+    }
+
+    /// Resolve defaults and validate, producing what is needed to connect
+    pub fn into_target(self) -> Result<Target, String> {
+        let system_id = self
+            .system_id
+            .ok_or("no system_id: pass --system-id or set it in a profile")?;
+        let version = match self.version.as_deref() {
+            Some(version) => parse_version(version)?,
+            None => InterfaceVersion::SmppV34,
+        };
+        let password = match (self.password, &self.password_file) {
+            (Some(password), _) => Secret::from(password),
+            (None, Some(path)) => Secret::from_file(path).map_err(|e| e.to_string())?,
+            (None, None) => Secret::default(),
+        };
+        Ok(Target {
+            addr: format!(
+                "{}:{}",
+                self.host.as_deref().unwrap_or("localhost"),
+                self.port.unwrap_or(2775)
+            ),
+            system_id,
+            password,
+            system_type: self.system_type,
+            version,
+        })
+    }
+}
+
+/// Where to connect and how to bind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub addr: String,
+    pub system_id: String,
+    pub password: Secret,
+    pub system_type: Option<String>,
+    pub version: InterfaceVersion,
+}
+
+impl Target {
+    /// Connect and bind as `bind_type`
+    pub async fn bind(&self, bind_type: BindType) -> Result<DefaultClient, Box<dyn Error>> {
+        let mut credentials = BindCredentials::transmitter(&self.system_id, self.password.clone())
+            .with_version(self.version);
+        credentials.bind_type = bind_type;
+        if let Some(system_type) = &self.system_type {
+            credentials = credentials.with_system_type(system_type);
+        }
+
+        let mut client = DefaultClient::connect(self.addr.as_str()).await?;
+        client.bind(&credentials).await?;
+        Ok(client)
+    }
+}
+
+/// Parse an interface version written as 3.3, 3.4 or 5.0
+pub fn parse_version(value: &str) -> Result<InterfaceVersion, String> {
+    match value {
+        "3.3" => Ok(InterfaceVersion::SmppV33),
+        "3.4" => Ok(InterfaceVersion::SmppV34),
+        "5.0" => Ok(InterfaceVersion::SmppV50),
+        other => Err(format!("unknown interface version '{other}', expected 3.3, 3.4 or 5.0")),
+    }
+}
+
+/// Load the profile to use
+///
+/// The config file is `path` if given, else `$SMPP_CONFIG`, else
+/// `~/.config/smpp/config.toml` when it exists. The profile is `name` if
+/// given, else the file's `default`. Without a file or a profile name the
+/// result is an empty profile.
+pub fn load(path: Option<&Path>, name: Option<&str>) -> Result<Profile, String> {
+    let path = match path {
+        Some(path) => Some(path.to_path_buf()),
+        None => match std::env::var_os(CONFIG_ENV) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => default_path().filter(|path| path.exists()),
+        },
+    };
+    let config = match &path {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("reading {}: {e}", path.display()))?;
+            parse(&text).map_err(|e| format!("{}: {e}", path.display()))?
+        }
+        None => ConfigFile::default(),
+    };
+
+    match name.map(str::to_string).or(config.default) {
+        Some(name) => config.profiles.get(&name).cloned().ok_or_else(|| {
+            let known: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+            format!("no profile named '{name}' (known: {})", known.join(", "))
+        }),
+        None => Ok(Profile::default()),
+    }
+}
+
+fn parse(text: &str) -> Result<ConfigFile, String> {
+    toml::from_str(text).map_err(|e| e.to_string())
+}
+
+fn default_path() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join(".config/smpp/config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        default = "staging"
+
+        [profiles.staging]
+        host = "smsc.example.com"
+        system_id = "ops"
+        password = "secret"
+
+        [profiles.legacy]
+        host = "old.example.com"
+        port = 5016
+        system_id = "ops"
+        version = "3.3"
+    "#;
+
+    #[test]
+    fn test_profiles_merge_with_overrides() {
+        let config = parse(CONFIG).unwrap();
+        let staging = config.profiles["staging"].clone();
+        let overrides = Profile {
+            password: Some("other".to_string()),
+            ..Profile::default()
+        };
+        let target = staging.merge(overrides).into_target().unwrap();
+        assert_eq!(target.addr, "smsc.example.com:2775");
+        assert_eq!(target.password, "other");
+        assert_eq!(target.version, InterfaceVersion::SmppV34);
+
+        let legacy = config.profiles["legacy"].clone().into_target().unwrap();
+        assert_eq!(legacy.addr, "old.example.com:5016");
+        assert_eq!(legacy.version, InterfaceVersion::SmppV33);
+    }
+
+    #[test]
+    fn test_config_errors_are_reported() {
+        assert!(parse("[profiles.x]\nhots = \"typo\"").unwrap_err().contains("hots"));
+        assert!(Profile::default().into_target().unwrap_err().contains("system_id"));
+        let bad_version = Profile {
+            system_id: Some("ops".to_string()),
+            version: Some("4.0".to_string()),
+            ..Profile::default()
+        };
+        assert!(bad_version.into_target().unwrap_err().contains("4.0"));
+    }
+
+    #[test]
+    fn test_password_is_read_from_a_file_unless_given() {
+        let path = std::env::temp_dir().join(format!("smpp-cli-password-{}", std::process::id()));
+        std::fs::write(&path, "from-file\n").unwrap();
+        let profile = Profile {
+            system_id: Some("ops".to_string()),
+            password: Some("secret".to_string()),
+            password_file: Some(path.clone()),
+            ..Profile::default()
+        };
+        let overrides = Profile {
+            password_file: Some(path.clone()),
+            ..Profile::default()
+        };
+        let target = profile.clone().merge(overrides).into_target().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(target.password, "from-file");
+        assert!(!format!("{target:?}").contains("from-file"));
+
+        assert_eq!(profile.clone().into_target().unwrap().password, "secret");
+        let missing = Profile {
+            password: None,
+            ..profile
+        };
+        assert!(missing.into_target().unwrap_err().contains("smpp-cli-password"));
+    }
+}
@@ -0,0 +1,243 @@
+// ABOUTME: The send and submit-multi commands of the smpp CLI
+// ABOUTME: Builds submit_sm/submit_multi from text or hex, splitting long messages as asked
+
+use crate::message::{self, Coding, Split};
+use crate::output::Output;
+use crate::profile::Target;
+use argh::FromArgs;
+use serde_json::json;
+use smpp::client::{BindType, SmppClient, SmppConnection, SmppTransmitter};
+use smpp::datatypes::{
+    DestinationAddress, EsmClass, NumericPlanIndicator, PriorityFlag, ScheduleDeliveryTime,
+    ServiceType, ShortMessage, SourceAddr, SubmitMulti, SubmitSm, Tlv, TypeOfNumber,
+    ValidityPeriod, tags,
+};
+use std::error::Error;
+
+/// Send a message with submit_sm
+#[derive(FromArgs)]
+#[argh(subcommand, name = "send")]
+pub struct SendArgs {
+    /// the sender address
+    #[argh(option, short = 'f')]
+    from: String,
+
+    /// the recipient address
+    #[argh(option, short = 't')]
+    to: String,
+
+    /// the message text
+    #[argh(option, short = 'm')]
+    text: Option<String>,
+
+    /// the message body as hex, sent as is
+    #[argh(option)]
+    hex: Option<String>,
+
+    /// data coding: auto, default, ascii, latin1, binary, ucs2 or a number (default: auto)
+    #[argh(option, default = "Coding::Auto")]
+    coding: Coding,
+
+    /// how to send long messages: udh, sar or payload (default: udh)
+    #[argh(option, default = "Split::Udh")]
+    split: Split,
+
+    /// request a delivery receipt
+    #[argh(switch, short = 'r')]
+    receipt: bool,
+
+    /// an optional parameter as tag=hexvalue, the tag by name or number; repeatable
+    #[argh(option, from_str_fn(parse_tlv))]
+    tlv: Vec<Tlv>,
+}
+
+/// Send one message to several recipients with submit_multi
+#[derive(FromArgs)]
+#[argh(subcommand, name = "submit-multi")]
+pub struct SubmitMultiArgs {
+    /// the sender address
+    #[argh(option, short = 'f')]
+    from: String,
+
+    /// a recipient address; repeatable
+    #[argh(option, short = 't')]
+    to: Vec<String>,
+
+    /// a distribution list name; repeatable
+    #[argh(option)]
+    list: Vec<String>,
+
+    /// the message text
+    #[argh(option, short = 'm')]
+    text: Option<String>,
+
+    /// the message body as hex, sent as is
+    #[argh(option)]
+    hex: Option<String>,
+
+    /// data coding: auto, default, ascii, latin1, binary, ucs2 or a number (default: auto)
+    #[argh(option, default = "Coding::Auto")]
+    coding: Coding,
+
+    /// request a delivery receipt
+    #[argh(switch, short = 'r')]
+    receipt: bool,
+
+    /// an optional parameter as tag=hexvalue, the tag by name or number; repeatable
+    #[argh(option, from_str_fn(parse_tlv))]
+    tlv: Vec<Tlv>,
+}
+
+fn parse_tlv(value: &str) -> Result<Tlv, String> {
+    message::parse_tlv(value)
+}
+
+pub async fn send(target: &Target, args: SendArgs, output: Output) -> Result<(), Box<dyn Error>> {
+    let (data_coding, body) =
+        message::encode(args.text.as_deref(), args.hex.as_deref(), args.coding)?;
+    let parts = message::split(&body, data_coding, args.split, message::concat_reference())?;
+    let mut client = target.bind(BindType::Transmitter).await?;
+    let total = parts.len();
+
+    for (index, part) in parts.into_iter().enumerate() {
+        let mut esm_class = EsmClass::default();
+        if part.udhi {
+            esm_class = esm_class.with_udhi();
+        }
+        let mut submit = SubmitSm::builder()
+            .source_addr(&args.from)
+            .destination_addr(&args.to)
+            .esm_class(esm_class)
+            .data_coding(data_coding)
+            .registered_delivery(u8::from(args.receipt))
+            .build()?;
+        submit.short_message = ShortMessage::new(&part.short_message)?;
+        submit.sm_length = submit.short_message.len();
+        for tlv in &args.tlv {
+            message::set_tlv(&mut submit, tlv.clone())?;
+        }
+        if let Some(payload) = part.payload {
+            submit.message_payload = Some(Tlv::new(tags::MESSAGE_PAYLOAD, payload)?);
+        }
+        if let Some((reference, total, seqnum)) = part.sar {
+            submit.sar_msg_ref_num =
+                Some(Tlv::new(tags::SAR_MSG_REF_NUM, reference.to_be_bytes().to_vec())?);
+            submit.sar_total_segments = Some(Tlv::new(tags::SAR_TOTAL_SEGMENTS, vec![total])?);
+            submit.sar_segment_seqnum = Some(Tlv::new(tags::SAR_SEGMENT_SEQNUM, vec![seqnum])?);
+        }
+
+        submit.validate()?;
+        let message_id = client.submit_sm(&submit).await?;
+        let part = index + 1;
+        let text = if total > 1 {
+            format!("submitted part {part}/{total} to {}: message_id {message_id}", args.to)
+        } else {
+            format!("submitted to {}: message_id {message_id}", args.to)
+        };
+        output.emit(
+            "submitted",
+            text,
+            json!({
+                "to": args.to,
+                "message_id": message_id,
+                "part": part,
+                "parts": total,
+                "data_coding": data_coding.to_byte(),
+            }),
+        );
+    }
+
+    client.unbind().await?;
+    client.disconnect().await?;
+    Ok(())
+}
+
+pub async fn submit_multi(
+    target: &Target,
+    args: SubmitMultiArgs,
+    output: Output,
+) -> Result<(), Box<dyn Error>> {
+    if args.to.is_empty() && args.list.is_empty() {
+        return Err("give at least one --to or --list".into());
+    }
+    let (data_coding, body) =
+        message::encode(args.text.as_deref(), args.hex.as_deref(), args.coding)?;
+
+    // Messages too long for short_message go in message_payload
+    let part = message::split(&body, data_coding, Split::Payload, 0)?.remove(0);
+    let (short_message, payload) = (part.short_message, part.payload);
+    let mut client = target.bind(BindType::Transmitter).await?;
+
+    let mut destinations = Vec::new();
+    for to in &args.to {
+        destinations.push(DestinationAddress::sme_address(
+            TypeOfNumber::Unknown,
+            NumericPlanIndicator::Unknown,
+            to,
+        )?);
+    }
+    for list in &args.list {
+        destinations.push(DestinationAddress::distribution_list(list)?);
+    }
+    let mut submit = SubmitMulti::new(
+        // The client allocates the sequence number when it sends the PDU
+        0,
+        ServiceType::default(),
+        TypeOfNumber::Unknown,
+        NumericPlanIndicator::Unknown,
+        SourceAddr::new(&args.from, TypeOfNumber::Unknown)?,
+        destinations,
+        EsmClass::default(),
+        0,
+        PriorityFlag::Level0,
+        ScheduleDeliveryTime::default(),
+        ValidityPeriod::default(),
+        u8::from(args.receipt),
+        0,
+        data_coding,
+        0,
+        ShortMessage::new(&short_message)?,
+    )?;
+    for tlv in args.tlv {
+        submit.add_tlv(tlv);
+    }
+    if let Some(payload) = payload {
+        submit.add_tlv(Tlv::new(tags::MESSAGE_PAYLOAD, payload)?);
+    }
+
+    let response = client.submit_multi(&submit).await?;
+    let message_id = response.message_id.to_string();
+    let failed: Vec<_> = response
+        .unsuccess_sme
+        .iter()
+        .map(|sme| {
+            json!({
+                "destination": sme.destination_addr,
+                "status": format!("{:?}", sme.error_status_code),
+            })
+        })
+        .collect();
+    let mut text = format!(
+        "submitted to {} destinations: message_id {message_id}",
+        submit.destination_count()
+    );
+    for sme in &response.unsuccess_sme {
+        text.push_str(&format!(
+            "\n  failed {}: {:?}",
+            sme.destination_addr, sme.error_status_code
+        ));
+    }
+    output.emit(
+        "submitted",
+        text,
+        json!({
+            "message_id": message_id,
+            "destinations": submit.destination_count(),
+            "failed": failed,
+        }),
+    );
+
+    client.unbind().await?;
+    client.disconnect().await?;
+    Ok(())
+}
@@ -0,0 +1,149 @@
+// ABOUTME: Command line SMSC simulator for running SMPP clients against in tests and CI
+// ABOUTME: Wraps smpp::simulator with flags for accounts, versions, receipts, MO and failures
+
+use argh::FromArgs;
+use smpp::datatypes::InterfaceVersion;
+use smpp::simulator::{Account, FailureModes, MoMessage, Simulator, SimulatorConfig};
+use std::error::Error;
+use std::time::Duration;
+use tracing::Level;
+use tracing_subscriber::FmtSubscriber;
+
+/// An SMSC simulator that accepts binds, answers submissions and sends
+/// delivery receipts and MO messages
+#[derive(FromArgs)]
+struct CliArgs {
+    /// whether or not to enable debugging
+    #[argh(switch, short = 'd')]
+    debugging: bool,
+
+    /// the address to listen on (default: 127.0.0.1:2775)
+    #[argh(option, short = 'l', default = "String::from(\"127.0.0.1:2775\")")]
+    listen: String,
+
+    /// the system_id returned in bind responses (default: SMSC-SIM)
+    #[argh(option, default = "String::from(\"SMSC-SIM\")")]
+    system_id: String,
+
+    /// an accepted account as system_id:password; repeatable, none accepts any bind
+    #[argh(option, short = 'a', from_str_fn(parse_account))]
+    account: Vec<Account>,
+
+    /// an accepted interface version: 3.3, 3.4 or 5.0; repeatable (default: all)
+    #[argh(option, short = 'v', from_str_fn(parse_version))]
+    version: Vec<InterfaceVersion>,
+
+    /// milliseconds before a requested delivery receipt is sent (default: 500)
+    #[argh(option, default = "500")]
+    receipt_delay_ms: u64,
+
+    /// never send delivery receipts
+    #[argh(switch)]
+    no_receipts: bool,
+
+    /// an MO message sent after each receiver binds, as delay_ms:from:to:text; repeatable
+    #[argh(option, from_str_fn(parse_mo))]
+    mo: Vec<(Duration, MoMessage)>,
+
+    /// answer every Nth submission with ESME_RTHROTTLED
+    #[argh(option)]
+    throttle_every: Option<u32>,
+
+    /// answer every Nth submission with ESME_RMSGQFUL
+    #[argh(option)]
+    queue_full_every: Option<u32>,
+
+    /// never answer every Nth submission
+    #[argh(option)]
+    drop_every: Option<u32>,
+
+    /// delay the answer to every Nth submission by --slow-ms
+    #[argh(option)]
+    slow_every: Option<u32>,
+
+    /// milliseconds slow answers are delayed by (default: 5000)
+    #[argh(option, default = "5000")]
+    slow_ms: u64,
+
+    /// close the connection on the Nth submission
+    #[argh(option)]
+    disconnect_after: Option<u32>,
+}
+
+fn parse_account(value: &str) -> Result<Account, String> {
+    value
+        .split_once(':')
+        .map(|(system_id, password)| Account::new(system_id, password))
+        .ok_or_else(|| format!("expected system_id:password, got '{value}'"))
+}
+
+fn parse_version(value: &str) -> Result<InterfaceVersion, String> {
+    match value {
+        "3.3" => Ok(InterfaceVersion::SmppV33),
+        "3.4" => Ok(InterfaceVersion::SmppV34),
+        "5.0" => Ok(InterfaceVersion::SmppV50),
+        other => Err(format!("unknown interface version '{other}'")),
+    }
+}
+
+fn parse_mo(value: &str) -> Result<(Duration, MoMessage), String> {
+    let mut parts = value.splitn(4, ':');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(delay), Some(from), Some(to), Some(text)) => {
+            let delay = delay
+                .parse()
+                .map_err(|_| format!("invalid MO delay '{delay}'"))?;
+            Ok((Duration::from_millis(delay), MoMessage::new(from, to, text)))
+        }
+        _ => Err(format!("expected delay_ms:from:to:text, got '{value}'")),
+    }
+}
+
+impl CliArgs {
+    fn config(self) -> SimulatorConfig {
+        let mut failures = FailureModes::none();
+        failures.throttle_every = self.throttle_every;
+        failures.queue_full_every = self.queue_full_every;
+        failures.drop_every = self.drop_every;
+        failures.disconnect_after = self.disconnect_after;
+        if let Some(n) = self.slow_every {
+            failures = failures.with_slow_every(n, Duration::from_millis(self.slow_ms));
+        }
+
+        let receipt_delay =
+            (!self.no_receipts).then(|| Duration::from_millis(self.receipt_delay_ms));
+
+        let mut config = SimulatorConfig::new()
+            .with_system_id(self.system_id)
+            .with_receipt_delay(receipt_delay)
+            .with_failures(failures);
+        if !self.version.is_empty() {
+            config = config.with_versions(self.version);
+        }
+        for account in self.account {
+            config = config.with_account(account);
+        }
+        for (delay, message) in self.mo {
+            config = config.with_scripted_mo(delay, message);
+        }
+        config
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let cli_args: CliArgs = argh::from_env();
+
+    let level = if cli_args.debugging {
+        Level::DEBUG
+    } else {
+        Level::INFO
+    };
+    let subscriber = FmtSubscriber::builder().with_max_level(level).finish();
+    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+
+    let listen = cli_args.listen.clone();
+    let simulator = Simulator::bind(listen.as_str(), cli_args.config()).await?;
+    simulator.run().await?;
+    Ok(())
+}
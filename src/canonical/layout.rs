@@ -0,0 +1,466 @@
+// ABOUTME: Walks the shared wire layout to turn encoded PDUs into canonical values
+// ABOUTME: and canonical values back into the octets the decoders expect
+
+use super::{Fallible, Object, Value};
+use crate::codec::PduHeader;
+use crate::datatypes::{CommandId, CommandStatus, DataCoding};
+use crate::layout::{self, DEST, Layout, Octet, Text};
+
+/// The canonical value of an encoded PDU
+///
+/// A body that cannot be laid out field by field is given whole, as hex,
+/// under `body`.
+pub(super) fn read(pdu: &[u8]) -> Fallible<Value> {
+    if pdu.len() < PduHeader::SIZE {
+        return Err(format!("a PDU needs 16 octets but only {} were given", pdu.len()));
+    }
+    let word = |at: usize| u32::from_be_bytes([pdu[at], pdu[at + 1], pdu[at + 2], pdu[at + 3]]);
+    let command_id = CommandId::try_from(word(4))
+        .map_err(|_| format!("unknown command_id {:#010x}", word(4)))?;
+    let header = vec![
+        ("command_id".to_string(), Value::Text(command_id.name())),
+        ("command_status".to_string(), super::status_value(word(8))),
+        ("sequence_number".to_string(), Value::Number(word(12).into())),
+    ];
+
+    let mut reader = Reader {
+        pdu,
+        pos: PduHeader::SIZE,
+        fields: header.clone(),
+        data_coding: DataCoding::default(),
+        udhi: false,
+    };
+    if layout::body(&mut reader, command_id).and_then(|()| reader.tlvs()).is_ok() {
+        return Ok(Value::Object(reader.fields));
+    }
+    let mut fields = header;
+    fields.push(("body".to_string(), Value::Text(super::hex(&pdu[PduHeader::SIZE..]))));
+    Ok(Value::Object(fields))
+}
+
+/// The encoded PDU a canonical value describes
+///
+/// Returns the octets and whether the body was given whole, in which case
+/// the PDU need not decode to be kept.
+pub(super) fn write(value: Value) -> Fallible<(Vec<u8>, bool)> {
+    let mut object = Object::new(value, "a PDU")?;
+    let command_id = match object.take("command_id") {
+        Some(Value::Text(name)) => super::command_id(&name)?,
+        Some(other) => return Err(format!("command_id must be a name, not {other}")),
+        None => return Err("command_id is missing".to_string()),
+    };
+    let status = match object.take("command_status") {
+        Some(value) => super::status_from(&value)?,
+        None => CommandStatus::Ok as u32,
+    };
+    let sequence_number = match object.take("sequence_number") {
+        Some(value) => value.number("sequence_number", u32::MAX.into())? as u32,
+        None => return Err("sequence_number is missing".to_string()),
+    };
+
+    let mut out = Vec::new();
+    for word in [0, command_id as u32, status, sequence_number] {
+        out.extend_from_slice(&word.to_be_bytes());
+    }
+    let raw = match object.take("body") {
+        Some(Value::Text(hex)) => {
+            out.extend(super::unhex(&hex, "body")?);
+            object.finish(&command_id.name())?;
+            true
+        }
+        Some(other) => return Err(format!("body must be hex, not {other}")),
+        None => {
+            let mut writer = Writer {
+                object,
+                out,
+                status,
+                data_coding: DataCoding::default(),
+                udhi: false,
+            };
+            layout::body(&mut writer, command_id)?;
+            writer.tlvs()?;
+            writer.object.finish(&command_id.name())?;
+            out = writer.out;
+            false
+        }
+    };
+    let length = out.len() as u32;
+    out[..4].copy_from_slice(&length.to_be_bytes());
+    Ok((out, raw))
+}
+
+/// Reads canonical values out of an encoded PDU
+struct Reader<'a> {
+    pdu: &'a [u8],
+    pos: usize,
+    fields: Vec<(String, Value)>,
+    data_coding: DataCoding,
+    udhi: bool,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, name: &str, len: usize) -> Fallible<&[u8]> {
+        let remaining = self.pdu.len() - self.pos;
+        if remaining < len {
+            return Err(format!("{name} needs {len} octets but only {remaining} remain"));
+        }
+        let octets = &self.pdu[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(octets)
+    }
+
+    fn push(&mut self, name: &str, value: Value) {
+        self.fields.push((name.to_string(), value));
+    }
+
+    /// Read the fields `read` adds as one object of their own
+    fn nested(&mut self, read: impl FnOnce(&mut Self) -> Fallible) -> Fallible<Value> {
+        let outer = std::mem::take(&mut self.fields);
+        let result = read(self);
+        let inner = std::mem::replace(&mut self.fields, outer);
+        result.map(|()| Value::Object(inner))
+    }
+
+    fn tlvs(&mut self) -> Fallible {
+        let context = super::TlvContext {
+            data_coding: self.data_coding,
+            udhi: self.udhi,
+        };
+        let mut tlvs = Vec::new();
+        while self.pos < self.pdu.len() {
+            let header = self.take("optional_parameters", 4)?;
+            let tag = u16::from_be_bytes([header[0], header[1]]);
+            let len = usize::from(u16::from_be_bytes([header[2], header[3]]));
+            let value = self.take("optional_parameters", len)?;
+            tlvs.push(super::tlv_value(tag, value, &context));
+        }
+        if !tlvs.is_empty() {
+            self.push("tlvs", Value::List(tlvs));
+        }
+        Ok(())
+    }
+}
+
+impl Layout for Reader<'_> {
+    type Error = String;
+
+    fn octet(&mut self, name: &str, kind: Octet) -> Fallible {
+        let octet = self.take(name, 1)?[0];
+        match kind {
+            Octet::EsmClass => self.udhi = octet & 0x40 != 0,
+            Octet::DataCoding => self.data_coding = DataCoding::from_byte(octet),
+            _ => {}
+        }
+        self.push(name, kind.value(octet));
+        Ok(())
+    }
+
+    fn u16(&mut self, name: &str) -> Fallible {
+        let octets = self.take(name, 2)?;
+        let value = u16::from_be_bytes([octets[0], octets[1]]);
+        self.push(name, Value::Number(value.into()));
+        Ok(())
+    }
+
+    fn u32(&mut self, name: &str) -> Fallible {
+        let octets = self.take(name, 4)?;
+        let value = u32::from_be_bytes([octets[0], octets[1], octets[2], octets[3]]);
+        self.push(name, Value::Number(value.into()));
+        Ok(())
+    }
+
+    fn status(&mut self, name: &str) -> Fallible {
+        let octets = self.take(name, 4)?;
+        let value = u32::from_be_bytes([octets[0], octets[1], octets[2], octets[3]]);
+        self.push(name, super::status_value(value));
+        Ok(())
+    }
+
+    fn text(&mut self, name: &str, layout: Text) -> Fallible {
+        let window = &self.pdu[self.pos..];
+        let nul = |limit: usize| window.iter().take(limit).position(|&b| b == 0);
+        let (content, len) = match layout {
+            Text::Fixed(width) => (nul(width).unwrap_or(width), width),
+            Text::Bounded(max) => match nul(max) {
+                Some(nul) => (nul, nul + 1),
+                None => (max, max),
+            },
+            Text::COctet(_) | Text::Padded(_) | Text::Terminated => match nul(window.len()) {
+                Some(nul) => (nul, nul + 1),
+                None => return Err(format!("{name} has no NUL terminator")),
+            },
+        };
+        let len = match layout {
+            Text::Padded(width) => {
+                let padding = window[len..].iter().take(width.saturating_sub(len));
+                len + padding.take_while(|&&b| b == 0).count()
+            }
+            _ => len,
+        };
+        let octets = self.take(name, len)?;
+        let value = super::text_value(&octets[..content]);
+        self.push(name, value);
+        Ok(())
+    }
+
+    fn optional_text(&mut self, name: &str, layout: Text) -> Fallible {
+        match self.pos < self.pdu.len() {
+            true => self.text(name, layout),
+            false => Ok(()),
+        }
+    }
+
+    fn short_message(&mut self) -> Fallible {
+        let len = usize::from(self.take("sm_length", 1)?[0]);
+        let (coding, udhi) = (self.data_coding, self.udhi);
+        let message = super::message_value(self.take("short_message", len)?, coding, udhi);
+        self.push("short_message", message);
+        Ok(())
+    }
+
+    fn area(&mut self) -> Fallible {
+        let len = usize::from(self.take("broadcast_area_identifier_len", 1)?[0]);
+        let area = super::hex(self.take("broadcast_area_identifier", len)?);
+        self.push("broadcast_area_identifier", Value::Text(area));
+        Ok(())
+    }
+
+    fn dest_addresses(&mut self) -> Fallible {
+        let count = self.take("number_of_dests", 1)?[0];
+        let mut destinations = Vec::new();
+        for _ in 0..count {
+            let destination = match self.take("dest_flag", 1)?[0] {
+                1 => self.nested(|reader| reader.address(DEST, Text::Terminated))?,
+                2 => self.nested(|reader| reader.text("dl_name", Text::Terminated))?,
+                flag => return Err(format!("dest_flag {flag} is neither 1 nor 2")),
+            };
+            destinations.push(destination);
+        }
+        self.push("dest_address", Value::List(destinations));
+        Ok(())
+    }
+
+    fn unsuccess_smes(&mut self) -> Fallible {
+        let count = self.take("no_unsuccess", 1)?[0];
+        let mut smes = Vec::new();
+        for _ in 0..count {
+            smes.push(self.nested(|reader| {
+                reader.address(DEST, Text::Terminated)?;
+                reader.status("error_status_code")
+            })?);
+        }
+        self.push("unsuccess_sme", Value::List(smes));
+        Ok(())
+    }
+
+}
+
+/// Writes an encoded PDU from canonical values
+///
+/// A field that is left out is written empty, or zero, except that an error
+/// response leaves out the optional fields it could carry.
+struct Writer {
+    object: Object,
+    out: Vec<u8>,
+    status: u32,
+    data_coding: DataCoding,
+    udhi: bool,
+}
+
+impl Writer {
+    /// Write the fields `write` takes from one object of their own
+    fn nested(
+        &mut self,
+        entry: Value,
+        what: &str,
+        write: impl FnOnce(&mut Self) -> Fallible,
+    ) -> Fallible {
+        let inner = Object::new(entry, what)?;
+        let outer = std::mem::replace(&mut self.object, inner);
+        let result = write(self);
+        let inner = std::mem::replace(&mut self.object, outer);
+        result.and_then(|()| inner.finish(what))
+    }
+
+    fn list(&mut self, name: &str) -> Fallible<Vec<Value>> {
+        let entries = match self.object.take(name) {
+            Some(Value::List(entries)) => entries,
+            Some(other) => return Err(format!("{name} must be a list, not {other}")),
+            None => Vec::new(),
+        };
+        if entries.len() > usize::from(u8::MAX) {
+            return Err(format!("{name} has {} entries, more than 255", entries.len()));
+        }
+        Ok(entries)
+    }
+
+    fn tlvs(&mut self) -> Fallible {
+        let context = super::TlvContext {
+            data_coding: self.data_coding,
+            udhi: self.udhi,
+        };
+        let tlvs = match self.object.take("tlvs") {
+            Some(Value::List(tlvs)) => tlvs,
+            Some(other) => return Err(format!("tlvs must be a list, not {other}")),
+            None => Vec::new(),
+        };
+        for tlv in tlvs {
+            let (tag, value) = super::tlv_octets(tlv, &context)?;
+            let len = u16::try_from(value.len())
+                .map_err(|_| format!("TLV {tag:#06x} is too long"))?;
+            self.out.extend_from_slice(&tag.to_be_bytes());
+            self.out.extend_from_slice(&len.to_be_bytes());
+            self.out.extend(value);
+        }
+        Ok(())
+    }
+}
+
+impl Layout for Writer {
+    type Error = String;
+
+    fn octet(&mut self, name: &str, kind: Octet) -> Fallible {
+        let octet = match self.object.take(name) {
+            Some(value) => kind.octet(&value, name)?,
+            None => 0,
+        };
+        match kind {
+            Octet::EsmClass => self.udhi = octet & 0x40 != 0,
+            Octet::DataCoding => self.data_coding = DataCoding::from_byte(octet),
+            _ => {}
+        }
+        self.out.push(octet);
+        Ok(())
+    }
+
+    fn u16(&mut self, name: &str) -> Fallible {
+        let value = match self.object.take(name) {
+            Some(value) => value.number(name, u16::MAX.into())? as u16,
+            None => 0,
+        };
+        self.out.extend_from_slice(&value.to_be_bytes());
+        Ok(())
+    }
+
+    fn u32(&mut self, name: &str) -> Fallible {
+        let value = match self.object.take(name) {
+            Some(value) => value.number(name, u32::MAX.into())? as u32,
+            None => 0,
+        };
+        self.out.extend_from_slice(&value.to_be_bytes());
+        Ok(())
+    }
+
+    fn status(&mut self, name: &str) -> Fallible {
+        let value = match self.object.take(name) {
+            Some(value) => super::status_from(&value)?,
+            None => 0,
+        };
+        self.out.extend_from_slice(&value.to_be_bytes());
+        Ok(())
+    }
+
+    fn text(&mut self, name: &str, layout: Text) -> Fallible {
+        let octets = match self.object.take(name) {
+            Some(value) => super::text_octets(&value, name)?,
+            None => Vec::new(),
+        };
+        if octets.contains(&0) {
+            return Err(format!("{name} cannot contain NUL"));
+        }
+        let too_long = |max: usize| format!("{name} is longer than {max} octets");
+        match layout {
+            Text::Fixed(width) => {
+                if octets.len() >= width {
+                    return Err(too_long(width - 1));
+                }
+                self.out.extend_from_slice(&octets);
+                self.out.resize(self.out.len() + width - octets.len(), 0);
+            }
+            Text::Bounded(max) => {
+                if octets.len() > max {
+                    return Err(too_long(max));
+                }
+                self.out.extend_from_slice(&octets);
+                if octets.len() < max {
+                    self.out.push(0);
+                }
+            }
+            Text::COctet(_) | Text::Padded(_) | Text::Terminated => {
+                self.out.extend_from_slice(&octets);
+                self.out.push(0);
+            }
+        }
+        Ok(())
+    }
+
+    fn optional_text(&mut self, name: &str, layout: Text) -> Fallible {
+        match self.object.contains(name) || self.status == CommandStatus::Ok as u32 {
+            true => self.text(name, layout),
+            false => Ok(()),
+        }
+    }
+
+    fn short_message(&mut self) -> Fallible {
+        let message = match self.object.take("short_message") {
+            Some(value) => super::message_octets(value, self.data_coding, "short_message")?,
+            None => Vec::new(),
+        };
+        let len = u8::try_from(message.len())
+            .map_err(|_| format!("short_message is {} octets, more than 255", message.len()))?;
+        self.out.push(len);
+        self.out.extend(message);
+        Ok(())
+    }
+
+    fn area(&mut self) -> Fallible {
+        let name = "broadcast_area_identifier";
+        let area = match self.object.take(name) {
+            Some(Value::Text(hex)) => super::unhex(&hex, name)?,
+            Some(other) => return Err(format!("{name} must be hex, not {other}")),
+            None => Vec::new(),
+        };
+        let len = u8::try_from(area.len())
+            .map_err(|_| format!("{name} is {} octets, more than 255", area.len()))?;
+        self.out.push(len);
+        self.out.extend(area);
+        Ok(())
+    }
+
+    fn dest_addresses(&mut self) -> Fallible {
+        let destinations = self.list("dest_address")?;
+        self.out.push(destinations.len() as u8);
+        for destination in destinations {
+            let list = matches!(&destination, Value::Object(fields)
+                if fields.iter().any(|(key, _)| key == "dl_name"));
+            match list {
+                true => {
+                    self.out.push(2);
+                    self.nested(destination, "dest_address", |writer| {
+                        writer.text("dl_name", Text::Terminated)
+                    })?;
+                }
+                false => {
+                    self.out.push(1);
+                    self.nested(destination, "dest_address", |writer| {
+                        writer.address(DEST, Text::Terminated)
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn unsuccess_smes(&mut self) -> Fallible {
+        let smes = self.list("unsuccess_sme")?;
+        self.out.push(smes.len() as u8);
+        for sme in smes {
+            self.nested(sme, "unsuccess_sme", |writer| {
+                writer.address(DEST, Text::Terminated)?;
+                writer.status("error_status_code")
+            })?;
+        }
+        Ok(())
+    }
+
+}
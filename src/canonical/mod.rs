@@ -0,0 +1,1148 @@
+// ABOUTME: Serde support: a stable, human-readable mapping of every PDU and Frame that
+// ABOUTME: round-trips to the same wire octets, plus the datatypes PDUs are built from
+
+//! Canonical Representation (`serde` feature)
+//!
+//! [`Frame`] and every PDU type implement `Serialize` and `Deserialize` with
+//! a mapping meant for logs, queues and hand-written test fixtures. It is
+//! taken from the PDU's wire encoding, field by field, so a value that is
+//! serialized and deserialized again encodes to exactly the same octets:
+//!
+//! * **Header** - `command_id` by its specification name (`"submit_sm"`),
+//!   `command_status` by its variant name (`"Ok"`) and `sequence_number`;
+//!   `command_length` is left out
+//! * **Mandatory parameters** - by their specification names, in wire order.
+//!   Enumerated octets such as `source_addr_ton`, `priority_flag`,
+//!   `data_coding` and `message_state` are given by name, or as a number when
+//!   the octet names nothing; `esm_class` is an object of its mode, type and
+//!   flags. Lengths and counts (`sm_length`, `number_of_dests`) follow from
+//!   the values they count and are left out too
+//! * **Messages** - `short_message` and the `message_payload` TLV are text
+//!   when their data_coding can carry it losslessly (UCS-2, Latin-1, or UTF-8
+//!   for the default and ASCII alphabets), and `{"hex": "..."}` otherwise; a
+//!   user data header (when esm_class sets UDHI) is split out as
+//!   `{"udh": "...", "text": "..."}`
+//! * **TLVs** - `tlvs` lists `{"tag": name, "value": value}` in wire order.
+//!   Values are numbers, text or names according to the tag, `null` when
+//!   empty, and `{"hex": "..."}` when they are not what the tag calls for.
+//!   Unknown tags are given as numbers
+//!
+//! ```json
+//! {
+//!   "command_id": "submit_sm",
+//!   "command_status": "Ok",
+//!   "sequence_number": 7,
+//!   "service_type": "",
+//!   "source_addr_ton": "International",
+//!   "source_addr_npi": "Isdn",
+//!   "source_addr": "447700900123",
+//!   "...": "...",
+//!   "data_coding": "Ucs2",
+//!   "sm_default_msg_id": 0,
+//!   "short_message": "Grüße",
+//!   "tlvs": [{ "tag": "user_message_reference", "value": 42 }]
+//! }
+//! ```
+//!
+//! When deserializing, fields that are left out are empty or zero, except
+//! `command_id` and `sequence_number`, and unknown fields are rejected. The
+//! PDU is then decoded strictly as SMPP v5.0, so a value the decoders would
+//! reject on the wire is rejected here too. A body that cannot be laid out
+//! field by field is serialized whole, as hex, under `body`.
+//!
+//! ```rust
+//! use smpp::Encodable;
+//! use smpp::datatypes::SubmitSm;
+//!
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let submit = SubmitSm::builder()
+//!     .sequence_number(7)
+//!     .source_addr("1234")
+//!     .destination_addr("5678")
+//!     .short_message("Hello")
+//!     .build()?;
+//! let json = serde_json::to_string(&submit)?;
+//! assert!(json.contains(r#""short_message":"Hello""#));
+//!
+//! let decoded: SubmitSm = serde_json::from_str(&json)?;
+//! assert_eq!(decoded.to_bytes(), submit.to_bytes());
+//! # Ok(())
+//! # }
+//! # example().unwrap();
+//! ```
+//!
+//! The datatypes PDUs are built from serialize the same way on their own:
+//! enums by name, addresses, strings and times as text, and [`Tlv`] as a
+//! tag and value (a `message_payload` on its own is read as the default
+//! alphabet).
+
+mod layout;
+
+use crate::codec::{CodecError, Encodable, Frame, PduHeader, PduRegistry};
+use crate::datatypes::{
+    self, AlphanumericAddress, CommandId, CommandStatus, DataCoding, EsmClass, FixedString,
+    InterfaceVersion, MessageState, NumericPlanIndicator, PhoneNumber, PriorityFlag, ServiceType,
+    SmppDateTime, Tlv, TypeOfNumber, tags,
+};
+use crate::layout::Octet;
+use bytes::{Bytes, BytesMut};
+use serde::de::{self, Deserializer, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Cursor;
+use std::sync::OnceLock;
+
+/// Failures carry a message for the serializer's or deserializer's error
+type Fallible<T = ()> = Result<T, String>;
+
+/// A canonical value, before it is handed to (or after it is taken from) a format
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Bool(bool),
+    Number(u64),
+    Text(String),
+    List(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn number(&self, name: &str, max: u64) -> Fallible<u64> {
+        match self {
+            Value::Number(number) if *number <= max => Ok(*number),
+            Value::Number(number) => Err(format!("{name} is {number}, more than {max}")),
+            other => Err(format!("{name} must be a number, not {other}")),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(flag) => write!(f, "{flag}"),
+            Value::Number(number) => write!(f, "{number}"),
+            Value::Text(text) => write!(f, "{text:?}"),
+            Value::List(_) => write!(f, "a list"),
+            Value::Object(_) => write!(f, "an object"),
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(flag) => serializer.serialize_bool(*flag),
+            Value::Number(number) => serializer.serialize_u64(*number),
+            Value::Text(text) => serializer.serialize_str(text),
+            Value::List(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+            Value::Object(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (key, value) in fields {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a canonical SMPP value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_bool<E>(self, flag: bool) -> Result<Value, E> {
+        Ok(Value::Bool(flag))
+    }
+
+    fn visit_u64<E>(self, number: u64) -> Result<Value, E> {
+        Ok(Value::Number(number))
+    }
+
+    fn visit_i64<E: de::Error>(self, number: i64) -> Result<Value, E> {
+        u64::try_from(number)
+            .map(Value::Number)
+            .map_err(|_| E::custom(format!("{number} is negative")))
+    }
+
+    fn visit_str<E>(self, text: &str) -> Result<Value, E> {
+        Ok(Value::Text(text.to_string()))
+    }
+
+    fn visit_string<E>(self, text: String) -> Result<Value, E> {
+        Ok(Value::Text(text))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::List(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut fields = Vec::new();
+        while let Some(entry) = map.next_entry()? {
+            fields.push(entry);
+        }
+        Ok(Value::Object(fields))
+    }
+}
+
+/// The fields of an object, taken one by one so that any left over are known
+struct Object {
+    fields: Vec<(String, Value)>,
+}
+
+impl Object {
+    fn new(value: Value, what: &str) -> Fallible<Self> {
+        match value {
+            Value::Object(fields) => Ok(Object { fields }),
+            other => Err(format!("{what} must be an object, not {other}")),
+        }
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.fields.iter().any(|(name, _)| name == key)
+    }
+
+    fn take(&mut self, key: &str) -> Option<Value> {
+        let index = self.fields.iter().position(|(name, _)| name == key)?;
+        Some(self.fields.remove(index).1)
+    }
+
+    fn finish(self, what: &str) -> Fallible {
+        match self.fields.first() {
+            Some((key, _)) => Err(format!("unknown field `{key}` in {what}")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Octet {
+    /// The name of an octet, when it has one and the name stands for it alone
+    fn name(self, octet: u8) -> Option<String> {
+        fn named<T: fmt::Debug, E>(value: Result<T, E>) -> Option<String> {
+            value.ok().map(|value| format!("{value:?}"))
+        }
+
+        match self {
+            Octet::Number | Octet::EsmClass | Octet::RegisteredDelivery => None,
+            Octet::Ton => named(TypeOfNumber::try_from(octet)),
+            Octet::Npi => named(NumericPlanIndicator::try_from(octet)),
+            Octet::Priority => named(PriorityFlag::try_from(octet)),
+            // Every octet below 0x33 decodes as v3.3, but only 0x33 is named so
+            Octet::Version => InterfaceVersion::try_from(octet)
+                .ok()
+                .filter(|version| *version as u8 == octet)
+                .map(|version| format!("{version:?}")),
+            Octet::State => named(MessageState::try_from(octet)),
+            Octet::DataCoding => data_coding_name(octet),
+        }
+    }
+
+    fn value(self, octet: u8) -> Value {
+        match self {
+            Octet::EsmClass => esm_class_value(octet),
+            _ => self.name(octet).map_or(Value::Number(octet.into()), Value::Text),
+        }
+    }
+
+    fn octet(self, value: &Value, field: &str) -> Fallible<u8> {
+        match (self, value) {
+            (_, Value::Number(_)) => Ok(value.number(field, u8::MAX.into())? as u8),
+            (Octet::EsmClass, Value::Object(_)) => esm_class_octet(value.clone()),
+            (kind, Value::Text(name))
+                if !matches!(kind, Octet::Number | Octet::RegisteredDelivery) =>
+            {
+                (0..=u8::MAX)
+                    .find(|&octet| kind.name(octet).as_deref() == Some(name))
+                    .ok_or_else(|| format!("{name:?} is not a {field}"))
+            }
+            _ => Err(format!("{field} must be a name or a number, not {value}")),
+        }
+    }
+}
+
+/// Data codings by variant name, those with a message class as e.g. `Ucs2WithClass(Flash)`
+fn data_coding_name(octet: u8) -> Option<String> {
+    let coding = DataCoding::from_byte(octet);
+    if coding.to_byte() != octet {
+        return None;
+    }
+    let name = match coding {
+        DataCoding::SmscDefault => "SmscDefault".to_string(),
+        DataCoding::Ascii => "Ascii".to_string(),
+        DataCoding::Binary => "Binary".to_string(),
+        DataCoding::Latin1 => "Latin1".to_string(),
+        DataCoding::Ucs2 => "Ucs2".to_string(),
+        DataCoding::Cyrillic => "Cyrillic".to_string(),
+        DataCoding::LatinHebrew => "LatinHebrew".to_string(),
+        DataCoding::Jis => "Jis".to_string(),
+        DataCoding::Gsm7BitWithClass(class) => format!("Gsm7BitWithClass({class:?})"),
+        DataCoding::Ucs2WithClass(class) => format!("Ucs2WithClass({class:?})"),
+        DataCoding::Custom(_) => return None,
+    };
+    Some(name)
+}
+
+const ESM_FLAGS: [(&str, u8); 3] =
+    [("udhi", 0x40), ("reply_path", 0x20), ("status_report_request", 0x10)];
+
+/// esm_class as its mode, its type and each of its flags
+fn esm_class_value(octet: u8) -> Value {
+    let esm_class = match EsmClass::from_byte(octet) {
+        Ok(esm_class) if esm_class.to_byte() == octet => esm_class,
+        _ => return Value::Number(octet.into()),
+    };
+    let message_type = match esm_class.message_type() {
+        datatypes::MessageType::Default => "Default".to_string(),
+        datatypes::MessageType::StoreAndForward(kind) => format!("{kind:?}"),
+    };
+    let mut fields = vec![
+        ("message_mode".to_string(), Value::Text(format!("{:?}", esm_class.message_mode()))),
+        ("message_type".to_string(), Value::Text(message_type)),
+    ];
+    for (flag, bit) in ESM_FLAGS {
+        fields.push((flag.to_string(), Value::Bool(octet & bit != 0)));
+    }
+    Value::Object(fields)
+}
+
+fn esm_class_octet(value: Value) -> Fallible<u8> {
+    let mut object = Object::new(value, "esm_class")?;
+    let mut name = |key: &str, names: [&str; 4]| match object.take(key) {
+        None => Ok(0),
+        Some(Value::Text(name)) => names
+            .iter()
+            .position(|candidate| *candidate == name)
+            .map(|bits| bits as u8)
+            .ok_or_else(|| format!("{name:?} is not an esm_class {key}")),
+        Some(other) => Err(format!("esm_class {key} must be a name, not {other}")),
+    };
+    let mode = name("message_mode", ["Default", "Datagram", "Forward", "StoreAndForward"])?;
+    let kind = name("message_type", ["Default", "DeliveryAck", "UserAck", "BothAck"])?;
+    let mut octet = (mode << 2) | kind;
+    for (flag, bit) in ESM_FLAGS {
+        match object.take(flag) {
+            Some(Value::Bool(true)) => octet |= bit,
+            Some(Value::Bool(false)) | None => {}
+            Some(other) => {
+                return Err(format!("esm_class {flag} must be true or false, not {other}"));
+            }
+        }
+    }
+    object.finish("esm_class")?;
+    EsmClass::from_byte(octet).map_err(|e| e.to_string())?;
+    Ok(octet)
+}
+
+/// A command_status by name, or as a number when it names nothing
+fn status_value(status: u32) -> Value {
+    match CommandStatus::try_from(status) {
+        Ok(status) => Value::Text(format!("{status:?}")),
+        Err(_) => Value::Number(status.into()),
+    }
+}
+
+fn status_from(value: &Value) -> Fallible<u32> {
+    match value {
+        Value::Text(name) => by_name::<CommandStatus>(name).map(|status| status as u32),
+        other => Ok(other.number("command_status", u32::MAX.into())? as u32),
+    }
+}
+
+fn command_id(name: &str) -> Fallible<CommandId> {
+    by_name(name)
+}
+
+/// An enum from the name its derived `Deserialize` knows it by
+fn by_name<'a, T: Deserialize<'a>>(name: &'a str) -> Fallible<T> {
+    let name: de::value::StrDeserializer<'a, de::value::Error> = name.into_deserializer();
+    T::deserialize(name).map_err(|e| e.to_string())
+}
+
+/// Octets as contiguous lower-case hex
+fn hex(octets: &[u8]) -> String {
+    octets.iter().map(|octet| format!("{octet:02x}")).collect()
+}
+
+fn unhex(text: &str, name: &str) -> Fallible<Vec<u8>> {
+    crate::inspect::parse_hex(text).map_err(|e| format!("{name}: {e}"))
+}
+
+fn hex_object(octets: &[u8]) -> Value {
+    Value::Object(vec![("hex".to_string(), Value::Text(hex(octets)))])
+}
+
+/// A string field as text, or as hex when it is not UTF-8
+fn text_value(octets: &[u8]) -> Value {
+    match std::str::from_utf8(octets) {
+        Ok(text) => Value::Text(text.to_string()),
+        Err(_) => hex_object(octets),
+    }
+}
+
+fn text_octets(value: &Value, name: &str) -> Fallible<Vec<u8>> {
+    match value {
+        Value::Text(text) => Ok(text.as_bytes().to_vec()),
+        Value::Object(_) => {
+            let mut object = Object::new(value.clone(), name)?;
+            let octets = match object.take("hex") {
+                Some(Value::Text(hex)) => unhex(&hex, name)?,
+                _ => return Err(format!("{name} must be text or {{\"hex\": ...}}")),
+            };
+            object.finish(name)?;
+            Ok(octets)
+        }
+        other => Err(format!("{name} must be text, not {other}")),
+    }
+}
+
+/// Message text in a data_coding, when the coding can carry it losslessly
+fn decode_text(octets: &[u8], coding: DataCoding) -> Option<String> {
+    match coding {
+        DataCoding::Ucs2 | DataCoding::Ucs2WithClass(_) => {
+            if !octets.len().is_multiple_of(2) {
+                return None;
+            }
+            let units: Vec<u16> = octets
+                .chunks(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16(&units).ok()
+        }
+        DataCoding::Latin1 => Some(octets.iter().map(|&octet| char::from(octet)).collect()),
+        DataCoding::SmscDefault | DataCoding::Ascii | DataCoding::Gsm7BitWithClass(_) => {
+            String::from_utf8(octets.to_vec()).ok()
+        }
+        _ => None,
+    }
+}
+
+fn encode_text(text: &str, coding: DataCoding) -> Fallible<Vec<u8>> {
+    match coding {
+        DataCoding::Ucs2 | DataCoding::Ucs2WithClass(_) => {
+            Ok(text.encode_utf16().flat_map(u16::to_be_bytes).collect())
+        }
+        DataCoding::Latin1 => text
+            .chars()
+            .map(|c| u8::try_from(c).map_err(|_| format!("{c:?} is not in Latin-1")))
+            .collect(),
+        DataCoding::SmscDefault | DataCoding::Ascii | DataCoding::Gsm7BitWithClass(_) => {
+            Ok(text.as_bytes().to_vec())
+        }
+        other => Err(format!("a message in {other} must be given as hex")),
+    }
+}
+
+/// A message body as text or hex, any user data header apart
+fn message_value(body: &[u8], coding: DataCoding, udhi: bool) -> Value {
+    let (udh, content) = match body.first() {
+        Some(&len) if udhi && usize::from(len) < body.len() => body.split_at(usize::from(len) + 1),
+        _ => (&[][..], body),
+    };
+    let content = match decode_text(content, coding) {
+        Some(text) if udh.is_empty() => return Value::Text(text),
+        Some(text) => ("text".to_string(), Value::Text(text)),
+        None => ("hex".to_string(), Value::Text(hex(content))),
+    };
+    let mut fields = Vec::new();
+    if !udh.is_empty() {
+        fields.push(("udh".to_string(), Value::Text(hex(udh))));
+    }
+    fields.push(content);
+    Value::Object(fields)
+}
+
+fn message_octets(value: Value, coding: DataCoding, name: &str) -> Fallible<Vec<u8>> {
+    let mut object = match value {
+        Value::Null => return Ok(Vec::new()),
+        Value::Text(text) => return encode_text(&text, coding),
+        value => Object::new(value, name)?,
+    };
+    let mut octets = match object.take("udh") {
+        Some(Value::Text(udh)) => unhex(&udh, "udh")?,
+        Some(other) => return Err(format!("udh must be hex, not {other}")),
+        None => Vec::new(),
+    };
+    match (object.take("text"), object.take("hex")) {
+        (Some(Value::Text(text)), None) => octets.extend(encode_text(&text, coding)?),
+        (None, Some(Value::Text(hex))) => octets.extend(unhex(&hex, name)?),
+        (None, None) => {}
+        _ => return Err(format!("{name} takes text or hex, not both")),
+    }
+    object.finish(name)?;
+    Ok(octets)
+}
+
+/// What the PDU around a TLV says about reading it
+#[derive(Default)]
+struct TlvContext {
+    data_coding: DataCoding,
+    udhi: bool,
+}
+
+/// What a TLV's value holds, by tag
+enum TlvKind {
+    Message,
+    /// A C-Octet String
+    Text,
+    Integer(usize),
+    Named(Octet),
+    Status,
+    Octets,
+}
+
+fn tlv_kind(tag: u16) -> TlvKind {
+    use tags::*;
+
+    match tag {
+        MESSAGE_PAYLOAD => TlvKind::Message,
+        RECEIPTED_MESSAGE_ID | ADDITIONAL_STATUS_INFO_TEXT | SOURCE_NETWORK_ID
+        | DEST_NETWORK_ID | BROADCAST_END_TIME => TlvKind::Text,
+        SC_INTERFACE_VERSION => TlvKind::Named(Octet::Version),
+        MESSAGE_STATE => TlvKind::Named(Octet::State),
+        BROADCAST_ERROR_STATUS => TlvKind::Status,
+        USER_MESSAGE_REFERENCE | SOURCE_PORT | DESTINATION_PORT | SAR_MSG_REF_NUM | SMS_SIGNAL
+        | BROADCAST_REP_NUM => TlvKind::Integer(2),
+        PAYLOAD_TYPE | PRIVACY_INDICATOR | CALLBACK_NUM_PRES_IND | SOURCE_NETWORK_TYPE
+        | DEST_NETWORK_TYPE | MORE_MESSAGES_TO_SEND | DISPLAY_TIME | MS_VALIDITY
+        | MS_MSG_WAIT_FACILITIES | NUMBER_OF_MESSAGES | ALERT_ON_MSG_DELIVERY
+        | LANGUAGE_INDICATOR | ITS_REPLY_TYPE | SAR_TOTAL_SEGMENTS | SAR_SEGMENT_SEQNUM
+        | USSD_SERVICE_OP | CONGESTION_STATE | DEST_ADDR_NP_RESOLUTION
+        | BROADCAST_CHANNEL_INDICATOR | BROADCAST_MESSAGE_CLASS | DELIVERY_FAILURE_REASON
+        | DPF_RESULT | SET_DPF | MS_AVAILABILITY_STATUS => TlvKind::Integer(1),
+        _ => TlvKind::Octets,
+    }
+}
+
+fn tag_value(tag: u16) -> Value {
+    match tags::name(tag) {
+        Some(name) => Value::Text(name.to_string()),
+        None => Value::Number(tag.into()),
+    }
+}
+
+fn tag_from(value: &Value) -> Fallible<u16> {
+    match value {
+        Value::Text(name) => (0..=u16::MAX)
+            .find(|&tag| tags::name(tag) == Some(name))
+            .ok_or_else(|| format!("{name:?} is not a known TLV; give its tag as a number")),
+        other => Ok(other.number("tag", u16::MAX.into())? as u16),
+    }
+}
+
+/// A TLV as its tag and typed value
+fn tlv_value(tag: u16, value: &[u8], context: &TlvContext) -> Value {
+    let typed = match (tlv_kind(tag), value) {
+        (_, []) => Value::Null,
+        (TlvKind::Message, _) => message_value(value, context.data_coding, context.udhi),
+        (TlvKind::Text, [text @ .., 0]) if !text.contains(&0) => match text_value(text) {
+            Value::Text(text) => Value::Text(text),
+            _ => hex_object(value),
+        },
+        (TlvKind::Named(kind), &[octet]) => kind.value(octet),
+        (TlvKind::Status, &[a, b, c, d]) => status_value(u32::from_be_bytes([a, b, c, d])),
+        (TlvKind::Integer(width), _) if value.len() == width => {
+            Value::Number(value.iter().fold(0, |number, &octet| number << 8 | u64::from(octet)))
+        }
+        _ => hex_object(value),
+    };
+    Value::Object(vec![
+        ("tag".to_string(), tag_value(tag)),
+        ("value".to_string(), typed),
+    ])
+}
+
+fn tlv_octets(tlv: Value, context: &TlvContext) -> Fallible<(u16, Vec<u8>)> {
+    let mut object = Object::new(tlv, "a TLV")?;
+    let tag = match object.take("tag") {
+        Some(tag) => tag_from(&tag)?,
+        None => return Err("a TLV needs a tag".to_string()),
+    };
+    let name = tags::name(tag).map_or_else(|| format!("TLV {tag:#06x}"), str::to_string);
+    let value = object.take("value").unwrap_or(Value::Null);
+    object.finish(&name)?;
+
+    let octets = match (tlv_kind(tag), value) {
+        (_, Value::Null) => Vec::new(),
+        (TlvKind::Message, value) => message_octets(value, context.data_coding, &name)?,
+        (_, value @ Value::Object(_)) => text_octets(&value, &name)?,
+        (TlvKind::Text, Value::Text(text)) => [text.as_bytes(), &[0]].concat(),
+        (TlvKind::Named(kind), value) => vec![kind.octet(&value, &name)?],
+        (TlvKind::Status, value) => status_from(&value)?.to_be_bytes().to_vec(),
+        (TlvKind::Integer(width), value) => {
+            let max = (1u64 << (8 * width)) - 1;
+            let number = value.number(&name, max)?;
+            number.to_be_bytes()[8 - width..].to_vec()
+        }
+        (_, value) => return Err(format!("{name} must be {{\"hex\": ...}}, not {value}")),
+    };
+    Ok((tag, octets))
+}
+
+fn registry() -> &'static PduRegistry {
+    static REGISTRY: OnceLock<PduRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(|| PduRegistry::for_version(InterfaceVersion::SmppV50))
+}
+
+/// The frame a canonical value describes
+fn frame(value: Value) -> Fallible<Frame> {
+    let (pdu, raw) = layout::write(value)?;
+    let mut cursor = Cursor::new(pdu.as_slice());
+    let header = PduHeader::decode(&mut cursor).map_err(|e| e.to_string())?;
+    let command = header.command_id.name();
+    match registry().decode_frame(header.clone(), &mut cursor) {
+        Ok(decoded) => Ok(decoded.frame),
+        Err(_) if raw => Ok(Frame::Unknown {
+            header,
+            body: Bytes::copy_from_slice(&pdu[PduHeader::SIZE..]),
+        }),
+        Err(e) => Err(format!("{command}: {e}")),
+    }
+}
+
+/// A PDU's wire octets, failing where [`Encodable::to_bytes`] would panic
+fn encoded(pdu: &impl Encodable) -> Result<Vec<u8>, CodecError> {
+    let mut buf = BytesMut::new();
+    pdu.encode(&mut buf)?;
+    let length = buf.len() as u32;
+    buf[..4].copy_from_slice(&length.to_be_bytes());
+    Ok(buf.to_vec())
+}
+
+impl Serialize for Frame {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let pdu = match self {
+            Frame::Unknown { header, body } => {
+                let mut buf = BytesMut::new();
+                header.encode(&mut buf).map_err(serde::ser::Error::custom)?;
+                buf.extend_from_slice(body);
+                let length = buf.len() as u32;
+                buf[..4].copy_from_slice(&length.to_be_bytes());
+                buf.freeze()
+            }
+            frame => frame.to_bytes().unwrap_or_default(),
+        };
+        layout::read(&pdu).map_err(serde::ser::Error::custom)?.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Frame {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        frame(Value::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+/// Serialize a PDU type through its wire encoding, and deserialize it as a [`Frame`]
+macro_rules! canonical_pdu {
+    ($pdu:ident) => {
+        canonical_pdu!(@impl $pdu, pdu => pdu);
+    };
+    ($pdu:ident, boxed) => {
+        canonical_pdu!(@impl $pdu, pdu => *pdu);
+    };
+    ($pdu:ident as $variant:ident) => {
+        canonical_pdu!(@impl $pdu as $variant, pdu => pdu);
+    };
+    (@impl $pdu:ident, $bound:ident => $contents:expr) => {
+        canonical_pdu!(@impl $pdu as $pdu, $bound => $contents);
+    };
+    (@impl $pdu:ident as $variant:ident, $bound:ident => $contents:expr) => {
+        impl Serialize for datatypes::$pdu {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let pdu = encoded(self).map_err(serde::ser::Error::custom)?;
+                layout::read(&pdu).map_err(serde::ser::Error::custom)?.serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for datatypes::$pdu {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                match Frame::deserialize(deserializer)? {
+                    Frame::$variant($bound) => Ok($contents),
+                    other => Err(de::Error::custom(format!(
+                        "expected {}, not {}",
+                        CommandId::$variant.name(),
+                        other.command_id().name()
+                    ))),
+                }
+            }
+        }
+    };
+}
+
+canonical_pdu!(EnquireLink);
+canonical_pdu!(EnquireLinkResponse as EnquireLinkResp);
+canonical_pdu!(Unbind);
+canonical_pdu!(UnbindResponse as UnbindResp);
+canonical_pdu!(BindReceiver);
+canonical_pdu!(BindReceiverResponse as BindReceiverResp);
+canonical_pdu!(BindTransceiver);
+canonical_pdu!(BindTransceiverResponse as BindTransceiverResp);
+canonical_pdu!(BindTransmitter);
+canonical_pdu!(BindTransmitterResponse as BindTransmitterResp);
+canonical_pdu!(SubmitSm, boxed);
+canonical_pdu!(SubmitSmResponse as SubmitSmResp);
+canonical_pdu!(SubmitMulti, boxed);
+canonical_pdu!(SubmitMultiResponse as SubmitMultiResp);
+canonical_pdu!(QuerySm);
+canonical_pdu!(QuerySmResponse as QuerySmResp);
+canonical_pdu!(ReplaceSm, boxed);
+canonical_pdu!(ReplaceSmResponse as ReplaceSmResp);
+canonical_pdu!(CancelSm);
+canonical_pdu!(CancelSmResponse as CancelSmResp);
+canonical_pdu!(DataSm, boxed);
+canonical_pdu!(DataSmResponse as DataSmResp);
+canonical_pdu!(DeliverSm, boxed);
+canonical_pdu!(DeliverSmResponse as DeliverSmResp);
+canonical_pdu!(AlertNotification);
+canonical_pdu!(BroadcastSm, boxed);
+canonical_pdu!(BroadcastSmResponse as BroadcastSmResp);
+canonical_pdu!(QueryBroadcastSm);
+canonical_pdu!(QueryBroadcastSmResponse as QueryBroadcastSmResp);
+canonical_pdu!(CancelBroadcastSm);
+canonical_pdu!(CancelBroadcastSmResponse as CancelBroadcastSmResp);
+canonical_pdu!(GenericNack);
+canonical_pdu!(Outbind);
+
+impl Serialize for Tlv {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        tlv_value(self.tag, &self.value, &TlvContext::default()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Tlv {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let (tag, value) = tlv_octets(value, &TlvContext::default()).map_err(de::Error::custom)?;
+        Tlv::new(tag, value).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for DataCoding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Octet::DataCoding.value(self.to_byte()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DataCoding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let octet = Octet::DataCoding.octet(&value, "data_coding").map_err(de::Error::custom)?;
+        Ok(DataCoding::from_byte(octet))
+    }
+}
+
+impl Serialize for EsmClass {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Octet::EsmClass.value(self.to_byte()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for EsmClass {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let octet = Octet::EsmClass.octet(&value, "esm_class").map_err(de::Error::custom)?;
+        EsmClass::from_byte(octet).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for SmppDateTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        text_value(self.as_bytes()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SmppDateTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SmppDateTime::new(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+impl<const N: usize> Serialize for FixedString<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        text_value(self.as_str_bytes()).serialize(serializer)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for FixedString<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let octets = text_octets(&value, "string").map_err(de::Error::custom)?;
+        FixedString::new(&octets).map_err(de::Error::custom)
+    }
+}
+
+impl<const N: usize> Serialize for PhoneNumber<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        text_value(self.as_bytes()).serialize(serializer)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for PhoneNumber<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        PhoneNumber::from_parsed_string(String::deserialize(deserializer)?)
+            .map_err(de::Error::custom)
+    }
+}
+
+impl<const N: usize> Serialize for AlphanumericAddress<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        text_value(self.as_bytes()).serialize(serializer)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for AlphanumericAddress<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        AlphanumericAddress::from_parsed_string(String::deserialize(deserializer)?)
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for ServiceType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ServiceType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ServiceType::from_parsed_string(String::deserialize(deserializer)?)
+            .map_err(de::Error::custom)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datatypes::{
+        DeliverSm, MessageClass, ShortMessage, SourceAddr, StoreAndForwardType, SubmitSm,
+    };
+    use serde_json::json;
+
+    /// Serialize and deserialize a frame, checking that it encodes as before
+    fn round_trip(frame: &Frame) -> serde_json::Value {
+        let json = serde_json::to_value(frame).unwrap();
+        let decoded: Frame =
+            serde_json::from_value(json.clone()).unwrap_or_else(|e| panic!("{e}: {json}"));
+        assert_eq!(decoded.to_bytes(), frame.to_bytes(), "{json}");
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), json);
+        json
+    }
+
+    fn parse(json: serde_json::Value) -> Frame {
+        serde_json::from_value(json.clone()).unwrap_or_else(|e| panic!("{e}: {json}"))
+    }
+
+    fn error(json: serde_json::Value) -> String {
+        serde_json::from_value::<Frame>(json).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_every_command_round_trips_from_its_defaults() {
+        let commands = [
+            "generic_nack", "bind_receiver", "bind_receiver_resp", "bind_transmitter",
+            "bind_transmitter_resp", "query_sm", "query_sm_resp", "submit_sm", "submit_sm_resp",
+            "deliver_sm", "deliver_sm_resp", "unbind", "unbind_resp", "replace_sm",
+            "replace_sm_resp", "cancel_sm", "cancel_sm_resp", "bind_transceiver",
+            "bind_transceiver_resp", "outbind", "enquire_link", "enquire_link_resp",
+            "submit_multi", "submit_multi_resp", "alert_notification", "data_sm", "data_sm_resp",
+            "broadcast_sm", "broadcast_sm_resp", "query_broadcast_sm", "query_broadcast_sm_resp",
+            "cancel_broadcast_sm", "cancel_broadcast_sm_resp",
+        ];
+        for command in commands {
+            let mut json = json!({ "command_id": command, "sequence_number": 5 });
+            if command.ends_with("query_sm_resp") || command == "query_broadcast_sm_resp" {
+                json["message_state"] = json!("Delivered");
+            }
+            let frame = parse(json);
+            assert_eq!(frame.command_id().name(), command);
+            let json = round_trip(&frame);
+            assert_eq!(json["command_id"], command);
+            assert_eq!(json["command_status"], "Ok");
+            assert_eq!(json["sequence_number"], 5);
+        }
+    }
+
+    #[test]
+    fn test_submit_sm_maps_to_named_fields() {
+        let mut submit = SubmitSm::builder()
+            .sequence_number(7)
+            .source_addr("447700900123")
+            .destination_addr("447700900456")
+            .short_message("placeholder")
+            .user_message_reference(Tlv::new(tags::USER_MESSAGE_REFERENCE, vec![0, 42]).unwrap())
+            .build()
+            .unwrap();
+        submit.source_addr_ton = TypeOfNumber::International;
+        submit.source_addr_npi = NumericPlanIndicator::Isdn;
+        submit.data_coding = DataCoding::Ucs2;
+        let text: Vec<u8> = "Grüße".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        submit.short_message = ShortMessage::new(&text).unwrap();
+        submit.sm_length = text.len() as u8;
+
+        let json = serde_json::to_value(&submit).unwrap();
+        assert_eq!(json["command_id"], "submit_sm");
+        assert_eq!(json["command_status"], "Ok");
+        assert_eq!(json["source_addr_ton"], "International");
+        assert_eq!(json["source_addr_npi"], "Isdn");
+        assert_eq!(json["source_addr"], "447700900123");
+        assert_eq!(json["priority_flag"], "Level0");
+        assert_eq!(json["data_coding"], "Ucs2");
+        assert_eq!(json["short_message"], "Grüße");
+        assert_eq!(
+            json["esm_class"],
+            json!({
+                "message_mode": "Default",
+                "message_type": "Default",
+                "udhi": false,
+                "reply_path": false,
+                "status_report_request": false,
+            })
+        );
+        assert_eq!(json["tlvs"], json!([{ "tag": "user_message_reference", "value": 42 }]));
+        assert!(json.get("sm_length").is_none());
+
+        let decoded: SubmitSm = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.to_bytes(), submit.to_bytes());
+        round_trip(&Frame::SubmitSm(Box::new(submit)));
+    }
+
+    #[test]
+    fn test_messages_are_text_or_hex_by_data_coding() {
+        let deliver = |data_coding, esm_class, body: &[u8]| {
+            let mut deliver = DeliverSm::builder()
+                .sequence_number(3)
+                .source_addr("1234")
+                .destination_addr("5678")
+                .esm_class(esm_class)
+                .build()
+                .unwrap();
+            deliver.data_coding = data_coding;
+            deliver.short_message = ShortMessage::new(body).unwrap();
+            deliver.sm_length = body.len() as u8;
+            round_trip(&Frame::DeliverSm(Box::new(deliver)))["short_message"].clone()
+        };
+        let plain = EsmClass::default();
+        let udhi = EsmClass::default().with_udhi();
+
+        assert_eq!(deliver(DataCoding::Latin1, plain, &[0x47, 0xE9]), "Gé");
+        assert_eq!(deliver(DataCoding::SmscDefault, plain, &[0xFF]), json!({ "hex": "ff" }));
+        assert_eq!(deliver(DataCoding::Ucs2, plain, &[0x00]), json!({ "hex": "00" }));
+        assert_eq!(
+            deliver(DataCoding::Binary, udhi, &[0x05, 0x00, 0x03, 0x2A, 0x02, 0x01, 0xDE, 0xAD]),
+            json!({ "udh": "0500032a0201", "hex": "dead" })
+        );
+        assert_eq!(
+            deliver(DataCoding::SmscDefault, udhi, b"\x05\x00\x03\x2a\x02\x02Hi"),
+            json!({ "udh": "0500032a0202", "text": "Hi" })
+        );
+    }
+
+    #[test]
+    fn test_tlvs_are_typed_by_tag() {
+        let tlvs = json!([
+            { "tag": "message_payload", "value": "Привет" },
+            { "tag": "source_port", "value": 2948 },
+            { "tag": "alert_on_msg_delivery", "value": null },
+            { "tag": "callback_num", "value": { "hex": "010131323334" } },
+            { "tag": 5120, "value": { "hex": "ff00" } },
+        ]);
+        let frame = parse(json!({
+            "command_id": "data_sm",
+            "sequence_number": 9,
+            "source_addr": "1234",
+            "destination_addr": "5678",
+            "data_coding": "Ucs2WithClass(Flash)",
+            "tlvs": tlvs,
+        }));
+        let Frame::DataSm(data_sm) = &frame else {
+            panic!("{frame:?}");
+        };
+        let payload: Vec<u8> = "Привет".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(data_sm.message_payload().unwrap().as_ref(), &payload[..]);
+        assert_eq!(data_sm.data_coding, DataCoding::Ucs2WithClass(MessageClass::Flash));
+
+        let json = round_trip(&frame);
+        assert_eq!(json["tlvs"], tlvs);
+
+        let bind_response = parse(json!({
+            "command_id": "bind_transceiver_resp",
+            "sequence_number": 1,
+            "system_id": "SMSC",
+            "tlvs": [{ "tag": "sc_interface_version", "value": "SmppV50" }],
+        }));
+        let json = round_trip(&bind_response);
+        assert_eq!(json["tlvs"], json!([{ "tag": "sc_interface_version", "value": "SmppV50" }]));
+    }
+
+    #[test]
+    fn test_submit_multi_lists_destinations_and_failures() {
+        let submit = json!([
+            {
+                "dest_addr_ton": "International",
+                "dest_addr_npi": "Isdn",
+                "destination_addr": "4477",
+            },
+            { "dl_name": "friends" },
+        ]);
+        let frame = parse(json!({
+            "command_id": "submit_multi",
+            "sequence_number": 2,
+            "source_addr": "1234",
+            "dest_address": submit,
+            "short_message": "Hi all",
+        }));
+        let json = round_trip(&frame);
+        assert_eq!(json["dest_address"], submit);
+        assert!(json.get("number_of_dests").is_none());
+
+        let failures = json!([{
+            "dest_addr_ton": "International",
+            "dest_addr_npi": "Isdn",
+            "destination_addr": "4477",
+            "error_status_code": "InvalidDestinationAddress",
+        }]);
+        let response = parse(json!({
+            "command_id": "submit_multi_resp",
+            "sequence_number": 2,
+            "message_id": "M1",
+            "unsuccess_sme": failures,
+        }));
+        assert_eq!(round_trip(&response)["unsuccess_sme"], failures);
+    }
+
+    #[test]
+    fn test_fixtures_are_checked_as_they_are_read() {
+        let minimal = parse(json!({ "command_id": "submit_sm", "sequence_number": 1 }));
+        assert!(matches!(minimal, Frame::SubmitSm(_)));
+
+        assert!(error(json!({ "sequence_number": 1 })).contains("command_id is missing"));
+        assert!(
+            error(json!({ "command_id": "submit_sm", "sequence_number": 1, "colour": "red" }))
+                .contains("unknown field `colour` in submit_sm")
+        );
+        assert!(
+            error(json!({
+                "command_id": "submit_sm",
+                "sequence_number": 1,
+                "source_addr_ton": "Galactic",
+            }))
+            .contains(r#""Galactic" is not a source_addr_ton"#)
+        );
+        assert!(
+            error(json!({
+                "command_id": "bind_transmitter",
+                "sequence_number": 1,
+                "system_id": "a_system_id_too_long",
+            }))
+            .contains("system_id is longer than 15 octets")
+        );
+        // A value the decoder would refuse on the wire is refused here too
+        assert!(
+            error(json!({ "command_id": "enquire_link", "sequence_number": 0 }))
+                .contains("sequence")
+        );
+
+        let deliver = json!({ "command_id": "deliver_sm", "sequence_number": 1 });
+        let wrong = serde_json::from_value::<SubmitSm>(deliver).unwrap_err().to_string();
+        assert!(wrong.contains("expected submit_sm, not deliver_sm"), "{wrong}");
+    }
+
+    #[test]
+    fn test_unknown_body_is_kept_as_hex() {
+        let header = PduHeader {
+            command_length: 19,
+            command_id: CommandId::SubmitSm,
+            command_status: CommandStatus::Ok,
+            sequence_number: 4,
+        };
+        let unknown = Frame::Unknown {
+            header: header.clone(),
+            body: Bytes::from_static(&[1, 2, 3]),
+        };
+        let json = serde_json::to_value(&unknown).unwrap();
+        assert_eq!(json["body"], "010203");
+
+        let Frame::Unknown { header: decoded, body } = parse(json) else {
+            panic!("expected an unknown frame");
+        };
+        assert_eq!((decoded, body.as_ref()), (header, &[1, 2, 3][..]));
+    }
+
+    #[test]
+    fn test_datatypes_serialize_on_their_own() {
+        fn both<T>(value: T, json: serde_json::Value)
+        where
+            T: Serialize + for<'de> Deserialize<'de> + PartialEq + fmt::Debug,
+        {
+            assert_eq!(serde_json::to_value(&value).unwrap(), json);
+            assert_eq!(serde_json::from_value::<T>(json).unwrap(), value);
+        }
+
+        both(DataCoding::Ucs2, json!("Ucs2"));
+        both(DataCoding::Gsm7BitWithClass(MessageClass::Flash), json!("Gsm7BitWithClass(Flash)"));
+        both(DataCoding::Custom(0x42), json!(0x42));
+        both(TypeOfNumber::Alphanumeric, json!("Alphanumeric"));
+        both(CommandStatus::ThrottlingError, json!("ThrottlingError"));
+        both(CommandId::SubmitSmResp, json!("submit_sm_resp"));
+        both(InterfaceVersion::SmppV34, json!("SmppV34"));
+        both(MessageState::Delivered, json!("Delivered"));
+        both(
+            EsmClass::store_and_forward(StoreAndForwardType::DeliveryAck).with_reply_path(),
+            json!({
+                "message_mode": "StoreAndForward",
+                "message_type": "DeliveryAck",
+                "udhi": false,
+                "reply_path": true,
+                "status_report_request": false,
+            }),
+        );
+        both(
+            Tlv::new(tags::MESSAGE_STATE, vec![2]).unwrap(),
+            json!({ "tag": "message_state", "value": "Delivered" }),
+        );
+        both(
+            Tlv::new(tags::RECEIPTED_MESSAGE_ID, &b"abc\0"[..]).unwrap(),
+            json!({ "tag": "receipted_message_id", "value": "abc" }),
+        );
+        both(SmppDateTime::new("240101120000000+").unwrap(), json!("240101120000000+"));
+        both(SourceAddr::new("1234", TypeOfNumber::Unknown).unwrap(), json!("1234"));
+        both(ServiceType::cmt(), json!("CMT"));
+        both(crate::datatypes::SystemId::from("esme"), json!("esme"));
+    }
+}
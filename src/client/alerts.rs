@@ -0,0 +1,103 @@
+// ABOUTME: Typed alert_notification events and the handler trait for acting on them
+// ABOUTME: Reports subscribers becoming reachable with ms_availability_status decoded
+
+use crate::datatypes::{AlertNotification, MsAvailabilityStatus, NumericPlanIndicator, TypeOfNumber};
+
+/// An alert_notification from the SMSC about a mobile subscriber
+///
+/// SMSCs send these to ESMEs that submitted with `set_dpf` when a
+/// subscriber that was unreachable can receive messages again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriberAlert {
+    /// Sequence number of the alert_notification
+    pub sequence_number: u32,
+    /// Address of the subscriber
+    pub subscriber: String,
+    /// Type of number of the subscriber address
+    pub subscriber_ton: TypeOfNumber,
+    /// Numbering plan of the subscriber address
+    pub subscriber_npi: NumericPlanIndicator,
+    /// Address of the ESME the alert is for
+    pub esme_addr: String,
+    /// The subscriber's status, or `None` if the SMSC sent an unknown value
+    pub availability: Option<MsAvailabilityStatus>,
+}
+
+impl SubscriberAlert {
+    /// Whether messages to the subscriber can be delivered now
+    pub fn is_available(&self) -> bool {
+        self.availability == Some(MsAvailabilityStatus::Available)
+    }
+}
+
+impl From<&AlertNotification> for SubscriberAlert {
+    fn from(alert: &AlertNotification) -> Self {
+        Self {
+            sequence_number: alert.sequence_number,
+            subscriber: alert.subscriber_number().unwrap_or_default().to_string(),
+            subscriber_ton: alert.source_addr_ton,
+            subscriber_npi: alert.source_addr_npi,
+            esme_addr: alert.esme_address_string().unwrap_or_default().to_string(),
+            availability: alert.availability(),
+        }
+    }
+}
+
+/// Receives alert_notifications arriving on a session
+///
+/// Install one with
+/// [`DefaultClient::set_alert_handler`](crate::client::DefaultClient::set_alert_handler).
+/// alert_notification has no response, so the handler only observes; it is
+/// called on the task driving the client and should not block. Closures
+/// taking a `&SubscriberAlert` implement this trait.
+///
+/// # Example
+///
+/// ```rust
+/// use smpp::client::{AlertHandler, SubscriberAlert};
+/// use std::sync::Mutex;
+///
+/// struct Reachable(Mutex<Vec<String>>);
+///
+/// impl AlertHandler for Reachable {
+///     fn on_alert(&self, alert: &SubscriberAlert) {
+///         if alert.is_available() {
+///             self.0.lock().unwrap().push(alert.subscriber.clone());
+///         }
+///     }
+/// }
+/// ```
+pub trait AlertHandler: Send + Sync {
+    /// Called for each alert_notification received
+    fn on_alert(&self, alert: &SubscriberAlert);
+}
+
+impl<F> AlertHandler for F
+where
+    F: Fn(&SubscriberAlert) + Send + Sync,
+{
+    fn on_alert(&self, alert: &SubscriberAlert) {
+        self(alert)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscriber_alert_decodes_availability() {
+        let pdu =
+            AlertNotification::international_subscriber_available(3, "447700900123", "447700900999")
+                .unwrap();
+        let alert = SubscriberAlert::from(&pdu);
+        assert_eq!(alert.subscriber, "447700900123");
+        assert_eq!(alert.esme_addr, "447700900999");
+        assert_eq!(alert.subscriber_ton, TypeOfNumber::International);
+        assert!(alert.is_available());
+
+        let alert = SubscriberAlert::from(&pdu.with_availability(MsAvailabilityStatus::Denied));
+        assert_eq!(alert.availability, Some(MsAvailabilityStatus::Denied));
+        assert!(!alert.is_available());
+    }
+}
@@ -3,8 +3,14 @@
 
 use crate::client::default::DefaultClient;
 use crate::client::error::SmppResult;
+use crate::client::retry::RetryPolicy;
+use crate::client::secret::Secret;
+use crate::client::sequence::SequenceGenerator;
+use crate::client::timeouts::TimeoutConfig;
 use crate::client::traits::{SmppClient, SmppConnection, SmppTransmitter};
 use crate::client::types::BindCredentials;
+use crate::codec::DecodePolicy;
+use std::sync::Arc;
 use tokio::net::ToSocketAddrs;
 
 /// Factory for creating different types of SMPP clients
@@ -58,7 +64,7 @@ impl ClientBuilder {
     pub async fn quick_transmitter<T: ToSocketAddrs + Send>(
         addr: T,
         system_id: impl Into<String>,
-        password: impl Into<String>,
+        password: impl Into<Secret>,
     ) -> SmppResult<impl SmppTransmitter> {
         let credentials = BindCredentials::transmitter(system_id, password);
         Self::transmitter(addr, credentials).await
@@ -71,7 +77,7 @@ impl ClientBuilder {
     pub async fn quick_client<T: ToSocketAddrs + Send>(
         addr: T,
         system_id: impl Into<String>,
-        password: impl Into<String>,
+        password: impl Into<Secret>,
     ) -> SmppResult<impl SmppClient> {
         let credentials = BindCredentials::transmitter(system_id, password);
         Self::client(addr, credentials).await
@@ -84,7 +90,7 @@ impl ClientBuilder {
     pub async fn quick_transmitter_v50<T: ToSocketAddrs + Send>(
         addr: T,
         system_id: impl Into<String>,
-        password: impl Into<String>,
+        password: impl Into<Secret>,
     ) -> SmppResult<impl SmppTransmitter> {
         let credentials = BindCredentials::transmitter_v50(system_id, password);
         Self::transmitter(addr, credentials).await
@@ -97,7 +103,7 @@ impl ClientBuilder {
     pub async fn quick_client_v50<T: ToSocketAddrs + Send>(
         addr: T,
         system_id: impl Into<String>,
-        password: impl Into<String>,
+        password: impl Into<Secret>,
     ) -> SmppResult<impl SmppClient> {
         let credentials = BindCredentials::transmitter_v50(system_id, password);
         Self::client(addr, credentials).await
@@ -114,7 +120,14 @@ pub struct ClientOptions {
     pub enable_v50_features: bool,
     /// Whether to auto-negotiate version
     pub auto_negotiate_version: bool,
-    // Future: connection timeout, retry settings, etc.
+    /// How strictly PDUs received from the SMSC are decoded
+    pub decode_policy: DecodePolicy,
+    /// Connect, bind, response and inactivity timers
+    pub timeouts: TimeoutConfig,
+    /// Sequence number source to share with other clients (default: one per client)
+    pub sequence_generator: Option<Arc<dyn SequenceGenerator>>,
+    /// When failed message submissions are resent (default: never)
+    pub retry_policy: RetryPolicy,
 }
 
 impl Default for ClientOptions {
@@ -122,6 +135,10 @@ impl Default for ClientOptions {
         Self {
             enable_v50_features: false,
             auto_negotiate_version: true,
+            decode_policy: DecodePolicy::Strict,
+            timeouts: TimeoutConfig::default(),
+            sequence_generator: None,
+            retry_policy: RetryPolicy::disabled(),
         }
     }
 }
@@ -144,6 +161,32 @@ impl ClientOptions {
         self
     }
 
+    /// Tolerate recoverable protocol violations from the SMSC
+    ///
+    /// See [`DecodePolicy::Lenient`] for what is accepted.
+    pub fn with_lenient_decoding(mut self) -> Self {
+        self.decode_policy = DecodePolicy::Lenient;
+        self
+    }
+
+    /// Set the session timers
+    pub fn with_timeouts(mut self, timeouts: TimeoutConfig) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Allocate sequence numbers from a shared generator
+    pub fn with_sequence_generator(mut self, sequence: Arc<dyn SequenceGenerator>) -> Self {
+        self.sequence_generator = Some(sequence);
+        self
+    }
+
+    /// Resend submissions that fail with transient errors
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
     /// Build a transmitter client with these options
     ///
     /// Applies version selection and other advanced configuration.
@@ -156,8 +199,8 @@ impl ClientOptions {
         if self.enable_v50_features && !credentials.is_v50() {
             credentials = credentials.with_version(crate::datatypes::InterfaceVersion::SmppV50);
         }
-        
-        ClientBuilder::transmitter(addr, credentials).await
+
+        self.connect_and_bind(addr, &credentials).await
     }
 
     /// Build a client with these options
@@ -170,7 +213,22 @@ impl ClientOptions {
         if self.enable_v50_features && !credentials.is_v50() {
             credentials = credentials.with_version(crate::datatypes::InterfaceVersion::SmppV50);
         }
-        
-        ClientBuilder::client(addr, credentials).await
+
+        self.connect_and_bind(addr, &credentials).await
+    }
+
+    async fn connect_and_bind<T: ToSocketAddrs + Send>(
+        &self,
+        addr: T,
+        credentials: &BindCredentials,
+    ) -> SmppResult<DefaultClient> {
+        let mut client = DefaultClient::connect_with_timeouts(addr, self.timeouts.clone()).await?;
+        client.set_decode_policy(self.decode_policy);
+        if let Some(sequence) = &self.sequence_generator {
+            client.set_sequence_generator(Arc::clone(sequence));
+        }
+        client.set_retry_policy(self.retry_policy.clone());
+        client.bind(credentials).await?;
+        Ok(client)
     }
 }
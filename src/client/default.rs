@@ -2,13 +2,30 @@
 // ABOUTME: Based on the robust example client with proper error handling and response validation
 
 use crate::Frame;
-use crate::client::error::{SmppError, SmppResult};
+use crate::client::alerts::{AlertHandler, SubscriberAlert};
+use crate::client::error::{SmppError, SmppResult, TimeoutKind};
+use crate::client::inbound::{InboundConfig, InboundSession};
 use crate::client::keepalive::{KeepAliveConfig, KeepAliveManager, KeepAliveStatus};
+use crate::client::retry::RetryPolicy;
+use crate::client::sequence::{SequenceGenerator, WrappingSequenceGenerator};
+use crate::client::timeouts::TimeoutConfig;
 use crate::client::traits::{SmppClient, SmppConnection, SmppTransmitter};
 use crate::client::types::{BindCredentials, BindType, SmsMessage};
+use crate::codec::PduRegistry;
 use crate::connection::Connection;
 use crate::datatypes::*;
+use crate::metrics::{BindState, MetricEvent, SessionMetrics};
+use crate::recording::Recorder;
+use crate::redaction::RedactionPolicy;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::net::{TcpStream, ToSocketAddrs};
+use tracing::field::{Empty, debug as field_debug};
+use tracing::{Instrument, Span, debug, debug_span, info, info_span, warn};
+
+/// How many timed-out requests to remember when discarding late responses
+const MAX_ABANDONED_REQUESTS: usize = 64;
 
 /// Default SMPP client implementation
 ///
@@ -18,25 +35,29 @@ use tokio::net::{TcpStream, ToSocketAddrs};
 pub struct DefaultClient {
     /// The TCP connection with SMPP protocol frame handling
     connection: Connection,
-    /// Sequence number for PDU correlation
-    sequence_number: u32,
+    /// Source of sequence numbers for PDU correlation, possibly shared with other binds
+    sequence: Arc<dyn SequenceGenerator>,
     /// Current connection state
     connected: bool,
     /// Keep-alive manager for automatic enquire_link handling
     keep_alive: Option<KeepAliveManager>,
+    /// Session timers bounding connect, bind and response waits
+    timeouts: TimeoutConfig,
+    /// Sequence numbers of requests that timed out, whose responses are dropped
+    abandoned: VecDeque<u32>,
+    /// When the last request other than enquire_link completed
+    last_activity: Instant,
+    /// When failed message submissions are resent
+    retry: RetryPolicy,
+    /// Receives alert_notifications arriving while waiting for responses
+    alerts: Option<Arc<dyn AlertHandler>>,
+    /// Span of the session, the parent of each request's span
+    span: Span,
 }
 
 impl SmppConnection for DefaultClient {
     async fn connect<T: ToSocketAddrs + Send>(addr: T) -> SmppResult<Self> {
-        let socket = TcpStream::connect(addr).await?;
-        let connection = Connection::new(socket);
-
-        Ok(DefaultClient {
-            connection,
-            sequence_number: 0,
-            connected: true,
-            keep_alive: None,
-        })
+        Self::connect_with_timeouts(addr, TimeoutConfig::default()).await
     }
 
     async fn disconnect(&mut self) -> SmppResult<()> {
@@ -48,6 +69,9 @@ impl SmppConnection for DefaultClient {
 
         // Note: Connection doesn't expose close method, so we just mark as disconnected
         // The underlying TcpStream will be dropped when Connection is dropped
+        if self.connected {
+            self.record(MetricEvent::BindState(BindState::Closed));
+        }
         self.connected = false;
         Ok(())
     }
@@ -63,17 +87,52 @@ impl SmppClient for DefaultClient {
             return Err(SmppError::InvalidState("Not connected".to_string()));
         }
 
-        self.sequence_number += 1;
+        // bind_transceiver only exists from v3.4 onwards
+        if credentials.bind_type == BindType::Transceiver
+            && !credentials
+                .interface_version
+                .supports_command(CommandId::BindTransceiver)
+        {
+            return Err(SmppError::InvalidData(format!(
+                "bind_transceiver is not supported by {:?}",
+                credentials.interface_version
+            )));
+        }
+
+        // Credentials are checked here since they are kept as plain strings until bound
+        let system_id: SystemId = credentials
+            .system_id
+            .parse()
+            .map_err(|e| SmppError::InvalidData(format!("Invalid system_id: {e}")))?;
+        let password: Password = credentials
+            .password
+            .expose()
+            .parse()
+            .map_err(|e| SmppError::InvalidData(format!("Invalid password: {e}")))?;
+        let system_type: SystemType = credentials
+            .system_type
+            .as_deref()
+            .unwrap_or("")
+            .parse()
+            .map_err(|e| SmppError::InvalidData(format!("Invalid system_type: {e}")))?;
+
+        if let Some(metrics) = self.connection.metrics_mut() {
+            metrics.set_system_id(&credentials.system_id);
+        }
+        self.span.record("system_id", credentials.system_id.as_str());
+        self.span.record("bind_type", field_debug(credentials.bind_type));
+
+        let sequence_number = self.allocate_sequence()?;
 
         let frame = match credentials.bind_type {
             BindType::Transmitter => {
                 let bind_transmitter = BindTransmitter {
                     command_status: CommandStatus::Ok,
-                    sequence_number: self.sequence_number,
-                    system_id: SystemId::from(credentials.system_id.as_str()),
-                    password: Some(Password::from(credentials.password.as_str())),
-                    system_type: SystemType::from(credentials.system_type.as_deref().unwrap_or("")),
-                    interface_version: InterfaceVersion::SmppV34,
+                    sequence_number,
+                    system_id,
+                    password: Some(password),
+                    system_type,
+                    interface_version: credentials.interface_version,
                     addr_ton: TypeOfNumber::Unknown,
                     addr_npi: NumericPlanIndicator::Unknown,
                     address_range: AddressRange::default(),
@@ -81,68 +140,86 @@ impl SmppClient for DefaultClient {
                 Frame::BindTransmitter(bind_transmitter)
             }
             BindType::Receiver => {
-                let _bind_receiver = BindReceiver {
+                let bind_receiver = BindReceiver {
                     command_status: CommandStatus::Ok,
-                    sequence_number: self.sequence_number,
-                    system_id: SystemId::from(credentials.system_id.as_str()),
-                    password: Some(Password::from(credentials.password.as_str())),
-                    system_type: SystemType::from(credentials.system_type.as_deref().unwrap_or("")),
-                    interface_version: InterfaceVersion::SmppV34,
+                    sequence_number,
+                    system_id,
+                    password: Some(password),
+                    system_type,
+                    interface_version: credentials.interface_version,
                     addr_ton: TypeOfNumber::Unknown,
                     addr_npi: NumericPlanIndicator::Unknown,
                     address_range: AddressRange::default(),
                 };
-                // TODO: Add BindReceiver support
-                return Err(SmppError::InvalidData(
-                    "BindReceiver not yet supported".to_string(),
-                ));
+                Frame::BindReceiver(bind_receiver)
             }
             BindType::Transceiver => {
-                let _bind_transceiver = BindTransceiver {
+                let bind_transceiver = BindTransceiver {
                     command_status: CommandStatus::Ok,
-                    sequence_number: self.sequence_number,
-                    system_id: SystemId::from(credentials.system_id.as_str()),
-                    password: Some(Password::from(credentials.password.as_str())),
-                    system_type: SystemType::from(credentials.system_type.as_deref().unwrap_or("")),
-                    interface_version: InterfaceVersion::SmppV34,
+                    sequence_number,
+                    system_id,
+                    password: Some(password),
+                    system_type,
+                    interface_version: credentials.interface_version,
                     addr_ton: TypeOfNumber::Unknown,
                     addr_npi: NumericPlanIndicator::Unknown,
                     address_range: AddressRange::default(),
                 };
-                // TODO: Add BindTransceiver support
-                return Err(SmppError::InvalidData(
-                    "BindTransceiver not yet supported".to_string(),
-                ));
+                Frame::BindTransceiver(bind_transceiver)
             }
         };
 
-        self.connection
-            .write_frame(&frame)
-            .await
-            .map_err(SmppError::Connection)?;
+        // Decode and emit PDUs according to the version we bind with
+        let policy = self.connection.registry().policy();
+        *self.connection.registry_mut() =
+            PduRegistry::for_version(credentials.interface_version).with_policy(policy);
 
         // Wait for and validate bind response
-        match self.connection.read_frame().await {
-            Ok(Some(response)) => {
-                let command_status = match &response {
-                    // TODO: Add response support for missing bind types
-                    Frame::Unknown { header, .. } => header.command_status,
+        let session_init = self.timeouts.session_init;
+        match self.request(&frame, session_init).await? {
+            Some(response) => {
+                let (command_status, sc_version) = match &response {
+                    Frame::BindTransmitterResp(resp) => {
+                        (resp.command_status, resp.interface_version())
+                    }
+                    Frame::BindReceiverResp(resp) => (resp.command_status, resp.interface_version()),
+                    Frame::BindTransceiverResp(resp) => {
+                        (resp.command_status, resp.interface_version())
+                    }
+                    Frame::GenericNack(nack) => (nack.command_status, None),
                     other => {
-                        return Err(SmppError::UnexpectedPdu {
-                            expected: format!("Bind{:?}Response", credentials.bind_type),
-                            actual: format!("{other:?}"),
-                        });
+                        let expected = match credentials.bind_type {
+                            BindType::Transmitter => CommandId::BindTransmitterResp,
+                            BindType::Receiver => CommandId::BindReceiverResp,
+                            BindType::Transceiver => CommandId::BindTransceiverResp,
+                        };
+                        return Err(unexpected(&frame, expected, other));
                     }
                 };
 
                 if command_status != CommandStatus::Ok {
-                    return Err(SmppError::Protocol(command_status));
+                    return Err(rejected(&frame, command_status));
                 }
 
+                // Settle on the version both sides support for the rest of the session
+                let version = self
+                    .connection
+                    .registry_mut()
+                    .negotiate(credentials.interface_version, sc_version);
+                self.span.record("version", field_debug(version));
+                if version != credentials.interface_version {
+                    info!(
+                        parent: &self.span,
+                        requested = ?credentials.interface_version,
+                        negotiated = ?version,
+                        "SMSC does not support the requested interface version"
+                    );
+                }
+
+                self.record(MetricEvent::BindState(BindState::Bound(credentials.bind_type)));
                 Ok(())
             }
-            Ok(None) => Err(SmppError::ConnectionClosed),
-            Err(e) => Err(SmppError::from(e)),
+            None => Err(SmppError::ConnectionClosed),
         }
     }
 
@@ -151,36 +228,32 @@ impl SmppClient for DefaultClient {
             return Err(SmppError::InvalidState("Not connected".to_string()));
         }
 
-        self.sequence_number += 1;
+        let sequence_number = self.allocate_sequence()?;
 
         let unbind = Unbind {
             command_status: CommandStatus::Ok,
-            sequence_number: self.sequence_number,
+            sequence_number,
         };
 
         let frame = Frame::Unbind(unbind);
-        self.connection
-            .write_frame(&frame)
-            .await
-            .map_err(SmppError::Connection)?;
 
         // Wait for unbind response
-        match self.connection.read_frame().await {
-            Ok(Some(Frame::UnbindResp(response))) => {
+        let limit = self.timeouts.response_for(CommandId::Unbind);
+        match self.request(&frame, limit).await? {
+            Some(Frame::UnbindResp(response)) => {
                 if response.command_status != CommandStatus::Ok {
-                    return Err(SmppError::Protocol(response.command_status));
+                    return Err(rejected(&frame, response.command_status));
                 }
+                self.record(MetricEvent::BindState(BindState::Unbound));
                 Ok(())
             }
-            Ok(Some(other)) => Err(SmppError::UnexpectedPdu {
-                expected: "UnbindResponse".to_string(),
-                actual: format!("{other:?}"),
-            }),
-            Ok(None) => {
+            Some(Frame::GenericNack(nack)) => Err(rejected(&frame, nack.command_status)),
+            Some(other) => Err(unexpected(&frame, CommandId::UnbindResp, &other)),
+            None => {
                 // Connection closed during unbind is acceptable
+                self.record(MetricEvent::BindState(BindState::Closed));
                 Ok(())
             }
-            Err(e) => Err(SmppError::from(e)),
         }
     }
 
@@ -194,18 +267,19 @@ impl SmppClient for DefaultClient {
             keep_alive.on_ping_sent();
         }
 
-        self.sequence_number += 1;
+        let sequence_number = self.allocate_sequence()?;
 
-        let enquire_link = EnquireLink::new(self.sequence_number);
+        let enquire_link = EnquireLink::new(sequence_number);
 
         let frame = Frame::EnquireLink(enquire_link);
-        self.connection
-            .write_frame(&frame)
-            .await
-            .map_err(SmppError::Connection)?;
 
-        // Wait for enquire_link response
-        match self.connection.read_frame().await {
+        // Wait for enquire_link response, bounded by the keep-alive timeout when running
+        let limit = self
+            .keep_alive
+            .as_ref()
+            .map(|ka| ka.timeout())
+            .unwrap_or_else(|| self.timeouts.response_for(CommandId::EnquireLink));
+        match self.request(&frame, limit).await {
             Ok(Some(Frame::EnquireLinkResp(_response))) => {
                 // EnquireLinkResponse doesn't have command_status field - it's always OK
 
@@ -217,28 +291,16 @@ impl SmppClient for DefaultClient {
                 Ok(())
             }
             Ok(Some(other)) => {
-                // Record failed ping
-                if let Some(keep_alive) = &mut self.keep_alive {
-                    keep_alive.on_ping_failure();
-                }
-                Err(SmppError::UnexpectedPdu {
-                    expected: "EnquireLinkResponse".to_string(),
-                    actual: format!("{other:?}"),
-                })
+                self.keep_alive_failed();
+                Err(unexpected(&frame, CommandId::EnquireLinkResp, &other))
             }
             Ok(None) => {
-                // Record failed ping
-                if let Some(keep_alive) = &mut self.keep_alive {
-                    keep_alive.on_ping_failure();
-                }
+                self.keep_alive_failed();
                 Err(SmppError::ConnectionClosed)
             }
             Err(e) => {
-                // Record failed ping
-                if let Some(keep_alive) = &mut self.keep_alive {
-                    keep_alive.on_ping_failure();
-                }
-                Err(SmppError::from(e))
+                self.keep_alive_failed();
+                Err(e)
             }
         }
     }
@@ -275,13 +337,25 @@ impl SmppClient for DefaultClient {
             })
     }
 
+    /// # Panics
+    ///
+    /// When the client's [`SequenceGenerator`] has every number in flight.
+    /// The client's own requests report that as an error instead.
     fn next_sequence_number(&mut self) -> u32 {
-        self.sequence_number += 1;
-        self.sequence_number
+        self.sequence
+            .next_sequence()
+            .expect("every sequence number is in flight")
     }
 }
 
 impl DefaultClient {
+    /// Allocate the sequence number of a request the client sends
+    fn allocate_sequence(&self) -> SmppResult<u32> {
+        self.sequence.next_sequence().ok_or_else(|| {
+            SmppError::InvalidState("Every sequence number is in flight".to_string())
+        })
+    }
+
     /// Check if a keep-alive ping should be sent and send it if needed
     ///
     /// This is a convenience method that integrates the keep-alive manager
@@ -323,6 +397,8 @@ impl DefaultClient {
     /// # }
     /// ```
     pub async fn maintain_keep_alive(&mut self) -> SmppResult<bool> {
+        self.check_inactivity().await?;
+
         if let Some(keep_alive) = &self.keep_alive {
             if keep_alive.should_ping() {
                 self.enquire_link().await?;
@@ -374,6 +450,543 @@ impl DefaultClient {
             .map(|ka| ka.is_connection_failed())
             .unwrap_or(false)
     }
+
+    /// Unbind the session if the inactivity timer has expired
+    ///
+    /// When [`TimeoutConfig::inactivity`] is set and no request other than
+    /// enquire_link has completed for that long, the session is unbound (on a
+    /// best-effort basis), marked disconnected and a [`SmppError::Timeout`]
+    /// is returned. [`maintain_keep_alive`](Self::maintain_keep_alive) calls
+    /// this before deciding whether to ping.
+    pub async fn check_inactivity(&mut self) -> SmppResult<()> {
+        let Some(limit) = self.timeouts.inactivity else {
+            return Ok(());
+        };
+        if !self.connected || self.last_activity.elapsed() < limit {
+            return Ok(());
+        }
+
+        let idle = self.last_activity.elapsed();
+        info!(parent: &self.span, ?idle, "Inactivity timer expired, unbinding");
+        if let Err(e) = self.unbind().await {
+            debug!(parent: &self.span, "Unbind after inactivity failed: {e}");
+        }
+        self.disconnect().await?;
+        Err(SmppError::Timeout {
+            operation: TimeoutKind::Inactivity,
+            timeout: limit,
+        })
+    }
+
+    /// Connect to an SMSC, bounding the TCP connect by `timeouts.connect`
+    ///
+    /// The timers are used for every later operation on the client.
+    pub async fn connect_with_timeouts<T: ToSocketAddrs + Send>(
+        addr: T,
+        timeouts: TimeoutConfig,
+    ) -> SmppResult<Self> {
+        let socket = tokio::time::timeout(timeouts.connect, TcpStream::connect(addr))
+            .await
+            .map_err(|_| SmppError::Timeout {
+                operation: TimeoutKind::Connect,
+                timeout: timeouts.connect,
+            })??;
+        // Filled in by bind
+        let span = info_span!(
+            "smpp_session",
+            peer = %socket.peer_addr()?,
+            system_id = Empty,
+            bind_type = Empty,
+            version = Empty,
+        );
+        let connection = Connection::new(socket);
+
+        Ok(DefaultClient {
+            connection,
+            sequence: Arc::new(WrappingSequenceGenerator::new()),
+            connected: true,
+            keep_alive: None,
+            timeouts,
+            abandoned: VecDeque::new(),
+            last_activity: Instant::now(),
+            retry: RetryPolicy::disabled(),
+            alerts: None,
+            span,
+        })
+    }
+
+    /// Allocate sequence numbers from `sequence`
+    ///
+    /// Share one generator between several clients bound to the same SMSC
+    /// to keep their sequence numbers distinct, or use a
+    /// [`FileSequenceGenerator`](crate::client::FileSequenceGenerator) to keep
+    /// them unique across restarts.
+    pub fn set_sequence_generator(&mut self, sequence: Arc<dyn SequenceGenerator>) {
+        self.sequence = sequence;
+    }
+
+    /// Replace the session timers
+    pub fn set_timeouts(&mut self, timeouts: TimeoutConfig) {
+        self.timeouts = timeouts;
+    }
+
+    /// The session timers in use
+    pub fn timeouts(&self) -> &TimeoutConfig {
+        &self.timeouts
+    }
+
+    /// Resend submit_sm, data_sm and submit_multi on transient failures
+    ///
+    /// Retrying is disabled by default. Every attempt, the first included, is
+    /// given a new sequence number from the client's generator.
+    pub fn set_retry_policy(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    /// The retry policy applied to message submissions
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// Pass alert_notifications from the SMSC to `handler`
+    ///
+    /// The SMSC may send an alert_notification at any time on a receiver or
+    /// transceiver bind. Alerts arriving while the client waits for a
+    /// response go to the handler; without one they are logged and dropped.
+    pub fn set_alert_handler(&mut self, handler: impl AlertHandler + 'static) {
+        self.alerts = Some(Arc::new(handler));
+    }
+
+    /// Stop passing alert_notifications to a handler
+    pub fn clear_alert_handler(&mut self) {
+        self.alerts = None;
+    }
+
+    /// Report the session's metrics from now on
+    ///
+    /// PDUs are counted by the connection, so a session handed to
+    /// [`into_inbound`](Self::into_inbound) goes on being counted. Binding
+    /// labels the metrics with the system_id bound with.
+    pub fn set_metrics(&mut self, metrics: SessionMetrics) {
+        self.connection.set_metrics(metrics);
+    }
+
+    /// Set what is left out of the PDUs the connection logs at trace level
+    ///
+    /// Passwords and message content are redacted by default.
+    pub fn set_redaction_policy(&mut self, policy: RedactionPolicy) {
+        self.connection.set_redaction_policy(policy);
+    }
+
+    /// The session's span
+    ///
+    /// Named `smpp_session`, with the SMSC's address as `peer` and, once
+    /// bound, the `system_id`, `bind_type` and negotiated `version`. Each
+    /// request gets a child span, `smpp_exchange`, recording its
+    /// `command_id`, `sequence_number`, the response's `command_status` and
+    /// the `latency`. Instrument work of your own with it to log it in the
+    /// session's context.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Record every PDU sent and received from now on
+    ///
+    /// The recorder stays with the connection, so a session handed to
+    /// [`into_inbound`](Self::into_inbound) goes on being recorded.
+    pub fn set_recorder(&mut self, recorder: Recorder) {
+        self.connection.set_recorder(recorder);
+    }
+
+    /// Hand the bound session to a background reader delivering inbound PDUs as a stream
+    ///
+    /// Use on receiver and transceiver binds. The client is consumed: the
+    /// returned [`InboundSession`] owns the connection, answers the SMSC's
+    /// enquire_link and keeps sending enquire_link at the keep-alive interval
+    /// if keep-alive was running. alert_notifications are delivered on the
+    /// stream rather than to the alert handler.
+    pub fn into_inbound(self, config: InboundConfig) -> SmppResult<InboundSession> {
+        if !self.connected {
+            return Err(SmppError::InvalidState("Not connected".to_string()));
+        }
+        let enquire_interval = self
+            .keep_alive
+            .as_ref()
+            .filter(|keep_alive| keep_alive.is_running())
+            .map(KeepAliveManager::interval);
+        Ok(InboundSession::spawn(
+            self.connection,
+            self.sequence,
+            self.timeouts,
+            enquire_interval,
+            config,
+            self.span,
+        ))
+    }
+
+    /// Send a data_sm, returning the message ID assigned by the SMSC
+    ///
+    /// Transient failures are retried according to the
+    /// [retry policy](Self::set_retry_policy).
+    pub async fn data_sm(&mut self, data: &DataSm) -> SmppResult<String> {
+        let build = |sequence_number| {
+            Frame::DataSm(Box::new(DataSm {
+                sequence_number,
+                ..data.clone()
+            }))
+        };
+        self.send_with_retry(build, |request, response| {
+            match response {
+                Frame::DataSmResp(response) => {
+                    if response.command_status != CommandStatus::Ok {
+                        return Err(rejected(request, response.command_status));
+                    }
+                    Ok(response.message_id.to_string())
+                }
+                Frame::GenericNack(nack) => Err(rejected(request, nack.command_status)),
+                other => Err(unexpected(request, CommandId::DataSmResp, &other)),
+            }
+        })
+        .await
+    }
+
+    /// Send a submit_multi, returning the SMSC's response
+    ///
+    /// The response lists any destinations the SMSC could not accept.
+    /// Transient failures of the whole request are retried according to the
+    /// [retry policy](Self::set_retry_policy).
+    pub async fn submit_multi(&mut self, submit: &SubmitMulti) -> SmppResult<SubmitMultiResponse> {
+        let build = |sequence_number| {
+            Frame::SubmitMulti(Box::new(SubmitMulti {
+                sequence_number,
+                ..submit.clone()
+            }))
+        };
+        self.send_with_retry(build, |request, response| {
+            match response {
+                Frame::SubmitMultiResp(response) => {
+                    if response.command_status != CommandStatus::Ok {
+                        return Err(rejected(request, response.command_status));
+                    }
+                    Ok(response)
+                }
+                Frame::GenericNack(nack) => Err(rejected(request, nack.command_status)),
+                other => Err(unexpected(request, CommandId::SubmitMultiResp, &other)),
+            }
+        })
+        .await
+    }
+
+    /// Send a query_sm, returning the SMSC's report of the message's state
+    pub async fn query_sm(&mut self, query: &QuerySm) -> SmppResult<QuerySmResponse> {
+        let build = |sequence_number| {
+            Frame::QuerySm(QuerySm {
+                sequence_number,
+                ..query.clone()
+            })
+        };
+        self.send_with_retry(build, |request, response| {
+            match response {
+                Frame::QuerySmResp(response) => {
+                    if response.command_status != CommandStatus::Ok {
+                        return Err(rejected(request, response.command_status));
+                    }
+                    Ok(response)
+                }
+                Frame::GenericNack(nack) => Err(rejected(request, nack.command_status)),
+                other => Err(unexpected(request, CommandId::QuerySmResp, &other)),
+            }
+        })
+        .await
+    }
+
+    /// Send a cancel_sm, withdrawing messages the SMSC has not yet delivered
+    pub async fn cancel_sm(&mut self, cancel: &CancelSm) -> SmppResult<()> {
+        let build = |sequence_number| {
+            Frame::CancelSm(CancelSm {
+                sequence_number,
+                ..cancel.clone()
+            })
+        };
+        self.send_with_retry(build, |request, response| {
+            match response {
+                Frame::CancelSmResp(response) => {
+                    if response.command_status != CommandStatus::Ok {
+                        return Err(rejected(request, response.command_status));
+                    }
+                    Ok(())
+                }
+                Frame::GenericNack(nack) => Err(rejected(request, nack.command_status)),
+                other => Err(unexpected(request, CommandId::CancelSmResp, &other)),
+            }
+        })
+        .await
+    }
+
+    /// Send a replace_sm, changing a message the SMSC has not yet delivered
+    pub async fn replace_sm(&mut self, replace: &ReplaceSm) -> SmppResult<()> {
+        let build = |sequence_number| {
+            Frame::ReplaceSm(Box::new(ReplaceSm {
+                sequence_number,
+                ..replace.clone()
+            }))
+        };
+        self.send_with_retry(build, |request, response| {
+            match response {
+                Frame::ReplaceSmResp(response) => {
+                    if response.command_status != CommandStatus::Ok {
+                        return Err(rejected(request, response.command_status));
+                    }
+                    Ok(())
+                }
+                Frame::GenericNack(nack) => Err(rejected(request, nack.command_status)),
+                other => Err(unexpected(request, CommandId::ReplaceSmResp, &other)),
+            }
+        })
+        .await
+    }
+
+    /// Send a request, resending it as the retry policy allows
+    ///
+    /// `build` creates the request for a sequence number. Every attempt is
+    /// given a newly allocated one, whatever number the caller's PDU carries,
+    /// so a late response to an earlier attempt cannot be mistaken for the
+    /// current one. `accept` turns the SMSC's reply into the result of the
+    /// attempt.
+    async fn send_with_retry<T>(
+        &mut self,
+        build: impl Fn(u32) -> Frame + Send,
+        accept: impl Fn(&Frame, Frame) -> SmppResult<T> + Send,
+    ) -> SmppResult<T> {
+        let mut attempt = 1;
+        loop {
+            if !self.connected {
+                return Err(SmppError::InvalidState("Not connected".to_string()));
+            }
+
+            let sequence_number = self.allocate_sequence()?;
+            let frame = build(sequence_number);
+            let command_id = frame.command_id();
+            debug!(
+                parent: &self.span,
+                attempt,
+                max_attempts = self.retry.max_attempts,
+                sequence_number,
+                "Sending {command_id:?}"
+            );
+
+            let limit = self.timeouts.response_for(command_id);
+            let error = match self.request(&frame, limit).await {
+                Ok(Some(response)) => match accept(&frame, response) {
+                    Ok(result) => {
+                        if attempt > 1 {
+                            info!(
+                                parent: &self.span,
+                                attempt,
+                                sequence_number,
+                                "{command_id:?} succeeded after retry"
+                            );
+                        }
+                        return Ok(result);
+                    }
+                    Err(e) => e,
+                },
+                Ok(None) => SmppError::ConnectionClosed,
+                Err(e) => e,
+            };
+
+            match self.retry.retry_delay(&error, attempt) {
+                Some(delay) => {
+                    warn!(
+                        parent: &self.span,
+                        attempt,
+                        max_attempts = self.retry.max_attempts,
+                        sequence_number,
+                        ?delay,
+                        "{command_id:?} failed, retrying: {error}"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                None => {
+                    if attempt > 1 {
+                        warn!(
+                            parent: &self.span,
+                            attempt,
+                            sequence_number,
+                            "{command_id:?} failed, giving up: {error}"
+                        );
+                    }
+                    return Err(error);
+                }
+            }
+        }
+    }
+
+    /// Send a request and wait for the next PDU from the SMSC, in a span of its own
+    ///
+    /// The span and the metrics record how long the response took.
+    async fn request(&mut self, frame: &Frame, limit: Duration) -> SmppResult<Option<Frame>> {
+        let command_id = frame.command_id();
+        let span = debug_span!(
+            parent: &self.span,
+            "smpp_exchange",
+            command_id = %command_id.name(),
+            sequence_number = frame.sequence_number(),
+            command_status = Empty,
+            latency = Empty,
+        );
+        let sent_at = Instant::now();
+        let result = self.exchange(frame, limit).instrument(span.clone()).await;
+
+        if let Ok(Some(response)) = &result {
+            let latency = sent_at.elapsed();
+            span.record("command_status", field_debug(response.command_status()));
+            span.record("latency", field_debug(latency));
+            self.record(MetricEvent::ResponseLatency {
+                command_id,
+                latency,
+            });
+        }
+        result
+    }
+
+    /// Send a request and wait for the next PDU from the SMSC
+    ///
+    /// Writing the request and waiting for the reply share one deadline,
+    /// `limit`. When it expires the request is abandoned: its sequence number
+    /// is remembered so that a response arriving later is discarded rather
+    /// than taken as the answer to a subsequent request. A write that times
+    /// out may have left a partial PDU on the wire, so the client is marked
+    /// disconnected and the failure reported as a connection error.
+    async fn exchange(&mut self, frame: &Frame, limit: Duration) -> SmppResult<Option<Frame>> {
+        let command_id = frame.command_id();
+        let sequence_number = frame.sequence_number();
+        let deadline = tokio::time::Instant::now() + limit;
+
+        match tokio::time::timeout_at(deadline, self.connection.write_frame(frame)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                self.sequence.release(sequence_number);
+                return Err(write_error(e));
+            }
+            Err(_) => {
+                self.sequence.release(sequence_number);
+                self.connected = false;
+                return Err(SmppError::Connection(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("writing {command_id:?} did not complete within {limit:?}"),
+                )));
+            }
+        }
+
+        loop {
+            match tokio::time::timeout_at(deadline, self.connection.read_frame()).await {
+                Ok(Ok(Some(response)))
+                    if response.is_response() && self.forget_abandoned(response.sequence_number()) =>
+                {
+                    self.sequence.release(response.sequence_number());
+                    debug!(
+                        sequence_number = response.sequence_number(),
+                        "Discarding late {:?} to a timed-out request",
+                        response.command_id()
+                    );
+                }
+                Ok(Ok(Some(Frame::AlertNotification(alert)))) => self.alert(&alert),
+                Ok(Ok(response)) => {
+                    self.sequence.release(sequence_number);
+                    if command_id != CommandId::EnquireLink {
+                        self.last_activity = Instant::now();
+                    }
+                    return Ok(response);
+                }
+                Ok(Err(e)) => {
+                    self.sequence.release(sequence_number);
+                    return Err(SmppError::from_read(e, Some(command_id), Some(sequence_number)));
+                }
+                Err(_) => {
+                    // Stays in flight until its late response arrives or it is
+                    // pushed out of the abandoned list
+                    warn!(sequence_number, "No response to {command_id:?} within {limit:?}");
+                    if self.abandoned.len() == MAX_ABANDONED_REQUESTS
+                        && let Some(oldest) = self.abandoned.pop_front()
+                    {
+                        self.sequence.release(oldest);
+                    }
+                    self.abandoned.push_back(sequence_number);
+                    return Err(SmppError::Timeout {
+                        operation: TimeoutKind::Response {
+                            command_id,
+                            sequence_number,
+                        },
+                        timeout: limit,
+                    });
+                }
+            }
+        }
+    }
+
+    fn record(&self, event: MetricEvent) {
+        if let Some(metrics) = self.connection.metrics() {
+            metrics.record(event);
+        }
+    }
+
+    /// Note an enquire_link that failed with the keep-alive manager and in the metrics
+    fn keep_alive_failed(&mut self) {
+        if let Some(keep_alive) = &mut self.keep_alive {
+            keep_alive.on_ping_failure();
+        }
+        self.record(MetricEvent::KeepAliveFailure);
+    }
+
+    /// Pass an alert_notification received while waiting for a response to the handler
+    fn alert(&self, alert: &AlertNotification) {
+        let alert = SubscriberAlert::from(alert);
+        match &self.alerts {
+            Some(handler) => handler.on_alert(&alert),
+            None => debug!(
+                parent: &self.span,
+                subscriber = %alert.subscriber,
+                "Dropping alert_notification: no alert handler installed"
+            ),
+        }
+    }
+
+    /// Remove `sequence_number` from the abandoned requests, returning whether it was there
+    fn forget_abandoned(&mut self, sequence_number: u32) -> bool {
+        match self.abandoned.iter().position(|&seq| seq == sequence_number) {
+            Some(index) => {
+                self.abandoned.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set how strictly PDUs received from the SMSC are decoded
+    ///
+    /// With [`DecodePolicy::Lenient`](crate::codec::DecodePolicy::Lenient),
+    /// recoverable specification violations (missing NUL terminators, bad
+    /// `priority_flag` values, addresses that do not match their TON, ...) are
+    /// normalised and logged instead of failing the read, so a single
+    /// non-conformant PDU does not tear down the session.
+    pub fn set_decode_policy(&mut self, policy: crate::codec::DecodePolicy) {
+        self.connection.registry_mut().set_policy(policy);
+    }
+
+    /// The decode policy applied to PDUs received from the SMSC
+    pub fn decode_policy(&self) -> crate::codec::DecodePolicy {
+        self.connection.registry().policy()
+    }
+
+    /// The SMPP version in use on this session
+    ///
+    /// After a successful bind this is the version negotiated with the SMSC,
+    /// which may be lower than the one requested in [`BindCredentials`].
+    pub fn interface_version(&self) -> InterfaceVersion {
+        self.connection.registry().version()
+    }
 }
 
 impl SmppTransmitter for DefaultClient {
@@ -385,11 +998,10 @@ impl SmppTransmitter for DefaultClient {
             ));
         }
 
-        let sequence_number = self.next_sequence_number();
-
         let submit_sm = SubmitSm {
             command_status: CommandStatus::Ok,
-            sequence_number,
+            // Allocated by submit_sm when it is sent
+            sequence_number: 0,
             service_type: ServiceType::default(),
             source_addr_ton: message.options.source_ton,
             source_addr_npi: message.options.source_npi,
@@ -438,36 +1050,66 @@ impl SmppTransmitter for DefaultClient {
             its_reply_type: None,
             its_session_info: None,
             ussd_service_op: None,
+            network_routing: NetworkRouting::default(),
         };
 
         self.submit_sm(&submit_sm).await
     }
 
     async fn submit_sm(&mut self, submit: &SubmitSm) -> SmppResult<String> {
-        if !self.connected {
-            return Err(SmppError::InvalidState("Not connected".to_string()));
-        }
-
-        let frame = Frame::SubmitSm(Box::new(submit.clone()));
-        self.connection
-            .write_frame(&frame)
-            .await
-            .map_err(SmppError::Connection)?;
+        let build = |sequence_number| {
+            Frame::SubmitSm(Box::new(SubmitSm {
+                sequence_number,
+                ..submit.clone()
+            }))
+        };
 
-        // Wait for and validate submit response
-        match self.connection.read_frame().await {
-            Ok(Some(Frame::SubmitSmResp(response))) => {
-                if response.command_status != CommandStatus::Ok {
-                    return Err(SmppError::Protocol(response.command_status));
+        // Validate the submit response, retrying transient failures
+        self.send_with_retry(build, |request, response| {
+            match response {
+                Frame::SubmitSmResp(response) => {
+                    if response.command_status != CommandStatus::Ok {
+                        return Err(rejected(request, response.command_status));
+                    }
+                    Ok(response.message_id.to_string())
                 }
-                Ok(response.message_id.to_string())
+                // SMPP v3.3 SMSCs commonly reject a submit_sm with generic_nack
+                Frame::GenericNack(nack) => Err(rejected(request, nack.command_status)),
+                other => Err(unexpected(request, CommandId::SubmitSmResp, &other)),
             }
-            Ok(Some(other)) => Err(SmppError::UnexpectedPdu {
-                expected: "SubmitSmResp".to_string(),
-                actual: format!("{other:?}"),
-            }),
-            Ok(None) => Err(SmppError::ConnectionClosed),
-            Err(e) => Err(SmppError::from(e)),
-        }
+        })
+        .await
+    }
+}
+
+/// Error for a request the SMSC answered with a non-zero command_status
+fn rejected(request: &Frame, status: CommandStatus) -> SmppError {
+    SmppError::Protocol {
+        command_id: request.command_id(),
+        sequence_number: request.sequence_number(),
+        status,
+    }
+}
+
+/// Error for a reply that is not the response `request` called for
+fn unexpected(request: &Frame, expected: CommandId, actual: &Frame) -> SmppError {
+    SmppError::UnexpectedPdu {
+        expected,
+        sequence_number: request.sequence_number(),
+        actual: actual.command_id(),
+        actual_sequence_number: actual.sequence_number(),
+    }
+}
+
+/// Map a `write_frame` failure to a client error
+///
+/// `InvalidData` means the PDU was refused before it reached the socket (for
+/// example a v3.4-only parameter on a v3.3 session), so it is reported as
+/// invalid data rather than a connection failure.
+fn write_error(err: std::io::Error) -> SmppError {
+    if err.kind() == std::io::ErrorKind::InvalidData {
+        SmppError::InvalidData(err.to_string())
+    } else {
+        SmppError::Connection(err)
     }
 }
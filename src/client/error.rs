@@ -1,35 +1,76 @@
 // ABOUTME: SMPP client error types for comprehensive error handling across all client operations
 // ABOUTME: Provides structured error reporting with automatic conversion from underlying I/O and protocol errors
 
-use crate::datatypes::CommandStatus;
+use crate::codec::CodecError;
+use crate::datatypes::{CommandId, CommandStatus};
+use std::fmt;
 use std::io;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Comprehensive error type for SMPP client operations
 ///
 /// Provides structured error handling for all client operations including
-/// connection management, protocol operations, and data validation.
+/// connection management, protocol operations, and data validation. Errors
+/// raised while waiting for a response carry the `command_id` and
+/// `sequence_number` of the request they belong to, so callers can classify
+/// them without inspecting strings; see [`is_retryable`](Self::is_retryable)
+/// and [`is_fatal_for_session`](Self::is_fatal_for_session).
 #[derive(Debug, Error)]
 pub enum SmppError {
     /// I/O error during network operations (connection, read, write)
     #[error("Connection error: {0}")]
     Connection(#[from] io::Error),
 
-    /// SMPP protocol error indicated by command_status field
-    #[error("Protocol error: {0:?}")]
-    Protocol(CommandStatus),
+    /// The SMSC rejected a request with a non-zero command_status
+    ///
+    /// Also produced for a generic_nack sent in reply to the request.
+    #[error("Protocol error: {command_id:?} (sequence {sequence_number}) failed with {status:?}")]
+    Protocol {
+        /// The request that was rejected
+        command_id: CommandId,
+        sequence_number: u32,
+        status: CommandStatus,
+    },
+
+    /// A PDU received from the SMSC could not be decoded
+    ///
+    /// `command_id` and `sequence_number` identify the request whose response
+    /// was awaited, when there was one.
+    #[error("Codec error{}: {source}", RequestContext(*command_id, *sequence_number))]
+    Codec {
+        command_id: Option<CommandId>,
+        sequence_number: Option<u32>,
+        #[source]
+        source: CodecError,
+    },
 
     /// Data validation error (invalid message length, malformed addresses, etc.)
     #[error("Invalid data: {0}")]
     InvalidData(String),
 
     /// Operation timeout
-    #[error("Operation timeout")]
-    Timeout,
+    ///
+    /// `operation` identifies what was being waited for and `timeout` is the
+    /// limit that expired.
+    #[error("Timeout: {operation} did not complete within {timeout:?}")]
+    Timeout {
+        operation: TimeoutKind,
+        timeout: Duration,
+    },
 
     /// Unexpected PDU received (wrong response type for request)
-    #[error("Unexpected PDU: expected {expected}, got {actual}")]
-    UnexpectedPdu { expected: String, actual: String },
+    #[error(
+        "Unexpected PDU: expected {expected:?} for sequence {sequence_number}, got {actual:?} (sequence {actual_sequence_number})"
+    )]
+    UnexpectedPdu {
+        /// The response command_id the request called for
+        expected: CommandId,
+        /// Sequence number of the request
+        sequence_number: u32,
+        actual: CommandId,
+        actual_sequence_number: u32,
+    },
 
     /// Connection closed unexpectedly
     #[error("Connection closed unexpectedly")]
@@ -38,17 +79,285 @@ pub enum SmppError {
     /// Client not in correct state for operation
     #[error("Invalid client state: {0}")]
     InvalidState(String),
+
+    /// A message's validity period passed before it could be submitted
+    #[error("Message expired before submission: validity period {validity_period}")]
+    Expired { validity_period: String },
+
+    /// Reading or writing the outbound spool failed
+    #[error("Spool error: {0}")]
+    Spool(#[source] io::Error),
+}
+
+/// What a [`SmppError::Timeout`] was waiting for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutKind {
+    /// Establishing the TCP connection
+    Connect,
+    /// The response to a request (including binds)
+    Response {
+        command_id: CommandId,
+        sequence_number: u32,
+    },
+    /// The session was idle for longer than the inactivity timer
+    Inactivity,
+}
+
+impl fmt::Display for TimeoutKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeoutKind::Connect => write!(f, "connect"),
+            TimeoutKind::Response {
+                command_id,
+                sequence_number,
+            } => write!(f, "{command_id:?} (sequence {sequence_number})"),
+            TimeoutKind::Inactivity => write!(f, "inactivity_timer"),
+        }
+    }
+}
+
+/// Formats the optional request context of a codec error
+struct RequestContext(Option<CommandId>, Option<u32>);
+
+impl fmt::Display for RequestContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.0, self.1) {
+            (Some(command_id), Some(sequence_number)) => {
+                write!(f, " awaiting response to {command_id:?} (sequence {sequence_number})")
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Result type alias for SMPP operations
 pub type SmppResult<T> = Result<T, SmppError>;
 
+impl SmppError {
+    /// The request this error belongs to, if it arose from one
+    pub fn command_id(&self) -> Option<CommandId> {
+        match self {
+            SmppError::Protocol { command_id, .. } => Some(*command_id),
+            SmppError::Codec { command_id, .. } => *command_id,
+            SmppError::Timeout {
+                operation: TimeoutKind::Response { command_id, .. },
+                ..
+            } => Some(*command_id),
+            _ => None,
+        }
+    }
+
+    /// Sequence number of the request this error belongs to, if any
+    pub fn sequence_number(&self) -> Option<u32> {
+        match self {
+            SmppError::Protocol {
+                sequence_number, ..
+            }
+            | SmppError::UnexpectedPdu {
+                sequence_number, ..
+            }
+            | SmppError::Timeout {
+                operation: TimeoutKind::Response {
+                    sequence_number, ..
+                },
+                ..
+            } => Some(*sequence_number),
+            SmppError::Codec {
+                sequence_number, ..
+            } => *sequence_number,
+            _ => None,
+        }
+    }
+
+    /// The command_status returned by the SMSC, for protocol errors
+    pub fn command_status(&self) -> Option<CommandStatus> {
+        match self {
+            SmppError::Protocol { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Whether sending the same request again may succeed
+    ///
+    /// True for transient SMSC conditions (see [`CommandStatus::should_retry`]),
+    /// timeouts and lost connections. Check
+    /// [`is_fatal_for_session`](Self::is_fatal_for_session) to decide whether
+    /// the retry needs a new session.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SmppError::Protocol { status, .. } => status.should_retry(),
+            SmppError::Timeout { .. } | SmppError::Connection(_) | SmppError::ConnectionClosed => {
+                true
+            }
+            SmppError::Codec { .. }
+            | SmppError::InvalidData(_)
+            | SmppError::UnexpectedPdu { .. }
+            | SmppError::InvalidState(_)
+            | SmppError::Expired { .. }
+            | SmppError::Spool(_) => false,
+        }
+    }
+
+    /// How long to wait before retrying, when the SMSC status suggests a delay
+    ///
+    /// Based on [`CommandStatus::suggested_retry_delay`].
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            SmppError::Protocol { status, .. } => status
+                .suggested_retry_delay()
+                .map(|seconds| Duration::from_secs(seconds as u64)),
+            _ => None,
+        }
+    }
+
+    /// Whether the session can no longer be used and must be re-established
+    ///
+    /// Covers lost connections, undecodable input (the read buffer cannot be
+    /// resynchronised), failed or timed-out binds, an expired inactivity
+    /// timer and an SMSC reporting that the session is not bound.
+    pub fn is_fatal_for_session(&self) -> bool {
+        match self {
+            SmppError::Connection(_)
+            | SmppError::ConnectionClosed
+            | SmppError::Codec { .. }
+            | SmppError::InvalidState(_) => true,
+            SmppError::Protocol {
+                command_id, status, ..
+            } => is_bind(*command_id) || *status == CommandStatus::IncorrectBindStatus,
+            SmppError::Timeout { operation, .. } => match operation {
+                TimeoutKind::Connect | TimeoutKind::Inactivity => true,
+                TimeoutKind::Response { command_id, .. } => is_bind(*command_id),
+            },
+            SmppError::InvalidData(_)
+            | SmppError::UnexpectedPdu { .. }
+            | SmppError::Expired { .. }
+            | SmppError::Spool(_) => false,
+        }
+    }
+
+    /// Build the error for a failed read while awaiting a response
+    pub(crate) fn from_read(
+        err: crate::Error,
+        command_id: Option<CommandId>,
+        sequence_number: Option<u32>,
+    ) -> Self {
+        let err = match err.downcast::<io::Error>() {
+            Ok(io_err) => return SmppError::Connection(*io_err),
+            Err(err) => err,
+        };
+        let err = match err.downcast::<crate::frame::Error>() {
+            Ok(frame_err) => match *frame_err {
+                crate::frame::Error::Other(inner) => inner,
+                crate::frame::Error::Incomplete => {
+                    return SmppError::Codec {
+                        command_id,
+                        sequence_number,
+                        source: CodecError::Incomplete,
+                    };
+                }
+            },
+            Err(err) => err,
+        };
+        match err.downcast::<CodecError>() {
+            Ok(source) => SmppError::Codec {
+                command_id,
+                sequence_number,
+                source: *source,
+            },
+            Err(err) => SmppError::InvalidData(err.to_string()),
+        }
+    }
+}
+
+fn is_bind(command_id: CommandId) -> bool {
+    matches!(
+        command_id,
+        CommandId::BindTransmitter | CommandId::BindReceiver | CommandId::BindTransceiver
+    )
+}
+
 impl From<Box<dyn std::error::Error + Send + Sync>> for SmppError {
     fn from(err: Box<dyn std::error::Error + Send + Sync>) -> Self {
-        if let Some(io_err) = err.downcast_ref::<io::Error>() {
-            SmppError::Connection(io::Error::new(io_err.kind(), err.to_string()))
-        } else {
-            SmppError::InvalidData(err.to_string())
+        SmppError::from_read(err, None, None)
+    }
+}
+
+impl From<CodecError> for SmppError {
+    fn from(source: CodecError) -> Self {
+        SmppError::Codec {
+            command_id: None,
+            sequence_number: None,
+            source,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_error_classification() {
+        let throttled = SmppError::Protocol {
+            command_id: CommandId::SubmitSm,
+            sequence_number: 7,
+            status: CommandStatus::MessageQueueFull,
+        };
+        assert!(throttled.is_retryable());
+        assert_eq!(throttled.retry_after(), Some(Duration::from_secs(10)));
+        assert!(!throttled.is_fatal_for_session());
+        assert_eq!(throttled.command_id(), Some(CommandId::SubmitSm));
+        assert_eq!(throttled.sequence_number(), Some(7));
+
+        let bind_failed = SmppError::Protocol {
+            command_id: CommandId::BindTransceiver,
+            sequence_number: 1,
+            status: CommandStatus::InvalidPassword,
+        };
+        assert!(!bind_failed.is_retryable());
+        assert!(bind_failed.is_fatal_for_session());
+    }
+
+    #[test]
+    fn test_timeout_classification() {
+        let submit = SmppError::Timeout {
+            operation: TimeoutKind::Response {
+                command_id: CommandId::SubmitSm,
+                sequence_number: 3,
+            },
+            timeout: Duration::from_secs(30),
+        };
+        assert!(submit.is_retryable());
+        assert!(!submit.is_fatal_for_session());
+        assert_eq!(submit.sequence_number(), Some(3));
+
+        let connect = SmppError::Timeout {
+            operation: TimeoutKind::Connect,
+            timeout: Duration::from_secs(10),
+        };
+        assert!(connect.is_fatal_for_session());
+        assert_eq!(connect.command_id(), None);
+    }
+
+    #[test]
+    fn test_codec_error_is_typed_source() {
+        let boxed: crate::Error = Box::new(crate::frame::Error::from(CodecError::TlvError(
+            "truncated".to_string(),
+        )));
+        let err = SmppError::from_read(boxed, Some(CommandId::QuerySm), Some(9));
+        match &err {
+            SmppError::Codec {
+                source: CodecError::TlvError(reason),
+                ..
+            } => assert_eq!(reason, "truncated"),
+            other => panic!("Expected codec error, got {other:?}"),
         }
+        assert!(std::error::Error::source(&err).is_some());
+        assert!(err.is_fatal_for_session());
+        assert!(!err.is_retryable());
+        assert_eq!(
+            err.to_string(),
+            "Codec error awaiting response to QuerySm (sequence 9): TLV parsing error: truncated"
+        );
     }
 }
@@ -2,6 +2,7 @@
 // ABOUTME: Implements smart algorithms that respond to server congestion states and error conditions
 
 use crate::datatypes::CommandStatus;
+use crate::metrics::{MetricEvent, SessionMetrics};
 use std::time::{Duration, Instant};
 
 /// SMPP v5.0 flow control manager for adaptive rate limiting
@@ -26,6 +27,8 @@ pub struct FlowControlManager {
     statistics: FlowControlStatistics,
     /// Configuration parameters
     config: FlowControlConfig,
+    /// Where rate changes are reported, if anywhere
+    metrics: Option<SessionMetrics>,
 }
 
 /// Configuration for flow control behavior
@@ -93,6 +96,7 @@ impl FlowControlManager {
             last_congestion_update: None,
             statistics,
             config: FlowControlConfig::default(),
+            metrics: None,
         }
     }
 
@@ -224,6 +228,12 @@ impl FlowControlManager {
         self.congestion_state().map_or(false, |state| state > 20) // > 20% congestion
     }
 
+    /// Report the current rate limit to `metrics`, and every change to it from now on
+    pub fn set_metrics(&mut self, metrics: SessionMetrics) {
+        metrics.record(MetricEvent::FlowControlRate(self.current_rate_limit));
+        self.metrics = Some(metrics);
+    }
+
     /// Get flow control statistics
     pub fn statistics(&self) -> &FlowControlStatistics {
         &self.statistics
@@ -267,6 +277,9 @@ impl FlowControlManager {
         if new_rate < self.statistics.minimum_rate {
             self.statistics.minimum_rate = new_rate;
         }
+        if let Some(metrics) = &self.metrics {
+            metrics.record(MetricEvent::FlowControlRate(new_rate));
+        }
     }
 }
 
@@ -321,6 +334,30 @@ mod tests {
         assert!(manager.current_rate_limit() < 10.0); // Rate should be reduced
     }
 
+    #[test]
+    fn test_rate_changes_are_reported_to_metrics() {
+        use crate::metrics::SessionLabels;
+        use std::sync::{Arc, Mutex};
+
+        let rates = Arc::new(Mutex::new(Vec::new()));
+        let recorder = {
+            let rates = Arc::clone(&rates);
+            move |_: &SessionLabels, event: &MetricEvent| {
+                if let MetricEvent::FlowControlRate(rate) = event {
+                    rates.lock().unwrap().push(*rate);
+                }
+            }
+        };
+        let mut manager = FlowControlManager::new(10.0, 50.0, 1.0);
+        manager.set_metrics(SessionMetrics::new(Arc::new(recorder), "primary"));
+        manager.update_congestion_state(50);
+
+        let rates = rates.lock().unwrap();
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0], 10.0);
+        assert_eq!(rates[1], manager.current_rate_limit());
+    }
+
     #[test]
     fn test_error_based_adjustment() {
         let mut config = FlowControlConfig::default();
@@ -0,0 +1,656 @@
+// ABOUTME: Event-driven receiver API delivering inbound PDUs from the SMSC as a stream
+// ABOUTME: Classifies MO messages, receipts, data_sm and alerts, with ack handles and consumer backpressure
+
+use crate::Frame;
+use crate::client::alerts::SubscriberAlert;
+use crate::client::error::{SmppError, SmppResult};
+use crate::client::receipts::DeliveryReceipt;
+use crate::client::sequence::SequenceGenerator;
+use crate::client::timeouts::TimeoutConfig;
+use crate::connection::Connection;
+use crate::datatypes::{
+    AlertNotification, CommandId, CommandStatus, DataSm, DataSmResponse, DeliverSm,
+    DeliverSmResponse, EnquireLink, EnquireLinkResponse, GenericNack, MessageId, Unbind,
+    UnbindResponse,
+};
+use futures_core::Stream;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{Interval, MissedTickBehavior};
+use tracing::{Instrument, Span, debug, info, warn};
+
+/// esm_class bits 5-2 of a deliver_sm carrying an SMSC delivery receipt (SMPP v3.4 §5.2.12)
+const ESM_CLASS_TYPE_MASK: u8 = 0x3C;
+const ESM_CLASS_DELIVERY_RECEIPT: u8 = 0x04;
+
+/// Configuration for an [`InboundSession`]
+///
+/// # Example
+///
+/// ```rust
+/// use smpp::client::InboundConfig;
+///
+/// let config = InboundConfig::new().with_capacity(8).with_auto_ack(true);
+/// assert_eq!(config.capacity, 8);
+/// assert!(config.auto_ack);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboundConfig {
+    /// Events buffered before the session stops reading from the SMSC (default: 32)
+    pub capacity: usize,
+
+    /// Answer deliver_sm and data_sm with ESME_ROK as soon as they arrive (default: false)
+    pub auto_ack: bool,
+}
+
+impl Default for InboundConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 32,
+            auto_ack: false,
+        }
+    }
+}
+
+impl InboundConfig {
+    /// Create a configuration with the defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how many events are buffered before reading stops
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Set whether inbound messages are acknowledged automatically
+    pub fn with_auto_ack(mut self, auto_ack: bool) -> Self {
+        self.auto_ack = auto_ack;
+        self
+    }
+}
+
+/// A PDU received from the SMSC
+#[derive(Debug)]
+pub enum InboundEvent {
+    /// A mobile originated message delivered in a deliver_sm
+    Message {
+        /// The deliver_sm as received
+        pdu: Box<DeliverSm>,
+        /// Sends the deliver_sm_resp
+        ack: AckHandle,
+    },
+
+    /// A delivery receipt for a previously submitted message
+    Receipt {
+        /// The receipt parsed from the deliver_sm
+        receipt: DeliveryReceipt,
+        /// The deliver_sm as received
+        pdu: Box<DeliverSm>,
+        /// Sends the deliver_sm_resp
+        ack: AckHandle,
+    },
+
+    /// A data_sm from the SMSC
+    Data {
+        /// The data_sm as received
+        pdu: Box<DataSm>,
+        /// Sends the data_sm_resp
+        ack: AckHandle,
+    },
+
+    /// An alert_notification; it has no response
+    Alert {
+        /// The alert with its ms_availability_status decoded
+        alert: SubscriberAlert,
+        /// The alert_notification as received
+        pdu: AlertNotification,
+    },
+}
+
+impl InboundEvent {
+    /// Classify a deliver_sm as a delivery receipt or a mobile originated message
+    ///
+    /// A deliver_sm is a receipt when its esm_class says so or it carries a
+    /// `receipted_message_id` TLV, and the receipt can be parsed.
+    fn deliver(pdu: Box<DeliverSm>, ack: AckHandle) -> Self {
+        let flagged = pdu.esm_class.to_byte() & ESM_CLASS_TYPE_MASK == ESM_CLASS_DELIVERY_RECEIPT
+            || pdu.receipted_message_id.is_some();
+        match flagged.then(|| DeliveryReceipt::from_deliver_sm(&pdu)).flatten() {
+            Some(receipt) => InboundEvent::Receipt { receipt, pdu, ack },
+            None => InboundEvent::Message { pdu, ack },
+        }
+    }
+
+    /// The sequence number of the PDU
+    pub fn sequence_number(&self) -> u32 {
+        match self {
+            InboundEvent::Message { pdu, .. } | InboundEvent::Receipt { pdu, .. } => {
+                pdu.sequence_number
+            }
+            InboundEvent::Data { pdu, .. } => pdu.sequence_number,
+            InboundEvent::Alert { pdu, .. } => pdu.sequence_number,
+        }
+    }
+}
+
+/// Requests from the session and ack handles to the reader task
+enum Command {
+    Respond(Frame),
+    Close(oneshot::Sender<SmppResult<()>>),
+}
+
+/// Sends the response to one inbound deliver_sm or data_sm
+///
+/// Responding consumes the handle. A handle dropped without responding
+/// leaves the PDU unanswered, so the SMSC will time out and redeliver it.
+/// With [`InboundConfig::auto_ack`] the response has already been sent and
+/// responding again does nothing.
+pub struct AckHandle {
+    command_id: CommandId,
+    sequence_number: u32,
+    responder: Option<mpsc::UnboundedSender<Command>>,
+}
+
+impl AckHandle {
+    /// A handle whose response is sent through `responder`
+    fn new(command_id: CommandId, sequence_number: u32, responder: mpsc::UnboundedSender<Command>) -> Self {
+        Self {
+            command_id,
+            sequence_number,
+            responder: Some(responder),
+        }
+    }
+
+    /// A handle for a PDU that was acknowledged on arrival
+    fn acknowledged(command_id: CommandId, sequence_number: u32) -> Self {
+        Self {
+            command_id,
+            sequence_number,
+            responder: None,
+        }
+    }
+
+    /// The sequence number of the PDU being answered
+    pub fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+
+    /// Whether the response is still to be sent
+    pub fn is_pending(&self) -> bool {
+        self.responder.is_some()
+    }
+
+    /// Accept the PDU with ESME_ROK
+    pub fn ack(self) -> SmppResult<()> {
+        self.respond(CommandStatus::Ok)
+    }
+
+    /// Answer the PDU with `status`
+    ///
+    /// A non-OK status asks the SMSC to keep the message and retry it later
+    /// (e.g. [`CommandStatus::MessageQueueFull`]) or to drop it. Fails with
+    /// [`SmppError::ConnectionClosed`] when the session has ended.
+    pub fn respond(mut self, status: CommandStatus) -> SmppResult<()> {
+        let Some(responder) = self.responder.take() else {
+            return Ok(());
+        };
+        let frame = response_frame(self.command_id, self.sequence_number, status);
+        responder
+            .send(Command::Respond(frame))
+            .map_err(|_| SmppError::ConnectionClosed)
+    }
+}
+
+impl fmt::Debug for AckHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AckHandle")
+            .field("command_id", &self.command_id)
+            .field("sequence_number", &self.sequence_number)
+            .field("pending", &self.is_pending())
+            .finish()
+    }
+}
+
+impl Drop for AckHandle {
+    fn drop(&mut self) {
+        if self.responder.is_some() {
+            warn!(
+                sequence_number = self.sequence_number,
+                "{:?} dropped without a response; the SMSC will redeliver it", self.command_id
+            );
+        }
+    }
+}
+
+/// The response PDU answering `command_id` with `status`
+fn response_frame(command_id: CommandId, sequence_number: u32, status: CommandStatus) -> Frame {
+    match command_id {
+        CommandId::DataSm => Frame::DataSmResp(DataSmResponse {
+            command_status: status,
+            sequence_number,
+            message_id: MessageId::default(),
+            optional_parameters: Vec::new(),
+        }),
+        _ => Frame::DeliverSmResp(DeliverSmResponse {
+            command_status: status,
+            sequence_number,
+            message_id: MessageId::default(),
+        }),
+    }
+}
+
+/// A bound session delivering the SMSC's PDUs as a stream of [`InboundEvent`]s
+///
+/// Created by [`DefaultClient::into_inbound`](crate::client::DefaultClient::into_inbound),
+/// which hands the connection to a background task. The task answers
+/// enquire_link and unbind from the SMSC, sends enquire_link itself when the
+/// client had keep-alive running, and writes the responses sent through
+/// [`AckHandle`]s. At most [`InboundConfig::capacity`] events wait for the
+/// consumer; while they do the task stops reading, so a lagging consumer
+/// holds back the SMSC through its window rather than growing a buffer.
+///
+/// The stream ends when the SMSC unbinds or closes the connection; a read
+/// error is yielded once before the end. Dropping the session drops the
+/// connection without unbinding; use [`close`](Self::close) to unbind.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use smpp::client::{BindCredentials, DefaultClient, InboundConfig, InboundEvent};
+/// use smpp::client::{SmppClient, SmppConnection};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut client = DefaultClient::connect("localhost:2775").await?;
+/// client.bind(&BindCredentials::receiver("system_id", "password")).await?;
+/// let mut session = client.into_inbound(InboundConfig::default())?;
+///
+/// while let Some(event) = session.next().await {
+///     match event? {
+///         InboundEvent::Message { pdu, ack } => {
+///             println!("MO: {:?}", pdu.short_message);
+///             ack.ack()?;
+///         }
+///         InboundEvent::Receipt { receipt, ack, .. } => {
+///             println!("{} is {:?}", receipt.message_id, receipt.state);
+///             ack.ack()?;
+///         }
+///         InboundEvent::Data { ack, .. } => ack.ack()?,
+///         InboundEvent::Alert { alert, .. } => {
+///             println!("{} is {:?}", alert.subscriber, alert.availability);
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct InboundSession {
+    events: mpsc::Receiver<SmppResult<InboundEvent>>,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl InboundSession {
+    /// Start reading `connection` in a background task
+    pub(crate) fn spawn(
+        connection: Connection,
+        sequence: Arc<dyn SequenceGenerator>,
+        timeouts: TimeoutConfig,
+        enquire_interval: Option<Duration>,
+        config: InboundConfig,
+        span: Span,
+    ) -> Self {
+        let (events_tx, events) = mpsc::channel(config.capacity.max(1));
+        let (commands, commands_rx) = mpsc::unbounded_channel();
+        let ping = enquire_interval.map(|period| {
+            let mut ping = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            ping
+        });
+        let reader = Reader {
+            connection,
+            sequence,
+            timeouts,
+            auto_ack: config.auto_ack,
+            responder: commands.clone(),
+            events: events_tx,
+            commands: commands_rx,
+            ping,
+        };
+        tokio::spawn(reader.run().instrument(span));
+        Self { events, commands }
+    }
+
+    /// Wait for the next event
+    ///
+    /// Returns `None` once the session has ended.
+    pub async fn next(&mut self) -> Option<SmppResult<InboundEvent>> {
+        self.events.recv().await
+    }
+
+    /// Unbind and end the session
+    ///
+    /// Events not yet taken from the session are dropped unanswered, so the
+    /// SMSC will redeliver them.
+    pub async fn close(self) -> SmppResult<()> {
+        let (reply, result) = oneshot::channel();
+        if self.commands.send(Command::Close(reply)).is_err() {
+            // The session already ended
+            return Ok(());
+        }
+        result.await.unwrap_or(Ok(()))
+    }
+}
+
+impl Stream for InboundSession {
+    type Item = SmppResult<InboundEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+impl fmt::Debug for InboundSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InboundSession")
+            .field("buffered", &self.events.len())
+            .finish()
+    }
+}
+
+/// The background task owning the connection of an [`InboundSession`]
+struct Reader {
+    connection: Connection,
+    sequence: Arc<dyn SequenceGenerator>,
+    timeouts: TimeoutConfig,
+    auto_ack: bool,
+    /// Handed to ack handles
+    responder: mpsc::UnboundedSender<Command>,
+    events: mpsc::Sender<SmppResult<InboundEvent>>,
+    commands: mpsc::UnboundedReceiver<Command>,
+    ping: Option<Interval>,
+}
+
+/// Whether the reader carries on after handling a PDU or command
+enum Flow {
+    Continue,
+    Deliver(InboundEvent),
+    Stop,
+}
+
+impl Reader {
+    async fn run(mut self) {
+        let mut pending: Option<InboundEvent> = None;
+        loop {
+            let flow = match pending.take() {
+                // Stop reading until the consumer makes room, but keep
+                // writing the responses it sends meanwhile
+                Some(event) => {
+                    let events = self.events.clone();
+                    tokio::select! {
+                        permit = events.reserve() => match permit {
+                            Ok(permit) => {
+                                permit.send(Ok(event));
+                                Flow::Continue
+                            }
+                            Err(_) => Flow::Stop,
+                        },
+                        Some(command) = self.commands.recv() => {
+                            pending = Some(event);
+                            self.command(command).await
+                        }
+                        _ = tick(&mut self.ping) => {
+                            pending = Some(event);
+                            self.enquire_link().await
+                        }
+                    }
+                }
+                None => tokio::select! {
+                    _ = self.events.closed() => Flow::Stop,
+                    Some(command) = self.commands.recv() => self.command(command).await,
+                    _ = tick(&mut self.ping) => self.enquire_link().await,
+                    frame = self.connection.read_frame() => match frame {
+                        Ok(Some(frame)) => self.frame(frame).await,
+                        Ok(None) => {
+                            info!("SMSC closed the connection");
+                            Flow::Stop
+                        }
+                        Err(e) => self.fail(SmppError::from_read(e, None, None)).await,
+                    },
+                },
+            };
+
+            match flow {
+                Flow::Continue => {}
+                Flow::Deliver(event) => pending = Some(event),
+                Flow::Stop => return,
+            }
+        }
+    }
+
+    /// Handle a PDU from the SMSC
+    async fn frame(&mut self, frame: Frame) -> Flow {
+        let sequence_number = frame.sequence_number();
+        match frame {
+            Frame::DeliverSm(pdu) => match self.ack_handle(CommandId::DeliverSm, sequence_number).await {
+                Ok(ack) => Flow::Deliver(InboundEvent::deliver(pdu, ack)),
+                Err(e) => self.fail(e).await,
+            },
+            Frame::DataSm(pdu) => match self.ack_handle(CommandId::DataSm, sequence_number).await {
+                Ok(ack) => Flow::Deliver(InboundEvent::Data { pdu, ack }),
+                Err(e) => self.fail(e).await,
+            },
+            Frame::AlertNotification(pdu) => Flow::Deliver(InboundEvent::Alert {
+                alert: SubscriberAlert::from(&pdu),
+                pdu,
+            }),
+            Frame::EnquireLink(_) => {
+                let response = Frame::EnquireLinkResp(EnquireLinkResponse {
+                    command_status: CommandStatus::Ok,
+                    sequence_number,
+                });
+                self.write(&response).await
+            }
+            Frame::Unbind(_) => {
+                info!("SMSC unbound the session");
+                let response = Frame::UnbindResp(UnbindResponse {
+                    command_status: CommandStatus::Ok,
+                    sequence_number,
+                });
+                self.write(&response).await;
+                Flow::Stop
+            }
+            frame if frame.is_response() => {
+                // Answers to our own enquire_link, or late responses to
+                // requests the client abandoned before handing over
+                debug!(sequence_number, "Ignoring {:?}", frame.command_id());
+                self.sequence.release(sequence_number);
+                Flow::Continue
+            }
+            frame => {
+                warn!(sequence_number, "Rejecting unexpected {:?}", frame.command_id());
+                self.write(&Frame::GenericNack(GenericNack::invalid_command_id(sequence_number)))
+                    .await
+            }
+        }
+    }
+
+    /// The ack handle for an inbound request, acknowledging it now with auto-ack
+    async fn ack_handle(&mut self, command_id: CommandId, sequence_number: u32) -> SmppResult<AckHandle> {
+        if !self.auto_ack {
+            return Ok(AckHandle::new(command_id, sequence_number, self.responder.clone()));
+        }
+        let response = response_frame(command_id, sequence_number, CommandStatus::Ok);
+        self.connection
+            .write_frame(&response)
+            .await
+            .map_err(SmppError::Connection)?;
+        Ok(AckHandle::acknowledged(command_id, sequence_number))
+    }
+
+    /// Handle a command from the session or an ack handle
+    async fn command(&mut self, command: Command) -> Flow {
+        match command {
+            Command::Respond(frame) => self.write(&frame).await,
+            Command::Close(reply) => {
+                let _ = reply.send(self.unbind().await);
+                Flow::Stop
+            }
+        }
+    }
+
+    /// Send an unbind and wait for its response, dropping anything else received meanwhile
+    async fn unbind(&mut self) -> SmppResult<()> {
+        let sequence_number = self.sequence.next_sequence().ok_or_else(|| {
+            SmppError::InvalidState("Every sequence number is in flight".to_string())
+        })?;
+        let frame = Frame::Unbind(Unbind {
+            command_status: CommandStatus::Ok,
+            sequence_number,
+        });
+        let limit = self.timeouts.response_for(CommandId::Unbind);
+        let exchange = async {
+            self.connection
+                .write_frame(&frame)
+                .await
+                .map_err(SmppError::Connection)?;
+            loop {
+                match self.connection.read_frame().await {
+                    Ok(Some(Frame::UnbindResp(response))) if response.sequence_number == sequence_number => {
+                        return match response.command_status {
+                            CommandStatus::Ok => Ok(()),
+                            status => Err(SmppError::Protocol {
+                                command_id: CommandId::Unbind,
+                                sequence_number,
+                                status,
+                            }),
+                        };
+                    }
+                    Ok(Some(other)) => {
+                        debug!("Dropping {:?} received while unbinding", other.command_id());
+                    }
+                    // Connection closed during unbind is acceptable
+                    Ok(None) => return Ok(()),
+                    Err(e) => {
+                        return Err(SmppError::from_read(e, Some(CommandId::Unbind), Some(sequence_number)));
+                    }
+                }
+            }
+        };
+        let result = match tokio::time::timeout(limit, exchange).await {
+            Ok(result) => result,
+            Err(_) => Err(SmppError::Timeout {
+                operation: crate::client::error::TimeoutKind::Response {
+                    command_id: CommandId::Unbind,
+                    sequence_number,
+                },
+                timeout: limit,
+            }),
+        };
+        self.sequence.release(sequence_number);
+        result
+    }
+
+    /// Send an enquire_link; its response is ignored when it arrives
+    async fn enquire_link(&mut self) -> Flow {
+        let Some(sequence_number) = self.sequence.next_sequence() else {
+            warn!("Skipping enquire_link: every sequence number is in flight");
+            return Flow::Continue;
+        };
+        let frame = Frame::EnquireLink(EnquireLink::new(sequence_number));
+        self.write(&frame).await
+    }
+
+    /// Write a PDU, ending the session if the connection fails
+    async fn write(&mut self, frame: &Frame) -> Flow {
+        match self.connection.write_frame(frame).await {
+            Ok(()) => Flow::Continue,
+            Err(e) => self.fail(SmppError::Connection(e)).await,
+        }
+    }
+
+    /// Report `error` to the consumer and end the session
+    async fn fail(&mut self, error: SmppError) -> Flow {
+        warn!("Inbound session failed: {error}");
+        let _ = self.events.send(Err(error)).await;
+        Flow::Stop
+    }
+}
+
+/// Wait for the next keep-alive tick, or forever without keep-alive
+async fn tick(ping: &mut Option<Interval>) {
+    match ping {
+        Some(ping) => {
+            ping.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datatypes::{EsmClass, Tlv, tags};
+
+    fn deliver(text: &str) -> Box<DeliverSm> {
+        Box::new(DeliverSm::builder().short_message(text).build().unwrap())
+    }
+
+    #[test]
+    fn test_deliver_sm_is_classified_by_esm_class_and_tlvs() {
+        let text = "id:42 sub:001 dlvrd:001 submit date:2401011200 done date:2401011201 stat:DELIVRD err:000 text:hi";
+
+        // Receipt-looking text alone does not make an MO message a receipt
+        let event = InboundEvent::deliver(deliver(text), AckHandle::acknowledged(CommandId::DeliverSm, 1));
+        assert!(matches!(event, InboundEvent::Message { .. }));
+
+        let mut pdu = deliver(text);
+        pdu.esm_class = EsmClass::from_byte(ESM_CLASS_DELIVERY_RECEIPT).unwrap();
+        let event = InboundEvent::deliver(pdu, AckHandle::acknowledged(CommandId::DeliverSm, 1));
+        match event {
+            InboundEvent::Receipt { receipt, .. } => assert_eq!(receipt.message_id, "42"),
+            other => panic!("expected a receipt, got {other:?}"),
+        }
+
+        let mut pdu = deliver("");
+        pdu.receipted_message_id =
+            Some(Tlv::new(tags::RECEIPTED_MESSAGE_ID, b"abc\0".to_vec()).unwrap());
+        pdu.message_state = Some(Tlv::new(tags::MESSAGE_STATE, vec![2]).unwrap());
+        let event = InboundEvent::deliver(pdu, AckHandle::acknowledged(CommandId::DeliverSm, 1));
+        assert!(matches!(event, InboundEvent::Receipt { .. }));
+    }
+
+    #[test]
+    fn test_ack_handle_sends_response_once() {
+        let (responder, mut commands) = mpsc::unbounded_channel();
+        let ack = AckHandle::new(CommandId::DataSm, 9, responder);
+        assert!(ack.is_pending());
+        ack.respond(CommandStatus::MessageQueueFull).unwrap();
+
+        match commands.try_recv() {
+            Ok(Command::Respond(Frame::DataSmResp(response))) => {
+                assert_eq!(response.sequence_number, 9);
+                assert_eq!(response.command_status, CommandStatus::MessageQueueFull);
+            }
+            _ => panic!("expected a data_sm_resp"),
+        }
+        assert!(commands.try_recv().is_err());
+
+        // Auto-acknowledged handles have nothing left to send
+        let ack = AckHandle::acknowledged(CommandId::DeliverSm, 3);
+        assert!(!ack.is_pending());
+        assert!(ack.ack().is_ok());
+    }
+
+    #[test]
+    fn test_ack_after_session_end_reports_closed_connection() {
+        let (responder, commands) = mpsc::unbounded_channel();
+        drop(commands);
+        let ack = AckHandle::new(CommandId::DeliverSm, 1, responder);
+        assert!(matches!(ack.ack(), Err(SmppError::ConnectionClosed)));
+    }
+}
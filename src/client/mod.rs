@@ -10,6 +10,14 @@
 //! * **Type safety** - Different traits for transmitter/receiver/transceiver capabilities
 //! * **Builder patterns** - Easy client creation with sensible defaults
 //! * **Keep-alive support** - Automatic connection health monitoring
+//! * **Session timers** - Connect, bind, response and inactivity timeouts
+//! * **Retries** - Configurable resending of throttled or failed submissions
+//! * **Connection pooling** - Load-balanced, self-healing multi-bind pools
+//! * **Priority queueing** - Weighted per-priority lanes with backpressure
+//! * **Durable spooling** - Crash-safe replay of unacknowledged submissions
+//! * **Receipt correlation** - Matching delivery receipts to submitted messages
+//! * **Inbound streams** - Inbound PDUs as a stream of events with ack handles
+//! * **Subscriber alerts** - Typed alert_notification events and handlers
 //! * **Extensible** - Implement traits for custom client behavior
 //!
 //! ## Quick Start
@@ -136,20 +144,45 @@
 //! # }
 //! ```
 
+pub mod alerts;
 pub mod builder;
 pub mod default;
 pub mod error;
 pub mod flow_control;
+pub mod inbound;
 pub mod keepalive;
+pub mod pool;
+pub mod queue;
+pub mod receipts;
+pub mod retry;
+pub mod secret;
+pub mod sequence;
+pub mod spool;
+pub mod timeouts;
 pub mod traits;
 pub mod types;
 
 // Re-export the main types for easy access
+pub use alerts::{AlertHandler, SubscriberAlert};
 pub use builder::{ClientBuilder, ClientOptions};
 pub use default::DefaultClient;
-pub use error::{SmppError, SmppResult};
+pub use error::{SmppError, SmppResult, TimeoutKind};
 pub use flow_control::{FlowControlManager, FlowControlConfig, FlowControlAction, FlowControlStatistics};
+pub use inbound::{AckHandle, InboundConfig, InboundEvent, InboundSession};
 pub use keepalive::{KeepAliveConfig, KeepAliveManager, KeepAliveStatus};
+pub use pool::{LoadBalancing, PoolConfig, PooledSession, SmppPool};
+pub use queue::{OutboundQueue, PendingSubmit, QueueConfig, QueuedSubmit};
+pub use receipts::{
+    CorrelationStore, DeliveryReceipt, FileCorrelationStore, MemoryCorrelationStore,
+    ReceiptCorrelator, ReceiptEvent, Submission,
+};
+pub use retry::{BackoffPolicy, RetryPolicy};
+pub use secret::{CredentialError, Secret};
+pub use sequence::{
+    AtomicSequenceGenerator, FileSequenceGenerator, SequenceGenerator, WrappingSequenceGenerator,
+};
+pub use spool::{Recorded, Spool, SpoolConfig, SpoolEntry, SpoolId};
+pub use timeouts::TimeoutConfig;
 pub use traits::{
     SmppClient, SmppConnection, SmppReceiver, SmppTransceiver, SmppTransmitter,
     SmppV50Broadcaster, SmppV50Client,
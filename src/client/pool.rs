@@ -0,0 +1,649 @@
+// ABOUTME: Pool of bound SMPP sessions across one or more SMSC hosts with load-balanced dispatch
+// ABOUTME: Replaces unhealthy sessions, refills the pool and paces submits under one aggregate rate limit
+
+use crate::client::default::DefaultClient;
+use crate::client::error::{SmppError, SmppResult};
+use crate::client::keepalive::KeepAliveConfig;
+use crate::client::retry::RetryPolicy;
+use crate::client::timeouts::TimeoutConfig;
+use crate::client::traits::{SmppClient, SmppConnection, SmppTransmitter};
+use crate::client::types::{BindCredentials, SmsMessage};
+use crate::datatypes::{DataSm, SubmitMulti, SubmitMultiResponse, SubmitSm};
+use crate::metrics::{BindState, MetricEvent, SessionMetrics};
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex as StdMutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{Mutex, MutexGuard};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+/// How the pool picks a session for each request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoadBalancing {
+    /// Cycle through the bound sessions in turn
+    #[default]
+    RoundRobin,
+    /// Use the session with the fewest requests in progress or waiting
+    LeastOutstanding,
+}
+
+/// Configuration of a [`SmppPool`]
+///
+/// Session `i` connects to `endpoints[i % endpoints.len()]`, so the binds are
+/// spread evenly across the listed SMSC hosts. `size` should not exceed the
+/// number of concurrent binds the SMSC account allows.
+///
+/// # Example
+///
+/// ```rust
+/// use smpp::client::{BindCredentials, LoadBalancing, PoolConfig};
+///
+/// let config = PoolConfig::new(
+///     vec!["smsc-a:2775".to_string(), "smsc-b:2775".to_string()],
+///     BindCredentials::transmitter("system_id", "password"),
+/// )
+/// .with_size(4)
+/// .with_strategy(LoadBalancing::LeastOutstanding)
+/// .with_rate_limit(100.0);
+///
+/// assert_eq!(config.size, 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// SMSC addresses the sessions connect to
+    pub endpoints: Vec<String>,
+    /// Credentials used for every bind
+    pub credentials: BindCredentials,
+    /// Number of sessions to keep bound (default: 1)
+    pub size: usize,
+    /// How requests are spread over the sessions (default: round-robin)
+    pub strategy: LoadBalancing,
+    /// Maximum submits per second across all sessions (default: unlimited)
+    pub rate_limit: Option<f64>,
+    /// Keep-alive run on each session by [`SmppPool::maintain`] (default: none)
+    pub keep_alive: Option<KeepAliveConfig>,
+    /// Session timers applied to each session
+    pub timeouts: TimeoutConfig,
+    /// Retry policy applied to each session (default: no retries)
+    pub retry_policy: RetryPolicy,
+    /// Where each session's metrics are reported (default: nowhere)
+    pub metrics: Option<SessionMetrics>,
+}
+
+impl PoolConfig {
+    /// Create a configuration for a single session per pool
+    pub fn new(endpoints: Vec<String>, credentials: BindCredentials) -> Self {
+        Self {
+            endpoints,
+            credentials,
+            size: 1,
+            strategy: LoadBalancing::default(),
+            rate_limit: None,
+            keep_alive: None,
+            timeouts: TimeoutConfig::default(),
+            retry_policy: RetryPolicy::disabled(),
+            metrics: None,
+        }
+    }
+
+    /// Set the number of sessions to keep bound
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size.max(1);
+        self
+    }
+
+    /// Set how requests are spread over the sessions
+    pub fn with_strategy(mut self, strategy: LoadBalancing) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Limit submits across all sessions to `per_second`
+    ///
+    /// A rate that is not positive and finite leaves submits unlimited.
+    pub fn with_rate_limit(mut self, per_second: f64) -> Self {
+        self.rate_limit = Some(per_second);
+        self
+    }
+
+    /// Run keep-alive on every session
+    pub fn with_keep_alive(mut self, keep_alive: KeepAliveConfig) -> Self {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+
+    /// Set the session timers
+    pub fn with_timeouts(mut self, timeouts: TimeoutConfig) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    /// Set the retry policy used by every session
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Report every session's metrics to the recorder of `metrics`
+    ///
+    /// Session `i` is labelled `"{session}/{i}"`, where `session` is the
+    /// label `metrics` was created with. Besides the metrics of each client,
+    /// the pool reports the requests using or waiting for each session as its
+    /// window, and counts a reconnect whenever it binds a replacement.
+    pub fn with_metrics(mut self, metrics: SessionMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+/// One pool slot, holding a bound client while it is healthy
+struct Slot {
+    endpoint: String,
+    client: Mutex<Option<DefaultClient>>,
+    /// Requests using or waiting for this slot
+    outstanding: AtomicUsize,
+    /// Whether the slot holds a bound client
+    bound: AtomicBool,
+    /// How many times a client has been bound into the slot
+    binds: AtomicUsize,
+    metrics: Option<SessionMetrics>,
+}
+
+impl Slot {
+    fn record(&self, event: MetricEvent) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record(event);
+        }
+    }
+
+    fn record_window(&self) {
+        let in_flight = self.outstanding.load(Ordering::Relaxed);
+        self.record(MetricEvent::Window { in_flight });
+    }
+}
+
+/// Spaces permits evenly to enforce a requests-per-second limit
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    next: StdMutex<Instant>,
+}
+
+impl RateLimiter {
+    /// A limiter for `per_second` permits, or `None` unless that is a positive, finite rate
+    ///
+    /// An infinite rate means no limit; zero, negative and NaN rates are
+    /// ignored with a warning rather than stalling or panicking.
+    pub(crate) fn new(per_second: f64) -> Option<Self> {
+        if !(per_second.is_finite() && per_second > 0.0) {
+            if per_second != f64::INFINITY {
+                warn!(per_second, "Ignoring invalid rate limit; submits are not paced");
+            }
+            return None;
+        }
+        Some(Self {
+            interval: Duration::from_secs_f64(1.0 / per_second),
+            next: StdMutex::new(Instant::now()),
+        })
+    }
+
+    /// Wait until the next permit is available
+    pub(crate) async fn acquire(&self) {
+        let at = {
+            let mut next = self.next.lock().unwrap();
+            let at = (*next).max(Instant::now());
+            *next = at + self.interval;
+            at
+        };
+        tokio::time::sleep_until(at).await;
+    }
+}
+
+/// A fixed number of bound sessions shared by concurrent senders
+///
+/// All methods take `&self`, so the pool can be put in an `Arc` and used
+/// from many tasks. Each request is dispatched to a bound session chosen by
+/// the configured [`LoadBalancing`] strategy. A session is removed from the
+/// pool when a request on it fails with an error that
+/// [ends the session](SmppError::is_fatal_for_session), or when
+/// [`maintain`](Self::maintain) finds it disconnected or failing keep-alive;
+/// `maintain` then binds a replacement.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use smpp::client::{BindCredentials, PoolConfig, SmppPool, SmsMessage};
+/// use std::time::Duration;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let config = PoolConfig::new(
+///     vec!["localhost:2775".to_string()],
+///     BindCredentials::transmitter("system_id", "password"),
+/// )
+/// .with_size(3)
+/// .with_rate_limit(50.0);
+/// let pool = SmppPool::connect(config).await?;
+///
+/// let message_id = pool.send_sms(&SmsMessage::new("123456789", "987654321", "Hello!")).await?;
+///
+/// // Periodically replace dead sessions
+/// pool.maintain().await;
+/// pool.close().await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct SmppPool {
+    config: PoolConfig,
+    slots: Vec<Slot>,
+    next: AtomicUsize,
+    rate_limiter: Option<RateLimiter>,
+}
+
+impl SmppPool {
+    /// Create the pool and bind all of its sessions
+    ///
+    /// Sessions that fail to bind are left empty for
+    /// [`maintain`](Self::maintain) to retry; the pool is returned as long
+    /// as at least one session is bound.
+    pub async fn connect(config: PoolConfig) -> SmppResult<Self> {
+        if config.endpoints.is_empty() {
+            return Err(SmppError::InvalidData(
+                "Pool needs at least one SMSC endpoint".to_string(),
+            ));
+        }
+
+        let pool = Self::unbound(config);
+        let mut last_error = None;
+        for slot in &pool.slots {
+            if let Err(e) = pool.refill(slot).await {
+                last_error = Some(e);
+            }
+        }
+
+        match last_error {
+            Some(e) if pool.bound_sessions() == 0 => Err(e),
+            _ => Ok(pool),
+        }
+    }
+
+    /// Create the pool's slots without binding any session
+    fn unbound(config: PoolConfig) -> Self {
+        let slots = (0..config.size.max(1))
+            .map(|i| Slot {
+                endpoint: config.endpoints[i % config.endpoints.len()].clone(),
+                client: Mutex::new(None),
+                outstanding: AtomicUsize::new(0),
+                bound: AtomicBool::new(false),
+                binds: AtomicUsize::new(0),
+                metrics: config.metrics.as_ref().map(|metrics| {
+                    let session = format!("{}/{i}", metrics.labels().session);
+                    metrics
+                        .for_session(session)
+                        .with_system_id(&config.credentials.system_id)
+                }),
+            })
+            .collect();
+        Self {
+            rate_limiter: config.rate_limit.and_then(RateLimiter::new),
+            config,
+            slots,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// The pool's configuration
+    pub fn config(&self) -> &PoolConfig {
+        &self.config
+    }
+
+    /// Number of sessions currently bound
+    pub fn bound_sessions(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.bound.load(Ordering::Relaxed))
+            .count()
+    }
+
+    /// Number of requests using or waiting for a session
+    pub fn outstanding(&self) -> usize {
+        self.slots
+            .iter()
+            .map(|slot| slot.outstanding.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Check out a bound session for exclusive use
+    ///
+    /// The session is chosen by the load balancing strategy and returned to
+    /// the pool when the guard is dropped. Use this for operations the pool
+    /// does not wrap; call [`PooledSession::discard`] if the session turns
+    /// out to be unusable.
+    pub async fn session(&self) -> SmppResult<PooledSession<'_>> {
+        for _ in 0..self.slots.len() {
+            let Some(slot) = self.pick() else { break };
+            // Counted before waiting, so a cancelled wait is uncounted again
+            let outstanding = Outstanding::new(slot);
+            let guard = slot.client.lock().await;
+            let session = PooledSession {
+                slot,
+                guard,
+                _outstanding: outstanding,
+            };
+            if session.guard.is_some() {
+                return Ok(session);
+            }
+            // Removed while we were waiting for it; try another
+        }
+        Err(SmppError::InvalidState("No bound sessions in pool".to_string()))
+    }
+
+    /// Choose a bound slot according to the load balancing strategy
+    fn pick(&self) -> Option<&Slot> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let bound = (0..self.slots.len())
+            .map(|i| &self.slots[(start + i) % self.slots.len()])
+            .filter(|slot| slot.bound.load(Ordering::Relaxed));
+        match self.config.strategy {
+            LoadBalancing::RoundRobin => bound.into_iter().next(),
+            LoadBalancing::LeastOutstanding => {
+                bound.min_by_key(|slot| slot.outstanding.load(Ordering::Relaxed))
+            }
+        }
+    }
+
+    /// Send an SMS on one of the pool's sessions
+    pub async fn send_sms(&self, message: &SmsMessage) -> SmppResult<String> {
+        self.throttle().await;
+        let mut session = self.session().await?;
+        let result = session.client()?.send_sms(message).await;
+        session.observe(&result);
+        result
+    }
+
+    /// Send a submit_sm on one of the pool's sessions
+    ///
+    /// The PDU's sequence number is replaced by one allocated by the chosen
+    /// session.
+    pub async fn submit_sm(&self, submit: &SubmitSm) -> SmppResult<String> {
+        self.throttle().await;
+        let mut session = self.session().await?;
+        let result = session.client()?.submit_sm(submit).await;
+        session.observe(&result);
+        result
+    }
+
+    /// Send a data_sm on one of the pool's sessions
+    pub async fn data_sm(&self, data: &DataSm) -> SmppResult<String> {
+        self.throttle().await;
+        let mut session = self.session().await?;
+        let result = session.client()?.data_sm(data).await;
+        session.observe(&result);
+        result
+    }
+
+    /// Send a submit_multi on one of the pool's sessions
+    pub async fn submit_multi(&self, submit: &SubmitMulti) -> SmppResult<SubmitMultiResponse> {
+        self.throttle().await;
+        let mut session = self.session().await?;
+        let result = session.client()?.submit_multi(submit).await;
+        session.observe(&result);
+        result
+    }
+
+    /// Wait for the aggregate rate limit, if one is configured
+    async fn throttle(&self) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+    }
+
+    /// Check every session's health and bind replacements for removed ones
+    ///
+    /// Runs keep-alive on each idle session when configured, removes
+    /// sessions that are disconnected or have exhausted their keep-alive
+    /// failures, and binds a new session in every empty slot. Call this
+    /// periodically; it returns the number of bound sessions afterwards.
+    pub async fn maintain(&self) -> usize {
+        for slot in &self.slots {
+            // Sessions in use are healthy enough; check them next time
+            let Ok(mut guard) = slot.client.try_lock() else {
+                continue;
+            };
+            if let Some(client) = guard.as_mut() {
+                if let Err(e) = client.maintain_keep_alive().await {
+                    debug!(endpoint = %slot.endpoint, "Keep-alive failed: {e}");
+                }
+                if !client.is_connected() || client.is_keep_alive_failed() {
+                    warn!(endpoint = %slot.endpoint, "Removing unhealthy session from pool");
+                    *guard = None;
+                    slot.record(MetricEvent::BindState(BindState::Closed));
+                    slot.bound.store(false, Ordering::Relaxed);
+                }
+            }
+            drop(guard);
+
+            if !slot.bound.load(Ordering::Relaxed)
+                && let Err(e) = self.refill(slot).await
+            {
+                warn!(endpoint = %slot.endpoint, "Failed to bind pool session: {e}");
+            }
+        }
+        self.bound_sessions()
+    }
+
+    /// Bind a new session into an empty slot
+    async fn refill(&self, slot: &Slot) -> SmppResult<()> {
+        let mut guard = slot.client.lock().await;
+        if guard.is_some() {
+            return Ok(());
+        }
+
+        let mut client =
+            DefaultClient::connect_with_timeouts(slot.endpoint.as_str(), self.config.timeouts.clone())
+                .await?;
+        client.set_retry_policy(self.config.retry_policy.clone());
+        if let Some(metrics) = &slot.metrics {
+            client.set_metrics(metrics.clone());
+            if slot.binds.load(Ordering::Relaxed) > 0 {
+                metrics.record(MetricEvent::Reconnect);
+            }
+        }
+        client.bind(&self.config.credentials).await?;
+        if let Some(keep_alive) = &self.config.keep_alive {
+            client.start_keep_alive(keep_alive.clone()).await?;
+        }
+
+        info!(endpoint = %slot.endpoint, "Bound pool session");
+        *guard = Some(client);
+        slot.bound.store(true, Ordering::Relaxed);
+        slot.binds.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Unbind and disconnect every session
+    pub async fn close(&self) {
+        for slot in &self.slots {
+            let mut guard = slot.client.lock().await;
+            slot.bound.store(false, Ordering::Relaxed);
+            if let Some(mut client) = guard.take() {
+                if let Err(e) = client.unbind().await {
+                    debug!(endpoint = %slot.endpoint, "Unbind failed: {e}");
+                }
+                let _ = client.disconnect().await;
+            }
+        }
+    }
+}
+
+/// A session checked out of a [`SmppPool`]
+///
+/// Dereferences to the underlying [`DefaultClient`]. The session goes back
+/// into the pool when the guard is dropped, unless it has disconnected or
+/// been [discarded](Self::discard).
+pub struct PooledSession<'a> {
+    slot: &'a Slot,
+    guard: MutexGuard<'a, Option<DefaultClient>>,
+    _outstanding: Outstanding<'a>,
+}
+
+impl PooledSession<'_> {
+    /// The SMSC address this session is connected to
+    pub fn endpoint(&self) -> &str {
+        &self.slot.endpoint
+    }
+
+    /// Remove the session from the pool; [`SmppPool::maintain`] binds a replacement
+    pub fn discard(mut self) {
+        self.remove();
+    }
+
+    /// The client, unless the session has been removed from the pool
+    fn client(&mut self) -> SmppResult<&mut DefaultClient> {
+        self.guard
+            .as_mut()
+            .ok_or_else(|| SmppError::InvalidState("Session was removed from the pool".to_string()))
+    }
+
+    /// Discard the session if `result` is an error that ends it
+    fn observe<T>(mut self, result: &SmppResult<T>) {
+        if let Err(e) = result
+            && e.is_fatal_for_session()
+        {
+            warn!(endpoint = %self.slot.endpoint, "Removing session from pool: {e}");
+            self.remove();
+        }
+    }
+
+    /// Take the client out of its slot and mark the slot unbound
+    fn remove(&mut self) {
+        if self.guard.take().is_some() {
+            self.slot.record(MetricEvent::BindState(BindState::Closed));
+        }
+        self.slot.bound.store(false, Ordering::Relaxed);
+    }
+}
+
+// The guard is only emptied by `remove`, which is reached through methods
+// consuming the session or its drop, so a live session always holds a client
+impl Deref for PooledSession<'_> {
+    type Target = DefaultClient;
+
+    fn deref(&self) -> &DefaultClient {
+        self.guard.as_ref().expect("pooled session holds a client")
+    }
+}
+
+impl DerefMut for PooledSession<'_> {
+    fn deref_mut(&mut self) -> &mut DefaultClient {
+        self.guard.as_mut().expect("pooled session holds a client")
+    }
+}
+
+impl Drop for PooledSession<'_> {
+    fn drop(&mut self) {
+        if self.guard.as_ref().is_some_and(|client| !client.is_connected()) {
+            self.remove();
+        }
+    }
+}
+
+/// Counts one request as using or waiting for a slot until dropped
+struct Outstanding<'a>(&'a Slot);
+
+impl<'a> Outstanding<'a> {
+    fn new(slot: &'a Slot) -> Self {
+        slot.outstanding.fetch_add(1, Ordering::Relaxed);
+        slot.record_window();
+        Self(slot)
+    }
+}
+
+impl Drop for Outstanding<'_> {
+    fn drop(&mut self) {
+        self.0.outstanding.fetch_sub(1, Ordering::Relaxed);
+        self.0.record_window();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(size: usize, strategy: LoadBalancing) -> SmppPool {
+        let config = PoolConfig::new(
+            vec!["a:2775".to_string(), "b:2775".to_string()],
+            BindCredentials::transmitter("test", "pass"),
+        )
+        .with_size(size)
+        .with_strategy(strategy);
+        SmppPool::unbound(config)
+    }
+
+    #[test]
+    fn test_slots_spread_over_endpoints() {
+        let pool = pool(3, LoadBalancing::RoundRobin);
+        let endpoints: Vec<&str> = pool.slots.iter().map(|s| s.endpoint.as_str()).collect();
+        assert_eq!(endpoints, vec!["a:2775", "b:2775", "a:2775"]);
+        assert_eq!(pool.bound_sessions(), 0);
+        assert!(pool.pick().is_none());
+    }
+
+    #[test]
+    fn test_pick_skips_unbound_slots() {
+        let pool = pool(3, LoadBalancing::RoundRobin);
+        pool.slots[0].bound.store(true, Ordering::Relaxed);
+        pool.slots[2].bound.store(true, Ordering::Relaxed);
+
+        let picked: Vec<*const Slot> = (0..3).map(|_| pool.pick().unwrap() as *const Slot).collect();
+        assert_eq!(picked[0], &pool.slots[0] as *const Slot);
+        assert_eq!(picked[1], &pool.slots[2] as *const Slot);
+        assert_eq!(picked[2], &pool.slots[2] as *const Slot);
+    }
+
+    #[test]
+    fn test_least_outstanding_prefers_idle_slot() {
+        let pool = pool(2, LoadBalancing::LeastOutstanding);
+        for slot in &pool.slots {
+            slot.bound.store(true, Ordering::Relaxed);
+        }
+        pool.slots[0].outstanding.store(3, Ordering::Relaxed);
+        for _ in 0..4 {
+            assert!(std::ptr::eq(pool.pick().unwrap(), &pool.slots[1]));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_wait_for_session_is_not_counted() {
+        let pool = pool(1, LoadBalancing::RoundRobin);
+        pool.slots[0].bound.store(true, Ordering::Relaxed);
+        let busy = pool.slots[0].client.lock().await;
+
+        let waiting = tokio::time::timeout(Duration::from_millis(10), pool.session()).await;
+        assert!(waiting.is_err());
+        assert_eq!(pool.outstanding(), 0);
+        drop(busy);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_spaces_permits() {
+        let limiter = RateLimiter::new(50.0).unwrap();
+        let start = Instant::now();
+        for _ in 0..4 {
+            limiter.acquire().await;
+        }
+        // The first permit is immediate, the other three 20ms apart
+        assert!(start.elapsed() >= Duration::from_millis(55));
+    }
+    #[test]
+    fn test_rate_limiter_ignores_rates_it_cannot_pace() {
+        for per_second in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(RateLimiter::new(per_second).is_none(), "{per_second}");
+        }
+        let pool = SmppPool::unbound(
+            PoolConfig::new(vec!["a:2775".to_string()], BindCredentials::transmitter("t", "p"))
+                .with_rate_limit(0.0),
+        );
+        assert!(pool.rate_limiter.is_none());
+    }
+}
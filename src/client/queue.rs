@@ -0,0 +1,544 @@
+// ABOUTME: Priority-aware outbound submit_sm queue with per-priority lanes and weighted fair scheduling
+// ABOUTME: Applies backpressure to producers when senders fall behind and fails expired messages fast
+
+use crate::client::default::DefaultClient;
+use crate::client::error::{SmppError, SmppResult};
+use crate::client::pool::{RateLimiter, SmppPool};
+use crate::client::traits::SmppTransmitter;
+use crate::datatypes::{PriorityFlag, SubmitSm};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tokio::sync::{Notify, Semaphore, oneshot};
+use tracing::{debug, warn};
+
+/// Number of priority lanes, one per `priority_flag` level 0-3
+const LANES: usize = 4;
+
+/// Configuration of an [`OutboundQueue`]
+///
+/// # Example
+///
+/// ```rust
+/// use smpp::client::QueueConfig;
+///
+/// // Level 3 gets 16 sends for every level 0 send when all lanes are busy
+/// let config = QueueConfig::default()
+///     .with_capacity(10_000)
+///     .with_weights([1, 2, 4, 16])
+///     .with_window(8)
+///     .with_rate_limit(200.0);
+///
+/// assert_eq!(config.weights[3], 16);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueConfig {
+    /// Maximum number of queued messages across all lanes (default: 1000)
+    ///
+    /// Producers wait in [`OutboundQueue::enqueue`] while the queue is full.
+    pub capacity: usize,
+
+    /// Scheduling weight of each lane, indexed by `priority_flag` (default: 1, 2, 4, 8)
+    ///
+    /// While several lanes hold messages, each is served in proportion to
+    /// its weight, so high priorities go first without starving low ones.
+    pub weights: [u32; LANES],
+
+    /// Maximum submits in progress at once when draining into a pool (default: 1)
+    pub window: usize,
+
+    /// Maximum submits per second taken from the queue (default: unlimited)
+    pub rate_limit: Option<f64>,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1000,
+            weights: [1, 2, 4, 8],
+            window: 1,
+            rate_limit: None,
+        }
+    }
+}
+
+impl QueueConfig {
+    /// Set the maximum number of queued messages
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Set the scheduling weight of each priority lane
+    pub fn with_weights(mut self, weights: [u32; LANES]) -> Self {
+        self.weights = weights.map(|weight| weight.max(1));
+        self
+    }
+
+    /// Set the maximum number of submits in progress at once
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Limit submits taken from the queue to `per_second`
+    ///
+    /// A rate that is not positive and finite leaves submits unlimited.
+    pub fn with_rate_limit(mut self, per_second: f64) -> Self {
+        self.rate_limit = Some(per_second);
+        self
+    }
+}
+
+/// A message waiting in the queue together with the producer's result channel
+#[derive(Debug)]
+pub struct QueuedSubmit {
+    submit: SubmitSm,
+    enqueued_at: SystemTime,
+    responder: oneshot::Sender<SmppResult<String>>,
+}
+
+impl QueuedSubmit {
+    /// The queued PDU
+    pub fn submit(&self) -> &SubmitSm {
+        &self.submit
+    }
+
+    /// When the message was queued
+    pub fn enqueued_at(&self) -> SystemTime {
+        self.enqueued_at
+    }
+
+    /// Report the outcome of sending the message to its producer
+    pub fn complete(self, result: SmppResult<String>) {
+        // The producer may have stopped waiting; nothing to report to then
+        let _ = self.responder.send(result);
+    }
+
+    /// Fail the message if its validity period has passed at `now`
+    fn expire(self, now: SystemTime) -> Option<Self> {
+        if is_expired(&self.submit, self.enqueued_at, now) {
+            debug!(
+                validity_period = %self.submit.validity_period,
+                "Dropping expired message from queue"
+            );
+            let validity_period = self.submit.validity_period.to_string();
+            self.complete(Err(SmppError::Expired { validity_period }));
+            None
+        } else {
+            Some(self)
+        }
+    }
+}
+
+/// Handle to a queued message's eventual submit_sm result
+#[derive(Debug)]
+pub struct PendingSubmit {
+    receiver: oneshot::Receiver<SmppResult<String>>,
+}
+
+impl PendingSubmit {
+    /// Wait for the message to be sent, returning the SMSC's message ID
+    pub async fn wait(self) -> SmppResult<String> {
+        self.receiver.await.unwrap_or_else(|_| {
+            Err(SmppError::InvalidState(
+                "Message was dropped from the queue before it was sent".to_string(),
+            ))
+        })
+    }
+}
+
+/// Per-priority lanes with smooth weighted round-robin selection
+#[derive(Debug)]
+struct Lanes {
+    lanes: [VecDeque<QueuedSubmit>; LANES],
+    weights: [u32; LANES],
+    /// Running credit of each lane for the weighted round-robin
+    credit: [i64; LANES],
+}
+
+impl Lanes {
+    fn new(weights: [u32; LANES]) -> Self {
+        Self {
+            lanes: Default::default(),
+            weights,
+            credit: [0; LANES],
+        }
+    }
+
+    fn push(&mut self, item: QueuedSubmit) {
+        let lane = item.submit.priority_flag as usize;
+        self.lanes[lane].push_back(item);
+    }
+
+    /// Take the next message, serving busy lanes in proportion to their weights
+    fn pop(&mut self) -> Option<QueuedSubmit> {
+        let mut total = 0i64;
+        let mut best: Option<usize> = None;
+        for lane in 0..LANES {
+            if self.lanes[lane].is_empty() {
+                self.credit[lane] = 0;
+                continue;
+            }
+            let weight = self.weights[lane] as i64;
+            self.credit[lane] += weight;
+            total += weight;
+            // Ties go to the higher priority
+            if best.is_none_or(|b| self.credit[lane] >= self.credit[b]) {
+                best = Some(lane);
+            }
+        }
+        let lane = best?;
+        self.credit[lane] -= total;
+        self.lanes[lane].pop_front()
+    }
+
+    fn len(&self) -> usize {
+        self.lanes.iter().map(VecDeque::len).sum()
+    }
+}
+
+/// Outbound message queue placed in front of a transmitter
+///
+/// Producers [`enqueue`](Self::enqueue) submit_sm PDUs and get a
+/// [`PendingSubmit`] for the result. Messages are held in one lane per
+/// `priority_flag` level and taken out by weighted fair scheduling (see
+/// [`QueueConfig::weights`]), so an OTP at level 3 does not wait behind a
+/// marketing batch at level 0.
+///
+/// The queue is bounded: once the senders draining it fall behind - their
+/// window is full or the rate limit is reached - the queue fills up and
+/// `enqueue` waits for space, pushing back on producers. A message whose
+/// `validity_period` has passed is failed with [`SmppError::Expired`] instead
+/// of being sent, both when it is queued and when it is taken out. Relative
+/// validity periods count from the time the message was queued.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use smpp::client::{DefaultClient, OutboundQueue, QueueConfig, SmppConnection};
+/// use smpp::datatypes::{PriorityFlag, SubmitSm};
+/// use std::sync::Arc;
+///
+/// # async fn example(otp: SubmitSm) -> Result<(), Box<dyn std::error::Error>> {
+/// let queue = Arc::new(OutboundQueue::new(QueueConfig::default()));
+/// let mut client = DefaultClient::connect("localhost:2775").await?;
+///
+/// let sender = Arc::clone(&queue);
+/// tokio::spawn(async move { sender.run(&mut client).await });
+///
+/// let pending = queue.enqueue(SubmitSm { priority_flag: PriorityFlag::Level3, ..otp }).await?;
+/// let message_id = pending.wait().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct OutboundQueue {
+    config: QueueConfig,
+    lanes: Mutex<Lanes>,
+    /// One permit per free place in the queue
+    space: Semaphore,
+    /// Signalled when a message is queued or the queue is closed
+    ready: Notify,
+    rate_limiter: Option<RateLimiter>,
+}
+
+impl OutboundQueue {
+    /// Create an empty queue
+    pub fn new(config: QueueConfig) -> Self {
+        Self {
+            lanes: Mutex::new(Lanes::new(config.weights)),
+            space: Semaphore::new(config.capacity),
+            ready: Notify::new(),
+            rate_limiter: config.rate_limit.and_then(RateLimiter::new),
+            config,
+        }
+    }
+
+    /// The queue's configuration
+    pub fn config(&self) -> &QueueConfig {
+        &self.config
+    }
+
+    /// Number of queued messages
+    pub fn len(&self) -> usize {
+        self.lanes.lock().unwrap().len()
+    }
+
+    /// Whether no messages are queued
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of messages queued at one priority
+    pub fn len_for(&self, priority: PriorityFlag) -> usize {
+        self.lanes.lock().unwrap().lanes[priority as usize].len()
+    }
+
+    /// Queue a message, waiting while the queue is full
+    ///
+    /// Fails with [`SmppError::Expired`] if the validity period has already
+    /// passed, and with [`SmppError::InvalidState`] once the queue is closed.
+    pub async fn enqueue(&self, submit: SubmitSm) -> SmppResult<PendingSubmit> {
+        let enqueued_at = SystemTime::now();
+        if is_expired(&submit, enqueued_at, enqueued_at) {
+            return Err(SmppError::Expired {
+                validity_period: submit.validity_period.to_string(),
+            });
+        }
+
+        let permit = self.space.acquire().await.map_err(|_| closed())?;
+        permit.forget();
+
+        Ok(self.push(submit, enqueued_at))
+    }
+
+    /// Queue a message if there is room, without waiting
+    pub fn try_enqueue(&self, submit: SubmitSm) -> SmppResult<PendingSubmit> {
+        let enqueued_at = SystemTime::now();
+        if is_expired(&submit, enqueued_at, enqueued_at) {
+            return Err(SmppError::Expired {
+                validity_period: submit.validity_period.to_string(),
+            });
+        }
+
+        match self.space.try_acquire() {
+            Ok(permit) => permit.forget(),
+            Err(tokio::sync::TryAcquireError::Closed) => return Err(closed()),
+            Err(tokio::sync::TryAcquireError::NoPermits) => {
+                return Err(SmppError::InvalidState("Outbound queue is full".to_string()));
+            }
+        }
+
+        Ok(self.push(submit, enqueued_at))
+    }
+
+    /// Add a message to its lane once a place has been reserved for it
+    fn push(&self, submit: SubmitSm, enqueued_at: SystemTime) -> PendingSubmit {
+        let (responder, receiver) = oneshot::channel();
+        self.lanes.lock().unwrap().push(QueuedSubmit {
+            submit,
+            enqueued_at,
+            responder,
+        });
+        self.ready.notify_one();
+        PendingSubmit { receiver }
+    }
+
+    /// Stop accepting messages
+    ///
+    /// Messages already queued are still handed out by [`next`](Self::next),
+    /// which returns `None` once they are gone.
+    pub fn close(&self) {
+        self.space.close();
+        self.ready.notify_waiters();
+        self.ready.notify_one();
+    }
+
+    /// Whether [`close`](Self::close) has been called
+    pub fn is_closed(&self) -> bool {
+        self.space.is_closed()
+    }
+
+    /// Take the next message to send, waiting for one to be queued
+    ///
+    /// Honours the rate limit and fails expired messages on the way. Returns
+    /// `None` when the queue is closed and empty.
+    pub async fn next(&self) -> Option<QueuedSubmit> {
+        loop {
+            let ready = self.ready.notified();
+            let item = self.lanes.lock().unwrap().pop();
+            match item {
+                Some(item) => {
+                    self.space.add_permits(1);
+                    let Some(item) = item.expire(SystemTime::now()) else {
+                        continue;
+                    };
+                    if let Some(limiter) = &self.rate_limiter {
+                        limiter.acquire().await;
+                    }
+                    return Some(item);
+                }
+                None if self.is_closed() => return None,
+                None => ready.await,
+            }
+        }
+    }
+
+    /// Send queued messages on `client` until the queue is closed and empty
+    ///
+    /// Each PDU gets a sequence number from the client. Stops early, returning
+    /// the error, when a send fails in a way that ends the session; that
+    /// message is failed and the rest stay queued for another sender.
+    pub async fn run(&self, client: &mut DefaultClient) -> SmppResult<()> {
+        while let Some(item) = self.next().await {
+            match client.submit_sm(&item.submit).await {
+                Err(e) if e.is_fatal_for_session() => {
+                    warn!("Outbound queue sender stopped: {e}");
+                    let error = SmppError::InvalidState(format!("Session failed: {e}"));
+                    item.complete(Err(error));
+                    return Err(e);
+                }
+                result => item.complete(result),
+            }
+        }
+        Ok(())
+    }
+
+    /// Send queued messages through `pool` until the queue is closed and empty
+    ///
+    /// Up to [`QueueConfig::window`] submits are in progress at once; while
+    /// the window is full no more messages are taken from the queue.
+    pub async fn run_pool(self: Arc<Self>, pool: Arc<SmppPool>) {
+        let window = Arc::new(Semaphore::new(self.config.window));
+        loop {
+            let Ok(permit) = Arc::clone(&window).acquire_owned().await else {
+                return;
+            };
+            let Some(item) = self.next().await else { break };
+            let pool = Arc::clone(&pool);
+            tokio::spawn(async move {
+                let result = pool.submit_sm(&item.submit).await;
+                item.complete(result);
+                drop(permit);
+            });
+        }
+        // Wait for the sends still in progress
+        let _ = window.acquire_many(self.config.window as u32).await;
+    }
+}
+
+fn closed() -> SmppError {
+    SmppError::InvalidState("Outbound queue is closed".to_string())
+}
+
+/// Whether the message's validity period has passed at `now`
+///
+/// Relative periods count from `enqueued_at`. Periods that cannot be
+/// interpreted are left for the SMSC to judge.
+fn is_expired(submit: &SubmitSm, enqueued_at: SystemTime, now: SystemTime) -> bool {
+    if submit.validity_period.is_immediate() {
+        return false;
+    }
+    match submit.validity_period.resolve(enqueued_at) {
+        Ok(expires_at) => expires_at <= now,
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datatypes::SmppDateTime;
+    use std::time::Duration;
+
+    fn base() -> SubmitSm {
+        SubmitSm::builder()
+            .source_addr("1234")
+            .destination_addr("5678")
+            .short_message("Hello")
+            .build()
+            .unwrap()
+    }
+
+    fn submit(priority_flag: PriorityFlag) -> SubmitSm {
+        SubmitSm {
+            priority_flag,
+            ..base()
+        }
+    }
+
+    fn queued(priority_flag: PriorityFlag) -> QueuedSubmit {
+        QueuedSubmit {
+            submit: submit(priority_flag),
+            enqueued_at: SystemTime::now(),
+            responder: oneshot::channel().0,
+        }
+    }
+
+    #[test]
+    fn test_lanes_share_by_weight() {
+        let mut lanes = Lanes::new([1, 2, 4, 8]);
+        let priorities = [
+            PriorityFlag::Level0,
+            PriorityFlag::Level1,
+            PriorityFlag::Level2,
+            PriorityFlag::Level3,
+        ];
+        for priority in priorities {
+            for _ in 0..20 {
+                lanes.push(queued(priority));
+            }
+        }
+
+        let mut served = [0; LANES];
+        for _ in 0..15 {
+            served[lanes.pop().unwrap().submit.priority_flag as usize] += 1;
+        }
+        assert_eq!(served, [1, 2, 4, 8]);
+
+        // The highest priority goes first, but level 0 is not starved
+        let mut lanes = Lanes::new([1, 2, 4, 8]);
+        lanes.push(queued(PriorityFlag::Level0));
+        lanes.push(queued(PriorityFlag::Level3));
+        lanes.push(queued(PriorityFlag::Level3));
+        let order: Vec<u8> = std::iter::from_fn(|| lanes.pop())
+            .map(|item| item.submit.priority_flag as u8)
+            .collect();
+        assert_eq!(order, vec![3, 3, 0]);
+    }
+
+    #[test]
+    fn test_expired_validity_period_fails_fast() {
+        let now = SystemTime::now();
+        let past = SmppDateTime::from_system_time(now - Duration::from_secs(60)).unwrap();
+        let expired = SubmitSm {
+            validity_period: past,
+            ..base()
+        };
+        assert!(is_expired(&expired, now, now));
+
+        let relative = SubmitSm {
+            validity_period: SmppDateTime::relative(Duration::from_secs(60)).unwrap(),
+            ..base()
+        };
+        assert!(!is_expired(&relative, now, now));
+        assert!(is_expired(&relative, now, now + Duration::from_secs(61)));
+        assert!(!is_expired(&base(), now, now));
+
+        let queue = OutboundQueue::new(QueueConfig::default());
+        assert!(matches!(queue.try_enqueue(expired), Err(SmppError::Expired { .. })));
+        assert!(queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_pushes_back_on_producers() {
+        let queue = Arc::new(OutboundQueue::new(QueueConfig::default().with_capacity(2)));
+        queue.enqueue(submit(PriorityFlag::Level0)).await.unwrap();
+        queue.enqueue(submit(PriorityFlag::Level0)).await.unwrap();
+        assert!(matches!(
+            queue.try_enqueue(submit(PriorityFlag::Level0)),
+            Err(SmppError::InvalidState(_))
+        ));
+
+        let producer = {
+            let queue = Arc::clone(&queue);
+            tokio::spawn(async move { queue.enqueue(submit(PriorityFlag::Level3)).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!producer.is_finished());
+
+        // Taking a message out frees a place for the waiting producer
+        let item = queue.next().await.unwrap();
+        item.complete(Ok("id".to_string()));
+        let pending = producer.await.unwrap().unwrap();
+        assert_eq!(queue.len_for(PriorityFlag::Level3), 1);
+
+        queue.close();
+        assert_eq!(queue.next().await.unwrap().submit().priority_flag, PriorityFlag::Level3);
+        assert_eq!(queue.next().await.unwrap().submit().priority_flag, PriorityFlag::Level0);
+        assert!(queue.next().await.is_none());
+        assert!(matches!(pending.wait().await, Err(SmppError::InvalidState(_))));
+    }
+}
@@ -0,0 +1,805 @@
+// ABOUTME: Delivery receipt parsing and correlation of receipts with submitted messages
+// ABOUTME: Matches receipts to submit_sm_resp message IDs across hex/decimal formats, in memory or file-backed
+
+use crate::datatypes::{DeliverSm, MessageState, SmppDateTime};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// A delivery receipt carried by a deliver_sm (or data_sm) from the SMSC
+///
+/// Built from the `receipted_message_id` and `message_state` TLVs when the
+/// SMSC sends them, otherwise from the de-facto standard receipt text of
+/// SMPP v3.4 Appendix B:
+///
+/// ```text
+/// id:IIIIIIIIII sub:SSS dlvrd:DDD submit date:YYMMDDhhmm done date:YYMMDDhhmm stat:DDDDDDD err:E text:...
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeliveryReceipt {
+    /// The message_id the SMSC returned in submit_sm_resp, as the receipt gives it
+    pub message_id: String,
+    /// State of the message
+    pub state: MessageState,
+    /// The receipt's `err:` code, if any
+    pub error: Option<String>,
+    /// When the message was submitted, per the receipt
+    pub submit_date: Option<SmppDateTime>,
+    /// When the message reached the reported state, per the receipt
+    pub done_date: Option<SmppDateTime>,
+    /// The start of the original message text, if included
+    pub text: Option<String>,
+}
+
+impl DeliveryReceipt {
+    /// Extract the receipt from a deliver_sm, if it carries one
+    ///
+    /// TLVs take precedence over the receipt text. Returns `None` when
+    /// neither a message id nor a state can be found.
+    pub fn from_deliver_sm(deliver: &DeliverSm) -> Option<Self> {
+        let text = match &deliver.message_payload {
+            Some(payload) => String::from_utf8_lossy(&payload.value).into_owned(),
+            None => String::from_utf8_lossy(deliver.short_message.as_bytes()).into_owned(),
+        };
+        let fields = ReceiptText::parse(&text);
+
+        let message_id = deliver
+            .receipted_message_id
+            .as_ref()
+            .map(|tlv| {
+                String::from_utf8_lossy(&tlv.value)
+                    .trim_end_matches('\0')
+                    .to_string()
+            })
+            .filter(|id| !id.is_empty())
+            .or_else(|| fields.id.map(str::to_string))?;
+
+        let state = deliver
+            .message_state
+            .as_ref()
+            .and_then(|tlv| tlv.value.first().copied())
+            .and_then(|value| MessageState::try_from(value).ok())
+            .or_else(|| fields.stat.and_then(state_from_stat))?;
+
+        Some(Self {
+            message_id,
+            state,
+            error: fields.err.map(str::to_string),
+            submit_date: fields
+                .submit_date
+                .and_then(|date| SmppDateTime::from_receipt_date(date).ok()),
+            done_date: fields
+                .done_date
+                .and_then(|date| SmppDateTime::from_receipt_date(date).ok()),
+            text: fields.text.map(str::to_string),
+        })
+    }
+
+    /// Whether the state is final, so no further receipts will follow
+    pub fn is_final(&self) -> bool {
+        !matches!(self.state, MessageState::Enroute)
+    }
+}
+
+/// Fields of the receipt text format
+#[derive(Debug, Default)]
+struct ReceiptText<'a> {
+    id: Option<&'a str>,
+    submit_date: Option<&'a str>,
+    done_date: Option<&'a str>,
+    stat: Option<&'a str>,
+    err: Option<&'a str>,
+    text: Option<&'a str>,
+}
+
+impl<'a> ReceiptText<'a> {
+    fn parse(text: &'a str) -> Self {
+        Self {
+            id: receipt_field(text, "id:"),
+            submit_date: receipt_field(text, "submit date:"),
+            done_date: receipt_field(text, "done date:"),
+            stat: receipt_field(text, "stat:"),
+            err: receipt_field(text, "err:"),
+            // The text runs to the end of the receipt
+            text: find_key(text, "text:").map(|start| &text[start..]),
+        }
+    }
+}
+
+/// Offset just past `key` where it starts a word, ignoring ASCII case
+fn find_key(text: &str, key: &str) -> Option<usize> {
+    let lower = text.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find(key) {
+        let start = from + pos;
+        if start == 0 || lower.as_bytes()[start - 1] == b' ' {
+            return Some(start + key.len());
+        }
+        from = start + key.len();
+    }
+    None
+}
+
+/// The space-delimited value following `key`
+fn receipt_field<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    let start = find_key(text, key)?;
+    let value = text[start..].split(' ').next().unwrap_or("");
+    (!value.is_empty()).then_some(value)
+}
+
+/// Map a receipt `stat:` value to a message state
+fn state_from_stat(stat: &str) -> Option<MessageState> {
+    match stat.to_ascii_uppercase().as_str() {
+        "ENROUTE" => Some(MessageState::Enroute),
+        "DELIVRD" => Some(MessageState::Delivered),
+        "EXPIRED" => Some(MessageState::Expired),
+        "DELETED" => Some(MessageState::Deleted),
+        "UNDELIV" => Some(MessageState::Undeliverable),
+        "ACCEPTD" => Some(MessageState::Accepted),
+        "UNKNOWN" => Some(MessageState::Unknown),
+        "REJECTD" => Some(MessageState::Rejected),
+        _ => None,
+    }
+}
+
+/// Canonical form of a message id: trimmed, lower case, without leading zeros
+pub fn normalize_message_id(message_id: &str) -> String {
+    let id = message_id.trim().trim_end_matches('\0').to_ascii_lowercase();
+    let stripped = id.trim_start_matches('0');
+    if stripped.is_empty() && !id.is_empty() {
+        "0".to_string()
+    } else {
+        stripped.to_string()
+    }
+}
+
+/// The forms a receipt may use to refer to `message_id`, canonical form first
+///
+/// Many SMSCs return the message_id in submit_sm_resp as hexadecimal but
+/// quote it in decimal in the receipt text, or the other way round. After
+/// the canonical id come the decimal reading of a hexadecimal id and the
+/// hexadecimal form of a decimal id. Numeric ids are ambiguous - "10" and
+/// "16" are each other's readings - so correlation only falls back on these
+/// readings when the canonical id matches nothing and exactly one reading
+/// does.
+pub fn message_id_variants(message_id: &str) -> Vec<String> {
+    let id = normalize_message_id(message_id);
+    let mut variants = vec![id.clone()];
+    if let Ok(value) = u128::from_str_radix(&id, 16) {
+        variants.push(value.to_string());
+    }
+    if id.bytes().all(|b| b.is_ascii_digit())
+        && let Ok(value) = id.parse::<u128>()
+    {
+        variants.push(format!("{value:x}"));
+    }
+    variants.dedup();
+    variants.retain(|variant| !variant.is_empty());
+    variants
+}
+
+/// A message awaiting its delivery receipt
+#[derive(Debug, Clone, PartialEq)]
+pub struct Submission {
+    /// The caller's own reference for the message
+    pub reference: String,
+    /// The message_id from submit_sm_resp
+    pub message_id: String,
+    pub submitted_at: SystemTime,
+}
+
+/// A message reached a final state
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceiptEvent {
+    /// The caller's reference given when the submission was recorded
+    pub reference: String,
+    /// The message_id from submit_sm_resp
+    pub message_id: String,
+    pub receipt: DeliveryReceipt,
+}
+
+/// Storage behind a [`ReceiptCorrelator`]
+///
+/// Holds submissions until their final receipt arrives and final receipts
+/// that arrive before their submission is recorded (the SMSC may send the
+/// receipt before our submit_sm_resp has been processed). Entries older than
+/// the store's time-to-live are dropped by
+/// [`evict_expired`](Self::evict_expired).
+pub trait CorrelationStore: Send + Sync + fmt::Debug {
+    /// Record a submission, returning the receipt if it already arrived
+    fn submitted(&self, submission: Submission) -> io::Result<Option<DeliveryReceipt>>;
+
+    /// Match a receipt to its submission
+    ///
+    /// A final receipt settles the submission, removing it from the store. A
+    /// final receipt with no matching submission is kept until the
+    /// submission is recorded; `None` is returned for it.
+    fn receipt(&self, receipt: &DeliveryReceipt) -> io::Result<Option<Submission>>;
+
+    /// Drop entries older than the time-to-live, returning how many were dropped
+    fn evict_expired(&self, now: SystemTime) -> io::Result<usize>;
+
+    /// Number of submissions awaiting a final receipt
+    fn pending(&self) -> usize;
+}
+
+#[derive(Debug, Default)]
+struct CorrelationState {
+    /// Submissions keyed by canonical message_id
+    submissions: HashMap<String, Submission>,
+    /// Early final receipts keyed by canonical message_id
+    receipts: HashMap<String, (DeliveryReceipt, SystemTime)>,
+}
+
+impl CorrelationState {
+    fn submitted(&mut self, submission: Submission) -> Option<DeliveryReceipt> {
+        if let Some(key) = matching_key(&self.receipts, &submission.message_id) {
+            return self.receipts.remove(&key).map(|(receipt, _)| receipt);
+        }
+        let key = normalize_message_id(&submission.message_id);
+        self.submissions.insert(key, submission);
+        None
+    }
+
+    fn receipt(&mut self, receipt: &DeliveryReceipt, now: SystemTime) -> Option<Submission> {
+        match matching_key(&self.submissions, &receipt.message_id) {
+            Some(key) => {
+                let submission = self.submissions.get(&key).cloned();
+                if receipt.is_final() {
+                    self.submissions.remove(&key);
+                }
+                submission
+            }
+            None => {
+                if receipt.is_final() {
+                    let key = normalize_message_id(&receipt.message_id);
+                    self.receipts.insert(key, (receipt.clone(), now));
+                }
+                None
+            }
+        }
+    }
+
+    fn settle(&mut self, message_id: &str) {
+        self.submissions.remove(&normalize_message_id(message_id));
+    }
+
+    fn evict(&mut self, cutoff: SystemTime) -> usize {
+        let before = self.pending() + self.receipts.len();
+        self.submissions.retain(|_, s| s.submitted_at >= cutoff);
+        self.receipts.retain(|_, (_, at)| *at >= cutoff);
+        before - self.pending() - self.receipts.len()
+    }
+
+    fn pending(&self) -> usize {
+        self.submissions.len()
+    }
+}
+
+/// The key `entries` holds `message_id` under, if any
+///
+/// The canonical id wins; otherwise another reading of the id is used only
+/// if it is the one reading present, since several would be ambiguous.
+fn matching_key<T>(entries: &HashMap<String, T>, message_id: &str) -> Option<String> {
+    let mut variants = message_id_variants(message_id).into_iter();
+    let canonical = variants.next()?;
+    if entries.contains_key(&canonical) {
+        return Some(canonical);
+    }
+    let mut readings = variants.filter(|variant| entries.contains_key(variant));
+    match (readings.next(), readings.next()) {
+        (Some(reading), None) => Some(reading),
+        (Some(_), Some(_)) => {
+            debug!(message_id, "Message id matches several readings; not correlating it");
+            None
+        }
+        _ => None,
+    }
+}
+
+/// In-memory [`CorrelationStore`]; its contents are lost on restart
+#[derive(Debug)]
+pub struct MemoryCorrelationStore {
+    ttl: Duration,
+    state: Mutex<CorrelationState>,
+}
+
+impl MemoryCorrelationStore {
+    /// Create a store keeping entries for `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            state: Mutex::new(CorrelationState::default()),
+        }
+    }
+}
+
+impl CorrelationStore for MemoryCorrelationStore {
+    fn submitted(&self, submission: Submission) -> io::Result<Option<DeliveryReceipt>> {
+        Ok(self.state.lock().unwrap().submitted(submission))
+    }
+
+    fn receipt(&self, receipt: &DeliveryReceipt) -> io::Result<Option<Submission>> {
+        Ok(self.state.lock().unwrap().receipt(receipt, SystemTime::now()))
+    }
+
+    fn evict_expired(&self, now: SystemTime) -> io::Result<usize> {
+        let cutoff = now.checked_sub(self.ttl).unwrap_or(UNIX_EPOCH);
+        Ok(self.state.lock().unwrap().evict(cutoff))
+    }
+
+    fn pending(&self) -> usize {
+        self.state.lock().unwrap().pending()
+    }
+}
+
+/// [`CorrelationStore`] that survives restarts
+///
+/// Every change is appended to a line-oriented journal, which is replayed
+/// and rewritten (dropping expired and settled entries) when the store is
+/// opened, and by [`compact`](Self::compact).
+#[derive(Debug)]
+pub struct FileCorrelationStore {
+    path: PathBuf,
+    ttl: Duration,
+    inner: Mutex<FileCorrelationState>,
+}
+
+#[derive(Debug)]
+struct FileCorrelationState {
+    state: CorrelationState,
+    journal: File,
+}
+
+impl FileCorrelationStore {
+    /// Open (or create) the journal at `path`, keeping entries for `ttl`
+    pub fn open(path: impl AsRef<Path>, ttl: Duration) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut state = CorrelationState::default();
+        match File::open(&path) {
+            Ok(file) => {
+                for (number, line) in BufReader::new(file).lines().enumerate() {
+                    let line = line?;
+                    if let Err(reason) = apply_journal_line(&mut state, &line) {
+                        // A crash can leave the last line incomplete
+                        warn!(path = %path.display(), line = number + 1, "Skipping journal line: {reason}");
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        let cutoff = SystemTime::now().checked_sub(ttl).unwrap_or(UNIX_EPOCH);
+        state.evict(cutoff);
+        let journal = rewrite_journal(&path, &state)?;
+        Ok(Self {
+            path,
+            ttl,
+            inner: Mutex::new(FileCorrelationState { state, journal }),
+        })
+    }
+
+    /// The journal backing this store
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rewrite the journal with only the current entries
+    pub fn compact(&self) -> io::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.journal = rewrite_journal(&self.path, &inner.state)?;
+        Ok(())
+    }
+}
+
+impl CorrelationStore for FileCorrelationStore {
+    fn submitted(&self, submission: Submission) -> io::Result<Option<DeliveryReceipt>> {
+        let mut inner = self.inner.lock().unwrap();
+        writeln!(inner.journal, "{}", submission_line(&submission))?;
+        let receipt = inner.state.submitted(submission.clone());
+        if receipt.is_some() {
+            writeln!(inner.journal, "M\t{}", escape(&submission.message_id))?;
+        }
+        Ok(receipt)
+    }
+
+    fn receipt(&self, receipt: &DeliveryReceipt) -> io::Result<Option<Submission>> {
+        let now = SystemTime::now();
+        let mut inner = self.inner.lock().unwrap();
+        let submission = inner.state.receipt(receipt, now);
+        if receipt.is_final() {
+            let line = match &submission {
+                Some(submission) => format!("M\t{}", escape(&submission.message_id)),
+                None => receipt_line(receipt, now),
+            };
+            writeln!(inner.journal, "{line}")?;
+        }
+        Ok(submission)
+    }
+
+    fn evict_expired(&self, now: SystemTime) -> io::Result<usize> {
+        let cutoff = now.checked_sub(self.ttl).unwrap_or(UNIX_EPOCH);
+        // Eviction is repeated when the journal is replayed, so needs no record
+        Ok(self.inner.lock().unwrap().state.evict(cutoff))
+    }
+
+    fn pending(&self) -> usize {
+        self.inner.lock().unwrap().state.pending()
+    }
+}
+
+/// Replace the journal with one recreating `state`, returning it open for appending
+fn rewrite_journal(path: &Path, state: &CorrelationState) -> io::Result<File> {
+    let mut contents = String::new();
+    for submission in state.submissions.values() {
+        contents.push_str(&submission_line(submission));
+        contents.push('\n');
+    }
+    for (receipt, at) in state.receipts.values() {
+        contents.push_str(&receipt_line(receipt, *at));
+        contents.push('\n');
+    }
+
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(&temp, path)?;
+    OpenOptions::new().append(true).open(path)
+}
+
+fn submission_line(submission: &Submission) -> String {
+    format!(
+        "S\t{}\t{}\t{}",
+        millis(submission.submitted_at),
+        escape(&submission.reference),
+        escape(&submission.message_id)
+    )
+}
+
+fn receipt_line(receipt: &DeliveryReceipt, at: SystemTime) -> String {
+    let date = |date: &Option<SmppDateTime>| date.as_ref().map(ToString::to_string).unwrap_or_default();
+    format!(
+        "R\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        millis(at),
+        escape(&receipt.message_id),
+        receipt.state.clone() as u8,
+        escape(receipt.error.as_deref().unwrap_or("")),
+        date(&receipt.submit_date),
+        date(&receipt.done_date),
+        escape(receipt.text.as_deref().unwrap_or(""))
+    )
+}
+
+fn apply_journal_line(state: &mut CorrelationState, line: &str) -> Result<(), String> {
+    let fields: Vec<String> = line.split('\t').map(unescape).collect();
+    let time = |field: &str| {
+        field
+            .parse::<u64>()
+            .map(|ms| UNIX_EPOCH + Duration::from_millis(ms))
+            .map_err(|e| format!("invalid timestamp: {e}"))
+    };
+    let optional = |field: &String| (!field.is_empty()).then(|| field.clone());
+    let date = |field: &String| SmppDateTime::new(field).ok();
+
+    match fields.as_slice() {
+        [kind, at, reference, message_id] if kind == "S" => {
+            let submission = Submission {
+                reference: reference.clone(),
+                message_id: message_id.clone(),
+                submitted_at: time(at)?,
+            };
+            // Receipts journalled before the submission settle it straight away
+            if state.submitted(submission.clone()).is_some() {
+                state.settle(&submission.message_id);
+            }
+        }
+        [kind, at, message_id, state_value, error, submit_date, done_date, text] if kind == "R" => {
+            let value = state_value
+                .parse::<u8>()
+                .map_err(|e| format!("invalid message state: {e}"))?;
+            let receipt = DeliveryReceipt {
+                message_id: message_id.clone(),
+                state: MessageState::try_from(value)
+                    .map_err(|_| format!("unknown message state {value}"))?,
+                error: optional(error),
+                submit_date: date(submit_date),
+                done_date: date(done_date),
+                text: optional(text),
+            };
+            state.receipt(&receipt, time(at)?);
+        }
+        [kind, message_id] if kind == "M" => state.settle(message_id),
+        _ => return Err("unrecognised record".to_string()),
+    }
+    Ok(())
+}
+
+fn millis(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0)
+}
+
+/// Escape tabs, newlines and backslashes for the journal
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Joins delivery receipts to the messages they report on
+///
+/// Record each submission from the transmitter path with
+/// [`record_submission`](Self::record_submission) once its submit_sm_resp
+/// arrives, and pass every deliver_sm from the receiver path to
+/// [`ingest`](Self::ingest). When a message reaches a final state a
+/// [`ReceiptEvent`] carrying the caller's reference is returned and also
+/// sent on the channel returned by [`new`](Self::new). The correlator is
+/// cheap to clone, so both paths can hold one.
+///
+/// # Example
+///
+/// ```rust
+/// use smpp::client::{MemoryCorrelationStore, ReceiptCorrelator};
+/// use smpp::datatypes::DeliverSm;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// let store = Arc::new(MemoryCorrelationStore::new(Duration::from_secs(72 * 3600)));
+/// let (correlator, mut events) = ReceiptCorrelator::new(store);
+///
+/// // submit_sm_resp returned a hexadecimal id...
+/// correlator.record_submission("order-17", "1F4").unwrap();
+///
+/// // ...and the receipt quotes it in decimal
+/// let receipt = DeliverSm::builder()
+///     .short_message("id:500 sub:001 dlvrd:001 submit date:2401011200 done date:2401011201 stat:DELIVRD err:000 text:")
+///     .build()
+///     .unwrap();
+/// let event = correlator.ingest(&receipt).unwrap().unwrap();
+/// assert_eq!(event.reference, "order-17");
+/// assert_eq!(events.try_recv().unwrap(), event);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReceiptCorrelator {
+    store: Arc<dyn CorrelationStore>,
+    events: mpsc::UnboundedSender<ReceiptEvent>,
+}
+
+impl ReceiptCorrelator {
+    /// Create a correlator over `store`, with the channel its events are sent on
+    pub fn new(store: Arc<dyn CorrelationStore>) -> (Self, mpsc::UnboundedReceiver<ReceiptEvent>) {
+        let (events, receiver) = mpsc::unbounded_channel();
+        (Self { store, events }, receiver)
+    }
+
+    /// Record that the message known to us as `reference` was given `message_id`
+    ///
+    /// Returns the final event straight away if the receipt arrived first.
+    pub fn record_submission(
+        &self,
+        reference: impl Into<String>,
+        message_id: &str,
+    ) -> io::Result<Option<ReceiptEvent>> {
+        let submission = Submission {
+            reference: reference.into(),
+            message_id: message_id.to_string(),
+            submitted_at: SystemTime::now(),
+        };
+        let receipt = self.store.submitted(submission.clone())?;
+        Ok(receipt.map(|receipt| self.emit(submission, receipt)))
+    }
+
+    /// Process a deliver_sm, returning the final event if it completes a message
+    ///
+    /// Deliver_sm PDUs that are not receipts are ignored.
+    pub fn ingest(&self, deliver: &DeliverSm) -> io::Result<Option<ReceiptEvent>> {
+        match DeliveryReceipt::from_deliver_sm(deliver) {
+            Some(receipt) => self.ingest_receipt(receipt),
+            None => Ok(None),
+        }
+    }
+
+    /// Process an already parsed receipt
+    pub fn ingest_receipt(&self, receipt: DeliveryReceipt) -> io::Result<Option<ReceiptEvent>> {
+        match self.store.receipt(&receipt)? {
+            Some(submission) if receipt.is_final() => Ok(Some(self.emit(submission, receipt))),
+            Some(_) => Ok(None),
+            None => {
+                debug!(message_id = %receipt.message_id, "Receipt arrived before its submission");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Drop submissions and early receipts older than the store's time-to-live
+    pub fn evict_expired(&self) -> io::Result<usize> {
+        self.store.evict_expired(SystemTime::now())
+    }
+
+    /// Number of submissions awaiting a final receipt
+    pub fn pending(&self) -> usize {
+        self.store.pending()
+    }
+
+    fn emit(&self, submission: Submission, receipt: DeliveryReceipt) -> ReceiptEvent {
+        let event = ReceiptEvent {
+            reference: submission.reference,
+            message_id: submission.message_id,
+            receipt,
+        };
+        // Callers may rely on the return value alone and drop the receiver
+        let _ = self.events.send(event.clone());
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datatypes::{Tlv, tags};
+
+    fn receipt_text(id: &str, stat: &str) -> DeliverSm {
+        let text = format!(
+            "id:{id} sub:001 dlvrd:001 submit date:2401011200 done date:2401011201 stat:{stat} err:000 text:Hello world"
+        );
+        DeliverSm::builder()
+            .short_message(&text)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_receipt_text() {
+        let receipt = DeliveryReceipt::from_deliver_sm(&receipt_text("0A1B", "DELIVRD")).unwrap();
+        assert_eq!(receipt.message_id, "0A1B");
+        assert_eq!(receipt.state, MessageState::Delivered);
+        assert_eq!(receipt.error.as_deref(), Some("000"));
+        assert_eq!(receipt.text.as_deref(), Some("Hello world"));
+        assert_eq!(receipt.done_date.unwrap().minute(), Some(1));
+        assert!(receipt.is_final());
+
+        let plain = DeliverSm::builder()
+            .short_message("Hi there")
+            .build()
+            .unwrap();
+        assert!(DeliveryReceipt::from_deliver_sm(&plain).is_none());
+    }
+
+    #[test]
+    fn test_receipt_tlvs_take_precedence() {
+        let mut deliver = receipt_text("1", "ENROUTE");
+        deliver.receipted_message_id =
+            Some(Tlv::new(tags::RECEIPTED_MESSAGE_ID, b"abc\0".to_vec()).unwrap());
+        deliver.message_state =
+            Some(Tlv::new(tags::MESSAGE_STATE, vec![MessageState::Undeliverable as u8]).unwrap());
+        let receipt = DeliveryReceipt::from_deliver_sm(&deliver).unwrap();
+        assert_eq!(receipt.message_id, "abc");
+        assert_eq!(receipt.state, MessageState::Undeliverable);
+    }
+
+    #[test]
+    fn test_message_id_variants() {
+        assert_eq!(message_id_variants("0A1B"), vec!["a1b", "2587"]);
+        assert_eq!(message_id_variants("2587"), vec!["2587", "9607", "a1b"]);
+        assert_eq!(message_id_variants("msg-1"), vec!["msg-1"]);
+        assert_eq!(normalize_message_id("000"), "0");
+    }
+
+    #[test]
+    fn test_intermediate_receipt_keeps_submission() {
+        let store = Arc::new(MemoryCorrelationStore::new(Duration::from_secs(60)));
+        let (correlator, mut events) = ReceiptCorrelator::new(store);
+        correlator.record_submission("ref", "2587").unwrap();
+
+        assert_eq!(correlator.ingest(&receipt_text("2587", "ENROUTE")).unwrap(), None);
+        assert_eq!(correlator.pending(), 1);
+
+        let event = correlator.ingest(&receipt_text("0a1b", "UNDELIV")).unwrap().unwrap();
+        assert_eq!(event.reference, "ref");
+        assert_eq!(event.receipt.state, MessageState::Undeliverable);
+        assert_eq!(correlator.pending(), 0);
+        assert_eq!(events.try_recv().unwrap(), event);
+    }
+
+    #[test]
+    fn test_ids_that_are_each_others_readings_are_not_confused() {
+        let store = Arc::new(MemoryCorrelationStore::new(Duration::from_secs(60)));
+        let (correlator, _events) = ReceiptCorrelator::new(store);
+        // "10" read as hex is 16, and 16 written in hex is "10"
+        correlator.record_submission("ten", "10").unwrap();
+        correlator.record_submission("sixteen", "16").unwrap();
+        assert_eq!(correlator.pending(), 2);
+
+        let event = correlator.ingest(&receipt_text("10", "DELIVRD")).unwrap().unwrap();
+        assert_eq!(event.reference, "ten");
+        let event = correlator.ingest(&receipt_text("16", "DELIVRD")).unwrap().unwrap();
+        assert_eq!(event.reference, "sixteen");
+        assert_eq!(correlator.pending(), 0);
+
+        // "16" has two readings, "22" and "10"; with both pending it matches neither
+        correlator.record_submission("twenty-two", "22").unwrap();
+        correlator.record_submission("ten again", "10").unwrap();
+        assert_eq!(correlator.ingest(&receipt_text("16", "DELIVRD")).unwrap(), None);
+        assert_eq!(correlator.pending(), 2);
+    }
+
+    #[test]
+    fn test_receipt_before_submit_response() {
+        let store = Arc::new(MemoryCorrelationStore::new(Duration::from_secs(60)));
+        let (correlator, _events) = ReceiptCorrelator::new(store);
+        assert_eq!(correlator.ingest(&receipt_text("77", "DELIVRD")).unwrap(), None);
+
+        let event = correlator.record_submission("early", "77").unwrap().unwrap();
+        assert_eq!(event.reference, "early");
+        assert_eq!(correlator.pending(), 0);
+    }
+
+    #[test]
+    fn test_ttl_eviction() {
+        let store = MemoryCorrelationStore::new(Duration::from_secs(60));
+        store
+            .submitted(Submission {
+                reference: "old".to_string(),
+                message_id: "123".to_string(),
+                submitted_at: SystemTime::now() - Duration::from_secs(120),
+            })
+            .unwrap();
+        assert_eq!(store.pending(), 1);
+        assert_eq!(store.evict_expired(SystemTime::now()).unwrap(), 1);
+        assert_eq!(store.pending(), 0);
+    }
+
+    #[test]
+    fn test_file_store_survives_restart() {
+        let path = std::env::temp_dir().join(format!("smpp-receipts-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let ttl = Duration::from_secs(3600);
+
+        {
+            let store = Arc::new(FileCorrelationStore::open(&path, ttl).unwrap());
+            let (correlator, _events) = ReceiptCorrelator::new(store);
+            correlator.record_submission("first\tref", "1F4").unwrap();
+            correlator.record_submission("second", "abc").unwrap();
+            correlator.ingest(&receipt_text("abc", "DELIVRD")).unwrap().unwrap();
+            // A receipt for a submission not yet recorded
+            correlator.ingest(&receipt_text("999", "EXPIRED")).unwrap();
+        }
+
+        let store = Arc::new(FileCorrelationStore::open(&path, ttl).unwrap());
+        let (correlator, _events) = ReceiptCorrelator::new(store);
+        assert_eq!(correlator.pending(), 1);
+        let event = correlator.ingest(&receipt_text("500", "DELIVRD")).unwrap().unwrap();
+        assert_eq!(event.reference, "first\tref");
+
+        let event = correlator.record_submission("third", "999").unwrap().unwrap();
+        assert_eq!(event.receipt.state, MessageState::Expired);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
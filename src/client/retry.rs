@@ -0,0 +1,258 @@
+// ABOUTME: Retry policy for message submission with separate backoff for throttling and system errors
+// ABOUTME: Decides whether a failed submit_sm/data_sm/submit_multi is resent and how long to wait first
+
+use crate::client::error::{SmppError, TimeoutKind};
+use std::time::Duration;
+
+/// Exponential backoff between attempts
+///
+/// The delay before retry `n` (1 for the first retry) is
+/// `initial * multiplier^(n - 1)`, capped at `max`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackoffPolicy {
+    /// Delay before the first retry
+    pub initial: Duration,
+    /// Factor applied to the delay after each retry
+    pub multiplier: f64,
+    /// Upper bound on the computed delay
+    pub max: Duration,
+}
+
+impl BackoffPolicy {
+    /// Create a backoff starting at `initial` and doubling up to `max`
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            multiplier: 2.0,
+            max,
+        }
+    }
+
+    /// Set the factor applied to the delay after each retry
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// The delay before retry number `retry` (starting at 1)
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.max(1.0).powi(retry.saturating_sub(1) as i32);
+        let delay = self.initial.as_secs_f64() * factor;
+        if !delay.is_finite() || delay >= self.max.as_secs_f64() {
+            self.max
+        } else {
+            Duration::from_secs_f64(delay)
+        }
+    }
+}
+
+/// When and how often failed message submissions are resent
+///
+/// Applies to submit_sm, data_sm and submit_multi sent through
+/// [`DefaultClient`](crate::client::DefaultClient). A request is resent only
+/// when the SMSC answered with a transient status (see
+/// [`CommandStatus::should_retry`](crate::datatypes::CommandStatus::should_retry));
+/// throttling statuses (see
+/// [`CommandStatus::is_throttling_related`](crate::datatypes::CommandStatus::is_throttling_related))
+/// use the `throttling` backoff and all others the `system` backoff. When
+/// `honour_suggested_delay` is set the wait is never shorter than
+/// [`CommandStatus::suggested_retry_delay`](crate::datatypes::CommandStatus::suggested_retry_delay).
+///
+/// A response timeout is ambiguous: the SMSC may have accepted the message
+/// and only the response was lost, so resending risks a duplicate. Such
+/// requests are resent only with `retry_ambiguous_timeouts`. Errors that end
+/// the session (lost connections, undecodable responses) are never retried.
+///
+/// # Example
+///
+/// ```rust
+/// use smpp::client::{BackoffPolicy, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::new(5)
+///     .with_throttling(BackoffPolicy::new(Duration::from_secs(1), Duration::from_secs(60)))
+///     .with_honour_suggested_delay(false);
+///
+/// assert_eq!(policy.max_attempts, 5);
+/// assert!(!policy.retry_ambiguous_timeouts);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first (default: 3)
+    ///
+    /// 1 disables retrying.
+    pub max_attempts: u32,
+
+    /// Backoff after throttling statuses (default: 1s doubling up to 30s)
+    pub throttling: BackoffPolicy,
+
+    /// Backoff after other transient statuses (default: 500ms doubling up to 10s)
+    pub system: BackoffPolicy,
+
+    /// Wait at least the status's suggested retry delay (default: true)
+    pub honour_suggested_delay: bool,
+
+    /// Resend after a response timeout, accepting possible duplicates (default: false)
+    pub retry_ambiguous_timeouts: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            throttling: BackoffPolicy::new(Duration::from_secs(1), Duration::from_secs(30)),
+            system: BackoffPolicy::new(Duration::from_millis(500), Duration::from_secs(10)),
+            honour_suggested_delay: true,
+            retry_ambiguous_timeouts: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a policy allowing `max_attempts` attempts with the default backoff
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            ..Self::default()
+        }
+    }
+
+    /// A policy that never retries
+    pub fn disabled() -> Self {
+        Self::new(1)
+    }
+
+    /// Set the total number of attempts, including the first
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the backoff used after throttling statuses
+    pub fn with_throttling(mut self, backoff: BackoffPolicy) -> Self {
+        self.throttling = backoff;
+        self
+    }
+
+    /// Set the backoff used after other transient statuses
+    pub fn with_system(mut self, backoff: BackoffPolicy) -> Self {
+        self.system = backoff;
+        self
+    }
+
+    /// Set whether the status's suggested retry delay is a lower bound on the wait
+    pub fn with_honour_suggested_delay(mut self, honour: bool) -> Self {
+        self.honour_suggested_delay = honour;
+        self
+    }
+
+    /// Set whether requests are resent after a response timeout
+    pub fn with_retry_ambiguous_timeouts(mut self, retry: bool) -> Self {
+        self.retry_ambiguous_timeouts = retry;
+        self
+    }
+
+    /// How long to wait before resending after `error` on attempt `attempt`
+    ///
+    /// `attempt` counts from 1 for the first send. Returns `None` when the
+    /// request should not be resent.
+    pub fn retry_delay(&self, error: &SmppError, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        let backoff = match error {
+            SmppError::Protocol { status, .. } if status.should_retry() => {
+                if status.is_throttling_related() {
+                    &self.throttling
+                } else {
+                    &self.system
+                }
+            }
+            SmppError::Timeout {
+                operation: TimeoutKind::Response { .. },
+                ..
+            } if self.retry_ambiguous_timeouts && !error.is_fatal_for_session() => &self.system,
+            _ => return None,
+        };
+
+        let delay = backoff.delay(attempt);
+        match error.retry_after() {
+            Some(suggested) if self.honour_suggested_delay => Some(delay.max(suggested)),
+            _ => Some(delay),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datatypes::{CommandId, CommandStatus};
+
+    fn rejected(status: CommandStatus) -> SmppError {
+        SmppError::Protocol {
+            command_id: CommandId::SubmitSm,
+            sequence_number: 1,
+            status,
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        let backoff = BackoffPolicy::new(Duration::from_millis(100), Duration::from_millis(350));
+        assert_eq!(backoff.delay(1), Duration::from_millis(100));
+        assert_eq!(backoff.delay(2), Duration::from_millis(200));
+        assert_eq!(backoff.delay(3), Duration::from_millis(350));
+        assert_eq!(backoff.delay(100), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_throttling_and_system_errors_use_separate_backoff() {
+        let policy = RetryPolicy::new(4)
+            .with_throttling(BackoffPolicy::new(Duration::from_secs(2), Duration::from_secs(60)))
+            .with_system(BackoffPolicy::new(Duration::from_millis(10), Duration::from_secs(1)))
+            .with_honour_suggested_delay(false);
+
+        let throttled = rejected(CommandStatus::ThrottlingError);
+        assert_eq!(policy.retry_delay(&throttled, 1), Some(Duration::from_secs(2)));
+        assert_eq!(policy.retry_delay(&throttled, 2), Some(Duration::from_secs(4)));
+
+        let system = rejected(CommandStatus::SystemError);
+        assert_eq!(policy.retry_delay(&system, 1), Some(Duration::from_millis(10)));
+
+        // The cap includes the first attempt
+        assert_eq!(policy.retry_delay(&system, 4), None);
+        // Permanent rejections are never resent
+        assert_eq!(policy.retry_delay(&rejected(CommandStatus::InvalidDestinationAddress), 1), None);
+    }
+
+    #[test]
+    fn test_suggested_delay_is_a_lower_bound() {
+        let policy = RetryPolicy::default()
+            .with_system(BackoffPolicy::new(Duration::from_millis(10), Duration::from_secs(1)));
+        let queue_full = rejected(CommandStatus::MessageQueueFull);
+        assert_eq!(policy.retry_delay(&queue_full, 1), queue_full.retry_after());
+
+        let policy = policy.with_honour_suggested_delay(false);
+        assert_eq!(policy.retry_delay(&queue_full, 1), Some(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn test_ambiguous_timeouts_need_opt_in() {
+        let timeout = SmppError::Timeout {
+            operation: TimeoutKind::Response {
+                command_id: CommandId::SubmitSm,
+                sequence_number: 5,
+            },
+            timeout: Duration::from_secs(30),
+        };
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.retry_delay(&timeout, 1), None);
+        assert!(policy.clone().with_retry_ambiguous_timeouts(true).retry_delay(&timeout, 1).is_some());
+
+        // A lost connection ends the session whatever the policy says
+        let policy = policy.with_retry_ambiguous_timeouts(true);
+        assert_eq!(policy.retry_delay(&SmppError::ConnectionClosed, 1), None);
+        assert_eq!(RetryPolicy::disabled().retry_delay(&rejected(CommandStatus::ThrottlingError), 1), None);
+    }
+}
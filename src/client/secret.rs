@@ -0,0 +1,170 @@
+// ABOUTME: A secret string for bind passwords, redacted when formatted and zeroised when dropped
+// ABOUTME: Secrets can be given directly or loaded from an environment variable or a file
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+/// A password or other secret held by the client
+///
+/// `Debug` and `Display` show `<redacted>` rather than the secret, and its
+/// memory is zeroised when it is dropped. [`expose`](Self::expose) gives the
+/// secret itself, for the few places that need it.
+///
+/// Besides converting a `String` or `&str`, a secret can be read from an
+/// environment variable or from a file, e.g. one mounted by a secret store:
+///
+/// ```rust,no_run
+/// use smpp::client::{BindCredentials, Secret};
+///
+/// # fn example() -> Result<(), smpp::client::CredentialError> {
+/// let password = Secret::from_file("/run/secrets/smpp_password")?;
+/// let credentials = BindCredentials::transmitter("system_id", password);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret(Zeroizing<String>);
+
+impl Secret {
+    /// Wrap `secret`, which is zeroised when the `Secret` is dropped
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(Zeroizing::new(secret.into()))
+    }
+
+    /// Read the secret from the environment variable `var`
+    pub fn from_env(var: &str) -> Result<Self, CredentialError> {
+        env_var(var).map(Self::new)
+    }
+
+    /// Read the secret from the file at `path`, without its trailing line break
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CredentialError> {
+        let path = path.as_ref();
+        let mut secret = std::fs::read_to_string(path).map_err(|source| CredentialError::File {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        secret.truncate(secret.trim_end_matches(['\r', '\n']).len());
+        // The truncated line break is zeroised along with the rest of the capacity
+        Ok(Self::new(secret))
+    }
+
+    /// The secret itself
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns true if the secret is empty, e.g. for an SMSC that takes no password
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret(<redacted>)")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<redacted>")
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl From<&String> for Secret {
+    fn from(secret: &String) -> Self {
+        Self::new(secret.as_str())
+    }
+}
+
+impl PartialEq<str> for Secret {
+    fn eq(&self, other: &str) -> bool {
+        self.expose() == other
+    }
+}
+
+impl PartialEq<&str> for Secret {
+    fn eq(&self, other: &&str) -> bool {
+        self.expose() == *other
+    }
+}
+
+/// The value of the environment variable `var`
+pub(crate) fn env_var(var: &str) -> Result<String, CredentialError> {
+    std::env::var(var).map_err(|e| match e {
+        std::env::VarError::NotPresent => CredentialError::MissingVariable(var.to_string()),
+        std::env::VarError::NotUnicode(_) => CredentialError::NotUnicode(var.to_string()),
+    })
+}
+
+/// Credentials could not be loaded
+#[derive(Debug, thiserror::Error)]
+pub enum CredentialError {
+    #[error("Environment variable {0} is not set")]
+    MissingVariable(String),
+
+    #[error("Environment variable {0} is not valid unicode")]
+    NotUnicode(String),
+
+    #[error("Cannot read {}: {source}", path.display())]
+    File {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_is_redacted_when_formatted() {
+        let secret = Secret::from("hunter2");
+        assert_eq!(format!("{secret:?}"), "Secret(<redacted>)");
+        assert_eq!(secret.to_string(), "<redacted>");
+        assert_eq!(secret.expose(), "hunter2");
+        assert_eq!(secret, "hunter2");
+    }
+
+    #[test]
+    fn test_secret_is_read_from_a_file_without_its_line_break() {
+        let path = std::env::temp_dir().join(format!("smpp-secret-{}", std::process::id()));
+        std::fs::write(&path, "hunter2\r\n").unwrap();
+        let secret = Secret::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(secret, "hunter2");
+
+        let missing = Secret::from_file(&path).unwrap_err();
+        assert!(matches!(missing, CredentialError::File { .. }), "{missing}");
+    }
+
+    #[test]
+    fn test_secret_is_read_from_the_environment() {
+        // Only read the environment, as changing it races other tests
+        let (var, value) = std::env::vars_os()
+            .find_map(|(var, value)| Some((var.into_string().ok()?, value.into_string().ok()?)))
+            .expect("no variable set in the environment");
+        assert_eq!(Secret::from_env(&var).unwrap(), value.as_str());
+
+        let var = format!("SMPP_SECRET_TEST_{}_UNSET", std::process::id());
+        assert!(matches!(
+            Secret::from_env(&var),
+            Err(CredentialError::MissingVariable(name)) if name == var
+        ));
+    }
+}
@@ -0,0 +1,388 @@
+// ABOUTME: Sequence number allocation for outbound PDUs within the SMPP 0x00000001-0x7FFFFFFF range
+// ABOUTME: Provides atomic, in-flight aware and file-persisted generators behind a shareable trait
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use tracing::warn;
+
+/// Lowest sequence number an ESME may allocate
+pub const MIN_SEQUENCE_NUMBER: u32 = 0x0000_0001;
+
+/// Highest sequence number an ESME may allocate
+///
+/// SMPP v3.4 Section 5.1.4 limits sequence numbers to 0x00000001-0x7FFFFFFF;
+/// allocation wraps back to [`MIN_SEQUENCE_NUMBER`] after this value.
+pub const MAX_SEQUENCE_NUMBER: u32 = 0x7FFF_FFFF;
+
+/// Source of sequence numbers for outbound requests
+///
+/// Implementations take `&self` so one generator can be shared, behind an
+/// `Arc`, by several binds to the same SMSC. Every value handed out lies in
+/// [`MIN_SEQUENCE_NUMBER`]..=[`MAX_SEQUENCE_NUMBER`]; 0 and values with the
+/// top bit set are never returned.
+pub trait SequenceGenerator: Send + Sync + fmt::Debug {
+    /// Allocate the next sequence number
+    ///
+    /// Returns `None` when every valid number is still in flight.
+    fn next_sequence(&self) -> Option<u32>;
+
+    /// Mark a sequence number as no longer in flight
+    ///
+    /// Called once the response to a request has been received (or the
+    /// request has been given up on). Generators that track in-flight values
+    /// may hand the number out again after this; the default does nothing.
+    fn release(&self, _sequence_number: u32) {}
+}
+
+/// The sequence number following `current`, wrapping at [`MAX_SEQUENCE_NUMBER`]
+pub fn next_after(current: u32) -> u32 {
+    if (MIN_SEQUENCE_NUMBER..MAX_SEQUENCE_NUMBER).contains(&current) {
+        current + 1
+    } else {
+        MIN_SEQUENCE_NUMBER
+    }
+}
+
+/// Lock-free generator counting through the valid range
+///
+/// Does not track in-flight values: a number is reused only after the other
+/// 2^31 - 2 have been allocated. Use [`WrappingSequenceGenerator`] when
+/// requests can stay unanswered for that long.
+#[derive(Debug)]
+pub struct AtomicSequenceGenerator {
+    last: AtomicU32,
+}
+
+impl AtomicSequenceGenerator {
+    /// Create a generator whose first value is [`MIN_SEQUENCE_NUMBER`]
+    pub fn new() -> Self {
+        Self::starting_at(MIN_SEQUENCE_NUMBER)
+    }
+
+    /// Create a generator whose first value is `first` (or 1 if `first` is reserved)
+    pub fn starting_at(first: u32) -> Self {
+        let first = if (MIN_SEQUENCE_NUMBER..=MAX_SEQUENCE_NUMBER).contains(&first) {
+            first
+        } else {
+            MIN_SEQUENCE_NUMBER
+        };
+        Self {
+            last: AtomicU32::new(first - 1),
+        }
+    }
+}
+
+impl Default for AtomicSequenceGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SequenceGenerator for AtomicSequenceGenerator {
+    fn next_sequence(&self) -> Option<u32> {
+        let previous = self
+            .last
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+                Some(next_after(last))
+            })
+            .unwrap_or_else(|last| last);
+        Some(next_after(previous))
+    }
+}
+
+/// Allocation state shared by the in-flight aware generators
+#[derive(Debug)]
+struct SequenceState {
+    last: u32,
+    in_flight: HashSet<u32>,
+}
+
+impl SequenceState {
+    fn new(first: u32) -> Self {
+        Self {
+            last: first.wrapping_sub(1),
+            in_flight: HashSet::new(),
+        }
+    }
+
+    /// Advance past any values still in flight and mark the result as in flight
+    ///
+    /// Returns `None`, allocating nothing, when every value is in flight.
+    fn allocate(&mut self) -> Option<u32> {
+        if self.in_flight.len() >= MAX_SEQUENCE_NUMBER as usize {
+            return None;
+        }
+        let mut candidate = next_after(self.last);
+        while self.in_flight.contains(&candidate) {
+            candidate = next_after(candidate);
+        }
+        self.last = candidate;
+        self.in_flight.insert(candidate);
+        Some(candidate)
+    }
+}
+
+/// Generator that skips values still awaiting a response
+///
+/// Each allocated number stays reserved until [`release`](SequenceGenerator::release)
+/// is called for it, so after wrapping around the range a long-outstanding
+/// request never shares its sequence number with a new one.
+#[derive(Debug)]
+pub struct WrappingSequenceGenerator {
+    state: Mutex<SequenceState>,
+}
+
+impl WrappingSequenceGenerator {
+    /// Create a generator whose first value is [`MIN_SEQUENCE_NUMBER`]
+    pub fn new() -> Self {
+        Self::starting_at(MIN_SEQUENCE_NUMBER)
+    }
+
+    /// Create a generator whose first value is `first` (or 1 if `first` is reserved)
+    pub fn starting_at(first: u32) -> Self {
+        Self {
+            state: Mutex::new(SequenceState::new(first)),
+        }
+    }
+
+    /// Number of allocated values not yet released
+    pub fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().in_flight.len()
+    }
+}
+
+impl Default for WrappingSequenceGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SequenceGenerator for WrappingSequenceGenerator {
+    fn next_sequence(&self) -> Option<u32> {
+        self.state.lock().unwrap().allocate()
+    }
+
+    fn release(&self, sequence_number: u32) {
+        self.state.lock().unwrap().in_flight.remove(&sequence_number);
+    }
+}
+
+/// In-flight aware generator that survives restarts
+///
+/// The file holds the next sequence number not yet reserved. Numbers are
+/// reserved in blocks of `block_size`, so the file is written once per block
+/// rather than once per PDU; after a restart allocation resumes at the end of
+/// the last reserved block, which may skip some unused numbers but never
+/// repeats one handed out before the restart. This keeps sequence numbers
+/// unique across restarts for correlating late responses and receipts.
+#[derive(Debug)]
+pub struct FileSequenceGenerator {
+    path: PathBuf,
+    block_size: u32,
+    inner: Mutex<FileSequenceState>,
+}
+
+#[derive(Debug)]
+struct FileSequenceState {
+    sequence: SequenceState,
+    /// First number past the current reserved block
+    reserved_until: u32,
+}
+
+impl FileSequenceGenerator {
+    /// Default number of sequence numbers reserved per file write
+    pub const DEFAULT_BLOCK_SIZE: u32 = 1000;
+
+    /// Open (or create) the sequence file at `path`
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::with_block_size(path, Self::DEFAULT_BLOCK_SIZE)
+    }
+
+    /// Open the sequence file, reserving `block_size` numbers per write
+    pub fn with_block_size(path: impl AsRef<Path>, block_size: u32) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let first = match fs::read_to_string(&path) {
+            Ok(contents) => contents.trim().parse::<u32>().map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid sequence file {}: {e}", path.display()),
+                )
+            })?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => MIN_SEQUENCE_NUMBER,
+            Err(e) => return Err(e),
+        };
+        let first = if (MIN_SEQUENCE_NUMBER..=MAX_SEQUENCE_NUMBER).contains(&first) {
+            first
+        } else {
+            MIN_SEQUENCE_NUMBER
+        };
+
+        let block_size = block_size.clamp(1, MAX_SEQUENCE_NUMBER / 2);
+        let reserved_until = advance(first, block_size);
+        persist(&path, reserved_until)?;
+
+        Ok(Self {
+            path,
+            block_size,
+            inner: Mutex::new(FileSequenceState {
+                sequence: SequenceState::new(first),
+                reserved_until,
+            }),
+        })
+    }
+
+    /// The file backing this generator
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl SequenceGenerator for FileSequenceGenerator {
+    fn next_sequence(&self) -> Option<u32> {
+        let mut inner = self.inner.lock().unwrap();
+        let sequence_number = inner.sequence.allocate()?;
+        // Skipping values in flight may jump to or past the end of the block
+        let ahead = distance(sequence_number, inner.reserved_until);
+        if ahead == 0 || ahead > self.block_size {
+            let reserved_until = advance(sequence_number, self.block_size);
+            // Keep allocating from memory if the write fails; only uniqueness
+            // across a restart is at risk, not within this process
+            if let Err(e) = persist(&self.path, reserved_until) {
+                warn!("Failed to persist sequence number to {}: {e}", self.path.display());
+            }
+            inner.reserved_until = reserved_until;
+        }
+        Some(sequence_number)
+    }
+
+    fn release(&self, sequence_number: u32) {
+        self.inner
+            .lock()
+            .unwrap()
+            .sequence
+            .in_flight
+            .remove(&sequence_number);
+    }
+}
+
+/// The sequence number `count` places after `from`, wrapping within the valid range
+fn advance(from: u32, count: u32) -> u32 {
+    let offset = (from - MIN_SEQUENCE_NUMBER) as u64 + count as u64;
+    (offset % MAX_SEQUENCE_NUMBER as u64) as u32 + MIN_SEQUENCE_NUMBER
+}
+
+/// How many places `to` lies after `from`, wrapping within the valid range
+fn distance(from: u32, to: u32) -> u32 {
+    if to >= from {
+        to - from
+    } else {
+        MAX_SEQUENCE_NUMBER - from + to
+    }
+}
+
+/// Atomically replace the sequence file's contents
+fn persist(path: &Path, next: u32) -> io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, format!("{next}\n"))?;
+    fs::rename(&temp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atomic_generator_wraps_before_reserved_range() {
+        let generator = AtomicSequenceGenerator::starting_at(MAX_SEQUENCE_NUMBER - 1);
+        assert_eq!(generator.next_sequence(), Some(MAX_SEQUENCE_NUMBER - 1));
+        assert_eq!(generator.next_sequence(), Some(MAX_SEQUENCE_NUMBER));
+        assert_eq!(generator.next_sequence(), Some(MIN_SEQUENCE_NUMBER));
+        assert_eq!(generator.next_sequence(), Some(2));
+
+        // Reserved starting values fall back to 1
+        assert_eq!(AtomicSequenceGenerator::starting_at(0).next_sequence(), Some(1));
+        assert_eq!(
+            AtomicSequenceGenerator::starting_at(0x8000_0000).next_sequence(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_wrapping_generator_skips_in_flight_values() {
+        let generator = WrappingSequenceGenerator::starting_at(MAX_SEQUENCE_NUMBER);
+        assert_eq!(generator.next_sequence(), Some(MAX_SEQUENCE_NUMBER));
+        assert_eq!(generator.next_sequence(), Some(1));
+        assert_eq!(generator.next_sequence(), Some(2));
+        generator.release(2);
+        assert_eq!(generator.in_flight(), 2);
+
+        // Force a wrap-around while 1 and MAX are still outstanding
+        generator.state.lock().unwrap().last = MAX_SEQUENCE_NUMBER - 1;
+        assert_eq!(generator.next_sequence(), Some(2));
+        generator.release(1);
+        generator.state.lock().unwrap().last = MAX_SEQUENCE_NUMBER - 1;
+        assert_eq!(generator.next_sequence(), Some(1));
+    }
+
+    #[test]
+    fn test_advance_wraps_within_range() {
+        assert_eq!(advance(1, 10), 11);
+        assert_eq!(advance(MAX_SEQUENCE_NUMBER, 1), MIN_SEQUENCE_NUMBER);
+        assert_eq!(advance(MAX_SEQUENCE_NUMBER - 1, 3), 2);
+    }
+
+    #[test]
+    fn test_file_generator_resumes_after_reserved_block() {
+        let path = std::env::temp_dir().join(format!(
+            "smpp-sequence-test-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let generator = FileSequenceGenerator::with_block_size(&path, 3).unwrap();
+        let first: Vec<u32> = (0..4).map(|_| generator.next_sequence().unwrap()).collect();
+        assert_eq!(first, vec![1, 2, 3, 4]);
+        drop(generator);
+
+        // 4 started a new block ending at 7, so a restart continues from there
+        let restarted = FileSequenceGenerator::with_block_size(&path, 3).unwrap();
+        assert_eq!(restarted.next_sequence(), Some(7));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_generator_reserves_a_block_when_skipping_past_its_end() {
+        let path = std::env::temp_dir().join(format!(
+            "smpp-sequence-skip-test-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+
+        let generator = FileSequenceGenerator::with_block_size(&path, 3).unwrap();
+        assert_eq!(generator.next_sequence(), Some(1));
+        // 2 to 5 are in flight, so allocation jumps from the block ending at 4 to 6
+        generator.inner.lock().unwrap().sequence.in_flight.extend(2..=5);
+        assert_eq!(generator.next_sequence(), Some(6));
+        drop(generator);
+
+        let restarted = FileSequenceGenerator::with_block_size(&path, 3).unwrap();
+        assert_eq!(restarted.next_sequence(), Some(9));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_distance_wraps_within_range() {
+        assert_eq!(distance(1, 4), 3);
+        assert_eq!(distance(4, 4), 0);
+        assert_eq!(distance(MAX_SEQUENCE_NUMBER, 2), 2);
+        assert_eq!(distance(2, 1), MAX_SEQUENCE_NUMBER - 1);
+    }
+}
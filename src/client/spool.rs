@@ -0,0 +1,605 @@
+// ABOUTME: Durable append-only spool of outbound submit_sm requests and their response state
+// ABOUTME: Replays unacknowledged messages after a restart with duplicate suppression on user_message_reference
+
+use crate::client::default::DefaultClient;
+use crate::client::error::{SmppError, SmppResult};
+use crate::client::traits::SmppTransmitter;
+use crate::codec::{DecodeContext, DecodePolicy, Decodable, Encodable, PduHeader};
+use crate::datatypes::{InterfaceVersion, SubmitSm};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+/// Identifier of a message within a [`Spool`]
+pub type SpoolId = u64;
+
+/// Size of a record header: kind, id, timestamp and payload length
+const RECORD_HEADER_SIZE: usize = 1 + 8 + 8 + 4;
+
+/// Kinds of record in the spool file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum RecordKind {
+    /// A submit_sm accepted for sending; payload is the encoded PDU
+    Submitted = 1,
+    /// The SMSC accepted the message; payload is the message_id
+    Acknowledged = 2,
+    /// The message will not be sent again; payload is the reason
+    Discarded = 3,
+    /// A user_message_reference seen within the duplicate window, kept by compaction
+    Reference = 4,
+}
+
+impl RecordKind {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(RecordKind::Submitted),
+            2 => Some(RecordKind::Acknowledged),
+            3 => Some(RecordKind::Discarded),
+            4 => Some(RecordKind::Reference),
+            _ => None,
+        }
+    }
+}
+
+/// Configuration of a [`Spool`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpoolConfig {
+    /// Reject a message whose user_message_reference was spooled this recently (default: none)
+    pub duplicate_window: Option<Duration>,
+
+    /// Flush every record to disk before returning (default: true)
+    ///
+    /// Without it a crash of the machine, rather than the process, can lose
+    /// the most recent records.
+    pub sync: bool,
+}
+
+impl Default for SpoolConfig {
+    fn default() -> Self {
+        Self {
+            duplicate_window: None,
+            sync: true,
+        }
+    }
+}
+
+impl SpoolConfig {
+    /// Suppress messages repeating a user_message_reference within `window`
+    pub fn with_duplicate_window(mut self, window: Duration) -> Self {
+        self.duplicate_window = Some(window);
+        self
+    }
+
+    /// Set whether every record is flushed to disk
+    pub fn with_sync(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+}
+
+/// Result of [`Spool::record`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recorded {
+    /// The message was spooled under this id
+    New(SpoolId),
+    /// A message with the same user_message_reference was spooled within the
+    /// duplicate window under this id; nothing was written
+    Duplicate(SpoolId),
+}
+
+/// A spooled message still awaiting its submit_sm_resp
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpoolEntry {
+    pub id: SpoolId,
+    pub submit: SubmitSm,
+    pub recorded_at: SystemTime,
+}
+
+#[derive(Debug)]
+struct SpoolState {
+    file: File,
+    next_id: SpoolId,
+    pending: BTreeMap<SpoolId, SpoolEntry>,
+    /// Latest message spooled for each user_message_reference value
+    references: HashMap<Vec<u8>, (SpoolId, SystemTime)>,
+}
+
+/// Append-only log of outbound submit_sm requests
+///
+/// Every message is written to the spool before it is sent, and its outcome
+/// afterwards. A message the SMSC never answered - because the process
+/// stopped, the connection dropped or the response timed out - stays
+/// pending and is sent again by [`replay`](Self::replay), typically after a
+/// restart. Delivery is therefore at-least-once: a message whose response
+/// was lost may reach the SMSC twice.
+///
+/// With a duplicate window, messages carrying the same
+/// `user_message_reference` within that window are treated as one: a second
+/// [`record`](Self::record) is refused and only the oldest pending copy is
+/// replayed.
+///
+/// The file only grows; call [`compact`](Self::compact) from time to time to
+/// drop settled messages.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use smpp::client::{DefaultClient, SmppConnection, Spool, SpoolConfig};
+/// use smpp::datatypes::SubmitSm;
+/// use std::time::Duration;
+///
+/// # async fn example(submit: SubmitSm) -> Result<(), Box<dyn std::error::Error>> {
+/// let config = SpoolConfig::default().with_duplicate_window(Duration::from_secs(3600));
+/// let spool = Spool::open_with_config("outbound.spool", config)?;
+/// let mut client = DefaultClient::connect("localhost:2775").await?;
+///
+/// // Resend whatever was in flight when the process last stopped
+/// spool.replay(&mut client).await?;
+///
+/// let message_id = spool.submit(&mut client, &submit).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Spool {
+    path: PathBuf,
+    config: SpoolConfig,
+    state: Mutex<SpoolState>,
+}
+
+impl Spool {
+    /// Open (or create) the spool file at `path` with the default configuration
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::open_with_config(path, SpoolConfig::default())
+    }
+
+    /// Open (or create) the spool file at `path`, recovering its pending messages
+    ///
+    /// A record cut short by a crash is dropped from the end of the file. A
+    /// spooled submit_sm that cannot be decoded is skipped with a warning.
+    pub fn open_with_config(path: impl AsRef<Path>, config: SpoolConfig) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)?;
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+
+        let mut state = SpoolState {
+            file,
+            next_id: 1,
+            pending: BTreeMap::new(),
+            references: HashMap::new(),
+        };
+        let valid = state.load(&contents)?;
+        if valid < contents.len() {
+            warn!(
+                path = %path.display(),
+                discarded = contents.len() - valid,
+                "Dropping incomplete record from end of spool"
+            );
+            state.file.set_len(valid as u64)?;
+        }
+        if !state.pending.is_empty() {
+            info!(path = %path.display(), pending = state.pending.len(), "Recovered spooled messages");
+        }
+
+        Ok(Self {
+            path,
+            config,
+            state: Mutex::new(state),
+        })
+    }
+
+    /// The file backing this spool
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Messages still awaiting a response, oldest first
+    pub fn pending(&self) -> Vec<SpoolEntry> {
+        self.state.lock().unwrap().pending.values().cloned().collect()
+    }
+
+    /// Write a message to the spool before sending it
+    pub fn record(&self, submit: &SubmitSm) -> io::Result<Recorded> {
+        self.record_with(submit, self.config.sync)
+    }
+
+    fn record_with(&self, submit: &SubmitSm, sync: bool) -> io::Result<Recorded> {
+        let now = SystemTime::now();
+        let mut state = self.state.lock().unwrap();
+
+        let reference = reference_key(submit);
+        if let (Some(window), Some(key)) = (self.config.duplicate_window, &reference)
+            && let Some(&(id, at)) = state.references.get(key)
+            && within(at, now, window)
+        {
+            return Ok(Recorded::Duplicate(id));
+        }
+
+        let id = state.next_id;
+        let pdu = submit.to_bytes();
+        state.append(RecordKind::Submitted, id, now, &pdu, sync)?;
+        state.next_id += 1;
+        if let Some(key) = reference {
+            state.references.insert(key, (id, now));
+        }
+        state.pending.insert(
+            id,
+            SpoolEntry {
+                id,
+                submit: submit.clone(),
+                recorded_at: now,
+            },
+        );
+        Ok(Recorded::New(id))
+    }
+
+    /// Record that the SMSC accepted message `id`
+    pub fn acknowledge(&self, id: SpoolId, message_id: &str) -> io::Result<()> {
+        self.settle(id, RecordKind::Acknowledged, message_id, self.config.sync)
+    }
+
+    /// Record that message `id` will not be sent again, with the reason
+    pub fn discard(&self, id: SpoolId, reason: &str) -> io::Result<()> {
+        self.settle(id, RecordKind::Discarded, reason, self.config.sync)
+    }
+
+    fn settle(&self, id: SpoolId, kind: RecordKind, payload: &str, sync: bool) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.pending.contains_key(&id) {
+            state.append(kind, id, SystemTime::now(), payload.as_bytes(), sync)?;
+            state.pending.remove(&id);
+        }
+        Ok(())
+    }
+
+    /// Flush the records written so far to disk on a blocking thread, if configured to
+    ///
+    /// The async methods write without syncing and call this instead, so
+    /// they do not stall the runtime while the disk catches up.
+    async fn sync(&self) -> io::Result<()> {
+        if !self.config.sync {
+            return Ok(());
+        }
+        let file = self.state.lock().unwrap().file.try_clone()?;
+        tokio::task::spawn_blocking(move || file.sync_data())
+            .await
+            .map_err(io::Error::other)?
+    }
+
+    /// Rewrite the file keeping only pending messages
+    ///
+    /// References still inside the duplicate window are kept so duplicate
+    /// suppression survives compaction. The new file replaces the old one
+    /// atomically.
+    pub fn compact(&self) -> io::Result<()> {
+        let now = SystemTime::now();
+        let mut state = self.state.lock().unwrap();
+
+        let mut temp = self.path.as_os_str().to_owned();
+        temp.push(".compact");
+        let temp = PathBuf::from(temp);
+        let mut contents = Vec::new();
+        if let Some(window) = self.config.duplicate_window {
+            for (key, &(id, at)) in &state.references {
+                if within(at, now, window) && !state.pending.contains_key(&id) {
+                    encode_record(&mut contents, RecordKind::Reference, id, at, key);
+                }
+            }
+        }
+        for entry in state.pending.values() {
+            let pdu = entry.submit.to_bytes();
+            encode_record(&mut contents, RecordKind::Submitted, entry.id, entry.recorded_at, &pdu);
+        }
+        std::fs::write(&temp, &contents)?;
+        File::open(&temp)?.sync_all()?;
+        std::fs::rename(&temp, &self.path)?;
+
+        state.file = OpenOptions::new().append(true).open(&self.path)?;
+        if let Some(window) = self.config.duplicate_window {
+            state.references.retain(|_, (_, at)| within(*at, now, window));
+        }
+        debug!(path = %self.path.display(), bytes = contents.len(), "Compacted spool");
+        Ok(())
+    }
+
+    /// Spool `submit`, send it on `client` and record the outcome
+    ///
+    /// The message stays pending, to be sent again by [`replay`](Self::replay),
+    /// unless the SMSC accepted it or rejected it with a permanent error.
+    /// A message repeating a recent `user_message_reference` is refused with
+    /// [`SmppError::InvalidData`] without being sent.
+    pub async fn submit(&self, client: &mut DefaultClient, submit: &SubmitSm) -> SmppResult<String> {
+        match self.record_with(submit, false).map_err(SmppError::Spool)? {
+            Recorded::New(id) => {
+                self.sync().await.map_err(SmppError::Spool)?;
+                self.send(client, id, submit).await
+            }
+            Recorded::Duplicate(id) => Err(SmppError::InvalidData(format!(
+                "Duplicate of spooled message {id} within the duplicate window"
+            ))),
+        }
+    }
+
+    /// Send every pending message on `client`, returning how many were accepted
+    ///
+    /// Pending copies of a message that repeat the `user_message_reference`
+    /// of an older pending message within the duplicate window are discarded
+    /// instead of sent. Stops at the first error that ends the session.
+    pub async fn replay(&self, client: &mut DefaultClient) -> SmppResult<usize> {
+        let mut accepted = 0;
+        let mut replayed: HashMap<Vec<u8>, SystemTime> = HashMap::new();
+        for entry in self.pending() {
+            if let (Some(window), Some(key)) =
+                (self.config.duplicate_window, reference_key(&entry.submit))
+            {
+                if let Some(&first) = replayed.get(&key)
+                    && within(first, entry.recorded_at, window)
+                {
+                    let reason = "duplicate user_message_reference";
+                    self.settle(entry.id, RecordKind::Discarded, reason, false)
+                        .map_err(SmppError::Spool)?;
+                    self.sync().await.map_err(SmppError::Spool)?;
+                    continue;
+                }
+                replayed.insert(key, entry.recorded_at);
+            }
+
+            debug!(id = entry.id, "Replaying spooled message");
+            match self.send(client, entry.id, &entry.submit).await {
+                Ok(_) => accepted += 1,
+                Err(e) if e.is_fatal_for_session() => return Err(e),
+                Err(e) => debug!(id = entry.id, "Replayed message failed: {e}"),
+            }
+        }
+        Ok(accepted)
+    }
+
+    async fn send(&self, client: &mut DefaultClient, id: SpoolId, submit: &SubmitSm) -> SmppResult<String> {
+        let result = client.submit_sm(submit).await;
+        let outcome = match &result {
+            Ok(message_id) => Some((RecordKind::Acknowledged, message_id.clone())),
+            Err(SmppError::Protocol { status, .. }) if !status.should_retry() => {
+                Some((RecordKind::Discarded, format!("rejected with {status:?}")))
+            }
+            Err(_) => None,
+        };
+        if let Some((kind, payload)) = outcome {
+            self.settle(id, kind, &payload, false).map_err(SmppError::Spool)?;
+            self.sync().await.map_err(SmppError::Spool)?;
+        }
+        result
+    }
+}
+
+impl SpoolState {
+    /// Apply the records in `contents`, returning the length of the valid prefix
+    fn load(&mut self, contents: &[u8]) -> io::Result<usize> {
+        let mut offset = 0;
+        while let Some((kind, id, at, payload)) = decode_record(&contents[offset..]) {
+            let Some(kind) = RecordKind::from_u8(kind) else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown spool record kind {kind} at offset {offset}"),
+                ));
+            };
+            match kind {
+                RecordKind::Submitted => match decode_submit(payload) {
+                    Ok(submit) => {
+                        if let Some(key) = reference_key(&submit) {
+                            self.references.insert(key, (id, at));
+                        }
+                        self.pending.insert(
+                            id,
+                            SpoolEntry {
+                                id,
+                                submit,
+                                recorded_at: at,
+                            },
+                        );
+                    }
+                    // One unreadable message must not keep the rest from being replayed
+                    Err(e) => warn!(id, "Skipping spooled submit_sm that cannot be decoded: {e}"),
+                },
+                RecordKind::Acknowledged | RecordKind::Discarded => {
+                    self.pending.remove(&id);
+                }
+                RecordKind::Reference => {
+                    self.references.insert(payload.to_vec(), (id, at));
+                }
+            }
+            self.next_id = self.next_id.max(id + 1);
+            offset += RECORD_HEADER_SIZE + payload.len();
+        }
+        Ok(offset)
+    }
+
+    fn append(
+        &mut self,
+        kind: RecordKind,
+        id: SpoolId,
+        at: SystemTime,
+        payload: &[u8],
+        sync: bool,
+    ) -> io::Result<()> {
+        let mut record = Vec::with_capacity(RECORD_HEADER_SIZE + payload.len());
+        encode_record(&mut record, kind, id, at, payload);
+        self.file.write_all(&record)?;
+        if sync {
+            self.file.sync_data()?;
+        }
+        Ok(())
+    }
+}
+
+fn encode_record(buf: &mut Vec<u8>, kind: RecordKind, id: SpoolId, at: SystemTime, payload: &[u8]) {
+    let millis = at
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    buf.push(kind as u8);
+    buf.extend_from_slice(&id.to_be_bytes());
+    buf.extend_from_slice(&millis.to_be_bytes());
+    buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    buf.extend_from_slice(payload);
+}
+
+/// Split the first complete record off `buf`
+fn decode_record(buf: &[u8]) -> Option<(u8, SpoolId, SystemTime, &[u8])> {
+    if buf.len() < RECORD_HEADER_SIZE {
+        return None;
+    }
+    let id = u64::from_be_bytes(buf[1..9].try_into().ok()?);
+    let millis = u64::from_be_bytes(buf[9..17].try_into().ok()?);
+    let len = u32::from_be_bytes(buf[17..21].try_into().ok()?) as usize;
+    let payload = buf.get(RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + len)?;
+    Some((buf[0], id, UNIX_EPOCH + Duration::from_millis(millis), payload))
+}
+
+fn decode_submit(pdu: &[u8]) -> Result<SubmitSm, crate::codec::CodecError> {
+    let mut buf = Cursor::new(pdu);
+    let header = PduHeader::decode(&mut buf)?;
+    // Accept every TLV the spool could have been given, whatever the session
+    // version, and whatever quirks the caller's PDU had
+    let mut ctx = DecodeContext::new(DecodePolicy::Lenient).with_version(InterfaceVersion::SmppV50);
+    SubmitSm::decode_with_context(header, &mut buf, &mut ctx)
+}
+
+/// The value duplicate suppression is keyed on
+fn reference_key(submit: &SubmitSm) -> Option<Vec<u8>> {
+    submit
+        .user_message_reference
+        .as_ref()
+        .map(|tlv| tlv.value.to_vec())
+}
+
+/// Whether `later` falls within `window` after `earlier`
+fn within(earlier: SystemTime, later: SystemTime, window: Duration) -> bool {
+    later
+        .duration_since(earlier)
+        .map(|elapsed| elapsed < window)
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datatypes::{Tlv, tags};
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("smpp-spool-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn submit(reference: Option<u16>) -> SubmitSm {
+        let mut builder = SubmitSm::builder()
+            .source_addr("1234")
+            .destination_addr("5678")
+            .short_message("Hello");
+        if let Some(reference) = reference {
+            builder = builder.user_message_reference(Tlv::new(
+                tags::USER_MESSAGE_REFERENCE,
+                reference.to_be_bytes().to_vec(),
+            ).unwrap());
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn test_pending_messages_survive_reopen() {
+        let path = temp_path("reopen");
+        let spool = Spool::open(&path).unwrap();
+        let Recorded::New(first) = spool.record(&submit(None)).unwrap() else {
+            panic!("expected new record");
+        };
+        let Recorded::New(second) = spool.record(&submit(Some(7))).unwrap() else {
+            panic!("expected new record");
+        };
+        spool.acknowledge(first, "abc").unwrap();
+        drop(spool);
+
+        let spool = Spool::open(&path).unwrap();
+        let pending = spool.pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, second);
+        assert_eq!(pending[0].submit, submit(Some(7)));
+
+        // Ids keep increasing after a restart
+        assert_eq!(spool.record(&submit(None)).unwrap(), Recorded::New(second + 1));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_truncated_tail_is_dropped() {
+        let path = temp_path("truncated");
+        let spool = Spool::open(&path).unwrap();
+        spool.record(&submit(None)).unwrap();
+        drop(spool);
+
+        let complete = std::fs::metadata(&path).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[RecordKind::Submitted as u8, 0, 0]).unwrap();
+        drop(file);
+
+        let spool = Spool::open(&path).unwrap();
+        assert_eq!(spool.pending().len(), 1);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), complete);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_undecodable_submit_is_skipped() {
+        let path = temp_path("undecodable");
+        let spool = Spool::open(&path).unwrap();
+        spool.record(&submit(None)).unwrap();
+        drop(spool);
+
+        let mut record = Vec::new();
+        encode_record(&mut record, RecordKind::Submitted, 2, SystemTime::now(), b"garbage");
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&record).unwrap();
+        drop(file);
+
+        let spool = Spool::open(&path).unwrap();
+        let pending = spool.pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, 1);
+        assert_eq!(spool.record(&submit(None)).unwrap(), Recorded::New(3));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_duplicate_window_survives_compaction() {
+        let path = temp_path("duplicates");
+        let config = SpoolConfig::default()
+            .with_duplicate_window(Duration::from_secs(60))
+            .with_sync(false);
+        let spool = Spool::open_with_config(&path, config.clone()).unwrap();
+
+        let Recorded::New(id) = spool.record(&submit(Some(42))).unwrap() else {
+            panic!("expected new record");
+        };
+        assert_eq!(spool.record(&submit(Some(42))).unwrap(), Recorded::Duplicate(id));
+        assert!(matches!(spool.record(&submit(Some(43))).unwrap(), Recorded::New(_)));
+
+        spool.acknowledge(id, "abc").unwrap();
+        spool.compact().unwrap();
+        assert_eq!(spool.pending().len(), 1);
+        drop(spool);
+
+        let spool = Spool::open_with_config(&path, config).unwrap();
+        assert_eq!(spool.record(&submit(Some(42))).unwrap(), Recorded::Duplicate(id));
+        assert_eq!(spool.pending().len(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
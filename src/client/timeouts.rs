@@ -0,0 +1,138 @@
+// ABOUTME: SMPP session timer configuration covering connect, bind, response and inactivity timeouts
+// ABOUTME: Mirrors the session_init_timer, response_timer and inactivity_timer of SMPP v3.4 Section 7.2
+
+use crate::datatypes::CommandId;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Timers bounding every network wait made by the client
+///
+/// SMPP v3.4 Section 7.2 defines the session timers an ESME should run; this
+/// configuration maps them onto client operations:
+///
+/// * `connect` - establishing the TCP connection
+/// * `session_init` (session_init_timer) - waiting for the bind response
+/// * `response` (response_timer) - waiting for the response to any other
+///   request, overridable per command type
+/// * `inactivity` (inactivity_timer) - how long the session may stay idle
+///   before it is unbound
+///
+/// The enquire_link_timer is the keep-alive interval, configured with
+/// [`KeepAliveConfig`](crate::client::KeepAliveConfig); while keep-alive is
+/// running its `timeout` bounds the wait for enquire_link_resp.
+///
+/// # Example
+///
+/// ```rust
+/// use smpp::client::TimeoutConfig;
+/// use smpp::datatypes::CommandId;
+/// use std::time::Duration;
+///
+/// let timeouts = TimeoutConfig::default()
+///     .with_connect(Duration::from_secs(5))
+///     .with_response_for(CommandId::SubmitSm, Duration::from_secs(20))
+///     .with_inactivity(Duration::from_secs(600));
+///
+/// assert_eq!(timeouts.response_for(CommandId::SubmitSm), Duration::from_secs(20));
+/// assert_eq!(timeouts.response_for(CommandId::QuerySm), Duration::from_secs(30));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TimeoutConfig {
+    /// Time allowed to establish the TCP connection (default: 10 seconds)
+    pub connect: Duration,
+
+    /// Time allowed for the SMSC to answer a bind (default: 30 seconds)
+    pub session_init: Duration,
+
+    /// Time allowed for the response to a request (default: 30 seconds)
+    pub response: Duration,
+
+    /// Per-command overrides of `response`, keyed by request command_id
+    pub response_overrides: HashMap<CommandId, Duration>,
+
+    /// Maximum idle time before the session is unbound (default: none)
+    ///
+    /// Only requests other than enquire_link count as activity, so keep-alive
+    /// pings do not hold an otherwise idle session open.
+    pub inactivity: Option<Duration>,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(10),
+            session_init: Duration::from_secs(30),
+            response: Duration::from_secs(30),
+            response_overrides: HashMap::new(),
+            inactivity: None,
+        }
+    }
+}
+
+impl TimeoutConfig {
+    /// Create a timer configuration with the default values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the TCP connect timeout
+    pub fn with_connect(mut self, timeout: Duration) -> Self {
+        self.connect = timeout;
+        self
+    }
+
+    /// Set the time allowed for the bind response (session_init_timer)
+    pub fn with_session_init(mut self, timeout: Duration) -> Self {
+        self.session_init = timeout;
+        self
+    }
+
+    /// Set the default response timeout (response_timer)
+    pub fn with_response(mut self, timeout: Duration) -> Self {
+        self.response = timeout;
+        self
+    }
+
+    /// Override the response timeout for one request type
+    pub fn with_response_for(mut self, command_id: CommandId, timeout: Duration) -> Self {
+        self.response_overrides.insert(command_id, timeout);
+        self
+    }
+
+    /// Unbind the session after this much idle time (inactivity_timer)
+    pub fn with_inactivity(mut self, timeout: Duration) -> Self {
+        self.inactivity = Some(timeout);
+        self
+    }
+
+    /// The response timeout that applies to a request
+    pub fn response_for(&self, command_id: CommandId) -> Duration {
+        self.response_overrides
+            .get(&command_id)
+            .copied()
+            .unwrap_or(self.response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeout_config_defaults() {
+        let config = TimeoutConfig::default();
+        assert_eq!(config.connect, Duration::from_secs(10));
+        assert_eq!(config.session_init, Duration::from_secs(30));
+        assert_eq!(config.response_for(CommandId::SubmitSm), Duration::from_secs(30));
+        assert_eq!(config.inactivity, None);
+    }
+
+    #[test]
+    fn test_timeout_config_overrides() {
+        let config = TimeoutConfig::new()
+            .with_response(Duration::from_secs(5))
+            .with_response_for(CommandId::EnquireLink, Duration::from_secs(2));
+        assert_eq!(config.response_for(CommandId::EnquireLink), Duration::from_secs(2));
+        assert_eq!(config.response_for(CommandId::Unbind), Duration::from_secs(5));
+    }
+}
@@ -155,7 +155,9 @@ pub trait SmppClient: SmppConnection {
     /// Get next sequence number for PDU
     ///
     /// Returns the next sequence number to use for outbound PDUs.
-    /// Sequence numbers must be unique within a session.
+    /// Sequence numbers must be unique within a session and lie in
+    /// 0x00000001-0x7FFFFFFF, wrapping around after the maximum (see
+    /// [`SequenceGenerator`](crate::client::SequenceGenerator)).
     fn next_sequence_number(&mut self) -> u32;
 }
 
@@ -1,18 +1,20 @@
 // ABOUTME: Supporting types for SMPP client operations including credentials and message builders
 // ABOUTME: Provides simplified interfaces for common SMPP operations with sensible defaults
 
+use crate::client::secret::{CredentialError, Secret, env_var};
 use crate::datatypes::{DataCoding, NumericPlanIndicator, PriorityFlag, TypeOfNumber, InterfaceVersion};
 
 /// SMPP bind operation credentials
 ///
 /// Contains authentication information and bind type for establishing
-/// SMPP sessions with the SMSC.
+/// SMPP sessions with the SMSC. The password is a [`Secret`], so the
+/// credentials can be logged with `{:?}` without revealing it.
 #[derive(Debug, Clone)]
 pub struct BindCredentials {
     /// System identifier for authentication
     pub system_id: String,
     /// Password for authentication
-    pub password: String,
+    pub password: Secret,
     /// System type (optional, defaults to empty string)
     pub system_type: Option<String>,
     /// Type of bind operation to perform
@@ -23,7 +25,7 @@ pub struct BindCredentials {
 
 impl BindCredentials {
     /// Create new bind credentials for transmitter session (defaults to SMPP v3.4)
-    pub fn transmitter(system_id: impl Into<String>, password: impl Into<String>) -> Self {
+    pub fn transmitter(system_id: impl Into<String>, password: impl Into<Secret>) -> Self {
         Self {
             system_id: system_id.into(),
             password: password.into(),
@@ -34,7 +36,7 @@ impl BindCredentials {
     }
 
     /// Create new bind credentials for receiver session (defaults to SMPP v3.4)
-    pub fn receiver(system_id: impl Into<String>, password: impl Into<String>) -> Self {
+    pub fn receiver(system_id: impl Into<String>, password: impl Into<Secret>) -> Self {
         Self {
             system_id: system_id.into(),
             password: password.into(),
@@ -45,7 +47,7 @@ impl BindCredentials {
     }
 
     /// Create new bind credentials for transceiver session (defaults to SMPP v3.4)
-    pub fn transceiver(system_id: impl Into<String>, password: impl Into<String>) -> Self {
+    pub fn transceiver(system_id: impl Into<String>, password: impl Into<Secret>) -> Self {
         Self {
             system_id: system_id.into(),
             password: password.into(),
@@ -56,7 +58,7 @@ impl BindCredentials {
     }
 
     /// Create new bind credentials for transmitter session with SMPP v5.0
-    pub fn transmitter_v50(system_id: impl Into<String>, password: impl Into<String>) -> Self {
+    pub fn transmitter_v50(system_id: impl Into<String>, password: impl Into<Secret>) -> Self {
         Self {
             system_id: system_id.into(),
             password: password.into(),
@@ -67,7 +69,7 @@ impl BindCredentials {
     }
 
     /// Create new bind credentials for receiver session with SMPP v5.0
-    pub fn receiver_v50(system_id: impl Into<String>, password: impl Into<String>) -> Self {
+    pub fn receiver_v50(system_id: impl Into<String>, password: impl Into<Secret>) -> Self {
         Self {
             system_id: system_id.into(),
             password: password.into(),
@@ -78,7 +80,7 @@ impl BindCredentials {
     }
 
     /// Create new bind credentials for transceiver session with SMPP v5.0
-    pub fn transceiver_v50(system_id: impl Into<String>, password: impl Into<String>) -> Self {
+    pub fn transceiver_v50(system_id: impl Into<String>, password: impl Into<Secret>) -> Self {
         Self {
             system_id: system_id.into(),
             password: password.into(),
@@ -88,6 +90,77 @@ impl BindCredentials {
         }
     }
 
+    /// Create new bind credentials for transmitter session with a legacy SMPP v3.3 SMSC
+    ///
+    /// The session emits and accepts only v3.3-compatible PDUs: no optional
+    /// parameters, no data_sm and 8-digit hexadecimal message IDs.
+    pub fn transmitter_v33(system_id: impl Into<String>, password: impl Into<Secret>) -> Self {
+        Self {
+            system_id: system_id.into(),
+            password: password.into(),
+            system_type: None,
+            bind_type: BindType::Transmitter,
+            interface_version: InterfaceVersion::SmppV33,
+        }
+    }
+
+    /// Create new bind credentials for receiver session with a legacy SMPP v3.3 SMSC
+    pub fn receiver_v33(system_id: impl Into<String>, password: impl Into<Secret>) -> Self {
+        Self {
+            system_id: system_id.into(),
+            password: password.into(),
+            system_type: None,
+            bind_type: BindType::Receiver,
+            interface_version: InterfaceVersion::SmppV33,
+        }
+    }
+
+    /// Load credentials from the environment, with variable names starting with `prefix`
+    ///
+    /// The system_id is read from `{prefix}_SYSTEM_ID` and the password from
+    /// `{prefix}_PASSWORD`, or when that is not set from the file named by
+    /// `{prefix}_PASSWORD_FILE`. The session uses SMPP v3.4 unless changed
+    /// with [`with_version`](Self::with_version).
+    ///
+    /// ```rust,no_run
+    /// use smpp::client::{BindCredentials, BindType};
+    ///
+    /// // SMPP_SYSTEM_ID=esme SMPP_PASSWORD_FILE=/run/secrets/smpp_password
+    /// let credentials = BindCredentials::from_env("SMPP", BindType::Transceiver)?;
+    /// # Ok::<(), smpp::client::CredentialError>(())
+    /// ```
+    pub fn from_env(prefix: &str, bind_type: BindType) -> Result<Self, CredentialError> {
+        Self::from_vars(prefix, bind_type, env_var)
+    }
+
+    /// Load credentials as [`from_env`](Self::from_env) does, looking variables up with `var`
+    pub(crate) fn from_vars(
+        prefix: &str,
+        bind_type: BindType,
+        var: impl Fn(&str) -> Result<String, CredentialError>,
+    ) -> Result<Self, CredentialError> {
+        let system_id = var(&format!("{prefix}_SYSTEM_ID"))?;
+        let password = match var(&format!("{prefix}_PASSWORD")) {
+            Err(CredentialError::MissingVariable(_)) => {
+                Secret::from_file(var(&format!("{prefix}_PASSWORD_FILE"))?)?
+            }
+            password => Secret::new(password?),
+        };
+        Ok(Self {
+            system_id,
+            password,
+            system_type: None,
+            bind_type,
+            interface_version: InterfaceVersion::SmppV34,
+        })
+    }
+
+    /// Set the password, e.g. one read with [`Secret::from_file`]
+    pub fn with_password(mut self, password: impl Into<Secret>) -> Self {
+        self.password = password.into();
+        self
+    }
+
     /// Set system type
     pub fn with_system_type(mut self, system_type: impl Into<String>) -> Self {
         self.system_type = Some(system_type.into());
@@ -104,10 +177,15 @@ impl BindCredentials {
     pub fn is_v50(&self) -> bool {
         self.interface_version == InterfaceVersion::SmppV50
     }
+
+    /// Check if this bind uses legacy SMPP v3.3
+    pub fn is_v33(&self) -> bool {
+        self.interface_version == InterfaceVersion::SmppV33
+    }
 }
 
 /// Type of SMPP bind operation
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BindType {
     /// Bind as transmitter (can send submit_sm)
     Transmitter,
@@ -124,6 +124,20 @@ pub trait Decodable: Sized {
     /// Decode this PDU from the buffer after header
     fn decode(header: PduHeader, buf: &mut Cursor<&[u8]>) -> Result<Self, CodecError>;
 
+    /// Decode this PDU under the policy carried by `ctx`
+    ///
+    /// PDUs that know how to recover from common peer quirks override this and
+    /// record what they normalised in `ctx`. The default simply delegates to
+    /// [`Decodable::decode`], so such PDUs behave identically under both policies.
+    fn decode_with_context(
+        header: PduHeader,
+        buf: &mut Cursor<&[u8]>,
+        ctx: &mut DecodeContext,
+    ) -> Result<Self, CodecError> {
+        let _ = ctx;
+        Self::decode(header, buf)
+    }
+
     /// Return the expected command_id for this PDU type
     fn command_id() -> CommandId;
 
@@ -279,6 +293,233 @@ impl CodecError {
     }
 }
 
+/// How strictly inbound PDUs are checked against the specification
+///
+/// Real SMSCs are not always conformant: C-strings arrive without a NUL
+/// terminator at the field limit, `sm_length` disagrees with the data that
+/// follows, `priority_flag` carries out-of-range values and addresses do not
+/// match their declared TON. `Strict` rejects all of these; `Lenient` accepts
+/// them, normalises the offending field and records a [`DecodeWarning`].
+///
+/// The policy covers the message PDUs an SMSC sends or relays: submit_sm,
+/// deliver_sm and data_sm.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DecodePolicy {
+    /// Reject any PDU that violates the specification (default)
+    #[default]
+    Strict,
+    /// Normalise recoverable violations and report them as warnings
+    Lenient,
+}
+
+/// A specification violation that was tolerated while decoding in lenient mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeWarning {
+    /// The PDU field that was normalised
+    pub field: &'static str,
+    /// What was wrong with the field and how it was treated
+    pub reason: String,
+}
+
+impl std::fmt::Display for DecodeWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.reason)
+    }
+}
+
+/// Decode state threaded through [`Decodable::decode_with_context`]
+///
+/// Carries the active [`DecodePolicy`] and protocol version, and collects the
+/// warnings raised while decoding a single PDU.
+#[derive(Debug)]
+pub struct DecodeContext {
+    policy: DecodePolicy,
+    version: crate::datatypes::InterfaceVersion,
+    warnings: Vec<DecodeWarning>,
+    // Reject version violations even under the lenient policy
+    enforce_version: bool,
+}
+
+impl Default for DecodeContext {
+    fn default() -> Self {
+        Self::new(DecodePolicy::default())
+    }
+}
+
+impl DecodeContext {
+    /// Create a context for the given policy, decoding SMPP v3.4
+    pub fn new(policy: DecodePolicy) -> Self {
+        Self {
+            policy,
+            version: crate::datatypes::InterfaceVersion::SmppV34,
+            warnings: Vec::new(),
+            enforce_version: false,
+        }
+    }
+
+    /// Context for checking our own outbound PDUs: field-level quirks are
+    /// tolerated, but anything the negotiated version does not allow is an error
+    fn outbound(version: crate::datatypes::InterfaceVersion) -> Self {
+        Self {
+            enforce_version: true,
+            ..Self::lenient().with_version(version)
+        }
+    }
+
+    /// Decode PDUs as defined by the given protocol version
+    pub fn with_version(mut self, version: crate::datatypes::InterfaceVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// The protocol version PDUs are decoded against
+    pub fn version(&self) -> crate::datatypes::InterfaceVersion {
+        self.version
+    }
+
+    /// Create a context that rejects any specification violation
+    pub fn strict() -> Self {
+        Self::new(DecodePolicy::Strict)
+    }
+
+    /// Create a context that normalises recoverable violations
+    pub fn lenient() -> Self {
+        Self::new(DecodePolicy::Lenient)
+    }
+
+    /// The policy this context decodes under
+    pub fn policy(&self) -> DecodePolicy {
+        self.policy
+    }
+
+    /// Whether recoverable violations should be normalised rather than rejected
+    pub fn is_lenient(&self) -> bool {
+        self.policy == DecodePolicy::Lenient
+    }
+
+    /// Record a tolerated violation
+    pub fn warn(&mut self, field: &'static str, reason: impl Into<String>) {
+        self.warnings.push(DecodeWarning {
+            field,
+            reason: reason.into(),
+        });
+    }
+
+    /// Warnings recorded so far
+    pub fn warnings(&self) -> &[DecodeWarning] {
+        &self.warnings
+    }
+
+    /// Consume the context, returning the recorded warnings
+    pub fn into_warnings(self) -> Vec<DecodeWarning> {
+        self.warnings
+    }
+
+    /// Report something the decoded version does not allow
+    ///
+    /// Rejected in strict mode; recorded as a warning in lenient mode.
+    pub fn version_violation(
+        &mut self,
+        field: &'static str,
+        reason: impl Into<String>,
+    ) -> Result<(), CodecError> {
+        if self.is_lenient() && !self.enforce_version {
+            self.warn(field, reason);
+            return Ok(());
+        }
+        Err(CodecError::FieldValidation {
+            field,
+            reason: reason.into(),
+        })
+    }
+
+    /// Check that any octets left after the mandatory parameters are permitted
+    ///
+    /// SMPP v3.3 has no optional parameters, so trailing octets are rejected in
+    /// strict mode and skipped with a warning in lenient mode. Later versions
+    /// accept them as TLVs and leave the cursor untouched.
+    pub fn check_optional_parameters(
+        &mut self,
+        buf: &mut Cursor<&[u8]>,
+    ) -> Result<(), CodecError> {
+        if self.version.supports_tlvs() || !buf.has_remaining() {
+            return Ok(());
+        }
+
+        let trailing = buf.remaining();
+        self.version_violation(
+            "optional_parameters",
+            format!("{trailing} trailing octets; SMPP v3.3 has no optional parameters"),
+        )?;
+        buf.advance(trailing);
+        Ok(())
+    }
+
+    /// Check that a TLV tag exists in the decoded version
+    ///
+    /// Returns whether the TLV should be kept: tags introduced by a later
+    /// version are rejected in strict mode and dropped with a warning in
+    /// lenient mode. Unknown (e.g. vendor-specific) tags are always kept.
+    pub fn check_tlv(&mut self, tag: u16) -> Result<bool, CodecError> {
+        match crate::datatypes::tags::introduced_in(tag) {
+            Some(introduced) if introduced > self.version => {
+                self.version_violation(
+                    "optional_parameters",
+                    format!(
+                        "TLV {tag:#06x} requires {introduced:?} but the session uses {:?}",
+                        self.version
+                    ),
+                )?;
+                Ok(false)
+            }
+            _ => Ok(true),
+        }
+    }
+
+    /// Apply the policy to a field-level decode result
+    ///
+    /// In strict mode the result is returned unchanged. In lenient mode a
+    /// validation failure is recorded as a warning and `fallback` supplies the
+    /// normalised value instead. `Incomplete` is never recovered, since it means
+    /// the PDU itself is truncated rather than malformed.
+    pub fn recover<T>(
+        &mut self,
+        result: Result<T, CodecError>,
+        fallback: impl FnOnce() -> T,
+    ) -> Result<T, CodecError> {
+        match result {
+            Ok(value) => Ok(value),
+            Err(CodecError::Incomplete) => Err(CodecError::Incomplete),
+            Err(err) if self.is_lenient() => {
+                let (field, reason) = match err {
+                    CodecError::FieldValidation { field, reason } => (field, reason),
+                    CodecError::Utf8Error { field, source } => (field, source.to_string()),
+                    other => ("pdu", other.to_string()),
+                };
+                self.warn(field, reason);
+                Ok(fallback())
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// A decoded frame together with any warnings raised in lenient mode
+#[derive(Debug)]
+pub struct DecodedFrame {
+    /// The decoded PDU
+    pub frame: Frame,
+    /// Violations that were normalised while decoding; always empty in strict mode
+    pub warnings: Vec<DecodeWarning>,
+}
+
+impl DecodedFrame {
+    /// Whether the peer sent a fully conformant PDU
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
 /// Utility functions for decoding common SMPP field types
 pub fn decode_cstring(
     buf: &mut Cursor<&[u8]>,
@@ -305,6 +546,146 @@ pub fn decode_cstring(
     })
 }
 
+/// Decode a variable-length C-Octet String of at most `max_len` octets
+/// (including the NUL terminator)
+///
+/// Unlike [`decode_cstring`], which reads a fixed-width padded field, this
+/// consumes only the content and its terminator. A field that reaches
+/// `max_len - 1` octets without a terminator is rejected in strict mode; in
+/// lenient mode the content is kept, the following octet is left for the next
+/// field and a warning is recorded. Lenient mode also replaces invalid UTF-8.
+pub fn decode_c_octet_string(
+    buf: &mut Cursor<&[u8]>,
+    max_len: usize,
+    field_name: &'static str,
+    ctx: &mut DecodeContext,
+) -> Result<String, CodecError> {
+    let mut string_bytes = Vec::new();
+
+    loop {
+        if !buf.has_remaining() {
+            // A lenient peer may drop the terminator on the last field of the body
+            if ctx.is_lenient() && !string_bytes.is_empty() {
+                ctx.warn(field_name, "missing NUL terminator at end of PDU");
+                break;
+            }
+            return Err(CodecError::Incomplete);
+        }
+
+        let byte = buf.chunk()[0];
+        if byte == 0 {
+            buf.advance(1);
+            break;
+        }
+
+        if string_bytes.len() == max_len - 1 {
+            if ctx.is_lenient() {
+                ctx.warn(
+                    field_name,
+                    format!("missing NUL terminator after {} octets", max_len - 1),
+                );
+                break;
+            }
+            return Err(CodecError::FieldValidation {
+                field: field_name,
+                reason: format!("missing NUL terminator within {max_len} octets"),
+            });
+        }
+
+        string_bytes.push(byte);
+        buf.advance(1);
+    }
+
+    match String::from_utf8(string_bytes) {
+        Ok(value) => Ok(value),
+        Err(e) if ctx.is_lenient() => {
+            ctx.warn(field_name, format!("{e}; invalid sequences replaced"));
+            Ok(String::from_utf8_lossy(e.as_bytes()).into_owned())
+        }
+        Err(e) => Err(CodecError::Utf8Error {
+            field: field_name,
+            source: e,
+        }),
+    }
+}
+
+/// Skip the NUL padding that follows a C-Octet String in a fixed-width field
+///
+/// `field_start` is the cursor position where the field began; no more than
+/// `width` octets from there are consumed. This lets decoders of
+/// variable-length fields also accept the fixed-width layout some encoders
+/// (including this crate's response PDUs) produce.
+pub fn skip_nul_padding(buf: &mut Cursor<&[u8]>, field_start: u64, width: usize) {
+    while buf.position() - field_start < width as u64 && buf.chunk().first() == Some(&0) {
+        buf.advance(1);
+    }
+}
+
+/// Build an address, tolerating a TON mismatch in lenient mode
+///
+/// When `addr` does not satisfy the format rules for `ton`, lenient mode falls
+/// back to `TypeOfNumber::Unknown` (dropping any non-printable characters) so
+/// that the decoded PDU re-encodes and validates cleanly. Returns the TON the
+/// address was actually built with.
+pub fn decode_address<const N: usize>(
+    addr: &str,
+    ton: crate::datatypes::TypeOfNumber,
+    field_name: &'static str,
+    ctx: &mut DecodeContext,
+) -> Result<(crate::datatypes::TypeOfNumber, crate::datatypes::PhoneNumber<N>), CodecError> {
+    use crate::datatypes::{PhoneNumber, TypeOfNumber};
+
+    match PhoneNumber::<N>::new(addr, ton) {
+        Ok(number) => Ok((ton, number)),
+        Err(e) if ctx.is_lenient() => {
+            ctx.warn(field_name, format!("{e}; treated as unknown TON"));
+            let printable: String = addr
+                .chars()
+                .filter(|c| c.is_ascii() && !c.is_control())
+                .take(N - 1)
+                .collect();
+            let number = PhoneNumber::<N>::new(&printable, TypeOfNumber::Unknown)
+                .unwrap_or_default();
+            Ok((TypeOfNumber::Unknown, number))
+        }
+        Err(e) => Err(CodecError::FieldValidation {
+            field: field_name,
+            reason: e.to_string(),
+        }),
+    }
+}
+
+/// Check a message_id against the format rules of the decoded version
+///
+/// SMPP v3.3 message IDs are hexadecimal strings of at most 8 digits; later
+/// versions place no constraint on the content. In lenient mode a violation is
+/// recorded as a warning and the ID is kept as received.
+pub fn check_message_id(
+    message_id: &str,
+    field_name: &'static str,
+    ctx: &mut DecodeContext,
+) -> Result<(), CodecError> {
+    let max_len = ctx.version().message_id_max_len() - 1;
+    let hex_only = !ctx.version().supports_tlvs();
+
+    if message_id.len() > max_len {
+        return ctx.version_violation(
+            field_name,
+            format!(
+                "'{message_id}' exceeds the {max_len} characters allowed by {:?}",
+                ctx.version()
+            ),
+        );
+    }
+    if hex_only && !message_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return ctx.version_violation(
+            field_name,
+            format!("'{message_id}' is not hexadecimal as required by SMPP v3.3"),
+        );
+    }
+    Ok(())
+}
+
 /// Decode a single byte
 pub fn decode_u8(buf: &mut Cursor<&[u8]>) -> Result<u8, CodecError> {
     if buf.remaining() < 1 {
@@ -386,13 +767,12 @@ pub enum Frame {
     UnbindResp(crate::datatypes::UnbindResponse),
 
     // Bind PDUs
-    // TODO: Add codec implementations for these PDUs
-    // BindReceiver(crate::datatypes::BindReceiver),
-    // BindReceiverResponse(crate::datatypes::BindReceiverResponse),
-    // BindTransceiver(crate::datatypes::BindTransceiver),
-    // BindTransceiverResponse(crate::datatypes::BindTransceiverResponse),
+    BindReceiver(crate::datatypes::BindReceiver),
+    BindReceiverResp(crate::datatypes::BindReceiverResponse),
+    BindTransceiver(crate::datatypes::BindTransceiver),
+    BindTransceiverResp(crate::datatypes::BindTransceiverResponse),
     BindTransmitter(crate::datatypes::BindTransmitter),
-    // BindTransmitterResponse(crate::datatypes::BindTransmitterResponse),
+    BindTransmitterResp(crate::datatypes::BindTransmitterResponse),
 
     // Message PDUs
     SubmitSm(Box<crate::datatypes::SubmitSm>),
@@ -407,9 +787,8 @@ pub enum Frame {
     CancelSmResp(crate::datatypes::CancelSmResponse),
     DataSm(Box<crate::datatypes::DataSm>),
     DataSmResp(crate::datatypes::DataSmResponse),
-    // TODO: Add codec implementations for these PDUs
-    // DeliverSm(Box<crate::datatypes::DeliverSm>),
-    // DeliverSmResponse(crate::datatypes::DeliverSmResponse),
+    DeliverSm(Box<crate::datatypes::DeliverSm>),
+    DeliverSmResp(crate::datatypes::DeliverSmResponse),
 
     // Notification PDUs
     AlertNotification(crate::datatypes::AlertNotification),
@@ -431,13 +810,27 @@ pub enum Frame {
 }
 
 /// Registry of PDU decoders for extensible parsing
-type DecoderFn =
-    Box<dyn Fn(PduHeader, &mut Cursor<&[u8]>) -> Result<Frame, CodecError> + Send + Sync>;
+type DecoderFn = Box<
+    dyn Fn(PduHeader, &mut Cursor<&[u8]>, &mut DecodeContext) -> Result<Frame, CodecError>
+        + Send
+        + Sync,
+>;
 
 pub struct PduRegistry {
     decoders: HashMap<CommandId, DecoderFn>,
     version: crate::datatypes::InterfaceVersion,
     supported_tlvs: std::collections::HashSet<u16>,
+    policy: DecodePolicy,
+}
+
+impl std::fmt::Debug for PduRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PduRegistry")
+            .field("version", &self.version)
+            .field("policy", &self.policy)
+            .field("registered_commands", &self.decoders.len())
+            .finish()
+    }
 }
 
 impl PduRegistry {
@@ -452,6 +845,7 @@ impl PduRegistry {
             decoders: HashMap::new(),
             version,
             supported_tlvs: std::collections::HashSet::new(),
+            policy: DecodePolicy::default(),
         };
 
         // Initialize supported TLVs based on version
@@ -465,6 +859,11 @@ impl PduRegistry {
     fn initialize_supported_tlvs(&mut self) {
         use crate::datatypes::tags;
 
+        // SMPP v3.3 has no optional parameters at all
+        if !self.version.supports_tlvs() {
+            return;
+        }
+
         // Add standard v3.4 TLVs
         self.supported_tlvs.insert(tags::USER_MESSAGE_REFERENCE);
         self.supported_tlvs.insert(tags::SOURCE_PORT);
@@ -513,12 +912,27 @@ impl PduRegistry {
 
         // Register bind PDUs
         self.register_pdu::<crate::datatypes::BindTransmitter, _>(Frame::BindTransmitter);
+        self.register_pdu::<crate::datatypes::BindTransmitterResponse, _>(
+            Frame::BindTransmitterResp,
+        );
+        self.register_pdu::<crate::datatypes::BindReceiver, _>(Frame::BindReceiver);
+        self.register_pdu::<crate::datatypes::BindReceiverResponse, _>(Frame::BindReceiverResp);
+        if self.version.supports_command(CommandId::BindTransceiver) {
+            self.register_pdu::<crate::datatypes::BindTransceiver, _>(Frame::BindTransceiver);
+            self.register_pdu::<crate::datatypes::BindTransceiverResponse, _>(
+                Frame::BindTransceiverResp,
+            );
+        }
 
         // Register message PDUs (boxed for large structs)
         self.register_boxed_pdu::<crate::datatypes::SubmitSm, _>(|pdu| {
             Frame::SubmitSm(Box::new(pdu))
         });
         self.register_pdu::<crate::datatypes::SubmitSmResponse, _>(Frame::SubmitSmResp);
+        self.register_boxed_pdu::<crate::datatypes::DeliverSm, _>(|pdu| {
+            Frame::DeliverSm(Box::new(pdu))
+        });
+        self.register_pdu::<crate::datatypes::DeliverSmResponse, _>(Frame::DeliverSmResp);
 
         // Register submit_multi PDUs
         self.register_boxed_pdu::<crate::datatypes::SubmitMulti, _>(|pdu| {
@@ -540,14 +954,16 @@ impl PduRegistry {
         self.register_pdu::<crate::datatypes::CancelSm, _>(Frame::CancelSm);
         self.register_pdu::<crate::datatypes::CancelSmResponse, _>(Frame::CancelSmResp);
 
-        // Register data_sm PDUs
-        self.register_boxed_pdu::<crate::datatypes::DataSm, _>(|pdu| {
-            Frame::DataSm(Box::new(pdu))
-        });
-        self.register_pdu::<crate::datatypes::DataSmResponse, _>(Frame::DataSmResp);
+        // data_sm and alert_notification were introduced in v3.4
+        if self.version.supports_command(CommandId::DataSm) {
+            self.register_boxed_pdu::<crate::datatypes::DataSm, _>(|pdu| {
+                Frame::DataSm(Box::new(pdu))
+            });
+            self.register_pdu::<crate::datatypes::DataSmResponse, _>(Frame::DataSmResp);
 
-        // Register notification PDUs
-        self.register_pdu::<crate::datatypes::AlertNotification, _>(Frame::AlertNotification);
+            // Register notification PDUs
+            self.register_pdu::<crate::datatypes::AlertNotification, _>(Frame::AlertNotification);
+        }
 
         // Register v5.0 specific PDUs
         if matches!(self.version, crate::datatypes::InterfaceVersion::SmppV50) {
@@ -569,10 +985,12 @@ impl PduRegistry {
         F: Fn(T) -> Frame + Send + Sync + 'static,
     {
         let command_id = T::command_id();
-        let decoder = Box::new(move |header: PduHeader, buf: &mut Cursor<&[u8]>| {
-            let pdu = T::decode(header, buf)?;
-            Ok(frame_constructor(pdu))
-        });
+        let decoder = Box::new(
+            move |header: PduHeader, buf: &mut Cursor<&[u8]>, ctx: &mut DecodeContext| {
+                let pdu = T::decode_with_context(header, buf, ctx)?;
+                Ok(frame_constructor(pdu))
+            },
+        );
         self.decoders.insert(command_id, decoder);
     }
 
@@ -583,37 +1001,124 @@ impl PduRegistry {
         F: Fn(T) -> Frame + Send + Sync + 'static,
     {
         let command_id = T::command_id();
-        let decoder = Box::new(move |header: PduHeader, buf: &mut Cursor<&[u8]>| {
-            let pdu = T::decode(header, buf)?;
-            Ok(frame_constructor(pdu))
-        });
+        let decoder = Box::new(
+            move |header: PduHeader, buf: &mut Cursor<&[u8]>, ctx: &mut DecodeContext| {
+                let pdu = T::decode_with_context(header, buf, ctx)?;
+                Ok(frame_constructor(pdu))
+            },
+        );
         self.decoders.insert(command_id, decoder);
     }
 
+    /// Use the given decode policy for subsequent PDUs
+    pub fn with_policy(mut self, policy: DecodePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Change the decode policy for subsequent PDUs
+    pub fn set_policy(&mut self, policy: DecodePolicy) {
+        self.policy = policy;
+    }
+
+    /// The decode policy applied to inbound PDUs
+    pub fn policy(&self) -> DecodePolicy {
+        self.policy
+    }
+
     /// Decode a PDU given its header and body
+    ///
+    /// Any warnings raised under a lenient policy are logged and discarded; use
+    /// [`PduRegistry::decode_frame`] to inspect them.
     pub fn decode_pdu(
         &self,
         header: PduHeader,
         buf: &mut Cursor<&[u8]>,
     ) -> Result<Frame, CodecError> {
-        match self.decoders.get(&header.command_id) {
-            Some(decoder) => decoder(header, buf),
+        let decoded = self.decode_frame(header, buf)?;
+        for warning in &decoded.warnings {
+            tracing::warn!(
+                "Tolerated non-conformant {:?}: {}",
+                decoded.frame.command_id(),
+                warning
+            );
+        }
+        Ok(decoded.frame)
+    }
+
+    /// Decode a PDU given its header and body, returning any warnings raised
+    ///
+    /// Decoding is confined to the `command_length - 16` octets of the body, so
+    /// a malformed PDU can never read into the one that follows it. On success
+    /// the cursor is advanced past the whole body.
+    pub fn decode_frame(
+        &self,
+        header: PduHeader,
+        buf: &mut Cursor<&[u8]>,
+    ) -> Result<DecodedFrame, CodecError> {
+        let body_size = (header.command_length as usize).saturating_sub(PduHeader::SIZE);
+        if buf.remaining() < body_size {
+            return Err(CodecError::Incomplete);
+        }
+
+        let start = buf.position() as usize;
+        let body = &buf.get_ref()[start..start + body_size];
+        let mut body_cursor = Cursor::new(body);
+        let mut ctx = DecodeContext::new(self.policy).with_version(self.version);
+
+        let frame = match self.decoders.get(&header.command_id) {
+            Some(decoder) => decoder(header, &mut body_cursor, &mut ctx)?,
             None => {
                 // Handle unknown PDU gracefully for forward compatibility
-                let body_size = header.command_length as usize - PduHeader::SIZE;
-                if buf.remaining() < body_size {
-                    return Err(CodecError::Incomplete);
-                }
-
-                let body = buf.copy_to_bytes(body_size);
                 tracing::warn!(
                     "Unknown PDU command_id: {:#x}, treating as opaque data",
                     header.command_id as u32
                 );
 
-                Ok(Frame::Unknown { header, body })
+                Frame::Unknown {
+                    header,
+                    body: Bytes::copy_from_slice(body),
+                }
             }
+        };
+
+        buf.advance(body_size);
+
+        Ok(DecodedFrame {
+            frame,
+            warnings: ctx.into_warnings(),
+        })
+    }
+
+    /// Check that an encoded outbound PDU is valid for this registry's version
+    ///
+    /// The command must exist in the negotiated version, and below v5.0 the PDU
+    /// is re-decoded so that parameters the version does not define (TLVs on
+    /// v3.3, v5.0 TLVs on v3.4, v3.4-style message IDs on v3.3) are caught
+    /// before they reach the peer. Field-level quirks are not re-checked.
+    pub fn check_outbound(&self, pdu: &[u8]) -> Result<(), CodecError> {
+        let mut cursor = Cursor::new(pdu);
+        let header = PduHeader::decode(&mut cursor)?;
+
+        if !self.version.supports_command(header.command_id) {
+            return Err(CodecError::FieldValidation {
+                field: "command_id",
+                reason: format!(
+                    "{:?} is not supported by {:?}",
+                    header.command_id, self.version
+                ),
+            });
         }
+
+        // Everything is permitted in the newest version, so skip the re-decode
+        if self.version < crate::datatypes::InterfaceVersion::SmppV50
+            && let Some(decoder) = self.decoders.get(&header.command_id)
+        {
+            let mut ctx = DecodeContext::outbound(self.version);
+            decoder(header, &mut cursor, &mut ctx)?;
+        }
+
+        Ok(())
     }
 
     /// Check if a command_id is registered
@@ -649,8 +1154,12 @@ impl PduRegistry {
     /// Check if this registry supports a specific feature
     pub fn supports_feature(&self, feature: &str) -> bool {
         match feature {
-            // Core v3.4 features
-            "submit_sm" | "deliver_sm" | "submit_multi" | "query_sm" | "replace_sm" | "cancel_sm" | "data_sm" => true,
+            // Core features available since v3.3
+            "submit_sm" | "deliver_sm" | "submit_multi" | "query_sm" | "replace_sm" | "cancel_sm" => true,
+            // Introduced in v3.4
+            "data_sm" | "bind_transceiver" | "alert_notification" | "tlv" => {
+                !matches!(self.version, crate::datatypes::InterfaceVersion::SmppV33)
+            }
             // v5.0 specific features
             "congestion_control" | "enhanced_billing" => {
                 matches!(self.version, crate::datatypes::InterfaceVersion::SmppV50)
@@ -666,51 +1175,75 @@ impl PduRegistry {
     /// Note: Does not downgrade - preserves existing capabilities
     pub fn upgrade_to_version(&mut self, version: crate::datatypes::InterfaceVersion) {
         // Only upgrade to higher versions, never downgrade
-        let should_upgrade = match (self.version, version) {
-            (crate::datatypes::InterfaceVersion::SmppV33, crate::datatypes::InterfaceVersion::SmppV34) => true,
-            (crate::datatypes::InterfaceVersion::SmppV33, crate::datatypes::InterfaceVersion::SmppV50) => true,
-            (crate::datatypes::InterfaceVersion::SmppV34, crate::datatypes::InterfaceVersion::SmppV50) => true,
-            _ => false,
-        };
-
-        if should_upgrade {
-            self.version = version;
-            // Re-initialize TLVs to include new version capabilities
-            self.supported_tlvs.clear();
-            self.initialize_supported_tlvs();
+        if version > self.version {
+            self.set_version(version);
         }
     }
 
+    /// Switch this registry to another SMPP version, upgrading or downgrading
+    ///
+    /// Decoders for commands the new version does not define are removed and
+    /// the supported TLVs are rebuilt, so a session that negotiated down stops
+    /// accepting PDUs its peer cannot legitimately send.
+    pub fn set_version(&mut self, version: crate::datatypes::InterfaceVersion) {
+        self.version = version;
+        self.decoders
+            .retain(|command_id, _| version.supports_command(*command_id));
+        self.supported_tlvs.clear();
+        self.initialize_supported_tlvs();
+        self.register_pdus_for_version();
+    }
+
+    /// Settle the session version after a bind and switch to it
+    ///
+    /// `requested` is the interface_version sent in the bind and `sc_version`
+    /// the sc_interface_version TLV from the bind response. The session uses
+    /// the lower of the two. An SMSC that omits the TLV is assumed to support
+    /// at most SMPP v3.4 (Section 5.3.2.25 only requires the TLV from v3.4
+    /// SMSCs, and v3.3 SMSCs cannot send it); if the peer really is v3.3 it
+    /// will still answer any v3.4-only PDU with an error status.
+    pub fn negotiate(
+        &mut self,
+        requested: crate::datatypes::InterfaceVersion,
+        sc_version: Option<crate::datatypes::InterfaceVersion>,
+    ) -> crate::datatypes::InterfaceVersion {
+        let offered = sc_version.unwrap_or(crate::datatypes::InterfaceVersion::SmppV34);
+        let version = Self::negotiate_version(requested, offered);
+        self.set_version(version);
+        version
+    }
+
     /// Detect SMPP version from a bind PDU
+    ///
+    /// Bind PDUs are parsed as specified, with variable-length C-Octet
+    /// Strings; if that does not account for exactly `command_length` octets
+    /// the fixed-width layout written by this crate's encoders is assumed.
     pub fn detect_version_from_bind(pdu_bytes: &[u8]) -> Option<crate::datatypes::InterfaceVersion> {
-        use std::io::Cursor;
-        
-        // Minimum PDU size check (header + interface_version field)
-        if pdu_bytes.len() < 16 + 15 + 9 + 13 + 1 {  // header + system_id + password + system_type + interface_version
-            return None;
+        // Offset just past a C-Octet String of at most `max_len` octets at `pos`
+        fn c_octet_string_end(pdu: &[u8], pos: usize, max_len: usize) -> Option<usize> {
+            let field = pdu.get(pos..)?;
+            let field = &field[..field.len().min(max_len)];
+            field.iter().position(|&b| b == 0).map(|nul| pos + nul + 1)
         }
 
-        let mut cursor = Cursor::new(pdu_bytes);
-        
-        // Skip PDU header (16 bytes)
-        cursor.set_position(16);
-        
-        // Skip system_id (16 bytes with null terminator)
-        cursor.set_position(cursor.position() + 16);
-        
-        // Skip password (9 bytes with null terminator)
-        cursor.set_position(cursor.position() + 9);
-        
-        // Skip system_type (13 bytes with null terminator)
-        cursor.set_position(cursor.position() + 13);
-        
-        // Read interface_version (1 byte)
-        if cursor.position() < pdu_bytes.len() as u64 {
-            let interface_version = pdu_bytes[cursor.position() as usize];
-            crate::datatypes::InterfaceVersion::try_from(interface_version).ok()
-        } else {
-            None
-        }
+        let length_bytes: [u8; 4] = pdu_bytes.get(..4)?.try_into().ok()?;
+        let pdu = pdu_bytes.get(..u32::from_be_bytes(length_bytes) as usize)?;
+
+        // system_id, password, system_type, interface_version, addr_ton,
+        // addr_npi, address_range
+        let variable_layout = || {
+            let mut pos = PduHeader::SIZE;
+            for max_len in [16, 9, 13] {
+                pos = c_octet_string_end(pdu, pos, max_len)?;
+            }
+            let interface_version = *pdu.get(pos)?;
+            pos = c_octet_string_end(pdu, pos + 3, 41)?;
+            (pos == pdu.len()).then_some(interface_version)
+        };
+        let fixed_layout = || pdu.get(PduHeader::SIZE + 16 + 9 + 13).copied();
+
+        let interface_version = variable_layout().or_else(fixed_layout)?;
+        crate::datatypes::InterfaceVersion::try_from(interface_version).ok()
     }
 
     /// Negotiate the highest common SMPP version between two versions
@@ -757,9 +1290,16 @@ impl Frame {
             Frame::EnquireLinkResp(_) => CommandId::EnquireLinkResp,
             Frame::Unbind(_) => CommandId::Unbind,
             Frame::UnbindResp(_) => CommandId::UnbindResp,
+            Frame::BindReceiver(_) => CommandId::BindReceiver,
+            Frame::BindReceiverResp(_) => CommandId::BindReceiverResp,
+            Frame::BindTransceiver(_) => CommandId::BindTransceiver,
+            Frame::BindTransceiverResp(_) => CommandId::BindTransceiverResp,
             Frame::BindTransmitter(_) => CommandId::BindTransmitter,
+            Frame::BindTransmitterResp(_) => CommandId::BindTransmitterResp,
             Frame::SubmitSm(_) => CommandId::SubmitSm,
             Frame::SubmitSmResp(_) => CommandId::SubmitSmResp,
+            Frame::DeliverSm(_) => CommandId::DeliverSm,
+            Frame::DeliverSmResp(_) => CommandId::DeliverSmResp,
             Frame::SubmitMulti(_) => CommandId::SubmitMulti,
             Frame::SubmitMultiResp(_) => CommandId::SubmitMultiResp,
             Frame::QuerySm(_) => CommandId::QuerySm,
@@ -790,9 +1330,16 @@ impl Frame {
             Frame::EnquireLinkResp(pdu) => pdu.sequence_number,
             Frame::Unbind(pdu) => pdu.sequence_number,
             Frame::UnbindResp(pdu) => pdu.sequence_number,
+            Frame::BindReceiver(pdu) => pdu.sequence_number,
+            Frame::BindReceiverResp(pdu) => pdu.sequence_number,
+            Frame::BindTransceiver(pdu) => pdu.sequence_number,
+            Frame::BindTransceiverResp(pdu) => pdu.sequence_number,
             Frame::BindTransmitter(pdu) => pdu.sequence_number,
+            Frame::BindTransmitterResp(pdu) => pdu.sequence_number,
             Frame::SubmitSm(pdu) => pdu.sequence_number,
             Frame::SubmitSmResp(pdu) => pdu.sequence_number,
+            Frame::DeliverSm(pdu) => pdu.sequence_number,
+            Frame::DeliverSmResp(pdu) => pdu.sequence_number,
             Frame::SubmitMulti(pdu) => pdu.sequence_number,
             Frame::SubmitMultiResp(pdu) => pdu.sequence_number,
             Frame::QuerySm(pdu) => pdu.sequence_number,
@@ -816,6 +1363,89 @@ impl Frame {
         }
     }
 
+    /// Get the command_status for this frame
+    pub fn command_status(&self) -> CommandStatus {
+        match self {
+            Frame::EnquireLink(pdu) => pdu.command_status,
+            Frame::EnquireLinkResp(pdu) => pdu.command_status,
+            Frame::Unbind(pdu) => pdu.command_status,
+            Frame::UnbindResp(pdu) => pdu.command_status,
+            Frame::BindReceiver(pdu) => pdu.command_status,
+            Frame::BindReceiverResp(pdu) => pdu.command_status,
+            Frame::BindTransceiver(pdu) => pdu.command_status,
+            Frame::BindTransceiverResp(pdu) => pdu.command_status,
+            Frame::BindTransmitter(pdu) => pdu.command_status,
+            Frame::BindTransmitterResp(pdu) => pdu.command_status,
+            Frame::SubmitSm(pdu) => pdu.command_status,
+            Frame::SubmitSmResp(pdu) => pdu.command_status,
+            Frame::DeliverSm(pdu) => pdu.command_status,
+            Frame::DeliverSmResp(pdu) => pdu.command_status,
+            Frame::SubmitMulti(pdu) => pdu.command_status,
+            Frame::SubmitMultiResp(pdu) => pdu.command_status,
+            Frame::QuerySm(pdu) => pdu.command_status,
+            Frame::QuerySmResp(pdu) => pdu.command_status,
+            Frame::ReplaceSm(pdu) => pdu.command_status,
+            Frame::ReplaceSmResp(pdu) => pdu.command_status,
+            Frame::CancelSm(pdu) => pdu.command_status,
+            Frame::CancelSmResp(pdu) => pdu.command_status,
+            Frame::DataSm(pdu) => pdu.command_status,
+            Frame::DataSmResp(pdu) => pdu.command_status,
+            Frame::AlertNotification(pdu) => pdu.command_status,
+            Frame::BroadcastSm(pdu) => pdu.command_status,
+            Frame::BroadcastSmResp(pdu) => pdu.command_status,
+            Frame::QueryBroadcastSm(pdu) => pdu.command_status,
+            Frame::QueryBroadcastSmResp(pdu) => pdu.command_status,
+            Frame::CancelBroadcastSm(pdu) => pdu.command_status,
+            Frame::CancelBroadcastSmResp(pdu) => pdu.command_status,
+            Frame::GenericNack(pdu) => pdu.command_status,
+            Frame::Outbind(pdu) => pdu.command_status,
+            Frame::Unknown { header, .. } => header.command_status,
+        }
+    }
+
+    /// Encode this frame to its wire representation
+    ///
+    /// Returns `None` for [`Frame::Unknown`], which carries no typed PDU to encode.
+    pub fn to_bytes(&self) -> Option<Bytes> {
+        let bytes = match self {
+            Frame::EnquireLink(pdu) => pdu.to_bytes(),
+            Frame::EnquireLinkResp(pdu) => pdu.to_bytes(),
+            Frame::Unbind(pdu) => pdu.to_bytes(),
+            Frame::UnbindResp(pdu) => pdu.to_bytes(),
+            Frame::BindReceiver(pdu) => pdu.to_bytes(),
+            Frame::BindReceiverResp(pdu) => pdu.to_bytes(),
+            Frame::BindTransceiver(pdu) => pdu.to_bytes(),
+            Frame::BindTransceiverResp(pdu) => pdu.to_bytes(),
+            Frame::BindTransmitter(pdu) => pdu.to_bytes(),
+            Frame::BindTransmitterResp(pdu) => pdu.to_bytes(),
+            Frame::SubmitSm(pdu) => pdu.to_bytes(),
+            Frame::SubmitSmResp(pdu) => pdu.to_bytes(),
+            Frame::DeliverSm(pdu) => pdu.to_bytes(),
+            Frame::DeliverSmResp(pdu) => pdu.to_bytes(),
+            Frame::SubmitMulti(pdu) => pdu.to_bytes(),
+            Frame::SubmitMultiResp(pdu) => pdu.to_bytes(),
+            Frame::QuerySm(pdu) => pdu.to_bytes(),
+            Frame::QuerySmResp(pdu) => pdu.to_bytes(),
+            Frame::ReplaceSm(pdu) => pdu.to_bytes(),
+            Frame::ReplaceSmResp(pdu) => pdu.to_bytes(),
+            Frame::CancelSm(pdu) => pdu.to_bytes(),
+            Frame::CancelSmResp(pdu) => pdu.to_bytes(),
+            Frame::DataSm(pdu) => pdu.to_bytes(),
+            Frame::DataSmResp(pdu) => pdu.to_bytes(),
+            Frame::AlertNotification(pdu) => pdu.to_bytes(),
+            Frame::BroadcastSm(pdu) => pdu.to_bytes(),
+            Frame::BroadcastSmResp(pdu) => pdu.to_bytes(),
+            Frame::QueryBroadcastSm(pdu) => pdu.to_bytes(),
+            Frame::QueryBroadcastSmResp(pdu) => pdu.to_bytes(),
+            Frame::CancelBroadcastSm(pdu) => pdu.to_bytes(),
+            Frame::CancelBroadcastSmResp(pdu) => pdu.to_bytes(),
+            Frame::GenericNack(pdu) => pdu.to_bytes(),
+            Frame::Outbind(pdu) => pdu.to_bytes(),
+            Frame::Unknown { .. } => return None,
+        };
+        Some(bytes)
+    }
+
     /// Check if this frame is a response PDU
     pub fn is_response(&self) -> bool {
         self.command_id().is_response()
@@ -969,8 +1599,9 @@ mod tests {
     fn registry_decode_unknown_pdu() {
         let registry = PduRegistry::new();
 
-        // Use BindReceiver command_id - exists in CommandId enum but not registered in registry
-        let unknown_command_id = 0x0000_0001u32; // CommandId::BindReceiver
+        // Use BroadcastSm command_id - exists in CommandId enum but is not
+        // registered in a v3.4 registry
+        let unknown_command_id = 0x0000_0111u32; // CommandId::BroadcastSm
 
         // Create a complete PDU with unknown command_id
         let mut pdu_data = Vec::new();
@@ -1227,4 +1858,90 @@ mod tests {
         assert!(registered.contains(&CommandId::GenericNack));
         assert!(registered.contains(&CommandId::Outbind));
     }
+
+    #[test]
+    fn decode_c_octet_string_stops_at_terminator() {
+        let data = b"hello\0next";
+        let mut cursor = Cursor::new(&data[..]);
+        let mut ctx = DecodeContext::strict();
+        let result = decode_c_octet_string(&mut cursor, 21, "test", &mut ctx).unwrap();
+        assert_eq!(result, "hello");
+        assert_eq!(cursor.position(), 6);
+    }
+
+    #[test]
+    fn decode_c_octet_string_missing_terminator() {
+        let data = b"12345X";
+
+        let mut cursor = Cursor::new(&data[..]);
+        let mut ctx = DecodeContext::strict();
+        assert!(matches!(
+            decode_c_octet_string(&mut cursor, 6, "test", &mut ctx),
+            Err(CodecError::FieldValidation { field: "test", .. })
+        ));
+
+        // Lenient mode keeps the content and leaves the next octet unread
+        let mut cursor = Cursor::new(&data[..]);
+        let mut ctx = DecodeContext::lenient();
+        let result = decode_c_octet_string(&mut cursor, 6, "test", &mut ctx).unwrap();
+        assert_eq!(result, "12345");
+        assert_eq!(cursor.position(), 5);
+        assert_eq!(ctx.warnings().len(), 1);
+        assert_eq!(ctx.warnings()[0].field, "test");
+    }
+
+    #[test]
+    fn decode_context_recover_respects_policy() {
+        let failure = || -> Result<u8, CodecError> {
+            Err(CodecError::FieldValidation {
+                field: "priority_flag",
+                reason: "Invalid priority flag".to_string(),
+            })
+        };
+
+        let mut strict = DecodeContext::strict();
+        assert!(strict.recover(failure(), || 0).is_err());
+        assert!(strict.warnings().is_empty());
+
+        let mut lenient = DecodeContext::lenient();
+        assert_eq!(lenient.recover(failure(), || 0).unwrap(), 0);
+        assert_eq!(lenient.warnings()[0].field, "priority_flag");
+
+        // Truncation is never recoverable
+        assert!(matches!(
+            lenient.recover::<u8>(Err(CodecError::Incomplete), || 0),
+            Err(CodecError::Incomplete)
+        ));
+    }
+
+    #[test]
+    fn registry_decode_policy_defaults_to_strict() {
+        let registry = PduRegistry::new();
+        assert_eq!(registry.policy(), DecodePolicy::Strict);
+
+        let mut registry = registry.with_policy(DecodePolicy::Lenient);
+        assert_eq!(registry.policy(), DecodePolicy::Lenient);
+
+        registry.set_policy(DecodePolicy::Strict);
+        assert_eq!(registry.policy(), DecodePolicy::Strict);
+    }
+
+    #[test]
+    fn registry_decode_frame_is_bounded_by_command_length() {
+        let mut data = EnquireLink::new(1).to_bytes().to_vec();
+        data.extend_from_slice(&EnquireLink::new(2).to_bytes());
+
+        let registry = PduRegistry::new();
+        let mut cursor = Cursor::new(data.as_slice());
+
+        let header = PduHeader::decode(&mut cursor).unwrap();
+        let decoded = registry.decode_frame(header, &mut cursor).unwrap();
+        assert!(decoded.is_clean());
+        assert_eq!(decoded.frame.sequence_number(), 1);
+        assert_eq!(cursor.position(), 16);
+
+        let header = PduHeader::decode(&mut cursor).unwrap();
+        let decoded = registry.decode_frame(header, &mut cursor).unwrap();
+        assert_eq!(decoded.frame.sequence_number(), 2);
+    }
 }
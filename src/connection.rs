@@ -1,9 +1,12 @@
 // ABOUTME: Provides TCP connection management for SMPP v3.4 protocol communication
 // ABOUTME: Implements frame-based I/O with buffering for optimal network performance
 
-use crate::codec::Encodable;
+use crate::codec::{CodecError, DecodedFrame, MAX_PDU_SIZE, PduHeader, PduRegistry};
 use crate::frame::{self, Frame};
-use bytes::{Buf, BytesMut};
+use crate::metrics::SessionMetrics;
+use crate::recording::{Direction, Recorder};
+use crate::redaction::{RedactedPdu, RedactionPolicy};
+use bytes::{Buf, Bytes, BytesMut};
 use std::io::{self, Cursor};
 use tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter};
 use tokio::net::TcpStream;
@@ -73,12 +76,32 @@ pub struct Connection {
 
     // The buffer for reading frames.
     buffer: BytesMut,
+
+    // Decoders for inbound PDUs, including the decode policy to apply.
+    registry: PduRegistry,
+
+    // Where every PDU read or written is recorded, if anywhere.
+    recorder: Option<Recorder>,
+
+    // Where every PDU read or written is counted, if anywhere.
+    metrics: Option<SessionMetrics>,
+
+    // What is left out of PDUs logged at trace level.
+    redaction: RedactionPolicy,
 }
 
 impl Connection {
     /// Create a new `Connection`, backed by `socket`. Read and write buffers
     /// are initialized.
     pub fn new(socket: TcpStream) -> Connection {
+        Self::with_registry(socket, PduRegistry::new())
+    }
+
+    /// Create a new `Connection` that decodes inbound PDUs with `registry`.
+    ///
+    /// Use this to select a protocol version or a lenient
+    /// [`DecodePolicy`](crate::codec::DecodePolicy) for a non-conformant peer.
+    pub fn with_registry(socket: TcpStream, registry: PduRegistry) -> Connection {
         Connection {
             stream: BufWriter::new(socket),
             // Default to a 4KB read buffer. For the use case of mini redis,
@@ -86,9 +109,65 @@ impl Connection {
             // value to their specific use case. There is a high likelihood that
             // a larger read buffer will work better.
             buffer: BytesMut::with_capacity(4 * 1024),
+            registry,
+            recorder: None,
+            metrics: None,
+            redaction: RedactionPolicy::default(),
         }
     }
 
+    /// Record every PDU read or written from now on to `recorder`.
+    ///
+    /// PDUs are recorded as they were on the wire, including inbound ones
+    /// that then fail to decode.
+    pub fn set_recorder(&mut self, recorder: Recorder) {
+        self.recorder = Some(recorder);
+    }
+
+    /// Stop recording PDUs.
+    pub fn clear_recorder(&mut self) {
+        self.recorder = None;
+    }
+
+    /// Count every PDU read or written from now on in `metrics`.
+    pub fn set_metrics(&mut self, metrics: SessionMetrics) {
+        self.metrics = Some(metrics);
+    }
+
+    /// The metrics PDUs are counted in, if any.
+    pub fn metrics(&self) -> Option<&SessionMetrics> {
+        self.metrics.as_ref()
+    }
+
+    /// Mutable access to the metrics, e.g. to label them with the system_id
+    /// once it is known.
+    pub fn metrics_mut(&mut self) -> Option<&mut SessionMetrics> {
+        self.metrics.as_mut()
+    }
+
+    /// Set what is left out of the PDUs logged at trace level.
+    ///
+    /// Passwords and message content are redacted by default.
+    pub fn set_redaction_policy(&mut self, policy: RedactionPolicy) {
+        self.redaction = policy;
+    }
+
+    /// What is left out of the PDUs logged at trace level.
+    pub fn redaction_policy(&self) -> RedactionPolicy {
+        self.redaction
+    }
+
+    /// The registry used to decode inbound PDUs.
+    pub fn registry(&self) -> &PduRegistry {
+        &self.registry
+    }
+
+    /// Mutable access to the registry, e.g. to change the decode policy or
+    /// upgrade the protocol version after bind negotiation.
+    pub fn registry_mut(&mut self) -> &mut PduRegistry {
+        &mut self.registry
+    }
+
     /// Read a single `Frame` value from the underlying stream.
     ///
     /// The function waits until it has retrieved enough data to parse a frame.
@@ -101,6 +180,24 @@ impl Connection {
     /// is closed in a way that doesn't break a frame in half, it returns
     /// `None`. Otherwise, an error is returned.
     pub async fn read_frame(&mut self) -> crate::Result<Option<Frame>> {
+        Ok(self.read_decoded_frame().await?.map(|decoded| {
+            for warning in &decoded.warnings {
+                tracing::warn!(
+                    "Tolerated non-conformant {:?}: {}",
+                    decoded.frame.command_id(),
+                    warning
+                );
+            }
+            decoded.frame
+        }))
+    }
+
+    /// Read a single frame together with any warnings raised while decoding it.
+    ///
+    /// Warnings are only produced when the registry uses a lenient
+    /// [`DecodePolicy`](crate::codec::DecodePolicy); they describe the
+    /// specification violations that were normalised rather than rejected.
+    pub async fn read_decoded_frame(&mut self) -> crate::Result<Option<DecodedFrame>> {
         loop {
             // Attempt to parse a frame from the buffered data. If enough data
             // has been buffered, the frame is returned.
@@ -127,11 +224,68 @@ impl Connection {
         }
     }
 
+    /// Read the octets of a single PDU without decoding it.
+    ///
+    /// Only the command_length is checked, so that PDUs this crate cannot
+    /// decode are passed through as they are. Returns `None` when the peer
+    /// closes the connection between PDUs.
+    pub async fn read_pdu(&mut self) -> crate::Result<Option<Bytes>> {
+        loop {
+            if self.buffer.len() >= 4 {
+                let len = u32::from_be_bytes([
+                    self.buffer[0],
+                    self.buffer[1],
+                    self.buffer[2],
+                    self.buffer[3],
+                ]);
+                if !(PduHeader::SIZE as u32..=MAX_PDU_SIZE).contains(&len) {
+                    let error = CodecError::InvalidPduLength {
+                        length: len,
+                        min: PduHeader::SIZE as u32,
+                        max: MAX_PDU_SIZE,
+                    };
+                    return Err(frame::Error::from(error).into());
+                }
+                if self.buffer.len() >= len as usize {
+                    let pdu = self.buffer.split_to(len as usize).freeze();
+                    self.observe(Direction::Inbound, &pdu);
+                    return Ok(Some(pdu));
+                }
+            }
+
+            if 0 == self.stream.read_buf(&mut self.buffer).await? {
+                return self
+                    .buffer
+                    .is_empty()
+                    .then(|| None)
+                    .ok_or_else(|| "connection reset by peer".into());
+            }
+        }
+    }
+
+    /// Record, count and log a PDU read or about to be written.
+    ///
+    /// Inbound PDUs are observed before they are decoded, so that ones that
+    /// then fail to decode are observed too.
+    fn observe(&self, direction: Direction, pdu: &[u8]) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(direction, pdu);
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.pdu(direction, pdu);
+        }
+        tracing::trace!(
+            ?direction,
+            pdu = %RedactedPdu::new(pdu, self.registry.version(), self.redaction),
+            "PDU"
+        );
+    }
+
     /// Tries to parse a frame from the buffer. If the buffer contains enough
     /// data, the frame is returned and the data removed from the buffer. If not
     /// enough data has been buffered yet, `Ok(None)` is returned. If the
     /// buffered data does not represent a valid frame, `Err` is returned.
-    fn parse_frame(&mut self) -> crate::Result<Option<Frame>> {
+    fn parse_frame(&mut self) -> crate::Result<Option<DecodedFrame>> {
         use frame::Error::Incomplete;
 
         // Cursor is used to track the "current" location in the
@@ -160,10 +314,11 @@ impl Connection {
                     header_buf[3],
                 ]);
                 let len = command_length as usize;
-                // Reset the position to zero before passing the cursor to
-                // `Frame::parse`.
+                // Reset the position to zero before decoding the header.
                 buf.set_position(0);
 
+                self.observe(Direction::Inbound, &self.buffer[..len]);
+
                 // Parse the frame from the buffer. This allocates the necessary
                 // structures to represent the frame and returns the frame
                 // value.
@@ -171,7 +326,11 @@ impl Connection {
                 // If the encoded frame representation is invalid, an error is
                 // returned. This should terminate the **current** connection
                 // but should not impact any other connected client.
-                let frame = Frame::parse(&mut buf)?;
+                let header = PduHeader::decode(&mut buf).map_err(frame::Error::from)?;
+                let frame = self
+                    .registry
+                    .decode_frame(header, &mut buf)
+                    .map_err(frame::Error::from)?;
 
                 // Discard the parsed data from the read buffer.
                 //
@@ -207,94 +366,33 @@ impl Connection {
     /// syscalls. However, it is fine to call these functions on a *buffered*
     /// write stream. The data will be written to the buffer. Once the buffer is
     /// full, it is flushed to the underlying socket.
+    ///
+    /// Before writing, the encoded PDU is checked against the registry's
+    /// protocol version (see [`PduRegistry::check_outbound`]), so that a
+    /// session bound as SMPP v3.3 never emits v3.4-only PDUs or parameters.
     pub async fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
-        match frame {
-            Frame::BindTransmitter(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::EnquireLink(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::EnquireLinkResp(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::SubmitSm(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::SubmitSmResp(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::SubmitMulti(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::SubmitMultiResp(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::QuerySm(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::QuerySmResp(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::ReplaceSm(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::ReplaceSmResp(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::CancelSm(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::CancelSmResp(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::DataSm(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::DataSmResp(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::AlertNotification(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::BroadcastSm(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::BroadcastSmResp(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::QueryBroadcastSm(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::QueryBroadcastSmResp(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::CancelBroadcastSm(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::CancelBroadcastSmResp(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::Unbind(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::UnbindResp(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::Outbind(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::GenericNack(pdu) => {
-                self.stream.write_all(&pdu.to_bytes()).await?;
-            }
-            Frame::Unknown { .. } => {
-                // For unknown frames, we can't serialize them back
-                return Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "Cannot write unknown frame type",
-                ));
-            }
-        }
+        let Some(bytes) = frame.to_bytes() else {
+            // For unknown frames, we can't serialize them back
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Cannot write unknown frame type",
+            ));
+        };
+
+        self.registry
+            .check_outbound(&bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        self.write_pdu(&bytes).await
+    }
+
+    /// Write an encoded PDU as it is.
+    ///
+    /// Unlike [`write_frame`](Self::write_frame) nothing is checked, so this
+    /// can reproduce PDUs exactly as another peer sent them.
+    pub async fn write_pdu(&mut self, pdu: &[u8]) -> io::Result<()> {
+        self.observe(Direction::Outbound, pdu);
+        self.stream.write_all(pdu).await?;
 
         // Ensure the encoded frame is written to the socket. The calls above
         // are to the buffered stream and writes. Calling `flush` writes the
@@ -2,9 +2,10 @@
 // ABOUTME: Provides notification functionality per specification Section 4.12
 
 use crate::datatypes::{
-    AddressError, CommandId, CommandStatus, NumericPlanIndicator, SourceAddr, TypeOfNumber,
+    AddressError, CommandId, CommandStatus, NumericPlanIndicator, SourceAddr, Tlv, TypeOfNumber,
+    tags,
 };
-use bytes::{BufMut, BytesMut};
+use bytes::{Buf, BufMut, BytesMut};
 use std::io::Cursor;
 use thiserror::Error;
 
@@ -23,6 +24,32 @@ pub enum AlertNotificationValidationError {
     EsmeAddr(AddressError),
 }
 
+/// Availability of a mobile subscriber, from the ms_availability_status TLV
+/// Per SMPP v3.4 specification Section 5.3.2.30
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum MsAvailabilityStatus {
+    /// The subscriber can receive messages
+    Available = 0x00,
+    /// The subscriber is reachable but messages to it are barred
+    Denied = 0x01,
+    /// The subscriber cannot be reached
+    Unavailable = 0x02,
+}
+
+impl TryFrom<u8> for MsAvailabilityStatus {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(MsAvailabilityStatus::Available),
+            0x01 => Ok(MsAvailabilityStatus::Denied),
+            0x02 => Ok(MsAvailabilityStatus::Unavailable),
+            _ => Err(()),
+        }
+    }
+}
+
 /// SMPP v3.4 alert_notification PDU (Section 4.12.1)
 ///
 /// The alert_notification PDU is sent by the SMSC to an ESME to notify it of
@@ -46,6 +73,9 @@ pub enum AlertNotificationValidationError {
 /// - esme_addr_npi: Numbering Plan Indicator for ESME address
 /// - esme_addr: Address of the ESME to be notified
 ///
+/// ## Optional Parameters
+/// - ms_availability_status: Availability of the subscriber; absent means available
+///
 /// ## Usage Scenarios
 /// 1. **Subscriber Availability**: Mobile device comes back online
 /// 2. **Network Recovery**: Network connectivity restored to subscriber
@@ -83,6 +113,10 @@ pub struct AlertNotification {
     /// Address of the ESME that should be notified.
     /// This identifies which ESME should be informed of the subscriber availability.
     pub esme_addr: SourceAddr, // Reusing SourceAddr type for ESME address
+
+    // Optional parameters
+    /// The status of the subscriber; see [`availability`](Self::availability).
+    pub ms_availability_status: Option<Tlv>,
 }
 
 impl AlertNotification {
@@ -105,6 +139,7 @@ impl AlertNotification {
             esme_addr_ton,
             esme_addr_npi,
             esme_addr,
+            ms_availability_status: None,
         };
 
         // Validate the PDU
@@ -185,6 +220,27 @@ impl AlertNotification {
         self.esme_addr.as_str().ok()
     }
 
+    /// Set the ms_availability_status TLV
+    pub fn with_availability(mut self, status: MsAvailabilityStatus) -> Self {
+        self.ms_availability_status =
+            Tlv::new(tags::MS_AVAILABILITY_STATUS, vec![status as u8]).ok();
+        self
+    }
+
+    /// Get the subscriber's availability
+    ///
+    /// The TLV is optional and its absence means the subscriber is available.
+    /// Returns `None` when the TLV carries an unknown value.
+    pub fn availability(&self) -> Option<MsAvailabilityStatus> {
+        match &self.ms_availability_status {
+            Some(tlv) => tlv
+                .value
+                .first()
+                .and_then(|&value| MsAvailabilityStatus::try_from(value).ok()),
+            None => Some(MsAvailabilityStatus::Available),
+        }
+    }
+
     /// Validate the AlertNotification PDU
     fn validate(&self) -> Result<(), AlertNotificationValidationError> {
         // source_addr validation is handled by SourceAddr type
@@ -220,11 +276,20 @@ impl Encodable for AlertNotification {
         // esme_addr (21 octets, null-terminated with padding)
         encode_cstring(buf, self.esme_addr.as_str().ok().unwrap_or(""), 21);
 
+        // Optional parameters
+        if let Some(ref tlv) = self.ms_availability_status {
+            tlv.encode(buf)?;
+        }
+
         Ok(())
     }
 
     fn encoded_size(&self) -> usize {
-        16 + 1 + 1 + 21 + 1 + 1 + 21 // header + fixed fields
+        let mut size = 16 + 1 + 1 + 21 + 1 + 1 + 21; // header + fixed fields
+        if let Some(ref tlv) = self.ms_availability_status {
+            size += tlv.encoded_size();
+        }
+        size
     }
 }
 
@@ -275,6 +340,15 @@ impl Decodable for AlertNotification {
             }
         })?;
 
+        // ms_availability_status is the only optional parameter; skip others
+        let mut ms_availability_status = None;
+        while buf.remaining() >= 4 {
+            let tlv = Tlv::decode(buf)?;
+            if tlv.tag == tags::MS_AVAILABILITY_STATUS {
+                ms_availability_status = Some(tlv);
+            }
+        }
+
         Ok(AlertNotification {
             command_status: header.command_status,
             sequence_number: header.sequence_number,
@@ -284,6 +358,7 @@ impl Decodable for AlertNotification {
             esme_addr_ton,
             esme_addr_npi,
             esme_addr,
+            ms_availability_status,
         })
     }
 
@@ -475,6 +550,30 @@ mod tests {
         assert_eq!(alert_notification, decoded);
     }
 
+    #[test]
+    fn test_alert_notification_ms_availability_status_roundtrip() {
+        let original =
+            AlertNotification::international_subscriber_available(7, "447700900123", "447700900999")
+                .unwrap();
+        assert_eq!(original.availability(), Some(MsAvailabilityStatus::Available));
+
+        let original = original.with_availability(MsAvailabilityStatus::Unavailable);
+        let mut buf = BytesMut::new();
+        original.encode(&mut buf).unwrap();
+        assert_eq!(buf.len(), original.encoded_size());
+
+        let header = PduHeader {
+            command_length: buf.len() as u32,
+            command_id: CommandId::AlertNotification,
+            command_status: CommandStatus::Ok,
+            sequence_number: 7,
+        };
+        let mut cursor = Cursor::new(&buf[16..]);
+        let decoded = AlertNotification::decode(header, &mut cursor).unwrap();
+        assert_eq!(decoded, original);
+        assert_eq!(decoded.availability(), Some(MsAvailabilityStatus::Unavailable));
+    }
+
     #[test]
     fn test_alert_notification_encoded_size() {
         let source_addr = SourceAddr::new("123", TypeOfNumber::National).unwrap();
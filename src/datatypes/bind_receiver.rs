@@ -1,7 +1,8 @@
 use crate::codec::{
-    CodecError, Decodable, Encodable, PduHeader, decode_cstring, decode_u8, encode_cstring,
-    encode_u8,
+    CodecError, Decodable, DecodeContext, Encodable, PduHeader, decode_cstring, decode_u8,
+    encode_cstring, encode_u8,
 };
+use crate::datatypes::bind_transmitter::{decode_bind_response_body, sc_interface_version};
 use crate::datatypes::interface_version::InterfaceVersion;
 use crate::datatypes::numeric_plan_indicator::NumericPlanIndicator;
 use crate::datatypes::tlv::Tlv;
@@ -214,6 +215,13 @@ impl Encodable for BindReceiverResponse {
     }
 }
 
+impl BindReceiverResponse {
+    /// The version reported by the SMSC in the sc_interface_version TLV, if any
+    pub fn interface_version(&self) -> Option<InterfaceVersion> {
+        sc_interface_version(self.sc_interface_version.as_ref())
+    }
+}
+
 // New codec trait implementations
 
 impl Decodable for BindReceiver {
@@ -295,6 +303,32 @@ impl Decodable for BindReceiver {
     }
 }
 
+impl Decodable for BindReceiverResponse {
+    fn command_id() -> CommandId {
+        CommandId::BindReceiverResp
+    }
+
+    fn decode(header: PduHeader, buf: &mut std::io::Cursor<&[u8]>) -> Result<Self, CodecError> {
+        Self::decode_with_context(header, buf, &mut DecodeContext::strict())
+    }
+
+    fn decode_with_context(
+        header: PduHeader,
+        buf: &mut std::io::Cursor<&[u8]>,
+        ctx: &mut DecodeContext,
+    ) -> Result<Self, CodecError> {
+        Self::validate_header(&header)?;
+        let (system_id, sc_interface_version) = decode_bind_response_body(buf, ctx)?;
+
+        Ok(BindReceiverResponse {
+            command_status: header.command_status,
+            sequence_number: header.sequence_number,
+            system_id,
+            sc_interface_version,
+        })
+    }
+}
+
 impl Encodable for BindReceiver {
     fn encode(&self, buf: &mut BytesMut) -> Result<(), CodecError> {
         // Calculate body size (fixed field sizes)
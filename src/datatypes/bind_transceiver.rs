@@ -1,7 +1,8 @@
 use crate::codec::{
-    CodecError, Decodable, Encodable, PduHeader, decode_cstring, decode_u8, encode_cstring,
-    encode_u8,
+    CodecError, Decodable, DecodeContext, Encodable, PduHeader, decode_cstring, decode_u8,
+    encode_cstring, encode_u8,
 };
+use crate::datatypes::bind_transmitter::{decode_bind_response_body, sc_interface_version};
 use crate::datatypes::interface_version::InterfaceVersion;
 use crate::datatypes::numeric_plan_indicator::NumericPlanIndicator;
 use crate::datatypes::tlv::Tlv;
@@ -215,6 +216,13 @@ impl Encodable for BindTransceiverResponse {
     }
 }
 
+impl BindTransceiverResponse {
+    /// The version reported by the SMSC in the sc_interface_version TLV, if any
+    pub fn interface_version(&self) -> Option<InterfaceVersion> {
+        sc_interface_version(self.sc_interface_version.as_ref())
+    }
+}
+
 // New codec trait implementations
 
 impl Decodable for BindTransceiver {
@@ -296,6 +304,32 @@ impl Decodable for BindTransceiver {
     }
 }
 
+impl Decodable for BindTransceiverResponse {
+    fn command_id() -> CommandId {
+        CommandId::BindTransceiverResp
+    }
+
+    fn decode(header: PduHeader, buf: &mut std::io::Cursor<&[u8]>) -> Result<Self, CodecError> {
+        Self::decode_with_context(header, buf, &mut DecodeContext::strict())
+    }
+
+    fn decode_with_context(
+        header: PduHeader,
+        buf: &mut std::io::Cursor<&[u8]>,
+        ctx: &mut DecodeContext,
+    ) -> Result<Self, CodecError> {
+        Self::validate_header(&header)?;
+        let (system_id, sc_interface_version) = decode_bind_response_body(buf, ctx)?;
+
+        Ok(BindTransceiverResponse {
+            command_status: header.command_status,
+            sequence_number: header.sequence_number,
+            system_id,
+            sc_interface_version,
+        })
+    }
+}
+
 impl Encodable for BindTransceiver {
     fn encode(&self, buf: &mut BytesMut) -> Result<(), CodecError> {
         // Calculate body size (fixed field sizes)
@@ -461,14 +495,10 @@ mod tests {
         let parsed_frame = Frame::parse(&mut cursor).unwrap();
 
         // Verify it matches
-        // TODO: Add BindTransceiver codec implementation
-        if let Frame::Unknown { .. } = parsed_frame {
-            // Once BindTransceiver codec is implemented, add proper assertions
-            // assert_eq!(parsed.command_status, original.command_status);
-            // assert_eq!(parsed.sequence_number, original.sequence_number);
-            // etc.
+        if let Frame::BindTransceiver(parsed) = parsed_frame {
+            assert_eq!(parsed, original);
         } else {
-            panic!("Expected Unknown frame (BindTransceiver codec not implemented)");
+            panic!("Expected BindTransceiver frame");
         }
     }
 }
@@ -1,15 +1,15 @@
 use crate::codec::{
-    CodecError, Decodable, Encodable, PduHeader, decode_cstring, decode_u8, encode_cstring,
-    encode_u8,
+    CodecError, Decodable, DecodeContext, Encodable, PduHeader, decode_c_octet_string,
+    decode_cstring, decode_u8, encode_cstring, encode_u8, skip_nul_padding,
 };
 use crate::datatypes::interface_version::InterfaceVersion;
 use crate::datatypes::numeric_plan_indicator::NumericPlanIndicator;
-use crate::datatypes::tlv::Tlv;
+use crate::datatypes::tlv::{Tlv, tags};
 use crate::datatypes::{
     AddressRange, CommandId, CommandStatus, Password, SystemId, SystemType, TypeOfNumber,
 };
 use crate::macros::builder_setters;
-use bytes::BytesMut;
+use bytes::{Buf, BytesMut};
 use std::io::Cursor;
 
 /// BindTransmitter is used to bind a transmitter ESME to the SMSC.
@@ -205,6 +205,58 @@ impl Encodable for BindTransmitterResponse {
     }
 }
 
+impl BindTransmitterResponse {
+    /// The version reported by the SMSC in the sc_interface_version TLV, if any
+    pub fn interface_version(&self) -> Option<InterfaceVersion> {
+        sc_interface_version(self.sc_interface_version.as_ref())
+    }
+}
+
+/// Interpret an sc_interface_version TLV
+pub(crate) fn sc_interface_version(tlv: Option<&Tlv>) -> Option<InterfaceVersion> {
+    tlv.and_then(|tlv| tlv.value.first())
+        .and_then(|&version| InterfaceVersion::try_from(version).ok())
+}
+
+/// Decode the body shared by all bind_*_resp PDUs
+///
+/// Accepts system_id both as a variable-length C-Octet String and as the
+/// 16-octet NUL-padded field written by [`BindTransmitterResponse::encode`].
+/// An error response may omit the body entirely. Of the optional parameters
+/// only sc_interface_version is kept; anything else is ignored as the
+/// specification requires for unrecognised TLVs.
+pub(crate) fn decode_bind_response_body(
+    buf: &mut Cursor<&[u8]>,
+    ctx: &mut DecodeContext,
+) -> Result<(SystemId, Option<Tlv>), CodecError> {
+    if !buf.has_remaining() {
+        return Ok((SystemId::default(), None));
+    }
+
+    let start = buf.position();
+    let system_id_str = decode_c_octet_string(buf, 16, "system_id", ctx)?;
+    let system_id = ctx.recover(
+        SystemId::from_parsed_string(system_id_str).map_err(|e| CodecError::FieldValidation {
+            field: "system_id",
+            reason: e.to_string(),
+        }),
+        SystemId::default,
+    )?;
+
+    // Skip the NUL padding of a fixed-width system_id
+    skip_nul_padding(buf, start, 16);
+
+    let mut sc_interface_version = None;
+    while buf.remaining() >= 4 {
+        let tlv = Tlv::decode(buf)?;
+        if tlv.tag == tags::SC_INTERFACE_VERSION {
+            sc_interface_version = Some(tlv);
+        }
+    }
+
+    Ok((system_id, sc_interface_version))
+}
+
 // New codec trait implementations
 
 impl Decodable for BindTransmitter {
@@ -286,6 +338,32 @@ impl Decodable for BindTransmitter {
     }
 }
 
+impl Decodable for BindTransmitterResponse {
+    fn command_id() -> CommandId {
+        CommandId::BindTransmitterResp
+    }
+
+    fn decode(header: PduHeader, buf: &mut Cursor<&[u8]>) -> Result<Self, CodecError> {
+        Self::decode_with_context(header, buf, &mut DecodeContext::strict())
+    }
+
+    fn decode_with_context(
+        header: PduHeader,
+        buf: &mut Cursor<&[u8]>,
+        ctx: &mut DecodeContext,
+    ) -> Result<Self, CodecError> {
+        Self::validate_header(&header)?;
+        let (system_id, sc_interface_version) = decode_bind_response_body(buf, ctx)?;
+
+        Ok(BindTransmitterResponse {
+            command_status: header.command_status,
+            sequence_number: header.sequence_number,
+            system_id,
+            sc_interface_version,
+        })
+    }
+}
+
 impl Encodable for BindTransmitter {
     fn encode(&self, buf: &mut BytesMut) -> Result<(), CodecError> {
         // Calculate body size (fixed field sizes)
@@ -711,14 +789,78 @@ mod tests {
         let parsed_frame = Frame::parse(&mut cursor).unwrap();
 
         // Verify it matches
-        // TODO: Add BindTransmitterResponse codec implementation
-        if let Frame::Unknown { .. } = parsed_frame {
-            // Once BindTransmitterResponse codec is implemented, add proper assertions
-            // assert_eq!(parsed.command_status, original.command_status);
-            // assert_eq!(parsed.sequence_number, original.sequence_number);
-            // etc.
+        if let Frame::BindTransmitterResp(parsed) = parsed_frame {
+            assert_eq!(parsed, original);
+            assert_eq!(parsed.interface_version(), None);
         } else {
-            panic!("Expected Unknown frame (BindTransmitterResponse codec not implemented)");
+            panic!("Expected BindTransmitterResp frame");
+        }
+    }
+
+    #[test]
+    fn bind_transmitter_response_variable_length_with_tlv() {
+        use crate::frame::Frame;
+        use std::io::Cursor;
+
+        // system_id as sent by most SMSCs: unpadded, followed by sc_interface_version
+        let data: Vec<u8> = vec![
+            0x00, 0x00, 0x00, 0x1A, // command_length (26)
+            0x80, 0x00, 0x00, 0x02, // command_id
+            0x00, 0x00, 0x00, 0x00, // command_status
+            0x00, 0x00, 0x00, 0x05, // sequence_number
+            0x53, 0x4D, 0x53, 0x43, 0x00, // system_id "SMSC"
+            0x02, 0x10, 0x00, 0x01, 0x34, // sc_interface_version = 0x34
+        ];
+
+        let mut cursor = Cursor::new(data.as_slice());
+        match Frame::parse(&mut cursor).unwrap() {
+            Frame::BindTransmitterResp(parsed) => {
+                assert_eq!(parsed.sequence_number, 5);
+                assert_eq!(parsed.system_id, SystemId::from("SMSC"));
+                assert_eq!(parsed.interface_version(), Some(InterfaceVersion::SmppV34));
+            }
+            other => panic!("Expected BindTransmitterResp frame, got {other:?}"),
         }
     }
+
+    #[test]
+    fn bind_transmitter_response_padded_with_tlv() {
+        use crate::frame::Frame;
+        use bytes::Bytes;
+        use std::io::Cursor;
+
+        let original = BindTransmitterResponse {
+            command_status: CommandStatus::Ok,
+            sequence_number: 3,
+            system_id: SystemId::from("SMSC"),
+            sc_interface_version: Some(Tlv {
+                tag: tags::SC_INTERFACE_VERSION,
+                length: 1,
+                value: Bytes::from_static(&[0x50]),
+            }),
+        };
+
+        let serialized = original.to_bytes();
+        let mut cursor = Cursor::new(serialized.as_ref());
+        match Frame::parse(&mut cursor).unwrap() {
+            Frame::BindTransmitterResp(parsed) => {
+                assert_eq!(parsed, original);
+                assert_eq!(parsed.interface_version(), Some(InterfaceVersion::SmppV50));
+            }
+            other => panic!("Expected BindTransmitterResp frame, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn bind_transmitter_debug_redacts_password() {
+        let bind_transmitter = BindTransmitter::builder()
+            .system_id("SMPP3TEST")
+            .password("secret08")
+            .build()
+            .unwrap();
+
+        let debug = format!("{bind_transmitter:?}");
+        assert!(debug.contains("password: Some(Password(<redacted>))"), "{debug}");
+        assert!(!debug.contains("secret08"));
+    }
 }
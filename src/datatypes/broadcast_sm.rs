@@ -1,15 +1,37 @@
 // ABOUTME: SMPP v5.0 broadcast_sm PDU implementation for broadcast messaging
 // ABOUTME: Handles broadcast message submission with area identification and scheduling
 
-use crate::codec::{CodecError, Decodable, Encodable};
+use crate::codec::{CodecError, DecodeContext, Decodable, Encodable};
 use crate::datatypes::{
     CommandId, CommandStatus, ServiceType, DataCoding, PriorityFlag, 
     ScheduleDeliveryTime, ValidityPeriod, MessageId, TypeOfNumber, NumericPlanIndicator
 };
 use crate::codec::{encode_cstring, encode_u8, encode_u16, encode_u32, decode_cstring, decode_u8, decode_u16, decode_u32};
-use bytes::{Buf, BufMut, BytesMut};
+use crate::datatypes::tlv::{Tlv, tags};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use num_enum::TryFromPrimitive;
 use std::io::Cursor;
 
+/// broadcast_channel_indicator TLV value (SMPP v5.0 Section 4.8.4.5)
+#[derive(TryFromPrimitive)]
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BroadcastChannelIndicator {
+    Basic = 0,
+    Extended = 1,
+}
+
+/// broadcast_message_class TLV value (SMPP v5.0 Section 4.8.4.11)
+#[derive(TryFromPrimitive)]
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BroadcastMessageClass {
+    NoClass = 0,
+    Class1 = 1,
+    Class2 = 2,
+    Class3 = 3,
+}
+
 /// SMPP v5.0 broadcast_sm PDU for sending broadcast messages
 #[derive(Clone, Debug, PartialEq)]
 pub struct BroadcastSm {
@@ -28,6 +50,12 @@ pub struct BroadcastSm {
     pub broadcast_content_type: u8,
     pub broadcast_rep_num: u16,
     pub broadcast_frequency_interval: u32,
+
+    // Optional parameters
+    pub broadcast_channel_indicator: Option<BroadcastChannelIndicator>,
+    pub broadcast_message_class: Option<BroadcastMessageClass>,
+    pub broadcast_content_type_info: Option<Bytes>,
+    pub broadcast_service_group: Option<Bytes>,
 }
 
 /// SMPP v5.0 broadcast_sm_resp PDU
@@ -119,6 +147,10 @@ pub struct BroadcastSmBuilder {
     broadcast_content_type: Option<u8>,
     broadcast_rep_num: Option<u16>,
     broadcast_frequency_interval: Option<u32>,
+    broadcast_channel_indicator: Option<BroadcastChannelIndicator>,
+    broadcast_message_class: Option<BroadcastMessageClass>,
+    broadcast_content_type_info: Option<Bytes>,
+    broadcast_service_group: Option<Bytes>,
 }
 
 impl BroadcastSmBuilder {
@@ -184,6 +216,26 @@ impl BroadcastSmBuilder {
         self
     }
 
+    pub fn broadcast_channel_indicator(mut self, indicator: BroadcastChannelIndicator) -> Self {
+        self.broadcast_channel_indicator = Some(indicator);
+        self
+    }
+
+    pub fn broadcast_message_class(mut self, class: BroadcastMessageClass) -> Self {
+        self.broadcast_message_class = Some(class);
+        self
+    }
+
+    pub fn broadcast_content_type_info(mut self, info: impl Into<Bytes>) -> Self {
+        self.broadcast_content_type_info = Some(info.into());
+        self
+    }
+
+    pub fn broadcast_service_group(mut self, service_group: impl Into<Bytes>) -> Self {
+        self.broadcast_service_group = Some(service_group.into());
+        self
+    }
+
     pub fn build(self) -> Result<BroadcastSm, BroadcastSmValidationError> {
         let broadcast_area_identifier = self
             .broadcast_area_identifier
@@ -223,12 +275,24 @@ impl BroadcastSmBuilder {
             broadcast_content_type: self.broadcast_content_type.unwrap_or(0),
             broadcast_rep_num,
             broadcast_frequency_interval: self.broadcast_frequency_interval.unwrap_or(3600),
+            broadcast_channel_indicator: self.broadcast_channel_indicator,
+            broadcast_message_class: self.broadcast_message_class,
+            broadcast_content_type_info: self.broadcast_content_type_info,
+            broadcast_service_group: self.broadcast_service_group,
         })
     }
 }
 
 impl Decodable for BroadcastSm {
     fn decode(header: crate::codec::PduHeader, buf: &mut Cursor<&[u8]>) -> Result<Self, CodecError> {
+        Self::decode_with_context(header, buf, &mut DecodeContext::strict())
+    }
+
+    fn decode_with_context(
+        header: crate::codec::PduHeader,
+        buf: &mut Cursor<&[u8]>,
+        ctx: &mut DecodeContext,
+    ) -> Result<Self, CodecError> {
         Self::validate_header(&header)?;
 
         // Decode mandatory parameters according to SMPP v5.0 broadcast_sm specification
@@ -299,6 +363,41 @@ impl Decodable for BroadcastSm {
         // broadcast_frequency_interval (4 octets)
         let broadcast_frequency_interval = decode_u32(buf)?;
 
+        // Optional parameters; unrecognised tags are skipped and, in lenient
+        // mode, so are values the broadcast TLVs do not allow
+        let mut broadcast_channel_indicator = None;
+        let mut broadcast_message_class = None;
+        let mut broadcast_content_type_info = None;
+        let mut broadcast_service_group = None;
+        while buf.remaining() >= 4 {
+            let tlv = Tlv::decode(buf)?;
+            match tlv.tag {
+                tags::BROADCAST_CHANNEL_INDICATOR => {
+                    let value = tlv.value.first().copied().unwrap_or_default();
+                    let indicator = BroadcastChannelIndicator::try_from(value).map_err(|_| {
+                        CodecError::FieldValidation {
+                            field: "broadcast_channel_indicator",
+                            reason: format!("Invalid channel indicator {value}"),
+                        }
+                    });
+                    broadcast_channel_indicator = ctx.recover(indicator.map(Some), || None)?;
+                }
+                tags::BROADCAST_MESSAGE_CLASS => {
+                    let value = tlv.value.first().copied().unwrap_or_default();
+                    let class = BroadcastMessageClass::try_from(value).map_err(|_| {
+                        CodecError::FieldValidation {
+                            field: "broadcast_message_class",
+                            reason: format!("Invalid message class {value}"),
+                        }
+                    });
+                    broadcast_message_class = ctx.recover(class.map(Some), || None)?;
+                }
+                tags::BROADCAST_CONTENT_TYPE_INFO => broadcast_content_type_info = Some(tlv.value),
+                tags::BROADCAST_SERVICE_GROUP => broadcast_service_group = Some(tlv.value),
+                _ => {}
+            }
+        }
+
         Ok(BroadcastSm {
             command_status: header.command_status,
             sequence_number: header.sequence_number,
@@ -315,6 +414,10 @@ impl Decodable for BroadcastSm {
             broadcast_content_type,
             broadcast_rep_num,
             broadcast_frequency_interval,
+            broadcast_channel_indicator,
+            broadcast_message_class,
+            broadcast_content_type_info,
+            broadcast_service_group,
         })
     }
 
@@ -375,6 +478,20 @@ impl Encodable for BroadcastSm {
         // broadcast_frequency_interval (4 octets)
         encode_u32(buf, self.broadcast_frequency_interval);
 
+        // Optional parameters
+        if let Some(indicator) = self.broadcast_channel_indicator {
+            Tlv::new(tags::BROADCAST_CHANNEL_INDICATOR, vec![indicator as u8])?.encode(buf)?;
+        }
+        if let Some(class) = self.broadcast_message_class {
+            Tlv::new(tags::BROADCAST_MESSAGE_CLASS, vec![class as u8])?.encode(buf)?;
+        }
+        if let Some(ref info) = self.broadcast_content_type_info {
+            Tlv::new(tags::BROADCAST_CONTENT_TYPE_INFO, info.clone())?.encode(buf)?;
+        }
+        if let Some(ref service_group) = self.broadcast_service_group {
+            Tlv::new(tags::BROADCAST_SERVICE_GROUP, service_group.clone())?.encode(buf)?;
+        }
+
         Ok(())
     }
 }
@@ -454,6 +571,54 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_broadcast_sm_optional_tlvs_roundtrip() {
+        let pdu = BroadcastSm::builder()
+            .sequence_number(9)
+            .broadcast_area_identifier(vec![0x00, 0x12])
+            .broadcast_channel_indicator(BroadcastChannelIndicator::Extended)
+            .broadcast_message_class(BroadcastMessageClass::Class2)
+            .broadcast_service_group(&b"\x01\x02"[..])
+            .build()
+            .unwrap();
+
+        let bytes = pdu.to_bytes();
+        let mut cursor = Cursor::new(&bytes[..]);
+        let header = crate::codec::PduHeader::decode(&mut cursor).unwrap();
+        let decoded = BroadcastSm::decode(header, &mut cursor).unwrap();
+
+        assert_eq!(decoded, pdu);
+        assert_eq!(
+            decoded.broadcast_channel_indicator,
+            Some(BroadcastChannelIndicator::Extended)
+        );
+        assert_eq!(decoded.broadcast_content_type_info, None);
+    }
+
+    #[test]
+    fn test_broadcast_sm_invalid_tlv_is_dropped_when_lenient() {
+        let pdu = BroadcastSm::builder()
+            .broadcast_area_identifier(vec![0x00, 0x12])
+            .broadcast_message_class(BroadcastMessageClass::Class2)
+            .build()
+            .unwrap();
+        let mut bytes = pdu.to_bytes().to_vec();
+        // broadcast_message_class is the last TLV; 9 is not a class
+        *bytes.last_mut().unwrap() = 9;
+
+        let decode = |ctx: &mut DecodeContext| {
+            let mut cursor = Cursor::new(&bytes[..]);
+            let header = crate::codec::PduHeader::decode(&mut cursor).unwrap();
+            BroadcastSm::decode_with_context(header, &mut cursor, ctx)
+        };
+        assert!(decode(&mut DecodeContext::strict()).is_err());
+
+        let mut ctx = DecodeContext::lenient();
+        let decoded = decode(&mut ctx).unwrap();
+        assert_eq!(decoded.broadcast_message_class, None);
+        assert_eq!(ctx.warnings()[0].field, "broadcast_message_class");
+    }
+
     #[test]
     fn test_broadcast_sm_response() {
         let response = BroadcastSmResponse::new(42, CommandStatus::Ok, "MSG123");
@@ -28,6 +28,8 @@ use num_enum::TryFromPrimitive;
 #[derive(TryFromPrimitive)]
 #[repr(u32)]
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum CommandId {
     /// generic_nack PDU (Section 4.3.1) - Error response for invalid PDUs
     GenericNack = 0x8000_0000,
@@ -148,3 +150,17 @@ pub enum CommandId {
     //          0x80010200 - 0x800102FF
     // Reserved 0x00010300 - 0xFFFFFFFF
 }
+
+impl CommandId {
+    /// The command's name as written in the specification, e.g. `submit_sm_resp`
+    pub fn name(&self) -> String {
+        let mut name = String::new();
+        for (i, c) in format!("{self:?}").chars().enumerate() {
+            if c.is_ascii_uppercase() && i > 0 {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+        name
+    }
+}
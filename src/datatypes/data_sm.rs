@@ -2,8 +2,8 @@
 // ABOUTME: Provides advanced messaging functionality per specification Section 4.7
 
 use crate::datatypes::{
-    AddressError, CommandId, CommandStatus, DataCoding, DestinationAddr, EsmClass, NumericPlanIndicator,
-    ServiceType, SourceAddr, Tlv, TypeOfNumber,
+    AddressError, CommandId, CommandStatus, DataCoding, DestinationAddr, EsmClass, NetworkRouting,
+    NumericPlanIndicator, ServiceType, SourceAddr, Tlv, TypeOfNumber,
 };
 use bytes::{Buf, BufMut, BytesMut};
 use std::io::Cursor;
@@ -11,8 +11,8 @@ use thiserror::Error;
 
 // Import codec traits
 use crate::codec::{
-    CodecError, Decodable, Encodable, PduHeader, decode_cstring, decode_u8, encode_cstring,
-    encode_u8,
+    CodecError, DecodeContext, Decodable, Encodable, PduHeader, decode_address, decode_cstring,
+    decode_u8, encode_cstring, encode_u8,
 };
 
 /// Validation errors for DataSm PDU
@@ -121,6 +121,10 @@ pub struct DataSm {
     /// Common TLVs include message_payload, source_port, destination_port,
     /// SAR parameters for concatenated messages, and callback information.
     pub optional_parameters: Vec<Tlv>,
+
+    /// SMPP v5.0 billing, network routing and number portability TLVs.
+    /// These are kept out of `optional_parameters` when decoding.
+    pub network_routing: NetworkRouting,
 }
 
 impl DataSm {
@@ -152,6 +156,7 @@ impl DataSm {
             registered_delivery,
             data_coding,
             optional_parameters: Vec::new(),
+            network_routing: NetworkRouting::default(),
         };
 
         // Validate the PDU
@@ -352,6 +357,7 @@ impl Encodable for DataSm {
         for tlv in &self.optional_parameters {
             tlv.encode(buf)?;
         }
+        self.network_routing.encode(buf)?;
 
         Ok(())
     }
@@ -363,6 +369,7 @@ impl Encodable for DataSm {
         for tlv in &self.optional_parameters {
             size += tlv.encoded_size();
         }
+        size += self.network_routing.encoded_size();
 
         size
     }
@@ -370,66 +377,79 @@ impl Encodable for DataSm {
 
 impl Decodable for DataSm {
     fn decode(header: PduHeader, buf: &mut Cursor<&[u8]>) -> Result<Self, CodecError> {
+        Self::decode_with_context(header, buf, &mut DecodeContext::strict())
+    }
+
+    fn decode_with_context(
+        header: PduHeader,
+        buf: &mut Cursor<&[u8]>,
+        ctx: &mut DecodeContext,
+    ) -> Result<Self, CodecError> {
         Self::validate_header(&header)?;
 
         // Decode mandatory parameters
         let service_type_str = decode_cstring(buf, 6, "service_type")?;
-        let service_type = ServiceType::new(&service_type_str).map_err(|e| {
-            CodecError::FieldValidation {
+        let service_type = ctx.recover(
+            ServiceType::new(&service_type_str).map_err(|e| CodecError::FieldValidation {
                 field: "service_type",
                 reason: format!("{e}"),
-            }
-        })?;
+            }),
+            ServiceType::default,
+        )?;
 
-        let source_addr_ton =
+        let source_addr_ton = ctx.recover(
             TypeOfNumber::try_from(decode_u8(buf)?).map_err(|_| CodecError::FieldValidation {
                 field: "source_addr_ton",
                 reason: "Invalid TypeOfNumber value".to_string(),
-            })?;
+            }),
+            || TypeOfNumber::Unknown,
+        )?;
 
-        let source_addr_npi = NumericPlanIndicator::try_from(decode_u8(buf)?).map_err(|_| {
-            CodecError::FieldValidation {
-                field: "source_addr_npi",
-                reason: "Invalid NumericPlanIndicator value".to_string(),
-            }
-        })?;
+        let source_addr_npi = ctx.recover(
+            NumericPlanIndicator::try_from(decode_u8(buf)?).map_err(|_| {
+                CodecError::FieldValidation {
+                    field: "source_addr_npi",
+                    reason: "Invalid NumericPlanIndicator value".to_string(),
+                }
+            }),
+            || NumericPlanIndicator::Unknown,
+        )?;
 
-        let source_addr_str = decode_cstring(buf, 21, "source_addr")?;
-        let source_addr = SourceAddr::new(&source_addr_str, source_addr_ton).map_err(|e| {
-            CodecError::FieldValidation {
-                field: "source_addr",
-                reason: format!("{e}"),
-            }
-        })?;
+        let source_addr_str = ctx.recover(decode_cstring(buf, 21, "source_addr"), String::new)?;
+        let (source_addr_ton, source_addr) =
+            decode_address(&source_addr_str, source_addr_ton, "source_addr", ctx)?;
 
-        let dest_addr_ton =
+        let dest_addr_ton = ctx.recover(
             TypeOfNumber::try_from(decode_u8(buf)?).map_err(|_| CodecError::FieldValidation {
                 field: "dest_addr_ton",
                 reason: "Invalid TypeOfNumber value".to_string(),
-            })?;
+            }),
+            || TypeOfNumber::Unknown,
+        )?;
 
-        let dest_addr_npi = NumericPlanIndicator::try_from(decode_u8(buf)?).map_err(|_| {
-            CodecError::FieldValidation {
-                field: "dest_addr_npi",
-                reason: "Invalid NumericPlanIndicator value".to_string(),
-            }
-        })?;
+        let dest_addr_npi = ctx.recover(
+            NumericPlanIndicator::try_from(decode_u8(buf)?).map_err(|_| {
+                CodecError::FieldValidation {
+                    field: "dest_addr_npi",
+                    reason: "Invalid NumericPlanIndicator value".to_string(),
+                }
+            }),
+            || NumericPlanIndicator::Unknown,
+        )?;
 
-        let destination_addr_str = decode_cstring(buf, 21, "destination_addr")?;
-        let destination_addr = DestinationAddr::new(&destination_addr_str, dest_addr_ton).map_err(|e| {
-            CodecError::FieldValidation {
-                field: "destination_addr",
-                reason: format!("{e}"),
-            }
-        })?;
+        let destination_addr_str =
+            ctx.recover(decode_cstring(buf, 21, "destination_addr"), String::new)?;
+        let (dest_addr_ton, destination_addr) =
+            decode_address(&destination_addr_str, dest_addr_ton, "destination_addr", ctx)?;
 
         let esm_class_byte = decode_u8(buf)?;
-        let esm_class = EsmClass::from_byte(esm_class_byte).map_err(|e| {
-            CodecError::FieldValidation {
+        let esm_class = ctx.recover(
+            EsmClass::from_byte(esm_class_byte).map_err(|e| CodecError::FieldValidation {
                 field: "esm_class",
                 reason: format!("{e}"),
-            }
-        })?;
+            }),
+            EsmClass::default,
+        )?;
 
         let registered_delivery = decode_u8(buf)?;
 
@@ -438,9 +458,16 @@ impl Decodable for DataSm {
 
         // Decode optional TLV parameters
         let mut optional_parameters = Vec::new();
+        let mut network_routing = NetworkRouting::default();
         while buf.remaining() > 0 {
             match Tlv::decode(buf) {
-                Ok(tlv) => optional_parameters.push(tlv),
+                Ok(tlv) => {
+                    if ctx.check_tlv(tlv.tag)?
+                        && let Some(tlv) = network_routing.decode_tlv(tlv, ctx)?
+                    {
+                        optional_parameters.push(tlv);
+                    }
+                }
                 Err(CodecError::Incomplete) => break, // End of TLVs
                 Err(e) => return Err(e),
             }
@@ -460,6 +487,7 @@ impl Decodable for DataSm {
             registered_delivery,
             data_coding,
             optional_parameters,
+            network_routing,
         })
     }
 
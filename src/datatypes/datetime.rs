@@ -1,13 +1,25 @@
 // ABOUTME: Strongly-typed SMPP date/time types with format validation
 // ABOUTME: Provides compile-time guarantees for YYMMDDhhmmsstnnp timestamp format
 
+use std::cmp::Ordering;
 use std::fmt;
 use std::str;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+/// Fixed lengths used for the years and months of a relative time
+const RELATIVE_YEAR_SECONDS: u64 = 365 * SECONDS_PER_DAY;
+const RELATIVE_MONTH_SECONDS: u64 = 30 * SECONDS_PER_DAY;
+/// Largest UTC offset the absolute format can express, in quarter hours
+const MAX_UTC_OFFSET_QUARTER_HOURS: u8 = 48;
 
 /// A strongly-typed SMPP date/time in YYMMDDhhmmsstnnp format
 /// YY = year (00-99), MM = month (01-12), DD = day (01-31)
 /// hh = hour (00-23), mm = minute (00-59), ss = second (00-59)
-/// t = tenths of second (0-9), nn = UTC offset hours, p = UTC offset sign (+/-)
+/// t = tenths of second (0-9), nn = UTC offset in quarter hours, p = UTC offset sign (+/-)
+///
+/// A relative time (p = 'R') has the form YYMMDDhhmmss000R, where each field
+/// is a count of years, months, days and so on from the time of submission.
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SmppDateTime {
     data: [u8; 16], // 16 chars exactly, no null terminator needed
@@ -82,6 +94,25 @@ impl SmppDateTime {
             }
         }
 
+        // Relative times are a period, not a calendar date: tnn must be 000
+        // and the remaining fields are plain counts (SMPP v3.4 Section 7.1.1.2)
+        if bytes[15] == b'R' {
+            if let Some(position) = (12..15).find(|&i| bytes[i] != b'0') {
+                return Err(DateTimeError::InvalidCharacter {
+                    position,
+                    character: bytes[position] as char,
+                    expected: "0 in relative time".to_string(),
+                });
+            }
+
+            let mut data = [0u8; 16];
+            data.copy_from_slice(bytes);
+            return Ok(Self {
+                data,
+                is_empty: false,
+            });
+        }
+
         // Validate ranges for date/time components
         let _year = parse_two_digits(&bytes[0..2])?;
         let month = parse_two_digits(&bytes[2..4])?;
@@ -167,11 +198,9 @@ impl SmppDateTime {
         }
     }
 
-    /// Creates a SmppDateTime for current UTC time (placeholder - would use actual time in real implementation)
+    /// Creates a SmppDateTime for the current UTC time
     pub fn now_utc() -> Self {
-        // In a real implementation, this would use chrono or std::time
-        // For now, return a valid example timestamp
-        Self::new("240712120000000+").unwrap()
+        Self::from_system_time(SystemTime::now()).unwrap_or_else(|_| Self::immediate())
     }
 
     /// Creates a SmppDateTime with specified components
@@ -297,7 +326,7 @@ impl SmppDateTime {
         }
     }
 
-    /// Extracts the UTC offset hours (00-99)
+    /// Extracts the raw UTC offset field (00-99), counted in quarter hours
     pub fn utc_offset_hours(&self) -> Option<u8> {
         if self.is_empty {
             None
@@ -325,6 +354,316 @@ impl SmppDateTime {
     }
 }
 
+impl SmppDateTime {
+    /// Creates an absolute UTC time from a `SystemTime`
+    ///
+    /// Two-digit years are taken to be 20YY, so only times in 2000-2099 can
+    /// be represented. Precision beyond tenths of a second is dropped.
+    pub fn from_system_time(time: SystemTime) -> Result<Self, DateTimeError> {
+        Self::from_system_time_with_offset(time, 0)
+    }
+
+    /// Creates an absolute time expressed in the given UTC offset
+    ///
+    /// The offset is in quarter hours (-48 to +48), as carried on the wire.
+    pub fn from_system_time_with_offset(
+        time: SystemTime,
+        offset_quarter_hours: i8,
+    ) -> Result<Self, DateTimeError> {
+        let offset = offset_quarter_hours.unsigned_abs();
+        if offset > MAX_UTC_OFFSET_QUARTER_HOURS {
+            return Err(DateTimeError::InvalidRange {
+                field: "UTC offset".to_string(),
+                value: offset as u32,
+                min: 0,
+                max: MAX_UTC_OFFSET_QUARTER_HOURS as u32,
+            });
+        }
+
+        let since_epoch = time.duration_since(UNIX_EPOCH).map_err(|_| year_out_of_range(1969))?;
+        let local_seconds = since_epoch.as_secs() as i64 + offset_quarter_hours as i64 * 900;
+        let days = local_seconds.div_euclid(SECONDS_PER_DAY as i64);
+        let second_of_day = local_seconds.rem_euclid(SECONDS_PER_DAY as i64);
+        let (year, month, day) = civil_from_days(days);
+        if !(2000..=2099).contains(&year) {
+            return Err(year_out_of_range(year));
+        }
+
+        let datetime_str = format!(
+            "{:02}{:02}{:02}{:02}{:02}{:02}{}{:02}{}",
+            year - 2000,
+            month,
+            day,
+            second_of_day / 3600,
+            second_of_day % 3600 / 60,
+            second_of_day % 60,
+            since_epoch.subsec_millis() / 100,
+            offset,
+            if offset_quarter_hours < 0 { '-' } else { '+' }
+        );
+        Self::new(&datetime_str)
+    }
+
+    /// Converts an absolute time to a `SystemTime`, applying its UTC offset
+    ///
+    /// Immediate and relative times have no instant of their own; use
+    /// [`resolve`](Self::resolve) for those.
+    pub fn to_system_time(&self) -> Result<SystemTime, DateTimeError> {
+        if self.is_empty || self.is_relative() {
+            return Err(DateTimeError::NotAbsolute);
+        }
+
+        let offset = self.utc_offset_quarter_hours().unwrap_or_default();
+        if offset.unsigned_abs() > MAX_UTC_OFFSET_QUARTER_HOURS {
+            return Err(DateTimeError::InvalidRange {
+                field: "UTC offset".to_string(),
+                value: offset.unsigned_abs() as u32,
+                min: 0,
+                max: MAX_UTC_OFFSET_QUARTER_HOURS as u32,
+            });
+        }
+
+        let year = 2000 + self.year().unwrap_or_default() as i64;
+        let month = self.month().unwrap_or_default();
+        let day = self.day().unwrap_or_default();
+        let last_day = days_in_month(year, month);
+        if day > last_day {
+            return Err(DateTimeError::InvalidRange {
+                field: "day".to_string(),
+                value: day as u32,
+                min: 1,
+                max: last_day as u32,
+            });
+        }
+
+        let seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY as i64
+            + self.hour().unwrap_or_default() as i64 * 3600
+            + self.minute().unwrap_or_default() as i64 * 60
+            + self.second().unwrap_or_default() as i64
+            - offset as i64 * 900;
+        let millis = self.tenth().unwrap_or_default() as u64 * 100;
+
+        Ok(UNIX_EPOCH + Duration::from_secs(seconds as u64) + Duration::from_millis(millis))
+    }
+
+    /// Creates a relative time (YYMMDDhhmmss000R) covering the given period
+    ///
+    /// Years are counted as 365 days and months as 30 days, the same lengths
+    /// [`relative_duration`](Self::relative_duration) uses, so the two
+    /// round-trip. Sub-second precision is dropped.
+    pub fn relative(period: Duration) -> Result<Self, DateTimeError> {
+        let mut remaining = period.as_secs();
+        let years = remaining / RELATIVE_YEAR_SECONDS;
+        if years > 99 {
+            return Err(DateTimeError::InvalidRange {
+                field: "relative years".to_string(),
+                value: years.min(u32::MAX as u64) as u32,
+                min: 0,
+                max: 99,
+            });
+        }
+        remaining %= RELATIVE_YEAR_SECONDS;
+        let months = remaining / RELATIVE_MONTH_SECONDS;
+        remaining %= RELATIVE_MONTH_SECONDS;
+
+        let datetime_str = format!(
+            "{:02}{:02}{:02}{:02}{:02}{:02}000R",
+            years,
+            months,
+            remaining / SECONDS_PER_DAY,
+            remaining % SECONDS_PER_DAY / 3600,
+            remaining % 3600 / 60,
+            remaining % 60
+        );
+        Self::new(&datetime_str)
+    }
+
+    /// Returns true for a relative time ('R' format)
+    pub fn is_relative(&self) -> bool {
+        !self.is_empty && self.data[15] == b'R'
+    }
+
+    /// The period of a relative time, or `None` for absolute and immediate times
+    pub fn relative_duration(&self) -> Option<Duration> {
+        if !self.is_relative() {
+            return None;
+        }
+        let field = |range: std::ops::Range<usize>| parse_two_digits(&self.data[range]).unwrap() as u64;
+        Some(Duration::from_secs(
+            field(0..2) * RELATIVE_YEAR_SECONDS
+                + field(2..4) * RELATIVE_MONTH_SECONDS
+                + field(4..6) * SECONDS_PER_DAY
+                + field(6..8) * 3600
+                + field(8..10) * 60
+                + field(10..12),
+        ))
+    }
+
+    /// The signed UTC offset of an absolute time, in quarter hours
+    pub fn utc_offset_quarter_hours(&self) -> Option<i8> {
+        let offset = self.utc_offset_hours()? as i8;
+        match self.utc_offset_sign()? {
+            '+' => Some(offset),
+            '-' => Some(-offset),
+            _ => None,
+        }
+    }
+
+    /// Resolves this time to an instant, given the time of submission
+    ///
+    /// Immediate times resolve to `reference`, relative times to `reference`
+    /// plus their period and absolute times to themselves.
+    pub fn resolve(&self, reference: SystemTime) -> Result<SystemTime, DateTimeError> {
+        if self.is_empty {
+            Ok(reference)
+        } else if let Some(period) = self.relative_duration() {
+            Ok(reference + period)
+        } else {
+            self.to_system_time()
+        }
+    }
+
+    /// Adds a duration, keeping the format and UTC offset of this time
+    ///
+    /// Returns `None` for immediate times or if the result is not representable.
+    pub fn checked_add(&self, duration: Duration) -> Option<Self> {
+        if let Some(period) = self.relative_duration() {
+            return Self::relative(period.checked_add(duration)?).ok();
+        }
+        let time = self.to_system_time().ok()?.checked_add(duration)?;
+        Self::from_system_time_with_offset(time, self.utc_offset_quarter_hours()?).ok()
+    }
+
+    /// Subtracts a duration, keeping the format and UTC offset of this time
+    ///
+    /// Returns `None` for immediate times or if the result is not representable.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Self> {
+        if let Some(period) = self.relative_duration() {
+            return Self::relative(period.checked_sub(duration)?).ok();
+        }
+        let time = self.to_system_time().ok()?.checked_sub(duration)?;
+        Self::from_system_time_with_offset(time, self.utc_offset_quarter_hours()?).ok()
+    }
+
+    /// Time elapsed from `earlier` to this time, taking UTC offsets into account
+    ///
+    /// Returns `None` if either time is not absolute or `earlier` is later.
+    pub fn duration_since(&self, earlier: &SmppDateTime) -> Option<Duration> {
+        self.to_system_time()
+            .ok()?
+            .duration_since(earlier.to_system_time().ok()?)
+            .ok()
+    }
+
+    /// Orders two absolute times by the instant they denote
+    ///
+    /// Unlike `==`, which compares the encoded form, times written in
+    /// different UTC offsets compare equal when they denote the same instant.
+    pub fn instant_cmp(&self, other: &SmppDateTime) -> Option<Ordering> {
+        Some(self.to_system_time().ok()?.cmp(&other.to_system_time().ok()?))
+    }
+
+    /// Parses a delivery receipt date (YYMMDDhhmm or YYMMDDhhmmss)
+    ///
+    /// Receipts carry no UTC offset, so the date is taken to be UTC.
+    pub fn from_receipt_date(date: &str) -> Result<Self, DateTimeError> {
+        match date.len() {
+            10 => Self::new(&format!("{date}00000+")),
+            12 => Self::new(&format!("{date}000+")),
+            actual => Err(DateTimeError::InvalidLength {
+                expected: 10,
+                actual,
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl SmppDateTime {
+    /// Creates an absolute time in the UTC offset of `time`
+    ///
+    /// The offset must be a whole number of quarter hours.
+    pub fn from_chrono<Tz: chrono::TimeZone>(
+        time: &chrono::DateTime<Tz>,
+    ) -> Result<Self, DateTimeError> {
+        use chrono::Offset;
+
+        let offset_seconds = time.offset().fix().local_minus_utc();
+        if offset_seconds % 900 != 0 {
+            return Err(DateTimeError::InvalidRange {
+                field: "UTC offset seconds".to_string(),
+                value: offset_seconds.unsigned_abs(),
+                min: 0,
+                max: MAX_UTC_OFFSET_QUARTER_HOURS as u32 * 900,
+            });
+        }
+        let offset_quarter_hours = i8::try_from(offset_seconds / 900).map_err(|_| {
+            DateTimeError::InvalidRange {
+                field: "UTC offset".to_string(),
+                value: (offset_seconds / 900).unsigned_abs(),
+                min: 0,
+                max: MAX_UTC_OFFSET_QUARTER_HOURS as u32,
+            }
+        })?;
+
+        Self::from_system_time_with_offset(SystemTime::from(time.clone()), offset_quarter_hours)
+    }
+
+    /// Converts an absolute time to a chrono `DateTime` in its own UTC offset
+    pub fn to_chrono(&self) -> Result<chrono::DateTime<chrono::FixedOffset>, DateTimeError> {
+        let time = chrono::DateTime::<chrono::Utc>::from(self.to_system_time()?);
+        let offset = chrono::FixedOffset::east_opt(
+            self.utc_offset_quarter_hours().unwrap_or_default() as i32 * 900,
+        )
+        .ok_or(DateTimeError::NotAbsolute)?;
+        Ok(time.with_timezone(&offset))
+    }
+}
+
+fn year_out_of_range(year: i64) -> DateTimeError {
+    DateTimeError::InvalidRange {
+        field: "year".to_string(),
+        value: year.clamp(0, u32::MAX as i64) as u32,
+        min: 2000,
+        max: 2099,
+    }
+}
+
+fn days_in_month(year: i64, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian date for a count of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
 /// Helper function to parse two ASCII digits into a u8
 fn parse_two_digits(bytes: &[u8]) -> Result<u8, DateTimeError> {
     if bytes.len() != 2 {
@@ -363,6 +702,8 @@ pub enum DateTimeError {
         min: u32,
         max: u32,
     },
+    /// The time is immediate or relative, so has no instant of its own
+    NotAbsolute,
 }
 
 impl fmt::Display for DateTimeError {
@@ -392,6 +733,9 @@ impl fmt::Display for DateTimeError {
             } => {
                 write!(f, "Invalid {field} value: {value} (must be {min}-{max})")
             }
+            DateTimeError::NotAbsolute => {
+                write!(f, "Immediate or relative time has no absolute instant")
+            }
         }
     }
 }
@@ -641,4 +985,154 @@ mod tests {
         assert_eq!(dt_min.month(), Some(1));
         assert_eq!(dt_min.day(), Some(1));
     }
+
+    #[test]
+    fn test_smpp_datetime_relative_format_validation() {
+        // Relative periods may have zero months and days
+        let seven_days = SmppDateTime::new("000007000000000R").unwrap();
+        assert!(seven_days.is_relative());
+        assert_eq!(
+            seven_days.relative_duration(),
+            Some(Duration::from_secs(7 * 86_400))
+        );
+
+        // tnn must be 000
+        assert!(matches!(
+            SmppDateTime::new("000007000000100R"),
+            Err(DateTimeError::InvalidCharacter { position: 12, .. })
+        ));
+        assert!(matches!(
+            SmppDateTime::new("000007000000004R"),
+            Err(DateTimeError::InvalidCharacter { position: 14, .. })
+        ));
+    }
+
+    #[test]
+    fn test_smpp_datetime_relative_from_duration() {
+        let period = Duration::from_secs(2 * 86_400 + 3 * 3600 + 30 * 60);
+        let dt = ValidityPeriod::relative(period).unwrap();
+        assert_eq!(dt.as_str().unwrap(), "000002033000000R");
+        assert_eq!(dt.relative_duration(), Some(period));
+
+        let long = SmppDateTime::relative(Duration::from_secs(400 * 86_400)).unwrap();
+        assert_eq!(long.as_str().unwrap(), "010105000000000R");
+
+        assert!(SmppDateTime::relative(Duration::from_secs(100 * 365 * 86_400)).is_err());
+        assert_eq!(SmppDateTime::now_utc().relative_duration(), None);
+    }
+
+    #[test]
+    fn test_smpp_datetime_system_time_roundtrip() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_720_785_600_300);
+        let dt = SmppDateTime::from_system_time(time).unwrap();
+        assert_eq!(dt.as_str().unwrap(), "240712120000300+");
+        assert_eq!(dt.to_system_time().unwrap(), time);
+
+        let before_2000 = UNIX_EPOCH + Duration::from_secs(86_400);
+        assert!(SmppDateTime::from_system_time(before_2000).is_err());
+        assert_eq!(
+            SmppDateTime::immediate().to_system_time(),
+            Err(DateTimeError::NotAbsolute)
+        );
+    }
+
+    #[test]
+    fn test_smpp_datetime_applies_quarter_hour_offset() {
+        // 12:00 at UTC+1 (4 quarter hours) is 11:00 UTC
+        let dt = SmppDateTime::new("240712120000004+").unwrap();
+        assert_eq!(dt.utc_offset_quarter_hours(), Some(4));
+        assert_eq!(
+            dt.to_system_time().unwrap(),
+            UNIX_EPOCH + Duration::from_secs(1_720_782_000)
+        );
+
+        // 05:30 at UTC-5:30 is 11:00 UTC
+        let dt = SmppDateTime::new("240712053000022-").unwrap();
+        assert_eq!(
+            dt.to_system_time().unwrap(),
+            UNIX_EPOCH + Duration::from_secs(1_720_782_000)
+        );
+
+        let time = UNIX_EPOCH + Duration::from_secs(1_720_782_000);
+        let dt = SmppDateTime::from_system_time_with_offset(time, -22).unwrap();
+        assert_eq!(dt.as_str().unwrap(), "240712053000022-");
+        assert!(SmppDateTime::from_system_time_with_offset(time, 49).is_err());
+    }
+
+    #[test]
+    fn test_smpp_datetime_rejects_impossible_dates() {
+        let dt = SmppDateTime::new("230230120000000+").unwrap();
+        assert!(matches!(
+            dt.to_system_time(),
+            Err(DateTimeError::InvalidRange { max: 28, .. })
+        ));
+        assert!(SmppDateTime::new("240229120000000+").unwrap().to_system_time().is_ok());
+    }
+
+    #[test]
+    fn test_smpp_datetime_resolve() {
+        let submitted = UNIX_EPOCH + Duration::from_secs(1_720_785_600);
+        assert_eq!(SmppDateTime::immediate().resolve(submitted).unwrap(), submitted);
+        assert_eq!(
+            SmppDateTime::new("000000010000000R")
+                .unwrap()
+                .resolve(submitted)
+                .unwrap(),
+            submitted + Duration::from_secs(3600)
+        );
+        assert_eq!(
+            SmppDateTime::new("240712120000000+")
+                .unwrap()
+                .resolve(UNIX_EPOCH)
+                .unwrap(),
+            submitted
+        );
+    }
+
+    #[test]
+    fn test_smpp_datetime_arithmetic() {
+        let dt = SmppDateTime::new("240731233000004+").unwrap();
+        let later = dt.checked_add(Duration::from_secs(3600)).unwrap();
+        assert_eq!(later.as_str().unwrap(), "240801003000004+");
+        assert_eq!(later.checked_sub(Duration::from_secs(3600)), Some(dt));
+        assert_eq!(later.duration_since(&dt), Some(Duration::from_secs(3600)));
+        assert_eq!(dt.duration_since(&later), None);
+
+        let relative = SmppDateTime::new("000001000000000R").unwrap();
+        assert_eq!(
+            relative.checked_add(Duration::from_secs(3600)).unwrap().as_str().unwrap(),
+            "000001010000000R"
+        );
+        assert_eq!(SmppDateTime::immediate().checked_add(Duration::from_secs(1)), None);
+
+        // The same instant written in two offsets
+        let utc = SmppDateTime::new("240712110000000+").unwrap();
+        let cet = SmppDateTime::new("240712120000004+").unwrap();
+        assert_ne!(utc, cet);
+        assert_eq!(utc.instant_cmp(&cet), Some(Ordering::Equal));
+        assert_eq!(utc.instant_cmp(&later), Some(Ordering::Less));
+    }
+
+    #[test]
+    fn test_smpp_datetime_receipt_dates() {
+        let submitted = SmppDateTime::from_receipt_date("2201011200").unwrap();
+        let done = SmppDateTime::from_receipt_date("220101120530").unwrap();
+        assert_eq!(done.duration_since(&submitted), Some(Duration::from_secs(330)));
+        assert!(SmppDateTime::from_receipt_date("22010112").is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_smpp_datetime_chrono_roundtrip() {
+        use chrono::{FixedOffset, TimeZone};
+
+        let offset = FixedOffset::east_opt(5 * 3600 + 45 * 60).unwrap();
+        let time = offset.with_ymd_and_hms(2024, 7, 12, 16, 45, 0).unwrap();
+        let dt = SmppDateTime::from_chrono(&time).unwrap();
+        assert_eq!(dt.as_str().unwrap(), "240712164500023+");
+        assert_eq!(dt.to_chrono().unwrap(), time);
+
+        let odd_offset = FixedOffset::east_opt(600).unwrap();
+        assert!(SmppDateTime::from_chrono(&odd_offset.with_ymd_and_hms(2024, 7, 12, 0, 0, 0).unwrap()).is_err());
+    }
 }
@@ -1,11 +1,16 @@
-use crate::codec::{CodecError, Encodable, PduHeader, encode_cstring, encode_u8};
+use crate::codec::{
+    CodecError, Decodable, DecodeContext, Encodable, PduHeader, decode_c_octet_string,
+    encode_cstring, encode_u8, skip_nul_padding,
+};
+use crate::datatypes::message_body::MessageBody;
 use crate::datatypes::numeric_plan_indicator::NumericPlanIndicator;
-use crate::datatypes::tlv::Tlv;
+use crate::datatypes::tlv::{Tlv, tags};
 use crate::datatypes::{
-    CommandId, CommandStatus, DataCoding, DestinationAddr, EsmClass, MessageId,
+    CommandId, CommandStatus, DataCoding, DestinationAddr, EsmClass, MessageId, NetworkRouting,
     ScheduleDeliveryTime, ServiceType, ShortMessage, SourceAddr, TypeOfNumber, ValidityPeriod,
 };
-use bytes::BytesMut;
+use bytes::{Buf, BufMut, BytesMut};
+use std::io::Cursor;
 
 /// This operation is used by the SMSC to deliver a short message to an ESME.
 /// The deliver_sm PDU is used to deliver both mobile originated messages and
@@ -92,23 +97,23 @@ pub struct DeliverSm {
     /// source address of the message.
     pub source_port: Option<Tlv>,
 
-    /// Destination Port TLV (0x020C): Indicates the application port number associated with
+    /// Destination Port TLV (0x020B): Indicates the application port number associated with
     /// the destination address of the message.
     pub destination_port: Option<Tlv>,
 
-    /// SAR Message Reference Number TLV (0x020E): The reference number for a particular
+    /// SAR Message Reference Number TLV (0x020C): The reference number for a particular
     /// concatenated short message.
     pub sar_msg_ref_num: Option<Tlv>,
 
-    /// SAR Total Segments TLV (0x020F): Indicates the total number of short messages within
+    /// SAR Total Segments TLV (0x020E): Indicates the total number of short messages within
     /// the concatenated short message.
     pub sar_total_segments: Option<Tlv>,
 
-    /// SAR Segment Sequence Number TLV (0x0210): Indicates the sequence number of a particular
+    /// SAR Segment Sequence Number TLV (0x020F): Indicates the sequence number of a particular
     /// short message within the concatenated short message.
     pub sar_segment_seqnum: Option<Tlv>,
 
-    /// User Data Header TLV (0x0005): User Data Header for advanced messaging features.
+    /// TLV 0x0005, which the specification defines as `dest_addr_subunit`
     pub user_data_header: Option<Tlv>,
 
     /// Privacy Indicator TLV (0x0201): Indicates the level of privacy associated with the message.
@@ -123,7 +128,7 @@ pub struct DeliverSm {
     /// Destination Subaddress TLV (0x0203): The subaddress of the message destination.
     pub dest_subaddress: Option<Tlv>,
 
-    /// Language Indicator TLV (0x000D): Indicates the language of the short message.
+    /// Language Indicator TLV (0x020D): Indicates the language of the short message.
     pub language_indicator: Option<Tlv>,
 
     /// ITS Session Info TLV (0x1383): Session control information for Interactive Teleservice.
@@ -162,6 +167,9 @@ pub struct DeliverSm {
     /// Message State TLV (0x0427): The state of the message at the time the receipt was generated.
     /// Used in delivery receipts.
     pub message_state: Option<Tlv>,
+
+    /// SMPP v5.0 billing, network routing and number portability TLVs
+    pub network_routing: NetworkRouting,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -246,6 +254,7 @@ pub struct DeliverSmBuilder {
     ms_availability_status: Option<Tlv>,
     receipted_message_id: Option<Tlv>,
     message_state: Option<Tlv>,
+    network_routing: NetworkRouting,
     sm_length: u8,
 }
 
@@ -300,6 +309,7 @@ impl DeliverSmBuilder {
             ms_availability_status: None,
             receipted_message_id: None,
             message_state: None,
+            network_routing: NetworkRouting::default(),
         }
     }
 
@@ -374,6 +384,12 @@ impl DeliverSmBuilder {
         self
     }
 
+    /// Billing and routing TLVs; most of these require an SMPP v5.0 session
+    pub fn network_routing(mut self, network_routing: NetworkRouting) -> Self {
+        self.network_routing = network_routing;
+        self
+    }
+
     /// Build the DeliverSm, performing validation and calculating sm_length automatically
     pub fn build(mut self) -> Result<DeliverSm, DeliverSmValidationError> {
         // Auto-calculate sm_length from short_message
@@ -422,6 +438,7 @@ impl DeliverSmBuilder {
             ms_availability_status: self.ms_availability_status,
             receipted_message_id: self.receipted_message_id,
             message_state: self.message_state,
+            network_routing: self.network_routing,
         };
 
         // Validate before returning
@@ -462,19 +479,24 @@ impl Encodable for DeliverSm {
         };
         header.encode(buf)?;
 
-        // Encode mandatory parameters as fixed-length fields
-        encode_cstring(buf, self.service_type.as_str(), 6);
+        // Encode mandatory parameters as C-Octet Strings (Section 4.6.1)
+        buf.extend_from_slice(self.service_type.as_str().as_bytes());
+        buf.put_u8(0); // null terminator
         encode_u8(buf, self.source_addr_ton as u8);
         encode_u8(buf, self.source_addr_npi as u8);
-        encode_cstring(buf, self.source_addr.as_str().unwrap_or(""), 21);
+        buf.extend_from_slice(self.source_addr.as_ref());
+        buf.put_u8(0); // null terminator
         encode_u8(buf, self.dest_addr_ton as u8);
         encode_u8(buf, self.dest_addr_npi as u8);
-        encode_cstring(buf, self.destination_addr.as_str().unwrap_or(""), 21);
+        buf.extend_from_slice(self.destination_addr.as_ref());
+        buf.put_u8(0); // null terminator
         encode_u8(buf, self.esm_class.to_byte());
         encode_u8(buf, self.protocol_id);
         encode_u8(buf, self.priority_flag);
-        encode_cstring(buf, self.schedule_delivery_time.as_str().unwrap_or(""), 17);
-        encode_cstring(buf, self.validity_period.as_str().unwrap_or(""), 17);
+        buf.extend_from_slice(self.schedule_delivery_time.as_ref());
+        buf.put_u8(0); // null terminator
+        buf.extend_from_slice(self.validity_period.as_ref());
+        buf.put_u8(0); // null terminator
         encode_u8(buf, self.registered_delivery);
         encode_u8(buf, self.replace_if_present_flag);
         encode_u8(buf, self.data_coding.to_byte());
@@ -552,6 +574,7 @@ impl Encodable for DeliverSm {
         if let Some(ref tlv) = self.message_state {
             tlv.encode(buf)?;
         }
+        self.network_routing.encode(buf)?;
 
         Ok(())
     }
@@ -559,8 +582,16 @@ impl Encodable for DeliverSm {
     fn encoded_size(&self) -> usize {
         let mut size = PduHeader::SIZE;
 
-        // Fixed mandatory fields
-        size += 6 + 1 + 1 + 21 + 1 + 1 + 21 + 1 + 1 + 1 + 17 + 17 + 1 + 1 + 1 + 1 + 1;
+        // Mandatory fields; C-Octet Strings are NUL terminated
+        size += self.service_type.as_str().len() + 1;
+        size += 1 + 1; // source_addr_ton + source_addr_npi
+        size += self.source_addr.as_ref().len() + 1;
+        size += 1 + 1; // dest_addr_ton + dest_addr_npi
+        size += self.destination_addr.as_ref().len() + 1;
+        size += 1 + 1 + 1; // esm_class + protocol_id + priority_flag
+        size += self.schedule_delivery_time.as_ref().len() + 1;
+        size += self.validity_period.as_ref().len() + 1;
+        size += 1 + 1 + 1 + 1 + 1; // registered_delivery .. sm_length
 
         // Variable short_message length
         size += self.sm_length as usize;
@@ -632,6 +663,7 @@ impl Encodable for DeliverSm {
         if let Some(ref tlv) = self.message_state {
             size += tlv.encoded_size();
         }
+        size += self.network_routing.encoded_size();
 
         size
     }
@@ -659,6 +691,132 @@ impl Encodable for DeliverSmResponse {
     }
 }
 
+impl Decodable for DeliverSm {
+    fn command_id() -> CommandId {
+        CommandId::DeliverSm
+    }
+
+    fn decode(header: PduHeader, buf: &mut Cursor<&[u8]>) -> Result<Self, CodecError> {
+        Self::decode_with_context(header, buf, &mut DecodeContext::strict())
+    }
+
+    fn decode_with_context(
+        header: PduHeader,
+        buf: &mut Cursor<&[u8]>,
+        ctx: &mut DecodeContext,
+    ) -> Result<Self, CodecError> {
+        Self::validate_header(&header)?;
+
+        let body = MessageBody::decode(buf, ctx, |priority_flag, _| Ok(priority_flag))?;
+        let mut tlvs = body.tlvs;
+
+        let deliver_sm = DeliverSm {
+            command_status: header.command_status,
+            sequence_number: header.sequence_number,
+            service_type: body.service_type,
+            source_addr_ton: body.source_addr_ton,
+            source_addr_npi: body.source_addr_npi,
+            source_addr: body.source_addr,
+            dest_addr_ton: body.dest_addr_ton,
+            dest_addr_npi: body.dest_addr_npi,
+            destination_addr: body.destination_addr,
+            esm_class: body.esm_class,
+            protocol_id: body.protocol_id,
+            priority_flag: body.priority_flag,
+            schedule_delivery_time: body.schedule_delivery_time,
+            validity_period: body.validity_period,
+            registered_delivery: body.registered_delivery,
+            replace_if_present_flag: body.replace_if_present_flag,
+            data_coding: body.data_coding,
+            sm_default_msg_id: body.sm_default_msg_id,
+            sm_length: body.sm_length,
+            short_message: body.short_message,
+            user_message_reference: tlvs.remove(&tags::USER_MESSAGE_REFERENCE),
+            source_port: tlvs.remove(&tags::SOURCE_PORT),
+            destination_port: tlvs.remove(&tags::DESTINATION_PORT),
+            sar_msg_ref_num: tlvs.remove(&tags::SAR_MSG_REF_NUM),
+            sar_total_segments: tlvs.remove(&tags::SAR_TOTAL_SEGMENTS),
+            sar_segment_seqnum: tlvs.remove(&tags::SAR_SEGMENT_SEQNUM),
+            user_data_header: tlvs.remove(&tags::DEST_ADDR_SUBMIT),
+            privacy_indicator: tlvs.remove(&tags::PRIVACY_INDICATOR),
+            callback_num: tlvs.remove(&tags::CALLBACK_NUM),
+            source_subaddress: tlvs.remove(&tags::SOURCE_SUBADDRESS),
+            dest_subaddress: tlvs.remove(&tags::DEST_SUBADDRESS),
+            language_indicator: tlvs.remove(&tags::LANGUAGE_INDICATOR),
+            its_session_info: tlvs.remove(&tags::ITS_SESSION_INFO),
+            network_error_code: tlvs.remove(&tags::NETWORK_ERROR_CODE),
+            message_payload: tlvs.remove(&tags::MESSAGE_PAYLOAD),
+            delivery_failure_reason: tlvs.remove(&tags::DELIVERY_FAILURE_REASON),
+            additional_status_info_text: tlvs.remove(&tags::ADDITIONAL_STATUS_INFO_TEXT),
+            dpf_result: tlvs.remove(&tags::DPF_RESULT),
+            set_dpf: tlvs.remove(&tags::SET_DPF),
+            ms_availability_status: tlvs.remove(&tags::MS_AVAILABILITY_STATUS),
+            receipted_message_id: tlvs.remove(&tags::RECEIPTED_MESSAGE_ID),
+            message_state: tlvs.remove(&tags::MESSAGE_STATE),
+            network_routing: body.network_routing,
+        };
+
+        ctx.recover(
+            deliver_sm
+                .validate()
+                .map_err(|e| CodecError::FieldValidation {
+                    field: "deliver_sm",
+                    reason: e.to_string(),
+                }),
+            || (),
+        )?;
+
+        Ok(deliver_sm)
+    }
+}
+
+impl Decodable for DeliverSmResponse {
+    fn command_id() -> CommandId {
+        CommandId::DeliverSmResp
+    }
+
+    fn decode(header: PduHeader, buf: &mut Cursor<&[u8]>) -> Result<Self, CodecError> {
+        Self::decode_with_context(header, buf, &mut DecodeContext::strict())
+    }
+
+    fn decode_with_context(
+        header: PduHeader,
+        buf: &mut Cursor<&[u8]>,
+        ctx: &mut DecodeContext,
+    ) -> Result<Self, CodecError> {
+        Self::validate_header(&header)?;
+
+        // message_id is unused; accept an absent body, a bare NUL or the
+        // 65-octet padded field written by the encoder
+        if !buf.has_remaining() {
+            return Ok(DeliverSmResponse {
+                command_status: header.command_status,
+                sequence_number: header.sequence_number,
+                message_id: MessageId::default(),
+            });
+        }
+
+        let start = buf.position();
+        let message_id_str = decode_c_octet_string(buf, 65, "message_id", ctx)?;
+        skip_nul_padding(buf, start, 65);
+        let message_id = ctx.recover(
+            MessageId::new(message_id_str.as_bytes()).map_err(|e| {
+                CodecError::FieldValidation {
+                    field: "message_id",
+                    reason: e.to_string(),
+                }
+            }),
+            MessageId::default,
+        )?;
+
+        Ok(DeliverSmResponse {
+            command_status: header.command_status,
+            sequence_number: header.sequence_number,
+            message_id,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -709,6 +867,7 @@ mod tests {
             ms_availability_status: None,
             receipted_message_id: None,
             message_state: None,
+            network_routing: NetworkRouting::default(),
         };
 
         let bytes = deliver_sm.to_bytes();
@@ -831,8 +990,141 @@ mod tests {
             ms_availability_status: None,
             receipted_message_id: None,
             message_state: None,
+            network_routing: NetworkRouting::default(),
         };
 
         let _ = deliver_sm.to_bytes(); // Should panic
     }
+
+    /// Build a raw deliver_sm PDU with the given quirky field values
+    fn raw_deliver_sm(
+        source_addr: &[u8],
+        validity_period: &[u8],
+        sm_length: u8,
+        short_message: &[u8],
+    ) -> Vec<u8> {
+        let mut body = vec![0u8]; // service_type
+        body.extend_from_slice(&[0x01, 0x01]);
+        body.extend_from_slice(source_addr);
+        body.extend_from_slice(&[0x01, 0x01]);
+        body.extend_from_slice(b"0987654321\0");
+        body.extend_from_slice(&[0x00, 0x00, 0x00]);
+        body.push(0x00); // schedule_delivery_time
+        body.extend_from_slice(validity_period);
+        body.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, sm_length]);
+        body.extend_from_slice(short_message);
+
+        let mut pdu = Vec::new();
+        pdu.extend_from_slice(&((16 + body.len()) as u32).to_be_bytes());
+        pdu.extend_from_slice(&(CommandId::DeliverSm as u32).to_be_bytes());
+        pdu.extend_from_slice(&0u32.to_be_bytes());
+        pdu.extend_from_slice(&7u32.to_be_bytes());
+        pdu.extend_from_slice(&body);
+        pdu
+    }
+
+    fn decode_with_policy(
+        pdu: &[u8],
+        policy: crate::codec::DecodePolicy,
+    ) -> Result<crate::codec::DecodedFrame, CodecError> {
+        let registry = crate::codec::PduRegistry::new().with_policy(policy);
+        let mut cursor = Cursor::new(pdu);
+        let header = PduHeader::decode(&mut cursor)?;
+        registry.decode_frame(header, &mut cursor)
+    }
+
+    #[test]
+    fn deliver_sm_strict_rejects_non_conformant_fields() {
+        use crate::codec::DecodePolicy;
+
+        // Source address fills the field with no NUL terminator
+        let pdu = raw_deliver_sm(b"12345678901234567890", b"\0", 2, b"Hi");
+        assert!(matches!(
+            decode_with_policy(&pdu, DecodePolicy::Strict),
+            Err(CodecError::FieldValidation { field: "source_addr", .. })
+        ));
+
+        // A validity_period that is not a valid SMPP time
+        let pdu = raw_deliver_sm(b"1234567890\0", b"not-a-time\0", 2, b"Hi");
+        assert!(matches!(
+            decode_with_policy(&pdu, DecodePolicy::Strict),
+            Err(CodecError::FieldValidation { field: "validity_period", .. })
+        ));
+    }
+
+    #[test]
+    fn deliver_sm_lenient_normalises_quirks() {
+        use crate::codec::{DecodePolicy, Frame};
+
+        // Unterminated source address, a malformed validity_period and an
+        // sm_length that overruns the body
+        let pdu = raw_deliver_sm(b"12345678901234567890", b"not-a-time\0", 10, b"Hi");
+        let decoded = decode_with_policy(&pdu, DecodePolicy::Lenient).unwrap();
+
+        let Frame::DeliverSm(deliver_sm) = decoded.frame else {
+            panic!("expected deliver_sm");
+        };
+        assert_eq!(deliver_sm.source_addr.as_str().unwrap(), "12345678901234567890");
+        assert_eq!(deliver_sm.destination_addr.as_str().unwrap(), "0987654321");
+        assert_eq!(deliver_sm.validity_period, ValidityPeriod::immediate());
+        assert_eq!(deliver_sm.sm_length, 2);
+        assert_eq!(deliver_sm.short_message.as_bytes(), b"Hi");
+
+        let fields: Vec<_> = decoded.warnings.iter().map(|w| w.field).collect();
+        assert_eq!(fields, vec!["source_addr", "validity_period", "sm_length"]);
+    }
+
+    #[test]
+    fn deliver_sm_mandatory_strings_are_c_octet_strings() {
+        use crate::codec::Frame;
+
+        let deliver_sm = DeliverSm::builder()
+            .sequence_number(3)
+            .source_addr("1234")
+            .destination_addr("5678")
+            .short_message("Hi")
+            .build()
+            .unwrap();
+        let bytes = deliver_sm.to_bytes();
+
+        // Section 4.6.1: service_type, source_addr, destination_addr,
+        // schedule_delivery_time and validity_period are Var. max C-Octet
+        // Strings, so each takes its content and one NUL, not its maximum size
+        let mut body = vec![0x00]; // service_type
+        body.extend_from_slice(&[0x00, 0x00]);
+        body.extend_from_slice(b"1234\0");
+        body.extend_from_slice(&[0x00, 0x00]);
+        body.extend_from_slice(b"5678\0");
+        body.extend_from_slice(&[0x00, 0x00, 0x00]); // esm_class, protocol_id, priority_flag
+        body.extend_from_slice(&[0x00, 0x00]); // schedule_delivery_time, validity_period
+        body.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x02]);
+        body.extend_from_slice(b"Hi");
+        assert_eq!(&bytes[16..], &body[..]);
+        assert_eq!(&bytes[0..4], &(16 + body.len() as u32).to_be_bytes());
+
+        // Padding each field to its maximum size, as this PDU was once
+        // encoded, shifts every later field for a conformant decoder
+        let mut padded = BytesMut::new();
+        encode_cstring(&mut padded, "", 6);
+        padded.extend_from_slice(&[0x00, 0x00]);
+        encode_cstring(&mut padded, "1234", 21);
+        padded.extend_from_slice(&[0x00, 0x00]);
+        encode_cstring(&mut padded, "5678", 21);
+        padded.extend_from_slice(&[0x00, 0x00, 0x00]);
+        encode_cstring(&mut padded, "", 17);
+        encode_cstring(&mut padded, "", 17);
+        padded.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x02]);
+        padded.extend_from_slice(b"Hi");
+        let mut pdu = bytes[..16].to_vec();
+        pdu[0..4].copy_from_slice(&(16 + padded.len() as u32).to_be_bytes());
+        pdu.extend_from_slice(&padded);
+
+        let decoded = Frame::parse(&mut Cursor::new(&pdu[..]));
+        assert!(!matches!(decoded, Ok(Frame::DeliverSm(ref d)) if **d == deliver_sm));
+        let Frame::DeliverSm(decoded) = Frame::parse(&mut Cursor::new(&bytes[..])).unwrap()
+        else {
+            panic!("expected deliver_sm");
+        };
+        assert_eq!(*decoded, deliver_sm);
+    }
 }
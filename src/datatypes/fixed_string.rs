@@ -170,10 +170,9 @@ impl fmt::Display for FixedStringError {
 impl std::error::Error for FixedStringError {}
 
 // SMPP-specific type aliases for common field sizes
-// Note: ServiceType, SourceAddr, DestinationAddr, ScheduleDeliveryTime, and ValidityPeriod
-// are now strongly-typed in their own modules
+// Note: ServiceType, SourceAddr, DestinationAddr, ScheduleDeliveryTime, ValidityPeriod and
+// Password are now strongly-typed in their own modules
 pub type SystemId = FixedString<16>; // 15 chars + null terminator
-pub type Password = FixedString<9>; // 8 chars + null terminator
 pub type SystemType = FixedString<13>; // 12 chars + null terminator
 pub type AddressRange = FixedString<41>; // 40 chars + null terminator
 pub type MessageId = FixedString<66>; // 65 chars + null terminator
@@ -1,11 +1,89 @@
+use crate::datatypes::CommandId;
 use num_enum::TryFromPrimitive;
 
 /// This parameter is used to indicate the version of the SMPP protocol.
+///
+/// Per SMPP v3.4 Section 5.2.4, any value from 0x00 to 0x33 identifies an
+/// SMPP v3.3 (or earlier) peer, so all of them decode to `SmppV33`.
+/// Versions are ordered, so the lower of two versions is the common one.
 #[derive(TryFromPrimitive)]
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InterfaceVersion {
+    #[num_enum(alternatives = [0x00..=0x32])]
     SmppV33 = 0x33,
     SmppV34 = 0x34,
     SmppV50 = 0x50,
 }
+
+impl InterfaceVersion {
+    /// Whether PDUs may carry optional (TLV) parameters
+    ///
+    /// TLVs were introduced in SMPP v3.4; a v3.3 PDU consists of its
+    /// mandatory parameters only.
+    pub fn supports_tlvs(&self) -> bool {
+        !matches!(self, InterfaceVersion::SmppV33)
+    }
+
+    /// Maximum length of a message_id in octets, including the NUL terminator
+    ///
+    /// SMPP v3.3 message IDs are hexadecimal C-Octet Strings of at most 9
+    /// octets; v3.4 and later allow up to 65.
+    pub fn message_id_max_len(&self) -> usize {
+        match self {
+            InterfaceVersion::SmppV33 => 9,
+            InterfaceVersion::SmppV34 | InterfaceVersion::SmppV50 => 65,
+        }
+    }
+
+    /// Whether the given command exists in this version of the protocol
+    pub fn supports_command(&self, command_id: CommandId) -> bool {
+        match command_id {
+            // Introduced in SMPP v3.4
+            CommandId::BindTransceiver
+            | CommandId::BindTransceiverResp
+            | CommandId::DataSm
+            | CommandId::DataSmResp
+            | CommandId::AlertNotification => !matches!(self, InterfaceVersion::SmppV33),
+            // Introduced in SMPP v5.0
+            CommandId::BroadcastSm
+            | CommandId::BroadcastSmResp
+            | CommandId::QueryBroadcastSm
+            | CommandId::QueryBroadcastSmResp
+            | CommandId::CancelBroadcastSm
+            | CommandId::CancelBroadcastSmResp => matches!(self, InterfaceVersion::SmppV50),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pre_v34_values_decode_as_v33() {
+        for value in 0x00..=0x33u8 {
+            assert_eq!(
+                InterfaceVersion::try_from(value).unwrap(),
+                InterfaceVersion::SmppV33
+            );
+        }
+        assert_eq!(InterfaceVersion::SmppV33 as u8, 0x33);
+    }
+
+    #[test]
+    fn v33_capabilities() {
+        let v33 = InterfaceVersion::SmppV33;
+        assert!(!v33.supports_tlvs());
+        assert_eq!(v33.message_id_max_len(), 9);
+        assert!(!v33.supports_command(CommandId::DataSm));
+        assert!(!v33.supports_command(CommandId::BindTransceiver));
+        assert!(v33.supports_command(CommandId::SubmitSm));
+
+        assert!(InterfaceVersion::SmppV34.supports_tlvs());
+        assert!(InterfaceVersion::SmppV34.supports_command(CommandId::DataSm));
+        assert!(!InterfaceVersion::SmppV34.supports_command(CommandId::BroadcastSm));
+    }
+}
@@ -0,0 +1,201 @@
+// ABOUTME: Decoding of the mandatory parameters and TLVs shared by submit_sm and deliver_sm
+// ABOUTME: Applies the decode policy field by field so both PDUs tolerate the same quirks
+
+use crate::codec::{
+    CodecError, DecodeContext, decode_address, decode_c_octet_string, decode_u8,
+};
+use crate::datatypes::{
+    DataCoding, DestinationAddr, EsmClass, NetworkRouting, NumericPlanIndicator,
+    ScheduleDeliveryTime, ServiceType, ShortMessage, SourceAddr, Tlv, TypeOfNumber,
+    ValidityPeriod,
+};
+use bytes::Buf;
+use std::collections::HashMap;
+use std::io::Cursor;
+
+/// The body of a submit_sm or deliver_sm, which share their layout (Sections 4.4.1 and 4.6.1)
+///
+/// `P` is the priority_flag: submit_sm checks it against the defined levels,
+/// deliver_sm keeps the octet as sent. TLVs not claimed by
+/// [`NetworkRouting`] are left in `tlvs` for the PDU to pick out.
+pub(crate) struct MessageBody<P> {
+    pub service_type: ServiceType,
+    pub source_addr_ton: TypeOfNumber,
+    pub source_addr_npi: NumericPlanIndicator,
+    pub source_addr: SourceAddr,
+    pub dest_addr_ton: TypeOfNumber,
+    pub dest_addr_npi: NumericPlanIndicator,
+    pub destination_addr: DestinationAddr,
+    pub esm_class: EsmClass,
+    pub protocol_id: u8,
+    pub priority_flag: P,
+    pub schedule_delivery_time: ScheduleDeliveryTime,
+    pub validity_period: ValidityPeriod,
+    pub registered_delivery: u8,
+    pub replace_if_present_flag: u8,
+    pub data_coding: DataCoding,
+    pub sm_default_msg_id: u8,
+    pub sm_length: u8,
+    pub short_message: ShortMessage,
+    pub tlvs: HashMap<u16, Tlv>,
+    pub network_routing: NetworkRouting,
+}
+
+impl<P> MessageBody<P> {
+    /// Decode the body following the PDU header
+    pub fn decode(
+        buf: &mut Cursor<&[u8]>,
+        ctx: &mut DecodeContext,
+        priority_flag: impl FnOnce(u8, &mut DecodeContext) -> Result<P, CodecError>,
+    ) -> Result<Self, CodecError> {
+        // service_type (null-terminated string, max 5 chars + null)
+        let service_type = decode_c_octet_string(buf, 6, "service_type", ctx)?;
+        let service_type = ctx.recover(
+            ServiceType::new(&service_type).map_err(|e| CodecError::FieldValidation {
+                field: "service_type",
+                reason: e.to_string(),
+            }),
+            ServiceType::default,
+        )?;
+
+        let source_addr_ton = decode_ton(buf, "source_addr_ton", ctx)?;
+        let source_addr_npi = decode_npi(buf, "source_addr_npi", ctx)?;
+
+        // source_addr (null-terminated string, max 20 chars + null)
+        let source_addr_str = decode_c_octet_string(buf, 21, "source_addr", ctx)?;
+        let (source_addr_ton, source_addr) =
+            decode_address(&source_addr_str, source_addr_ton, "source_addr", ctx)?;
+
+        let dest_addr_ton = decode_ton(buf, "dest_addr_ton", ctx)?;
+        let dest_addr_npi = decode_npi(buf, "dest_addr_npi", ctx)?;
+
+        // destination_addr (null-terminated string, max 20 chars + null)
+        let dest_addr_str = decode_c_octet_string(buf, 21, "destination_addr", ctx)?;
+        let (dest_addr_ton, destination_addr) =
+            decode_address(&dest_addr_str, dest_addr_ton, "destination_addr", ctx)?;
+
+        let esm_class = EsmClass::from(decode_u8(buf)?);
+        let protocol_id = decode_u8(buf)?;
+        let priority_flag = priority_flag(decode_u8(buf)?, ctx)?;
+
+        // schedule_delivery_time (null-terminated string, max 16 chars + null)
+        let schedule_time_str = decode_c_octet_string(buf, 17, "schedule_delivery_time", ctx)?;
+        let schedule_delivery_time = ctx.recover(
+            ScheduleDeliveryTime::try_from(schedule_time_str).map_err(|e| {
+                CodecError::FieldValidation {
+                    field: "schedule_delivery_time",
+                    reason: e.to_string(),
+                }
+            }),
+            ScheduleDeliveryTime::immediate,
+        )?;
+
+        // validity_period (null-terminated string, max 16 chars + null)
+        let validity_str = decode_c_octet_string(buf, 17, "validity_period", ctx)?;
+        let validity_period = ctx.recover(
+            ValidityPeriod::try_from(validity_str).map_err(|e| CodecError::FieldValidation {
+                field: "validity_period",
+                reason: e.to_string(),
+            }),
+            ValidityPeriod::immediate,
+        )?;
+
+        let registered_delivery = decode_u8(buf)?;
+        let replace_if_present_flag = decode_u8(buf)?;
+        let data_coding = DataCoding::from(decode_u8(buf)?);
+        let sm_default_msg_id = decode_u8(buf)?;
+        let mut sm_length = decode_u8(buf)?;
+
+        // short_message (binary data, not null-terminated)
+        if buf.remaining() < sm_length as usize {
+            if !ctx.is_lenient() {
+                return Err(CodecError::Incomplete);
+            }
+            let available = buf.remaining() as u8;
+            ctx.warn(
+                "sm_length",
+                format!("sm_length {sm_length} exceeds the {available} octets remaining; truncated"),
+            );
+            sm_length = available;
+        }
+        let mut short_message_bytes = vec![0u8; sm_length as usize];
+        buf.copy_to_slice(&mut short_message_bytes);
+        let short_message = ctx.recover(
+            ShortMessage::new(&short_message_bytes).map_err(|e| CodecError::FieldValidation {
+                field: "short_message",
+                reason: e.to_string(),
+            }),
+            || {
+                let truncated = &short_message_bytes[..254];
+                ShortMessage::new(truncated).unwrap_or_default()
+            },
+        )?;
+        sm_length = short_message.len() as u8;
+
+        // Parse optional TLV parameters
+        ctx.check_optional_parameters(buf)?;
+        let mut tlvs = HashMap::new();
+        let mut network_routing = NetworkRouting::default();
+        while buf.remaining() >= 4 {
+            let tlv = Tlv::decode(buf)?;
+            if ctx.check_tlv(tlv.tag)?
+                && let Some(tlv) = network_routing.decode_tlv(tlv, ctx)?
+            {
+                tlvs.insert(tlv.tag, tlv);
+            }
+        }
+
+        Ok(Self {
+            service_type,
+            source_addr_ton,
+            source_addr_npi,
+            source_addr,
+            dest_addr_ton,
+            dest_addr_npi,
+            destination_addr,
+            esm_class,
+            protocol_id,
+            priority_flag,
+            schedule_delivery_time,
+            validity_period,
+            registered_delivery,
+            replace_if_present_flag,
+            data_coding,
+            sm_default_msg_id,
+            sm_length,
+            short_message,
+            tlvs,
+            network_routing,
+        })
+    }
+}
+
+fn decode_ton(
+    buf: &mut Cursor<&[u8]>,
+    field: &'static str,
+    ctx: &mut DecodeContext,
+) -> Result<TypeOfNumber, CodecError> {
+    ctx.recover(
+        TypeOfNumber::try_from(decode_u8(buf)?).map_err(|_| CodecError::FieldValidation {
+            field,
+            reason: "Invalid type of number".to_string(),
+        }),
+        || TypeOfNumber::Unknown,
+    )
+}
+
+fn decode_npi(
+    buf: &mut Cursor<&[u8]>,
+    field: &'static str,
+    ctx: &mut DecodeContext,
+) -> Result<NumericPlanIndicator, CodecError> {
+    ctx.recover(
+        NumericPlanIndicator::try_from(decode_u8(buf)?).map_err(|_| {
+            CodecError::FieldValidation {
+                field,
+                reason: "Invalid numeric plan indicator".to_string(),
+            }
+        }),
+        || NumericPlanIndicator::Unknown,
+    )
+}
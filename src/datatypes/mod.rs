@@ -17,8 +17,11 @@ mod esm_class;
 mod fixed_string;
 mod generic_nack;
 mod interface_version;
+mod message_body;
+mod network_routing;
 mod numeric_plan_indicator;
 mod outbind;
+mod password;
 mod priority_flag;
 mod query_broadcast_sm;
 mod query_sm;
@@ -31,8 +34,10 @@ mod type_of_number;
 mod unbind;
 
 pub use address::{AddressError, AlphanumericAddress, DestinationAddr, PhoneNumber, SourceAddr};
-pub use alert_notification::{AlertNotification, AlertNotificationValidationError};
-pub(crate) use command_id::CommandId;
+pub use alert_notification::{
+    AlertNotification, AlertNotificationValidationError, MsAvailabilityStatus,
+};
+pub use command_id::CommandId;
 pub use command_status::{CommandStatus, ErrorSeverity, ErrorCategory};
 pub use data_coding::{DataCoding, DataCodingError, MessageClass};
 pub use datetime::{DateTimeError, ScheduleDeliveryTime, SmppDateTime, ValidityPeriod};
@@ -40,11 +45,15 @@ pub use esm_class::{
     EsmClass, EsmClassError, EsmFeatures, MessageMode, MessageType, StoreAndForwardType,
 };
 pub use fixed_string::{
-    AddressRange, FixedString, FixedStringError, MessageId, Password, ShortMessage, SystemId,
+    AddressRange, FixedString, FixedStringError, MessageId, ShortMessage, SystemId,
     SystemType,
 };
 pub use interface_version::InterfaceVersion;
+pub use network_routing::{
+    BillingIdentification, NetworkRouting, NetworkType, NodeId, NpResolution,
+};
 pub use numeric_plan_indicator::NumericPlanIndicator;
+pub use password::Password;
 pub use priority_flag::PriorityFlag;
 pub use service_type::{ServiceType, ServiceTypeError};
 pub use tlv::{Tlv, tags};
@@ -58,7 +67,8 @@ pub use bind_transmitter::{
     BindTransmitter, BindTransmitterResponse, BindTransmitterValidationError,
 };
 pub use broadcast_sm::{
-    BroadcastSm, BroadcastSmResponse, BroadcastSmValidationError,
+    BroadcastChannelIndicator, BroadcastMessageClass, BroadcastSm, BroadcastSmResponse,
+    BroadcastSmValidationError,
 };
 pub use cancel_broadcast_sm::{
     CancelBroadcastSm, CancelBroadcastSmResponse, CancelBroadcastSmValidationError,
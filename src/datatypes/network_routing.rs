@@ -0,0 +1,445 @@
+// ABOUTME: Typed SMPP v5.0 billing, network routing and number portability TLVs
+// ABOUTME: Shared by submit_sm, data_sm and deliver_sm for inter-operator billing
+
+use crate::codec::{CodecError, DecodeContext, Encodable};
+use crate::datatypes::InterfaceVersion;
+use crate::datatypes::tlv::{Tlv, tags};
+use bytes::{Bytes, BytesMut};
+use num_enum::TryFromPrimitive;
+
+/// billing_identification (SMPP v5.0 Section 4.8.4.3)
+///
+/// The first octet identifies the format of the billing data that follows:
+/// `0x01` is internal billing information, `0x80`-`0xFF` are vendor-specific
+/// and everything else is reserved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BillingIdentification {
+    pub format: u8,
+    pub data: Bytes,
+}
+
+impl BillingIdentification {
+    /// Format tag for internal billing information
+    pub const INTERNAL: u8 = 0x01;
+
+    /// Maximum length of the billing data, excluding the format octet
+    pub const MAX_DATA_LENGTH: usize = 1023;
+
+    /// Internal billing information
+    pub fn internal(data: impl Into<Bytes>) -> Result<Self, CodecError> {
+        Self::new(Self::INTERNAL, data)
+    }
+
+    /// Vendor-specific billing information; `format` must be in `0x80..=0xFF`
+    pub fn vendor_specific(format: u8, data: impl Into<Bytes>) -> Result<Self, CodecError> {
+        if format < 0x80 {
+            return Err(CodecError::FieldValidation {
+                field: "billing_identification",
+                reason: format!("vendor-specific format {format:#04x} must be 0x80 or above"),
+            });
+        }
+        Self::new(format, data)
+    }
+
+    fn new(format: u8, data: impl Into<Bytes>) -> Result<Self, CodecError> {
+        let data = data.into();
+        if data.len() > Self::MAX_DATA_LENGTH {
+            return Err(CodecError::FieldValidation {
+                field: "billing_identification",
+                reason: format!(
+                    "{} octets of billing data exceed the maximum of {}",
+                    data.len(),
+                    Self::MAX_DATA_LENGTH
+                ),
+            });
+        }
+        Ok(Self { format, data })
+    }
+
+    /// Whether the format octet is one of the vendor-specific values
+    pub fn is_vendor_specific(&self) -> bool {
+        self.format >= 0x80
+    }
+}
+
+/// source_node_id / dest_node_id: a node identifier of exactly 6 decimal digits
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId([u8; 6]);
+
+impl NodeId {
+    pub fn new(digits: &str) -> Result<Self, CodecError> {
+        let bytes: [u8; 6] = digits
+            .as_bytes()
+            .try_into()
+            .ok()
+            .filter(|bytes: &[u8; 6]| bytes.iter().all(u8::is_ascii_digit))
+            .ok_or_else(|| CodecError::FieldValidation {
+                field: "node_id",
+                reason: format!("'{digits}' is not exactly 6 decimal digits"),
+            })?;
+        Ok(Self(bytes))
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Only ASCII digits are ever stored
+        std::str::from_utf8(&self.0).unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for NodeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// dest_addr_np_resolution: outcome of the number portability query
+#[derive(TryFromPrimitive)]
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NpResolution {
+    QueryNotPerformed = 0,
+    NumberNotPorted = 1,
+    NumberPorted = 2,
+}
+
+/// source_network_type / dest_network_type: the network technology of an address
+#[derive(TryFromPrimitive)]
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NetworkType {
+    Unknown = 0,
+    Gsm = 1,
+    /// ANSI-136/TDMA
+    Ansi136 = 2,
+    /// IS-95/CDMA
+    Is95 = 3,
+    Pdc = 4,
+    Phs = 5,
+    Iden = 6,
+    Amps = 7,
+    PagingNetwork = 8,
+}
+
+/// Billing, network routing and number portability parameters
+///
+/// Carried as optional parameters on submit_sm, data_sm and deliver_sm.
+/// Apart from the network types, which date from v3.4, these TLVs only exist
+/// in SMPP v5.0: a session bound at a lower version refuses to send a PDU
+/// carrying them (see [`PduRegistry::check_outbound`]) rather than silently
+/// dropping billing data.
+///
+/// [`PduRegistry::check_outbound`]: crate::codec::PduRegistry::check_outbound
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NetworkRouting {
+    pub billing_identification: Option<BillingIdentification>,
+    /// Originating network, as a C-Octet String of up to 64 characters
+    pub source_network_id: Option<String>,
+    /// Terminating network, as a C-Octet String of up to 64 characters
+    pub dest_network_id: Option<String>,
+    pub source_node_id: Option<NodeId>,
+    pub dest_node_id: Option<NodeId>,
+    pub dest_addr_np_resolution: Option<NpResolution>,
+    /// Number portability data, such as a routing number (10 octets)
+    pub dest_addr_np_information: Option<[u8; 10]>,
+    /// E.164 country code of the ported number
+    pub dest_addr_np_country: Option<u32>,
+    pub source_network_type: Option<NetworkType>,
+    pub dest_network_type: Option<NetworkType>,
+}
+
+// Size of the dest_addr_np_country integer on the wire
+const NP_COUNTRY_LENGTH: usize = 5;
+// Maximum network id length, including the NUL terminator
+const NETWORK_ID_MAX_LENGTH: usize = 65;
+
+impl NetworkRouting {
+    /// Whether none of the parameters are set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The lowest interface version able to carry every parameter that is set
+    pub fn required_version(&self) -> InterfaceVersion {
+        // Parameters that cannot be encoded fail at any version
+        let tlvs = self.to_tlvs().unwrap_or_default();
+        tlvs.iter()
+            .filter_map(|tlv| tags::introduced_in(tlv.tag))
+            .max()
+            .unwrap_or(InterfaceVersion::SmppV34)
+    }
+
+    /// Encode the parameters that are set as TLVs
+    ///
+    /// Fails when a network id is longer than the 64 octets it may hold.
+    pub fn to_tlvs(&self) -> Result<Vec<Tlv>, CodecError> {
+        let mut tlvs = Vec::new();
+
+        if let Some(billing) = &self.billing_identification {
+            let mut value = Vec::with_capacity(1 + billing.data.len());
+            value.push(billing.format);
+            value.extend_from_slice(&billing.data);
+            tlvs.push(Tlv::new(tags::BILLING_IDENTIFICATION, value)?);
+        }
+        if let Some(id) = &self.source_network_id {
+            let value = network_id_value(id, "source_network_id")?;
+            tlvs.push(Tlv::new(tags::SOURCE_NETWORK_ID, value)?);
+        }
+        if let Some(id) = &self.dest_network_id {
+            let value = network_id_value(id, "dest_network_id")?;
+            tlvs.push(Tlv::new(tags::DEST_NETWORK_ID, value)?);
+        }
+        if let Some(node) = &self.source_node_id {
+            tlvs.push(Tlv::new(tags::SOURCE_NODE_ID, node.0.to_vec())?);
+        }
+        if let Some(node) = &self.dest_node_id {
+            tlvs.push(Tlv::new(tags::DEST_NODE_ID, node.0.to_vec())?);
+        }
+        if let Some(resolution) = self.dest_addr_np_resolution {
+            tlvs.push(Tlv::new(tags::DEST_ADDR_NP_RESOLUTION, vec![resolution as u8])?);
+        }
+        if let Some(information) = &self.dest_addr_np_information {
+            tlvs.push(Tlv::new(tags::DEST_ADDR_NP_INFORMATION, information.to_vec())?);
+        }
+        if let Some(country) = self.dest_addr_np_country {
+            let value = (country as u64).to_be_bytes()[8 - NP_COUNTRY_LENGTH..].to_vec();
+            tlvs.push(Tlv::new(tags::DEST_ADDR_NP_COUNTRY, value)?);
+        }
+        if let Some(network_type) = self.source_network_type {
+            tlvs.push(Tlv::new(tags::SOURCE_NETWORK_TYPE, vec![network_type as u8])?);
+        }
+        if let Some(network_type) = self.dest_network_type {
+            tlvs.push(Tlv::new(tags::DEST_NETWORK_TYPE, vec![network_type as u8])?);
+        }
+
+        Ok(tlvs)
+    }
+
+    /// Append the parameters that are set to a PDU body
+    pub fn encode(&self, buf: &mut BytesMut) -> Result<(), CodecError> {
+        for tlv in self.to_tlvs()? {
+            tlv.encode(buf)?;
+        }
+        Ok(())
+    }
+
+    pub fn encoded_size(&self) -> usize {
+        let tlvs = self.to_tlvs().unwrap_or_default();
+        tlvs.iter().map(Tlv::encoded_size).sum()
+    }
+
+    /// Take a TLV into these parameters if it is one of them
+    ///
+    /// Returns the TLV unchanged when its tag is not a routing parameter. A
+    /// malformed value is rejected in strict mode and dropped with a warning
+    /// in lenient mode.
+    pub fn decode_tlv(&mut self, tlv: Tlv, ctx: &mut DecodeContext) -> Result<Option<Tlv>, CodecError> {
+        let value = tlv.value.as_ref();
+        let parsed = match tlv.tag {
+            tags::BILLING_IDENTIFICATION => value
+                .split_first()
+                .ok_or_else(|| invalid("billing_identification", "missing format octet"))
+                .and_then(|(&format, data)| {
+                    BillingIdentification::new(format, Bytes::copy_from_slice(data))
+                })
+                .map(|billing| self.billing_identification = Some(billing)),
+            tags::SOURCE_NETWORK_ID => parse_network_id(value, "source_network_id")
+                .map(|id| self.source_network_id = Some(id)),
+            tags::DEST_NETWORK_ID => parse_network_id(value, "dest_network_id")
+                .map(|id| self.dest_network_id = Some(id)),
+            tags::SOURCE_NODE_ID => parse_node_id(value, "source_node_id")
+                .map(|node| self.source_node_id = Some(node)),
+            tags::DEST_NODE_ID => parse_node_id(value, "dest_node_id")
+                .map(|node| self.dest_node_id = Some(node)),
+            tags::DEST_ADDR_NP_RESOLUTION => single_octet(value, "dest_addr_np_resolution")
+                .and_then(|octet| {
+                    NpResolution::try_from(octet).map_err(|_| {
+                        invalid("dest_addr_np_resolution", format!("unknown value {octet}"))
+                    })
+                })
+                .map(|resolution| self.dest_addr_np_resolution = Some(resolution)),
+            tags::DEST_ADDR_NP_INFORMATION => <[u8; 10]>::try_from(value)
+                .map_err(|_| {
+                    invalid(
+                        "dest_addr_np_information",
+                        format!("expected 10 octets, got {}", value.len()),
+                    )
+                })
+                .map(|information| self.dest_addr_np_information = Some(information)),
+            tags::DEST_ADDR_NP_COUNTRY => parse_np_country(value)
+                .map(|country| self.dest_addr_np_country = Some(country)),
+            tags::SOURCE_NETWORK_TYPE => parse_network_type(value, "source_network_type")
+                .map(|network_type| self.source_network_type = Some(network_type)),
+            tags::DEST_NETWORK_TYPE => parse_network_type(value, "dest_network_type")
+                .map(|network_type| self.dest_network_type = Some(network_type)),
+            _ => return Ok(Some(tlv)),
+        };
+
+        ctx.recover(parsed, || ())?;
+        Ok(None)
+    }
+}
+
+fn invalid(field: &'static str, reason: impl Into<String>) -> CodecError {
+    CodecError::FieldValidation {
+        field,
+        reason: reason.into(),
+    }
+}
+
+/// A network id as the C-Octet String it is sent as
+fn network_id_value(id: &str, field: &'static str) -> Result<Vec<u8>, CodecError> {
+    if id.len() >= NETWORK_ID_MAX_LENGTH {
+        return Err(invalid(
+            field,
+            format!("{} octets exceed the maximum of {}", id.len(), NETWORK_ID_MAX_LENGTH - 1),
+        ));
+    }
+    let mut bytes = Vec::with_capacity(id.len() + 1);
+    bytes.extend_from_slice(id.as_bytes());
+    bytes.push(0);
+    Ok(bytes)
+}
+
+fn single_octet(value: &[u8], field: &'static str) -> Result<u8, CodecError> {
+    match value {
+        [octet] => Ok(*octet),
+        _ => Err(invalid(field, format!("expected 1 octet, got {}", value.len()))),
+    }
+}
+
+fn parse_network_id(value: &[u8], field: &'static str) -> Result<String, CodecError> {
+    if value.len() > NETWORK_ID_MAX_LENGTH {
+        return Err(invalid(
+            field,
+            format!("{} octets exceed the maximum of {NETWORK_ID_MAX_LENGTH}", value.len()),
+        ));
+    }
+    let content = value.strip_suffix(&[0]).unwrap_or(value);
+    String::from_utf8(content.to_vec()).map_err(|e| invalid(field, e.to_string()))
+}
+
+fn parse_node_id(value: &[u8], field: &'static str) -> Result<NodeId, CodecError> {
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|digits| NodeId::new(digits).ok())
+        .ok_or_else(|| invalid(field, "expected exactly 6 decimal digits"))
+}
+
+fn parse_np_country(value: &[u8]) -> Result<u32, CodecError> {
+    if value.is_empty() || value.len() > NP_COUNTRY_LENGTH {
+        return Err(invalid(
+            "dest_addr_np_country",
+            format!("expected 1 to {NP_COUNTRY_LENGTH} octets, got {}", value.len()),
+        ));
+    }
+    let country = value
+        .iter()
+        .fold(0u64, |acc, &octet| (acc << 8) | u64::from(octet));
+    u32::try_from(country).map_err(|_| invalid("dest_addr_np_country", "value out of range"))
+}
+
+fn parse_network_type(value: &[u8], field: &'static str) -> Result<NetworkType, CodecError> {
+    let octet = single_octet(value, field)?;
+    NetworkType::try_from(octet).map_err(|_| invalid(field, format!("unknown network type {octet}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(routing: &NetworkRouting) -> NetworkRouting {
+        let mut decoded = NetworkRouting::default();
+        let mut ctx = DecodeContext::strict();
+        for tlv in routing.to_tlvs().unwrap() {
+            assert!(decoded.decode_tlv(tlv, &mut ctx).unwrap().is_none());
+        }
+        decoded
+    }
+
+    #[test]
+    fn all_parameters_roundtrip() {
+        let routing = NetworkRouting {
+            billing_identification: Some(BillingIdentification::internal(&b"ACME-42"[..]).unwrap()),
+            source_network_id: Some("OPERATOR-A".to_string()),
+            dest_network_id: Some("OPERATOR-B".to_string()),
+            source_node_id: Some(NodeId::new("123456").unwrap()),
+            dest_node_id: Some(NodeId::new("654321").unwrap()),
+            dest_addr_np_resolution: Some(NpResolution::NumberPorted),
+            dest_addr_np_information: Some(*b"5551234567"),
+            dest_addr_np_country: Some(44),
+            source_network_type: Some(NetworkType::Gsm),
+            dest_network_type: Some(NetworkType::Is95),
+        };
+
+        assert_eq!(roundtrip(&routing), routing);
+        assert_eq!(routing.required_version(), InterfaceVersion::SmppV50);
+        assert_eq!(
+            routing.encoded_size(),
+            routing.to_tlvs().unwrap().iter().map(|tlv| 4 + tlv.value.len()).sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn wire_format() {
+        let routing = NetworkRouting {
+            billing_identification: Some(BillingIdentification::internal(&b"AB"[..]).unwrap()),
+            dest_addr_np_country: Some(44),
+            ..Default::default()
+        };
+
+        let mut buf = BytesMut::new();
+        routing.encode(&mut buf).unwrap();
+        assert_eq!(
+            buf.as_ref(),
+            &[
+                0x06, 0x0B, 0x00, 0x03, 0x01, b'A', b'B', // billing_identification
+                0x06, 0x13, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x2C, // dest_addr_np_country
+            ]
+        );
+    }
+
+    #[test]
+    fn network_types_need_only_v34() {
+        let routing = NetworkRouting {
+            source_network_type: Some(NetworkType::Gsm),
+            ..Default::default()
+        };
+        assert_eq!(routing.required_version(), InterfaceVersion::SmppV34);
+        assert!(NetworkRouting::default().is_empty());
+    }
+
+    #[test]
+    fn value_validation() {
+        assert!(NodeId::new("12345").is_err());
+        assert!(NodeId::new("12345a").is_err());
+        assert!(BillingIdentification::vendor_specific(0x7F, Bytes::new()).is_err());
+        assert!(BillingIdentification::vendor_specific(0x80, Bytes::new()).unwrap().is_vendor_specific());
+        assert!(BillingIdentification::internal(vec![0u8; 1024]).is_err());
+
+        let mut routing = NetworkRouting {
+            dest_network_id: Some("N".repeat(64)),
+            ..Default::default()
+        };
+        assert!(routing.to_tlvs().is_ok());
+        routing.dest_network_id = Some("N".repeat(65));
+        assert!(routing.to_tlvs().is_err());
+        assert!(routing.encode(&mut BytesMut::new()).is_err());
+    }
+
+    #[test]
+    fn malformed_values_follow_policy() {
+        let bad_node = Tlv::new(tags::SOURCE_NODE_ID, b"12".to_vec()).unwrap();
+
+        let mut routing = NetworkRouting::default();
+        assert!(routing.decode_tlv(bad_node.clone(), &mut DecodeContext::strict()).is_err());
+
+        let mut ctx = DecodeContext::lenient();
+        assert!(routing.decode_tlv(bad_node, &mut ctx).unwrap().is_none());
+        assert!(routing.source_node_id.is_none());
+        assert_eq!(ctx.warnings().len(), 1);
+
+        // Other TLVs are handed back untouched
+        let other = Tlv::new(tags::MESSAGE_PAYLOAD, b"hi".to_vec()).unwrap();
+        assert_eq!(routing.decode_tlv(other.clone(), &mut ctx).unwrap(), Some(other));
+    }
+}
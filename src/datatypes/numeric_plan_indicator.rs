@@ -35,6 +35,7 @@ use num_enum::TryFromPrimitive;
 #[derive(TryFromPrimitive)]
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NumericPlanIndicator {
     /// Unknown numbering plan - Use when NPI is not specified
     Unknown = 0b00000000,
@@ -303,4 +303,11 @@ mod tests {
             panic!("Expected Outbind frame");
         }
     }
+
+    #[test]
+    fn outbind_debug_redacts_password() {
+        let password = Password::from("secret08");
+        let outbind = Outbind::new(1, SystemId::from("SMPP3TEST"), Some(password));
+        assert!(!format!("{outbind:?}").contains("secret08"));
+    }
 }
@@ -0,0 +1,160 @@
+// ABOUTME: The password field of bind PDUs and outbind, kept out of logs and wiped from memory
+// ABOUTME: Debug and Display redact it; its octets are zeroised when it is dropped
+
+use crate::datatypes::FixedStringError;
+use std::fmt;
+use std::str;
+use std::str::FromStr;
+use zeroize::{Zeroize, Zeroizing};
+
+/// The password of bind PDUs and outbind (Section 5.2.2): up to 8 octets, NULL terminated
+///
+/// Unlike the other fixed strings it is not `Copy`, so that every copy is a
+/// clone that is zeroised in turn when dropped. `Debug` and `Display` never
+/// show it; use [`as_str`](Self::as_str) where the password itself is needed.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Password {
+    data: [u8; 9],
+}
+
+impl Password {
+    /// Creates a password from up to 8 octets, padding it with NULLs
+    pub fn new(s: &[u8]) -> Result<Self, FixedStringError> {
+        if s.len() >= 9 {
+            return Err(FixedStringError::TooLong {
+                max_len: 8,
+                actual_len: s.len(),
+            });
+        }
+
+        let mut data = [0u8; 9];
+        data[..s.len()].copy_from_slice(s);
+        Ok(Self { data })
+    }
+
+    /// Creates a password from a parsed C-string, which is zeroised afterwards
+    pub fn from_parsed_string(s: String) -> Result<Self, FixedStringError> {
+        Zeroizing::new(s).parse()
+    }
+
+    /// Returns the underlying byte array, including the NULL padding
+    pub const fn as_bytes(&self) -> &[u8; 9] {
+        &self.data
+    }
+
+    /// Returns the password as a byte slice, excluding NULL padding
+    pub fn as_str_bytes(&self) -> &[u8] {
+        let len = self.data.iter().position(|&b| b == 0).unwrap_or(9);
+        &self.data[..len]
+    }
+
+    /// Returns the password itself
+    pub fn as_str(&self) -> Result<&str, str::Utf8Error> {
+        str::from_utf8(self.as_str_bytes())
+    }
+
+    /// Returns the length of the password (excluding NULL padding)
+    pub fn len(&self) -> usize {
+        self.as_str_bytes().len()
+    }
+
+    /// Returns true if the password is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for Password {
+    fn drop(&mut self) {
+        self.data.zeroize();
+    }
+}
+
+impl fmt::Display for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<redacted>")
+    }
+}
+
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Password(<redacted>)")
+    }
+}
+
+impl From<&str> for Password {
+    fn from(s: &str) -> Self {
+        s.parse().expect("String too long for Password")
+    }
+}
+
+impl TryFrom<String> for Password {
+    type Error = FixedStringError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::from_parsed_string(s)
+    }
+}
+
+impl FromStr for Password {
+    type Err = FixedStringError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s.as_bytes())
+    }
+}
+
+impl AsRef<[u8]> for Password {
+    fn as_ref(&self) -> &[u8] {
+        self.as_str_bytes()
+    }
+}
+
+impl PartialEq<str> for Password {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == Ok(other)
+    }
+}
+
+impl PartialEq<&str> for Password {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == Ok(*other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_is_redacted_when_formatted() {
+        let password = Password::from("secret08");
+        assert_eq!(password.to_string(), "<redacted>");
+        assert_eq!(format!("{password:?}"), "Password(<redacted>)");
+        assert_eq!(format!("{:?}", Some(&password)), "Some(Password(<redacted>))");
+        assert_eq!(password.as_str(), Ok("secret08"));
+        assert_eq!(password, "secret08");
+    }
+
+    #[test]
+    fn test_password_is_at_most_eight_octets() {
+        assert_eq!(Password::new(b"12345678").unwrap().len(), 8);
+        assert_eq!(
+            "123456789".parse::<Password>(),
+            Err(FixedStringError::TooLong {
+                max_len: 8,
+                actual_len: 9
+            })
+        );
+        assert!(Password::default().is_empty());
+        assert_eq!(Password::from("pw").as_bytes(), b"pw\0\0\0\0\0\0\0");
+    }
+
+    #[test]
+    fn test_password_octets_are_zeroised_on_drop() {
+        let mut password = std::mem::ManuallyDrop::new(Password::from("secret"));
+        // SAFETY: only its plain octets are read after it has been dropped in place
+        unsafe { std::mem::ManuallyDrop::drop(&mut password) };
+        assert_eq!(password.data, [0u8; 9]);
+    }
+}
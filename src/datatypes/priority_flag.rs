@@ -39,6 +39,7 @@ use num_enum::TryFromPrimitive;
 #[derive(TryFromPrimitive)]
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PriorityFlag {
     /// Level 0 - Lowest priority (default)
     /// - **GSM**: Non-priority message (normal handling)
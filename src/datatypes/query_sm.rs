@@ -170,6 +170,7 @@ impl Decodable for QuerySm {
 /// Per SMPP v3.4 specification Section 4.8.2, Table 4-20
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MessageState {
     /// The message is in enroute state
     Enroute = 0x01,
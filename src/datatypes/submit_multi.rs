@@ -12,8 +12,8 @@ use thiserror::Error;
 
 // Import codec traits
 use crate::codec::{
-    CodecError, Decodable, Encodable, PduHeader, decode_cstring, decode_u8, encode_cstring,
-    encode_u8,
+    CodecError, Decodable, Encodable, PduHeader, decode_cstring, decode_u8, decode_u32,
+    encode_cstring, encode_u8,
 };
 
 /// Validation errors for SubmitMulti PDU
@@ -755,7 +755,7 @@ impl UnsuccessSmeAddress {
         })?;
 
         let destination_addr = decode_null_terminated_string(buf, "destination_addr")?;
-        let error_status_code = CommandStatus::try_from(buf.get_u32()).map_err(|_| {
+        let error_status_code = CommandStatus::try_from(decode_u32(buf)?).map_err(|_| {
             CodecError::FieldValidation {
                 field: "error_status_code",
                 reason: "Invalid CommandStatus value".to_string(),
@@ -1,16 +1,21 @@
+use crate::datatypes::message_body::MessageBody;
 use crate::datatypes::numeric_plan_indicator::NumericPlanIndicator;
 use crate::datatypes::priority_flag::PriorityFlag;
 use crate::datatypes::tlv::Tlv;
 use crate::datatypes::{
     AddressError, CommandId, CommandStatus, DataCoding, DataCodingError, DateTimeError,
-    DestinationAddr, EsmClass, EsmClassError, MessageId, ScheduleDeliveryTime, ServiceType,
+    DestinationAddr, EsmClass, EsmClassError, MessageId, NetworkRouting, ScheduleDeliveryTime,
+    ServiceType,
     ServiceTypeError, ShortMessage, SourceAddr, TypeOfNumber, ValidityPeriod,
 };
 use bytes::{Buf, BufMut, BytesMut};
 use std::io::Cursor;
 
 // Import codec traits
-use crate::codec::{CodecError, Decodable, Encodable, PduHeader};
+use crate::codec::{
+    CodecError, DecodeContext, Decodable, Encodable, PduHeader, check_message_id,
+    decode_c_octet_string,
+};
 use crate::macros::{builder_setters, encode_optional_tlvs, size_optional_tlvs};
 
 // SMPP v3.4 specification field length limits (excluding null terminator)
@@ -121,27 +126,27 @@ pub struct SubmitSm {
     /// source address of the message.
     pub source_port: Option<Tlv>,
 
-    /// Source Address Subunit TLV (0x020B): The subcomponent in the destination device for
+    /// Source Address Subunit TLV (0x000D): The subcomponent in the destination device for
     /// which the user data is intended.
     pub source_addr_submit: Option<Tlv>,
 
-    /// Destination Port TLV (0x020C): Indicates the application port number associated with
+    /// Destination Port TLV (0x020B): Indicates the application port number associated with
     /// the destination address of the message.
     pub destination_port: Option<Tlv>,
 
-    /// Destination Address Subunit TLV (0x020D): The subcomponent in the destination device
+    /// Destination Address Subunit TLV (0x0005): The subcomponent in the destination device
     /// for which the user data is intended.
     pub dest_addr_submit: Option<Tlv>,
 
-    /// SAR Message Reference Number TLV (0x020E): The reference number for a particular
+    /// SAR Message Reference Number TLV (0x020C): The reference number for a particular
     /// concatenated short message.
     pub sar_msg_ref_num: Option<Tlv>,
 
-    /// SAR Total Segments TLV (0x020F): Indicates the total number of short messages within
+    /// SAR Total Segments TLV (0x020E): Indicates the total number of short messages within
     /// the concatenated short message.
     pub sar_total_segments: Option<Tlv>,
 
-    /// SAR Segment Sequence Number TLV (0x0210): Indicates the sequence number of a particular
+    /// SAR Segment Sequence Number TLV (0x020F): Indicates the sequence number of a particular
     /// short message within the concatenated short message.
     pub sar_segment_seqnum: Option<Tlv>,
 
@@ -198,7 +203,7 @@ pub struct SubmitSm {
     /// short message is received.
     pub alert_on_msg_delivery: Option<Tlv>,
 
-    /// Language Indicator TLV (0x000D): Indicates the language of the short message.
+    /// Language Indicator TLV (0x020D): Indicates the language of the short message.
     pub language_indicator: Option<Tlv>,
 
     /// ITS Reply Type TLV (0x1380): Indicates the MS user's reply method to an ITS session
@@ -211,6 +216,9 @@ pub struct SubmitSm {
     /// USSD Service Operation TLV (0x0501): Indicates the USSD service operation when
     /// applicable.
     pub ussd_service_op: Option<Tlv>,
+
+    /// SMPP v5.0 billing, network routing and number portability TLVs
+    pub network_routing: NetworkRouting,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -282,13 +290,21 @@ impl SubmitSm {
         // Validate ESM class structure (mode/type combinations)
         self.esm_class.validate()?;
 
-        // Validate message text compatibility with data coding scheme
-        if !self.short_message.is_empty() {
-            if let Ok(message_text) = self.short_message.as_str() {
-                self.data_coding
-                    .validate_text(message_text)
-                    .map_err(|_| SubmitSmValidationError::MessageTextIncompatible)?;
+        // Validate message text compatibility with data coding scheme; a user
+        // data header is binary and binary codings carry no text at all
+        let text = match self.short_message.as_bytes() {
+            [udh_len, rest @ ..] if self.esm_class.has_udhi() => {
+                rest.get(usize::from(*udh_len)..).unwrap_or_default()
             }
+            bytes => bytes,
+        };
+        if !text.is_empty()
+            && !self.data_coding.is_binary()
+            && let Ok(message_text) = std::str::from_utf8(text)
+        {
+            self.data_coding
+                .validate_text(message_text)
+                .map_err(|_| SubmitSmValidationError::MessageTextIncompatible)?;
         }
 
         // Note: Service type, schedule delivery time, and validity period are
@@ -353,6 +369,7 @@ pub struct SubmitSmBuilder {
     its_reply_type: Option<Tlv>,
     its_session_info: Option<Tlv>,
     ussd_service_op: Option<Tlv>,
+    network_routing: NetworkRouting,
 }
 
 impl Default for SubmitSmBuilder {
@@ -411,6 +428,7 @@ impl SubmitSmBuilder {
             its_reply_type: None,
             its_session_info: None,
             ussd_service_op: None,
+            network_routing: NetworkRouting::default(),
         }
     }
 
@@ -466,6 +484,12 @@ impl SubmitSmBuilder {
         self
     }
 
+    /// Billing and routing TLVs; most of these require an SMPP v5.0 session
+    pub fn network_routing(mut self, network_routing: NetworkRouting) -> Self {
+        self.network_routing = network_routing;
+        self
+    }
+
     /// Build the SubmitSm, performing validation and calculating sm_length automatically
     pub fn build(mut self) -> Result<SubmitSm, SubmitSmValidationError> {
         // Auto-calculate sm_length from short_message
@@ -519,6 +543,7 @@ impl SubmitSmBuilder {
             its_reply_type: self.its_reply_type,
             its_session_info: self.its_session_info,
             ussd_service_op: self.ussd_service_op,
+            network_routing: self.network_routing,
         };
 
         // Validate before returning
@@ -630,6 +655,7 @@ impl Encodable for SubmitSm {
             its_session_info,
             ussd_service_op
         );
+        self.network_routing.encode(buf)?;
 
         Ok(())
     }
@@ -687,130 +713,44 @@ impl Encodable for SubmitSm {
             its_session_info,
             ussd_service_op
         );
+        size += self.network_routing.encoded_size();
 
         size
     }
 }
 
 impl Decodable for SubmitSm {
-    #[allow(clippy::unnecessary_fallible_conversions)]
     fn decode(header: PduHeader, buf: &mut Cursor<&[u8]>) -> Result<Self, CodecError> {
-        Self::validate_header(&header)?;
-
-        // Decode mandatory fields in order
-
-        // service_type (null-terminated string, max 5 chars + null)
-        let service_type = Self::read_c_string(buf, 6, "service_type")?;
-        let service_type = ServiceType::try_from(service_type.as_str()).map_err(|e| {
-            CodecError::FieldValidation {
-                field: "service_type",
-                reason: e.to_string(),
-            }
-        })?;
-
-        let source_addr_ton =
-            TypeOfNumber::try_from(buf.get_u8()).map_err(|_| CodecError::FieldValidation {
-                field: "source_addr_ton",
-                reason: "Invalid type of number".to_string(),
-            })?;
-
-        let source_addr_npi = NumericPlanIndicator::try_from(buf.get_u8()).map_err(|_| {
-            CodecError::FieldValidation {
-                field: "source_addr_npi",
-                reason: "Invalid numeric plan indicator".to_string(),
-            }
-        })?;
-
-        // source_addr (null-terminated string, max 20 chars + null)
-        let source_addr_str = Self::read_c_string(buf, 21, "source_addr")?;
-        let source_addr = SourceAddr::new(&source_addr_str, source_addr_ton).map_err(|e| {
-            CodecError::FieldValidation {
-                field: "source_addr",
-                reason: e.to_string(),
-            }
-        })?;
+        Self::decode_with_context(header, buf, &mut DecodeContext::strict())
+    }
 
-        let dest_addr_ton =
-            TypeOfNumber::try_from(buf.get_u8()).map_err(|_| CodecError::FieldValidation {
-                field: "dest_addr_ton",
-                reason: "Invalid type of number".to_string(),
-            })?;
+    fn decode_with_context(
+        header: PduHeader,
+        buf: &mut Cursor<&[u8]>,
+        ctx: &mut DecodeContext,
+    ) -> Result<Self, CodecError> {
+        Self::validate_header(&header)?;
 
-        let dest_addr_npi = NumericPlanIndicator::try_from(buf.get_u8()).map_err(|_| {
-            CodecError::FieldValidation {
-                field: "dest_addr_npi",
-                reason: "Invalid numeric plan indicator".to_string(),
-            }
+        let body = MessageBody::decode(buf, ctx, |priority_flag, ctx| {
+            ctx.recover(
+                PriorityFlag::try_from(priority_flag).map_err(|e| CodecError::FieldValidation {
+                    field: "priority_flag",
+                    reason: format!("Invalid priority flag {:#04x}; treated as level 0", e.number),
+                }),
+                || PriorityFlag::Level0,
+            )
         })?;
-
-        // destination_addr (null-terminated string, max 20 chars + null)
-        let dest_addr_str = Self::read_c_string(buf, 21, "destination_addr")?;
-        let destination_addr =
-            DestinationAddr::new(&dest_addr_str, dest_addr_ton).map_err(|e| {
-                CodecError::FieldValidation {
-                    field: "destination_addr",
-                    reason: e.to_string(),
-                }
-            })?;
-
-        let esm_class = EsmClass::from(buf.get_u8());
-
-        let protocol_id = buf.get_u8();
-
-        let priority_flag =
-            PriorityFlag::try_from(buf.get_u8()).map_err(|_| CodecError::FieldValidation {
-                field: "priority_flag",
-                reason: "Invalid priority flag".to_string(),
-            })?;
-
-        // schedule_delivery_time (null-terminated string, max 16 chars + null)
-        let schedule_time_str = Self::read_c_string(buf, 17, "schedule_delivery_time")?;
-        let schedule_delivery_time = ScheduleDeliveryTime::try_from(schedule_time_str.as_str())
-            .map_err(|e| CodecError::FieldValidation {
-                field: "schedule_delivery_time",
-                reason: e.to_string(),
-            })?;
-
-        // validity_period (null-terminated string, max 16 chars + null)
-        let validity_str = Self::read_c_string(buf, 17, "validity_period")?;
-        let validity_period = ValidityPeriod::from(validity_str.as_str());
-
-        let registered_delivery = buf.get_u8();
-        let replace_if_present_flag = buf.get_u8();
-
-        let data_coding = DataCoding::from(buf.get_u8());
-
-        let sm_default_msg_id = buf.get_u8();
-        let sm_length = buf.get_u8();
-
-        // short_message (binary data, not null-terminated)
-        if buf.remaining() < sm_length as usize {
-            return Err(CodecError::Incomplete);
-        }
-        let mut short_message_bytes = vec![0u8; sm_length as usize];
-        buf.copy_to_slice(&mut short_message_bytes);
-        let short_message =
-            ShortMessage::new(&short_message_bytes).map_err(|e| CodecError::FieldValidation {
-                field: "short_message",
-                reason: e.to_string(),
-            })?;
-
-        // Parse optional TLV parameters
-        let mut tlvs = std::collections::HashMap::new();
-        while buf.remaining() >= 4 {
-            let tlv = Tlv::decode(buf)?;
-            tlvs.insert(tlv.tag, tlv);
-        }
+        let mut tlvs = body.tlvs;
 
         // Extract specific TLVs
         let user_message_reference = tlvs.remove(&0x0204);
         let source_port = tlvs.remove(&0x020A);
-        let source_addr_submit = tlvs.remove(&0x020B);
-        let destination_port = tlvs.remove(&0x020C);
-        let dest_addr_submit = tlvs.remove(&0x020D);
-        let sar_msg_ref_num = tlvs.remove(&0x020E);
-        let sar_total_segments = tlvs.remove(&0x020F);
-        let sar_segment_seqnum = tlvs.remove(&0x0210);
+        let source_addr_submit = tlvs.remove(&0x000D);
+        let destination_port = tlvs.remove(&0x020B);
+        let dest_addr_submit = tlvs.remove(&0x0005);
+        let sar_msg_ref_num = tlvs.remove(&0x020C);
+        let sar_total_segments = tlvs.remove(&0x020E);
+        let sar_segment_seqnum = tlvs.remove(&0x020F);
         let more_messages_to_send = tlvs.remove(&0x0426);
         let payload_type = tlvs.remove(&0x0019);
         let message_payload = tlvs.remove(&0x0424);
@@ -826,7 +766,7 @@ impl Decodable for SubmitSm {
         let ms_msg_wait_facilities = tlvs.remove(&0x1205);
         let number_of_messages = tlvs.remove(&0x0205);
         let alert_on_msg_delivery = tlvs.remove(&0x130C);
-        let language_indicator = tlvs.remove(&0x000D);
+        let language_indicator = tlvs.remove(&0x020D);
         let its_reply_type = tlvs.remove(&0x1380);
         let its_session_info = tlvs.remove(&0x1383);
         let ussd_service_op = tlvs.remove(&0x0501);
@@ -834,24 +774,24 @@ impl Decodable for SubmitSm {
         let submit_sm = Self {
             command_status: header.command_status,
             sequence_number: header.sequence_number,
-            service_type,
-            source_addr_ton,
-            source_addr_npi,
-            source_addr,
-            dest_addr_ton,
-            dest_addr_npi,
-            destination_addr,
-            esm_class,
-            protocol_id,
-            priority_flag,
-            schedule_delivery_time,
-            validity_period,
-            registered_delivery,
-            replace_if_present_flag,
-            data_coding,
-            sm_default_msg_id,
-            sm_length,
-            short_message,
+            service_type: body.service_type,
+            source_addr_ton: body.source_addr_ton,
+            source_addr_npi: body.source_addr_npi,
+            source_addr: body.source_addr,
+            dest_addr_ton: body.dest_addr_ton,
+            dest_addr_npi: body.dest_addr_npi,
+            destination_addr: body.destination_addr,
+            esm_class: body.esm_class,
+            protocol_id: body.protocol_id,
+            priority_flag: body.priority_flag,
+            schedule_delivery_time: body.schedule_delivery_time,
+            validity_period: body.validity_period,
+            registered_delivery: body.registered_delivery,
+            replace_if_present_flag: body.replace_if_present_flag,
+            data_coding: body.data_coding,
+            sm_default_msg_id: body.sm_default_msg_id,
+            sm_length: body.sm_length,
+            short_message: body.short_message,
             user_message_reference,
             source_port,
             source_addr_submit,
@@ -879,15 +819,19 @@ impl Decodable for SubmitSm {
             its_reply_type,
             its_session_info,
             ussd_service_op,
+            network_routing: body.network_routing,
         };
 
         // Validate the decoded PDU
-        submit_sm
-            .validate()
-            .map_err(|e| CodecError::FieldValidation {
-                field: "submit_sm",
-                reason: e.to_string(),
-            })?;
+        ctx.recover(
+            submit_sm
+                .validate()
+                .map_err(|e| CodecError::FieldValidation {
+                    field: "submit_sm",
+                    reason: e.to_string(),
+                }),
+            || (),
+        )?;
 
         Ok(submit_sm)
     }
@@ -897,39 +841,6 @@ impl Decodable for SubmitSm {
     }
 }
 
-impl SubmitSm {
-    /// Helper function to read null-terminated C strings with length limits
-    fn read_c_string(
-        buf: &mut Cursor<&[u8]>,
-        max_len: usize,
-        field_name: &'static str,
-    ) -> Result<String, CodecError> {
-        let mut string_bytes = Vec::new();
-        let mut bytes_read = 0;
-
-        while bytes_read < max_len {
-            if buf.remaining() == 0 {
-                return Err(CodecError::Incomplete);
-            }
-
-            let byte = buf.get_u8();
-            bytes_read += 1;
-
-            if byte == 0 {
-                // Found null terminator
-                break;
-            }
-
-            string_bytes.push(byte);
-        }
-
-        String::from_utf8(string_bytes).map_err(|e| CodecError::Utf8Error {
-            field: field_name,
-            source: e,
-        })
-    }
-}
-
 // Also implement codec for SubmitSmResponse
 impl Encodable for SubmitSmResponse {
     fn encode(&self, buf: &mut BytesMut) -> Result<(), CodecError> {
@@ -956,12 +867,33 @@ impl Encodable for SubmitSmResponse {
 
 impl Decodable for SubmitSmResponse {
     fn decode(header: PduHeader, buf: &mut Cursor<&[u8]>) -> Result<Self, CodecError> {
+        Self::decode_with_context(header, buf, &mut DecodeContext::strict())
+    }
+
+    fn decode_with_context(
+        header: PduHeader,
+        buf: &mut Cursor<&[u8]>,
+        ctx: &mut DecodeContext,
+    ) -> Result<Self, CodecError> {
         Self::validate_header(&header)?;
 
+        // SMPP v3.4 omits the body entirely when command_status is non-zero,
+        // whereas v3.3 always returns a (NULL) message_id
+        if !buf.has_remaining() && header.command_status != CommandStatus::Ok {
+            return Ok(Self {
+                command_status: header.command_status,
+                sequence_number: header.sequence_number,
+                message_id: MessageId::default(),
+            });
+        }
+
         // message_id (null-terminated string, max 64 chars + null)
-        let message_id_str = Self::read_c_string(buf, 65, "message_id")?;
+        let message_id_str = decode_c_octet_string(buf, 65, "message_id", ctx)?;
+        check_message_id(&message_id_str, "message_id", ctx)?;
         let message_id = MessageId::from(message_id_str.as_str());
 
+        ctx.check_optional_parameters(buf)?;
+
         Ok(Self {
             command_status: header.command_status,
             sequence_number: header.sequence_number,
@@ -974,42 +906,10 @@ impl Decodable for SubmitSmResponse {
     }
 }
 
-impl SubmitSmResponse {
-    /// Helper function to read null-terminated C strings with length limits
-    fn read_c_string(
-        buf: &mut Cursor<&[u8]>,
-        max_len: usize,
-        field_name: &'static str,
-    ) -> Result<String, CodecError> {
-        let mut string_bytes = Vec::new();
-        let mut bytes_read = 0;
-
-        while bytes_read < max_len {
-            if buf.remaining() == 0 {
-                return Err(CodecError::Incomplete);
-            }
-
-            let byte = buf.get_u8();
-            bytes_read += 1;
-
-            if byte == 0 {
-                // Found null terminator
-                break;
-            }
-
-            string_bytes.push(byte);
-        }
-
-        String::from_utf8(string_bytes).map_err(|e| CodecError::Utf8Error {
-            field: field_name,
-            source: e,
-        })
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::datatypes::tags;
     use bytes::Bytes;
 
     #[test]
@@ -1064,6 +964,7 @@ mod tests {
             its_reply_type: None,
             its_session_info: None,
             ussd_service_op: None,
+            network_routing: NetworkRouting::default(),
         };
 
         let bytes = submit_sm.to_bytes();
@@ -1152,6 +1053,7 @@ mod tests {
             its_reply_type: None,
             its_session_info: None,
             ussd_service_op: None,
+            network_routing: NetworkRouting::default(),
         };
 
         let bytes = submit_sm.to_bytes();
@@ -1280,6 +1182,7 @@ mod tests {
             its_reply_type: None,
             its_session_info: None,
             ussd_service_op: None,
+            network_routing: NetworkRouting::default(),
         };
 
         let _ = submit_sm.to_bytes(); // Should panic
@@ -1371,8 +1274,154 @@ mod tests {
             its_reply_type: None,
             its_session_info: None,
             ussd_service_op: None,
+            network_routing: NetworkRouting::default(),
         };
 
         let _ = submit_sm.to_bytes(); // Should panic
     }
+
+    #[test]
+    fn submit_sm_validation_skips_user_data_header() {
+        let udh_and_text = [0x05, 0x00, 0x03, 0x01, 0x02, 0x01, b'H', b'i'];
+        let mut submit_sm = SubmitSm::builder()
+            .esm_class(EsmClass::default().with_udhi())
+            .build()
+            .unwrap();
+        submit_sm.short_message = ShortMessage::new(&udh_and_text).unwrap();
+        submit_sm.sm_length = submit_sm.short_message.len();
+        assert!(submit_sm.validate().is_ok());
+
+        // Without udhi the header octets are taken as text
+        submit_sm.esm_class = EsmClass::default();
+        assert!(submit_sm.validate().is_err());
+
+        submit_sm.data_coding = DataCoding::Binary;
+        assert!(submit_sm.validate().is_ok());
+    }
+
+    #[test]
+    fn submit_sm_sar_parameters_use_their_specification_tags() {
+        let mut submit_sm = SubmitSm::builder()
+            .source_addr("1234")
+            .destination_addr("5678")
+            .short_message("Hi")
+            .build()
+            .unwrap();
+        submit_sm.sar_msg_ref_num =
+            Some(Tlv::new(tags::SAR_MSG_REF_NUM, vec![0x12, 0x34]).unwrap());
+        submit_sm.sar_total_segments = Some(Tlv::new(tags::SAR_TOTAL_SEGMENTS, vec![2]).unwrap());
+        submit_sm.sar_segment_seqnum = Some(Tlv::new(tags::SAR_SEGMENT_SEQNUM, vec![1]).unwrap());
+
+        let pdu = submit_sm.to_bytes();
+        let tlvs = [
+            0x02, 0x0C, 0x00, 0x02, 0x12, 0x34, // sar_msg_ref_num
+            0x02, 0x0E, 0x00, 0x01, 0x02, // sar_total_segments
+            0x02, 0x0F, 0x00, 0x01, 0x01, // sar_segment_seqnum
+        ];
+        assert!(pdu.ends_with(&tlvs), "{pdu:02X?}");
+
+        let decoded = decode_with_policy(&pdu, crate::codec::DecodePolicy::Strict).unwrap();
+        let crate::codec::Frame::SubmitSm(decoded) = decoded.frame else {
+            panic!("expected submit_sm");
+        };
+        assert_eq!(decoded.sar_msg_ref_num, submit_sm.sar_msg_ref_num);
+        assert_eq!(decoded.sar_total_segments, submit_sm.sar_total_segments);
+        assert_eq!(decoded.sar_segment_seqnum, submit_sm.sar_segment_seqnum);
+    }
+
+    /// Build a raw submit_sm PDU with the given quirky field values
+    fn raw_submit_sm(
+        source_ton: u8,
+        source_addr: &[u8],
+        priority_flag: u8,
+        sm_length: u8,
+        short_message: &[u8],
+    ) -> Vec<u8> {
+        let mut body = vec![0u8]; // service_type
+        body.extend_from_slice(&[source_ton, 0x01]);
+        body.extend_from_slice(source_addr);
+        body.extend_from_slice(&[0x01, 0x01]);
+        body.extend_from_slice(b"0987654321\0");
+        body.extend_from_slice(&[0x00, 0x00, priority_flag]);
+        body.extend_from_slice(&[0x00, 0x00]); // schedule_delivery_time, validity_period
+        body.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, sm_length]);
+        body.extend_from_slice(short_message);
+
+        let mut pdu = Vec::new();
+        pdu.extend_from_slice(&((16 + body.len()) as u32).to_be_bytes());
+        pdu.extend_from_slice(&(CommandId::SubmitSm as u32).to_be_bytes());
+        pdu.extend_from_slice(&0u32.to_be_bytes());
+        pdu.extend_from_slice(&7u32.to_be_bytes());
+        pdu.extend_from_slice(&body);
+        pdu
+    }
+
+    fn decode_with_policy(
+        pdu: &[u8],
+        policy: crate::codec::DecodePolicy,
+    ) -> Result<crate::codec::DecodedFrame, CodecError> {
+        let registry = crate::codec::PduRegistry::new().with_policy(policy);
+        let mut cursor = Cursor::new(pdu);
+        let header = PduHeader::decode(&mut cursor)?;
+        registry.decode_frame(header, &mut cursor)
+    }
+
+    #[test]
+    fn submit_sm_strict_rejects_non_conformant_fields() {
+        use crate::codec::DecodePolicy;
+
+        // Source address fills the field with no NUL terminator
+        let pdu = raw_submit_sm(0x01, b"12345678901234567890", 0, 2, b"Hi");
+        assert!(matches!(
+            decode_with_policy(&pdu, DecodePolicy::Strict),
+            Err(CodecError::FieldValidation { field: "source_addr", .. })
+        ));
+
+        // Reserved priority_flag value
+        let pdu = raw_submit_sm(0x01, b"1234567890\0", 9, 2, b"Hi");
+        assert!(matches!(
+            decode_with_policy(&pdu, DecodePolicy::Strict),
+            Err(CodecError::FieldValidation { field: "priority_flag", .. })
+        ));
+    }
+
+    #[test]
+    fn submit_sm_lenient_normalises_quirks() {
+        use crate::codec::{DecodePolicy, Frame};
+
+        // Unterminated source address, reserved priority and an sm_length that
+        // overruns the body
+        let pdu = raw_submit_sm(0x01, b"12345678901234567890", 9, 10, b"Hi");
+        let decoded = decode_with_policy(&pdu, DecodePolicy::Lenient).unwrap();
+
+        let Frame::SubmitSm(submit_sm) = decoded.frame else {
+            panic!("expected submit_sm");
+        };
+        assert_eq!(submit_sm.source_addr.as_str().unwrap(), "12345678901234567890");
+        assert_eq!(submit_sm.destination_addr.as_str().unwrap(), "0987654321");
+        assert_eq!(submit_sm.priority_flag, PriorityFlag::Level0);
+        assert_eq!(submit_sm.sm_length, 2);
+        assert_eq!(submit_sm.short_message.as_bytes(), b"Hi");
+
+        let fields: Vec<_> = decoded.warnings.iter().map(|w| w.field).collect();
+        assert_eq!(fields, vec!["source_addr", "priority_flag", "sm_length"]);
+    }
+
+    #[test]
+    fn submit_sm_lenient_falls_back_to_unknown_ton() {
+        use crate::codec::{DecodePolicy, Frame};
+
+        // Alphanumeric sender that is declared as an international number
+        let pdu = raw_submit_sm(0x01, b"ACME-Co\0", 0, 2, b"Hi");
+        assert!(decode_with_policy(&pdu, DecodePolicy::Strict).is_err());
+
+        let decoded = decode_with_policy(&pdu, DecodePolicy::Lenient).unwrap();
+        let Frame::SubmitSm(submit_sm) = decoded.frame else {
+            panic!("expected submit_sm");
+        };
+        assert_eq!(submit_sm.source_addr_ton, TypeOfNumber::Unknown);
+        assert_eq!(submit_sm.source_addr.as_str().unwrap(), "ACME-Co");
+        assert_eq!(decoded.warnings.len(), 1);
+        assert!(submit_sm.validate().is_ok());
+    }
 }
@@ -8,12 +8,14 @@ use crate::codec::{CodecError, Encodable};
 pub mod tags {
     pub const USER_MESSAGE_REFERENCE: u16 = 0x0204;
     pub const SOURCE_PORT: u16 = 0x020A;
-    pub const SOURCE_ADDR_SUBMIT: u16 = 0x020B;
-    pub const DESTINATION_PORT: u16 = 0x020C;
-    pub const DEST_ADDR_SUBMIT: u16 = 0x020D;
-    pub const SAR_MSG_REF_NUM: u16 = 0x020E;
-    pub const SAR_TOTAL_SEGMENTS: u16 = 0x020F;
-    pub const SAR_SEGMENT_SEQNUM: u16 = 0x0210;
+    /// `source_addr_subunit`
+    pub const SOURCE_ADDR_SUBMIT: u16 = 0x000D;
+    pub const DESTINATION_PORT: u16 = 0x020B;
+    /// `dest_addr_subunit`
+    pub const DEST_ADDR_SUBMIT: u16 = 0x0005;
+    pub const SAR_MSG_REF_NUM: u16 = 0x020C;
+    pub const SAR_TOTAL_SEGMENTS: u16 = 0x020E;
+    pub const SAR_SEGMENT_SEQNUM: u16 = 0x020F;
     pub const MORE_MESSAGES_TO_SEND: u16 = 0x0426;
     pub const PAYLOAD_TYPE: u16 = 0x0019;
     pub const MESSAGE_PAYLOAD: u16 = 0x0424;
@@ -29,20 +31,40 @@ pub mod tags {
     pub const MS_MSG_WAIT_FACILITIES: u16 = 0x1205;
     pub const NUMBER_OF_MESSAGES: u16 = 0x0205;
     pub const ALERT_ON_MSG_DELIVERY: u16 = 0x130C;
-    pub const LANGUAGE_INDICATOR: u16 = 0x000D;
+    pub const LANGUAGE_INDICATOR: u16 = 0x020D;
     pub const ITS_REPLY_TYPE: u16 = 0x1380;
     pub const ITS_SESSION_INFO: u16 = 0x1383;
     pub const USSD_SERVICE_OP: u16 = 0x0501;
 
-    // SMPP v5.0 TLV tags
-    pub const CONGESTION_STATE: u16 = 0x142C;
-    pub const BILLING_IDENTIFICATION: u16 = 0x0600;
-    pub const SOURCE_NETWORK_ID: u16 = 0x060E;
-    pub const DEST_NETWORK_ID: u16 = 0x060F;
-    pub const SOURCE_NODE_ID: u16 = 0x060C;
-    pub const DEST_NODE_ID: u16 = 0x060D;
+    pub const SOURCE_NETWORK_TYPE: u16 = 0x000E;
+    pub const DEST_NETWORK_TYPE: u16 = 0x0006;
+
+    // SMPP v5.0 TLV tags (Section 4.8.4)
+    pub const CONGESTION_STATE: u16 = 0x0428;
+    pub const BILLING_IDENTIFICATION: u16 = 0x060B;
+    pub const SOURCE_NETWORK_ID: u16 = 0x060D;
+    pub const DEST_NETWORK_ID: u16 = 0x060E;
+    pub const SOURCE_NODE_ID: u16 = 0x060F;
+    pub const DEST_NODE_ID: u16 = 0x0610;
+    pub const DEST_ADDR_NP_RESOLUTION: u16 = 0x0611;
+    pub const DEST_ADDR_NP_INFORMATION: u16 = 0x0612;
+    pub const DEST_ADDR_NP_COUNTRY: u16 = 0x0613;
+
+    // SMPP v5.0 broadcast TLV tags
+    pub const BROADCAST_CHANNEL_INDICATOR: u16 = 0x0600;
+    pub const BROADCAST_CONTENT_TYPE: u16 = 0x0601;
+    pub const BROADCAST_CONTENT_TYPE_INFO: u16 = 0x0602;
+    pub const BROADCAST_MESSAGE_CLASS: u16 = 0x0603;
+    pub const BROADCAST_REP_NUM: u16 = 0x0604;
+    pub const BROADCAST_FREQUENCY_INTERVAL: u16 = 0x0605;
+    pub const BROADCAST_AREA_IDENTIFIER: u16 = 0x0606;
+    pub const BROADCAST_ERROR_STATUS: u16 = 0x0607;
+    pub const BROADCAST_AREA_SUCCESS: u16 = 0x0608;
+    pub const BROADCAST_END_TIME: u16 = 0x0609;
+    pub const BROADCAST_SERVICE_GROUP: u16 = 0x060A;
 
     // Additional TLV tags for deliver_sm and other PDUs
+    #[deprecated(note = "0x0005 is dest_addr_subunit; use DEST_ADDR_SUBMIT")]
     pub const USER_DATA_HEADER: u16 = 0x0005;
     pub const NETWORK_ERROR_CODE: u16 = 0x0423;
     pub const DELIVERY_FAILURE_REASON: u16 = 0x0425;
@@ -52,6 +74,107 @@ pub mod tags {
     pub const MS_AVAILABILITY_STATUS: u16 = 0x0422;
     pub const RECEIPTED_MESSAGE_ID: u16 = 0x001E;
     pub const MESSAGE_STATE: u16 = 0x0427;
+
+    // Returned by the SMSC in bind_*_resp (Section 5.3.2.25)
+    pub const SC_INTERFACE_VERSION: u16 = 0x0210;
+
+    /// The protocol version that introduced a TLV tag
+    ///
+    /// Returns `None` for tags this crate does not know, such as
+    /// vendor-specific parameters, which are never rejected on version grounds.
+    pub fn introduced_in(tag: u16) -> Option<crate::datatypes::InterfaceVersion> {
+        use crate::datatypes::InterfaceVersion;
+
+        match tag {
+            CONGESTION_STATE | BILLING_IDENTIFICATION | SOURCE_NETWORK_ID | DEST_NETWORK_ID
+            | SOURCE_NODE_ID | DEST_NODE_ID | DEST_ADDR_NP_RESOLUTION
+            | DEST_ADDR_NP_INFORMATION | DEST_ADDR_NP_COUNTRY => Some(InterfaceVersion::SmppV50),
+            BROADCAST_CHANNEL_INDICATOR..=BROADCAST_SERVICE_GROUP => {
+                Some(InterfaceVersion::SmppV50)
+            }
+            USER_MESSAGE_REFERENCE | SOURCE_PORT | SOURCE_ADDR_SUBMIT | DESTINATION_PORT
+            | DEST_ADDR_SUBMIT | SAR_MSG_REF_NUM | SAR_TOTAL_SEGMENTS | SAR_SEGMENT_SEQNUM
+            | MORE_MESSAGES_TO_SEND | PAYLOAD_TYPE | MESSAGE_PAYLOAD | PRIVACY_INDICATOR
+            | CALLBACK_NUM | CALLBACK_NUM_PRES_IND | CALLBACK_NUM_ATAG | SOURCE_SUBADDRESS
+            | DEST_SUBADDRESS | DISPLAY_TIME | SMS_SIGNAL | MS_VALIDITY
+            | MS_MSG_WAIT_FACILITIES | NUMBER_OF_MESSAGES | ALERT_ON_MSG_DELIVERY
+            | LANGUAGE_INDICATOR | ITS_REPLY_TYPE | ITS_SESSION_INFO | USSD_SERVICE_OP
+            | NETWORK_ERROR_CODE | DELIVERY_FAILURE_REASON
+            | ADDITIONAL_STATUS_INFO_TEXT | DPF_RESULT | SET_DPF | MS_AVAILABILITY_STATUS
+            | RECEIPTED_MESSAGE_ID | MESSAGE_STATE | SOURCE_NETWORK_TYPE | DEST_NETWORK_TYPE => {
+                Some(InterfaceVersion::SmppV34)
+            }
+            _ => None,
+        }
+    }
+
+    /// The specification name of a TLV tag, e.g. `message_payload`
+    ///
+    /// Returns `None` for tags this crate does not know.
+    pub fn name(tag: u16) -> Option<&'static str> {
+        let name = match tag {
+            USER_MESSAGE_REFERENCE => "user_message_reference",
+            SOURCE_PORT => "source_port",
+            SOURCE_ADDR_SUBMIT => "source_addr_submit",
+            DESTINATION_PORT => "destination_port",
+            DEST_ADDR_SUBMIT => "dest_addr_submit",
+            SAR_MSG_REF_NUM => "sar_msg_ref_num",
+            SAR_TOTAL_SEGMENTS => "sar_total_segments",
+            SAR_SEGMENT_SEQNUM => "sar_segment_seqnum",
+            MORE_MESSAGES_TO_SEND => "more_messages_to_send",
+            PAYLOAD_TYPE => "payload_type",
+            MESSAGE_PAYLOAD => "message_payload",
+            PRIVACY_INDICATOR => "privacy_indicator",
+            CALLBACK_NUM => "callback_num",
+            CALLBACK_NUM_PRES_IND => "callback_num_pres_ind",
+            CALLBACK_NUM_ATAG => "callback_num_atag",
+            SOURCE_SUBADDRESS => "source_subaddress",
+            DEST_SUBADDRESS => "dest_subaddress",
+            DISPLAY_TIME => "display_time",
+            SMS_SIGNAL => "sms_signal",
+            MS_VALIDITY => "ms_validity",
+            MS_MSG_WAIT_FACILITIES => "ms_msg_wait_facilities",
+            NUMBER_OF_MESSAGES => "number_of_messages",
+            ALERT_ON_MSG_DELIVERY => "alert_on_msg_delivery",
+            LANGUAGE_INDICATOR => "language_indicator",
+            ITS_REPLY_TYPE => "its_reply_type",
+            ITS_SESSION_INFO => "its_session_info",
+            USSD_SERVICE_OP => "ussd_service_op",
+            SOURCE_NETWORK_TYPE => "source_network_type",
+            DEST_NETWORK_TYPE => "dest_network_type",
+            CONGESTION_STATE => "congestion_state",
+            BILLING_IDENTIFICATION => "billing_identification",
+            SOURCE_NETWORK_ID => "source_network_id",
+            DEST_NETWORK_ID => "dest_network_id",
+            SOURCE_NODE_ID => "source_node_id",
+            DEST_NODE_ID => "dest_node_id",
+            DEST_ADDR_NP_RESOLUTION => "dest_addr_np_resolution",
+            DEST_ADDR_NP_INFORMATION => "dest_addr_np_information",
+            DEST_ADDR_NP_COUNTRY => "dest_addr_np_country",
+            BROADCAST_CHANNEL_INDICATOR => "broadcast_channel_indicator",
+            BROADCAST_CONTENT_TYPE => "broadcast_content_type",
+            BROADCAST_CONTENT_TYPE_INFO => "broadcast_content_type_info",
+            BROADCAST_MESSAGE_CLASS => "broadcast_message_class",
+            BROADCAST_REP_NUM => "broadcast_rep_num",
+            BROADCAST_FREQUENCY_INTERVAL => "broadcast_frequency_interval",
+            BROADCAST_AREA_IDENTIFIER => "broadcast_area_identifier",
+            BROADCAST_ERROR_STATUS => "broadcast_error_status",
+            BROADCAST_AREA_SUCCESS => "broadcast_area_success",
+            BROADCAST_END_TIME => "broadcast_end_time",
+            BROADCAST_SERVICE_GROUP => "broadcast_service_group",
+            NETWORK_ERROR_CODE => "network_error_code",
+            DELIVERY_FAILURE_REASON => "delivery_failure_reason",
+            ADDITIONAL_STATUS_INFO_TEXT => "additional_status_info_text",
+            DPF_RESULT => "dpf_result",
+            SET_DPF => "set_dpf",
+            MS_AVAILABILITY_STATUS => "ms_availability_status",
+            RECEIPTED_MESSAGE_ID => "receipted_message_id",
+            MESSAGE_STATE => "message_state",
+            SC_INTERFACE_VERSION => "sc_interface_version",
+            _ => return None,
+        };
+        Some(name)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -82,6 +205,22 @@ impl Encodable for Tlv {
 }
 
 impl Tlv {
+    /// Create a TLV, deriving the length from the value
+    ///
+    /// Fails when the value is longer than the 65535 octets a TLV's length
+    /// field can describe.
+    pub fn new(tag: u16, value: impl Into<Bytes>) -> Result<Self, CodecError> {
+        let value = value.into();
+        let length = u16::try_from(value.len()).map_err(|_| {
+            CodecError::TlvError(format!(
+                "TLV {tag:#06x} value is {} octets, more than {}",
+                value.len(),
+                u16::MAX
+            ))
+        })?;
+        Ok(Self { tag, length, value })
+    }
+
     /// Convert TLV to bytes without PDU header (overrides Encodable::to_bytes)
     pub fn to_bytes(&self) -> Bytes {
         let mut buf = BytesMut::new();
@@ -301,4 +440,35 @@ mod tests {
         assert_eq!(bytes[4], 0x42); // first value byte
         assert_eq!(bytes[bytes.len() - 1], 0x42); // last value byte
     }
+
+    #[test]
+    fn new_rejects_values_longer_than_the_length_field() {
+        let tlv = Tlv::new(tags::MESSAGE_PAYLOAD, vec![0x42; 65535]).unwrap();
+        assert_eq!(tlv.length, 0xFFFF);
+
+        let error = Tlv::new(tags::MESSAGE_PAYLOAD, vec![0x42; 65536]).unwrap_err();
+        assert!(matches!(error, CodecError::TlvError(_)));
+    }
+
+    #[test]
+    fn tags_have_their_specification_values() {
+        assert_eq!(tags::SOURCE_PORT, 0x020A);
+        assert_eq!(tags::DESTINATION_PORT, 0x020B);
+        assert_eq!(tags::SAR_MSG_REF_NUM, 0x020C);
+        assert_eq!(tags::LANGUAGE_INDICATOR, 0x020D);
+        assert_eq!(tags::SAR_TOTAL_SEGMENTS, 0x020E);
+        assert_eq!(tags::SAR_SEGMENT_SEQNUM, 0x020F);
+        assert_eq!(tags::SC_INTERFACE_VERSION, 0x0210);
+        assert_eq!(tags::SOURCE_ADDR_SUBMIT, 0x000D);
+        assert_eq!(tags::DEST_ADDR_SUBMIT, 0x0005);
+    }
+
+    #[test]
+    fn tag_names_follow_the_specification() {
+        assert_eq!(tags::name(tags::MESSAGE_PAYLOAD), Some("message_payload"));
+        assert_eq!(tags::name(tags::SAR_SEGMENT_SEQNUM), Some("sar_segment_seqnum"));
+        assert_eq!(tags::name(tags::SC_INTERFACE_VERSION), Some("sc_interface_version"));
+        assert_eq!(tags::name(tags::BROADCAST_AREA_IDENTIFIER), Some("broadcast_area_identifier"));
+        assert_eq!(tags::name(0x1400), None);
+    }
 }
@@ -0,0 +1,977 @@
+// ABOUTME: Annotated breakdown of raw PDUs: each field's offset, octets, name and decoded value
+// ABOUTME: Malformed or truncated PDUs are laid out as far as possible and the failure located
+
+use crate::codec::{CodecError, DecodePolicy, Frame, PduHeader, PduRegistry};
+use crate::datatypes::{CommandId, DataCoding, InterfaceVersion, tags};
+use crate::layout::{self, DEST, Layout, Octet, Text};
+use std::fmt;
+use std::io::Cursor;
+use thiserror::Error;
+
+/// One field of an inspected PDU
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// Offset of the field's first octet from the start of the PDU
+    pub offset: usize,
+    /// The field's octets as they appear on the wire
+    pub bytes: Vec<u8>,
+    /// The field's name in the specification; TLVs are named after their tag
+    pub name: String,
+    /// The decoded value in readable form
+    pub value: String,
+}
+
+/// A problem found in an inspected PDU, located at the field it concerns
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    /// Offset of the octet the problem was found at
+    pub offset: usize,
+    /// The field the problem was found in
+    pub field: String,
+    /// What is wrong
+    pub message: String,
+}
+
+/// The annotated breakdown of a PDU produced by [`inspect`]
+///
+/// `fields` covers every octet given, in order, including any that could not
+/// be attributed to a field. `frame` is the PDU as [`PduRegistry`] decodes it;
+/// when decoding fails it is `None` and `failure` says where and why.
+#[derive(Debug)]
+pub struct Inspection {
+    /// The command, when the header names a known one
+    pub command_id: Option<CommandId>,
+    /// The PDU's fields in wire order
+    pub fields: Vec<Field>,
+    /// Violations that were tolerated, or that the decoder does not check
+    pub warnings: Vec<Issue>,
+    /// Where and why decoding failed
+    pub failure: Option<Issue>,
+    /// The decoded PDU
+    pub frame: Option<Frame>,
+}
+
+impl Inspection {
+    /// Whether the PDU decoded without any warnings
+    pub fn is_clean(&self) -> bool {
+        self.failure.is_none() && self.warnings.is_empty()
+    }
+
+    /// The first field with the given name
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+}
+
+impl fmt::Display for Inspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let command = match self.command_id {
+            Some(command_id) => command_id.name(),
+            None => "unknown command".to_string(),
+        };
+        let length: usize = self.fields.iter().map(|field| field.bytes.len()).sum();
+        writeln!(f, "{command}, {length} octets")?;
+
+        let width = self.fields.iter().map(|field| field.name.len()).max().unwrap_or(0).max(5);
+        writeln!(f, "offset  {:<23}  {:<width$}  value", "octets", "field")?;
+        for field in &self.fields {
+            // Eight octets per row keeps long fields readable
+            let mut rows = field.bytes.chunks(8).map(hex_row);
+            let first = rows.next().unwrap_or_default();
+            let line = format!(
+                "{:#06x}  {first:<23}  {:<width$}  {}",
+                field.offset, field.name, field.value
+            );
+            writeln!(f, "{}", line.trim_end())?;
+            for row in rows {
+                writeln!(f, "        {row}")?;
+            }
+        }
+
+        for warning in &self.warnings {
+            let Issue { offset, field, message } = warning;
+            writeln!(f, "warning at {offset:#06x} in {field}: {message}")?;
+        }
+        if let Some(failure) = &self.failure {
+            let Issue { offset, field, message } = failure;
+            writeln!(f, "error at {offset:#06x} in {field}: {message}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error parsing hex text with [`parse_hex`]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum HexError {
+    #[error("invalid hex digit '{found}' at position {position}")]
+    InvalidDigit { position: usize, found: char },
+
+    #[error("odd number of hex digits")]
+    OddLength,
+}
+
+/// Parse hex text as copied from logs or packet captures
+///
+/// Whitespace, `:`, `-` and `,` between octets are ignored, as is a `0x`
+/// prefix on any group of digits.
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, HexError> {
+    let mut digits = Vec::new();
+    let mut group_start = true;
+    let mut chars = text.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        if c.is_whitespace() || matches!(c, ':' | '-' | ',') {
+            group_start = true;
+            continue;
+        }
+        if group_start && c == '0' && matches!(chars.peek(), Some((_, 'x' | 'X'))) {
+            chars.next();
+            group_start = false;
+            continue;
+        }
+        group_start = false;
+        let digit = c.to_digit(16).ok_or(HexError::InvalidDigit { position, found: c })?;
+        digits.push(digit as u8);
+    }
+
+    if !digits.len().is_multiple_of(2) {
+        return Err(HexError::OddLength);
+    }
+    Ok(digits.chunks(2).map(|pair| (pair[0] << 4) | pair[1]).collect())
+}
+
+/// Inspect a PDU, decoding it as SMPP v5.0 so that every command is known
+pub fn inspect(pdu: &[u8]) -> Inspection {
+    inspect_as(pdu, InterfaceVersion::SmppV50)
+}
+
+/// Inspect a PDU as a session of the given version would decode it
+///
+/// The PDU is decoded strictly and, when that fails, leniently. A PDU that
+/// decodes leniently carries the tolerated violations as warnings; one that
+/// does not decode at all has a `failure` at the field decoding stopped in.
+pub fn inspect_as(pdu: &[u8], version: InterfaceVersion) -> Inspection {
+    let mut walker = Walker::new(pdu, version);
+    let mut warnings = Vec::new();
+
+    if let Ok(length) = walker.header() {
+        let length = length as usize;
+        if length > pdu.len() {
+            warnings.push(Issue {
+                offset: 0,
+                field: "command_length".to_string(),
+                message: format!(
+                    "command_length is {length} but only {} octets were given",
+                    pdu.len()
+                ),
+            });
+        } else if length >= PduHeader::SIZE {
+            walker.end = length;
+        }
+        if let Some(command_id) = walker.command_id {
+            if !version.supports_command(command_id) {
+                warnings.push(Issue {
+                    offset: 4,
+                    field: "command_id".to_string(),
+                    message: format!("{} is not part of {version:?}", command_id.name()),
+                });
+            }
+            let _ = layout::body(&mut walker, command_id).and_then(|()| walker.tlvs());
+        }
+    }
+
+    let (frame, failure) = match decode(pdu, version) {
+        Ok((frame, tolerated)) => {
+            for (field, message) in tolerated {
+                warnings.push(walker.locate_field(field, message));
+            }
+            (Some(frame), None)
+        }
+        Err(error) => (None, Some(walker.locate(&error))),
+    };
+    // The decoder accepted what the walk could not lay out, so it is only a warning
+    if failure.is_none()
+        && let Some(stopped) = walker.stopped.take()
+    {
+        warnings.push(stopped);
+    }
+
+    walker.finish();
+    Inspection {
+        command_id: walker.command_id,
+        fields: walker.fields,
+        warnings,
+        failure,
+        frame,
+    }
+}
+
+/// Violations tolerated to decode a PDU, by field
+type Tolerated = Vec<(&'static str, String)>;
+
+/// Decode through the registry, strictly first and then leniently
+///
+/// Returns the frame and the violations tolerated to decode it.
+fn decode(pdu: &[u8], version: InterfaceVersion) -> Result<(Frame, Tolerated), CodecError> {
+    let mut cursor = Cursor::new(pdu);
+    let header = PduHeader::decode(&mut cursor)?;
+
+    let strict = PduRegistry::for_version(version);
+    let strict_error = match strict.decode_frame(header.clone(), &mut cursor.clone()) {
+        Ok(decoded) => return Ok((decoded.frame, Vec::new())),
+        Err(error) => error,
+    };
+
+    let lenient = PduRegistry::for_version(version).with_policy(DecodePolicy::Lenient);
+    let decoded = lenient.decode_frame(header, &mut cursor)?;
+    let mut tolerated: Tolerated = decoded
+        .warnings
+        .into_iter()
+        .map(|warning| (warning.field, warning.reason))
+        .collect();
+    if tolerated.is_empty() {
+        let field = match &strict_error {
+            CodecError::FieldValidation { field, .. } => field,
+            CodecError::Utf8Error { field, .. } => field,
+            _ => "pdu",
+        };
+        tolerated.push((field, strict_error.to_string()));
+    }
+    Ok((decoded.frame, tolerated))
+}
+
+/// The walk through a PDU's layout stopped; the reason is in `Walker::stopped`
+struct Stop;
+
+type Step<T = ()> = Result<T, Stop>;
+
+/// Lays a PDU out field by field the way the registry's decoders read it
+///
+/// Values are described rather than validated, so the walk goes on past a
+/// field the decoder would reject; it only stops when the octets run out or
+/// the layout itself cannot be followed.
+struct Walker<'a> {
+    pdu: &'a [u8],
+    pos: usize,
+    end: usize,
+    command_id: Option<CommandId>,
+    status: u32,
+    fields: Vec<Field>,
+    /// Indices of fields holding a value the field does not allow
+    invalid: Vec<usize>,
+    /// Where the first TLV starts, and the first TLV the version does not define
+    first_tlv: Option<usize>,
+    gated_tlv: Option<usize>,
+    stopped: Option<Issue>,
+    version: InterfaceVersion,
+    data_coding: DataCoding,
+    udhi: bool,
+}
+
+impl<'a> Walker<'a> {
+    fn new(pdu: &'a [u8], version: InterfaceVersion) -> Self {
+        Self {
+            pdu,
+            pos: 0,
+            end: pdu.len(),
+            command_id: None,
+            status: 0,
+            fields: Vec::new(),
+            invalid: Vec::new(),
+            first_tlv: None,
+            gated_tlv: None,
+            stopped: None,
+            version,
+            data_coding: DataCoding::default(),
+            udhi: false,
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.end.saturating_sub(self.pos)
+    }
+
+    fn push(&mut self, name: &str, len: usize, value: describe::Described) {
+        let value = value.unwrap_or_else(|invalid| {
+            self.invalid.push(self.fields.len());
+            invalid
+        });
+        self.fields.push(Field {
+            offset: self.pos,
+            bytes: self.pdu[self.pos..self.pos + len].to_vec(),
+            name: name.to_string(),
+            value,
+        });
+        self.pos += len;
+    }
+
+    fn stop(&mut self, name: &str, message: String) -> Stop {
+        self.stopped = Some(Issue {
+            offset: self.pos,
+            field: name.to_string(),
+            message,
+        });
+        Stop
+    }
+
+    fn need(&mut self, name: &str, len: usize) -> Step {
+        let remaining = self.remaining();
+        if remaining < len {
+            let message = format!("needs {} but only {remaining} remain", describe::octets(len));
+            return Err(self.stop(name, message));
+        }
+        Ok(())
+    }
+
+    fn u8(&mut self, name: &str, describe: fn(u8) -> describe::Described) -> Step<u8> {
+        self.need(name, 1)?;
+        let value = self.pdu[self.pos];
+        self.push(name, 1, describe(value));
+        Ok(value)
+    }
+
+    fn word(&mut self, name: &str, describe: fn(u32) -> describe::Described) -> Step<u32> {
+        self.need(name, 4)?;
+        let octets = &self.pdu[self.pos..self.pos + 4];
+        let value = u32::from_be_bytes([octets[0], octets[1], octets[2], octets[3]]);
+        self.push(name, 4, describe(value));
+        Ok(value)
+    }
+
+    /// A message body decoded per the PDU's data_coding, any UDH shown apart
+    fn message(&self, body: &[u8]) -> String {
+        let udh_len = body.first().map(|&len| usize::from(len) + 1);
+        match udh_len {
+            Some(udh_len) if self.udhi && udh_len <= body.len() => format!(
+                "udh {} {}",
+                hex_row(&body[..udh_len]).replace(' ', ""),
+                describe::message(&body[udh_len..], self.data_coding)
+            ),
+            _ => describe::message(body, self.data_coding),
+        }
+    }
+
+    fn header(&mut self) -> Step<u32> {
+        let length = self.word("command_length", describe::command_length)?;
+        let command_id = self.word("command_id", describe::command_id)?;
+        self.command_id = CommandId::try_from(command_id).ok();
+        self.status = self.word("command_status", describe::command_status)?;
+        self.word("sequence_number", describe::sequence_number)?;
+        Ok(length)
+    }
+
+    /// Whatever follows the mandatory parameters, read as TLVs
+    fn tlvs(&mut self) -> Step {
+        while self.remaining() > 0 {
+            let remaining = self.remaining();
+            if remaining < 4 {
+                return Err(self.stop(
+                    "optional_parameters",
+                    format!("{} are too few for a TLV", describe::octets(remaining)),
+                ));
+            }
+            let at = self.pos;
+            let tag = u16::from_be_bytes([self.pdu[at], self.pdu[at + 1]]);
+            let len = usize::from(u16::from_be_bytes([self.pdu[at + 2], self.pdu[at + 3]]));
+            let name = self.tlv_name(tag);
+            if remaining - 4 < len {
+                return Err(self.stop(
+                    &name,
+                    format!(
+                        "TLV length is {len} but only {} follow",
+                        describe::octets(remaining - 4)
+                    ),
+                ));
+            }
+
+            self.first_tlv.get_or_insert(at);
+            if tags::introduced_in(tag).is_some_and(|introduced| introduced > self.version) {
+                self.gated_tlv.get_or_insert(at);
+            }
+            let value = self.tlv_value(tag, &self.pdu[at + 4..at + 4 + len]);
+            self.push(&name, 4 + len, Ok(value));
+        }
+        Ok(())
+    }
+
+    fn tlv_name(&self, tag: u16) -> String {
+        match tags::name(tag) {
+            Some(name) => name.to_string(),
+            None => format!("tlv {tag:#06x}"),
+        }
+    }
+
+    fn tlv_value(&self, tag: u16, value: &[u8]) -> String {
+        match (tag, value) {
+            (tags::MESSAGE_PAYLOAD, _) => self.message(value),
+            (tags::RECEIPTED_MESSAGE_ID | tags::ADDITIONAL_STATUS_INFO_TEXT, _) => {
+                let content = value.iter().position(|&b| b == 0).unwrap_or(value.len());
+                describe::text(&value[..content])
+            }
+            (tags::MESSAGE_STATE, &[state]) => describe::message_state(state).unwrap_or_else(|e| e),
+            (tags::SC_INTERFACE_VERSION, &[version]) => {
+                describe::interface_version(version).unwrap_or_else(|e| e)
+            }
+            (_, []) => "(empty)".to_string(),
+            (_, &[octet]) => octet.to_string(),
+            (_, &[high, low]) => u16::from_be_bytes([high, low]).to_string(),
+            (_, &[a, b, c, d]) => u32::from_be_bytes([a, b, c, d]).to_string(),
+            _ => hex_row(value).replace(' ', ""),
+        }
+    }
+
+    /// Locate a decoding error at the field it concerns
+    fn locate(&self, error: &CodecError) -> Issue {
+        let (offset, field) = match error {
+            CodecError::InvalidPduLength { .. } => (0, "command_length"),
+            CodecError::InvalidCommandId(_) => (4, "command_id"),
+            CodecError::InvalidCommandStatus(_) | CodecError::InvalidRequestStatus { .. } => {
+                (8, "command_status")
+            }
+            CodecError::ReservedSequenceNumber(_) => (12, "sequence_number"),
+            CodecError::FieldValidation { field, .. } | CodecError::Utf8Error { field, .. } => {
+                return self.locate_field(field, error.to_string());
+            }
+            _ => return self.at_stop(error.to_string()),
+        };
+        Issue {
+            offset,
+            field: field.to_string(),
+            message: error.to_string(),
+        }
+    }
+
+    /// Locate a problem the decoder reported against a named field
+    ///
+    /// Fields of repeated structures are named with an index prefix, so the
+    /// name matches on its last component; a field holding a value it does not
+    /// allow is preferred over an earlier one with the same name.
+    fn locate_field(&self, name: &str, message: String) -> Issue {
+        let matches = |field: &&Field| {
+            field.name == name || field.name.rsplit_once('.').is_some_and(|(_, last)| last == name)
+        };
+        let invalid = self.invalid.iter().map(|&index| &self.fields[index]).find(matches);
+        let found = invalid.or_else(|| self.fields.iter().find(matches));
+        let tlv = match name {
+            "optional_parameters" => self.gated_tlv.or(self.first_tlv),
+            _ => None,
+        };
+        match (found, tlv) {
+            (Some(field), _) => Issue {
+                offset: field.offset,
+                field: field.name.clone(),
+                message,
+            },
+            (None, Some(offset)) => Issue {
+                offset,
+                field: name.to_string(),
+                message,
+            },
+            (None, None) => self.at_stop(message),
+        }
+    }
+
+    /// Locate a problem where the walk stopped, or else at the end of the PDU
+    fn at_stop(&self, message: String) -> Issue {
+        match &self.stopped {
+            Some(stopped) => Issue {
+                offset: stopped.offset,
+                field: stopped.field.clone(),
+                message: format!("{message} ({})", stopped.message),
+            },
+            None => Issue {
+                offset: self.end,
+                field: "end of PDU".to_string(),
+                message,
+            },
+        }
+    }
+
+    /// Account for the octets the walk did not reach and any after the PDU
+    fn finish(&mut self) {
+        if self.pos < self.end {
+            let len = self.end - self.pos;
+            self.push("unparsed", len, Ok(describe::octets(len)));
+        }
+        if self.end < self.pdu.len() {
+            let len = self.pdu.len() - self.end;
+            let value = format!("{} after command_length", describe::octets(len));
+            self.push("trailing", len, Ok(value));
+        }
+    }
+}
+
+impl Layout for Walker<'_> {
+    type Error = Stop;
+
+    fn octet(&mut self, name: &str, kind: Octet) -> Step {
+        let describe = match kind {
+            Octet::Number => describe::number,
+            Octet::Ton => describe::ton,
+            Octet::Npi => describe::npi,
+            Octet::Priority => describe::priority_flag,
+            Octet::Version => describe::interface_version,
+            Octet::State => describe::message_state,
+            Octet::EsmClass => describe::esm_class,
+            Octet::RegisteredDelivery => describe::registered_delivery,
+            Octet::DataCoding => describe::data_coding,
+        };
+        let value = self.u8(name, describe)?;
+        match kind {
+            Octet::EsmClass => self.udhi = value & 0x40 != 0,
+            Octet::DataCoding => self.data_coding = DataCoding::from_byte(value),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn u16(&mut self, name: &str) -> Step {
+        self.need(name, 2)?;
+        let value = u16::from_be_bytes([self.pdu[self.pos], self.pdu[self.pos + 1]]);
+        self.push(name, 2, Ok(value.to_string()));
+        Ok(())
+    }
+
+    fn u32(&mut self, name: &str) -> Step {
+        self.word(name, describe::seconds).map(drop)
+    }
+
+    fn status(&mut self, name: &str) -> Step {
+        self.word(name, describe::command_status).map(drop)
+    }
+
+    /// A text without a NUL terminator is taken to end with the PDU
+    fn text(&mut self, name: &str, layout: Text) -> Step {
+        let window = &self.pdu[self.pos..self.end.max(self.pos)];
+        let nul = |limit: usize| window.iter().take(limit).position(|&b| b == 0);
+        let (content, len) = match layout {
+            Text::Fixed(width) => {
+                self.need(name, width)?;
+                (nul(width).unwrap_or(width), width)
+            }
+            Text::COctet(max) | Text::Padded(max) => match nul(max) {
+                Some(nul) => (nul, nul + 1),
+                None if window.len() < max => {
+                    return Err(self.stop(name, "the PDU ends before its NUL terminator".into()));
+                }
+                None => {
+                    return Err(self.stop(name, format!("no NUL terminator within {max} octets")));
+                }
+            },
+            Text::Bounded(max) => match nul(max) {
+                Some(nul) => (nul, nul + 1),
+                None if window.len() < max => {
+                    return Err(self.stop(name, "the PDU ends before its NUL terminator".into()));
+                }
+                None => (max, max),
+            },
+            Text::Terminated => match nul(window.len()) {
+                Some(nul) => (nul, nul + 1),
+                None => (window.len(), window.len()),
+            },
+        };
+        let len = match layout {
+            Text::Padded(width) => {
+                let padding = window[len..].iter().take(width.saturating_sub(len));
+                len + padding.take_while(|&&b| b == 0).count()
+            }
+            _ => len,
+        };
+        self.push(name, len, Ok(describe::text(&window[..content])));
+        Ok(())
+    }
+
+    /// Only an error response may leave the field out
+    fn optional_text(&mut self, name: &str, layout: Text) -> Step {
+        match self.remaining() > 0 || self.status == 0 {
+            true => self.text(name, layout),
+            false => Ok(()),
+        }
+    }
+
+    fn short_message(&mut self) -> Step {
+        let len = usize::from(self.u8("sm_length", describe::number)?);
+        let remaining = self.remaining();
+        if remaining < len {
+            return Err(self.stop(
+                "short_message",
+                format!("sm_length is {len} but only {} remain", describe::octets(remaining)),
+            ));
+        }
+        let value = self.message(&self.pdu[self.pos..self.pos + len]);
+        self.push("short_message", len, Ok(value));
+        Ok(())
+    }
+
+    fn area(&mut self) -> Step {
+        let len = usize::from(self.u8("broadcast_area_identifier_len", describe::number)?);
+        self.need("broadcast_area_identifier", len)?;
+        let area = hex_row(&self.pdu[self.pos..self.pos + len]).replace(' ', "");
+        self.push("broadcast_area_identifier", len, Ok(area));
+        Ok(())
+    }
+
+    fn dest_addresses(&mut self) -> Step {
+        let count = self.u8("number_of_dests", describe::number)?;
+        for index in 0..count {
+            let prefix = format!("dest_address[{index}]");
+            match self.u8(&format!("{prefix}.dest_flag"), describe::dest_flag)? {
+                1 => {
+                    let names = DEST.map(|name| format!("{prefix}.{name}"));
+                    self.address([&names[0], &names[1], &names[2]], Text::Terminated)?;
+                }
+                2 => self.text(&format!("{prefix}.dl_name"), Text::Terminated)?,
+                // Without a valid flag the layout of what follows is unknown
+                flag => {
+                    self.stopped = Some(Issue {
+                        offset: self.pos - 1,
+                        field: format!("{prefix}.dest_flag"),
+                        message: format!("dest_flag {flag} is neither 1 nor 2"),
+                    });
+                    return Err(Stop);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn unsuccess_smes(&mut self) -> Step {
+        let count = self.u8("no_unsuccess", describe::number)?;
+        for index in 0..count {
+            let names = DEST.map(|name| format!("unsuccess_sme[{index}].{name}"));
+            self.address([&names[0], &names[1], &names[2]], Text::Terminated)?;
+            self.status(&format!("unsuccess_sme[{index}].error_status_code"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Readable descriptions of field values
+///
+/// `Err` carries the description of a value the field does not allow.
+mod describe {
+    use crate::codec::{MAX_PDU_SIZE, PduHeader};
+    use crate::datatypes::{
+        CommandId, CommandStatus, DataCoding, EsmClass, InterfaceVersion, MessageState,
+        NumericPlanIndicator, PriorityFlag, TypeOfNumber,
+    };
+    use std::fmt::Debug;
+
+    pub type Described = Result<String, String>;
+
+    fn named<T: Debug, E>(value: Result<T, E>, raw: u8) -> Described {
+        match value {
+            Ok(value) => Ok(format!("{value:?} ({raw})")),
+            Err(_) => Err(format!("invalid ({raw})")),
+        }
+    }
+
+    pub fn octets(count: usize) -> String {
+        match count {
+            1 => "1 octet".to_string(),
+            _ => format!("{count} octets"),
+        }
+    }
+
+    pub fn number(value: u8) -> Described {
+        Ok(value.to_string())
+    }
+
+    pub fn text(value: &[u8]) -> String {
+        format!("{:?}", String::from_utf8_lossy(value))
+    }
+
+    pub fn message(body: &[u8], coding: DataCoding) -> String {
+        match coding {
+            DataCoding::Ucs2 | DataCoding::Ucs2WithClass(_) => {
+                let units: Vec<u16> = body
+                    .chunks(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]))
+                    .collect();
+                format!("{:?}", String::from_utf16_lossy(&units))
+            }
+            DataCoding::Latin1 => {
+                format!("{:?}", body.iter().map(|&b| char::from(b)).collect::<String>())
+            }
+            DataCoding::Binary | DataCoding::Custom(_) => super::hex_row(body).replace(' ', ""),
+            _ => text(body),
+        }
+    }
+
+    pub fn ton(value: u8) -> Described {
+        named(TypeOfNumber::try_from(value), value)
+    }
+
+    pub fn npi(value: u8) -> Described {
+        named(NumericPlanIndicator::try_from(value), value)
+    }
+
+    pub fn priority_flag(value: u8) -> Described {
+        named(PriorityFlag::try_from(value), value)
+    }
+
+    pub fn interface_version(value: u8) -> Described {
+        named(InterfaceVersion::try_from(value), value)
+    }
+
+    pub fn message_state(value: u8) -> Described {
+        named(MessageState::try_from(value), value)
+    }
+
+    pub fn dest_flag(value: u8) -> Described {
+        match value {
+            1 => Ok("1 (SME address)".to_string()),
+            2 => Ok("2 (distribution list)".to_string()),
+            _ => Err(format!("invalid ({value})")),
+        }
+    }
+
+    pub fn esm_class(value: u8) -> Described {
+        match EsmClass::from_byte(value) {
+            Ok(esm_class) => {
+                let mut parts = vec![
+                    format!("{:?} mode", esm_class.message_mode()),
+                    format!("{:?}", esm_class.message_type()),
+                ];
+                if esm_class.has_udhi() {
+                    parts.push("udhi".to_string());
+                }
+                if esm_class.has_reply_path() {
+                    parts.push("reply path".to_string());
+                }
+                Ok(format!("{value:#04x}: {}", parts.join(", ")))
+            }
+            Err(e) => Err(format!("{value:#04x}: {e}")),
+        }
+    }
+
+    pub fn registered_delivery(value: u8) -> Described {
+        let receipt = match value & 0x03 {
+            0 => "no receipt",
+            1 => "receipt on success or failure",
+            2 => "receipt on failure",
+            _ => "receipt on success",
+        };
+        Ok(format!("{value:#04x}: {receipt}"))
+    }
+
+    pub fn data_coding(value: u8) -> Described {
+        Ok(format!("{} ({value:#04x})", DataCoding::from_byte(value)))
+    }
+
+    pub fn command_length(value: u32) -> Described {
+        if (PduHeader::SIZE as u32..=MAX_PDU_SIZE).contains(&value) {
+            Ok(value.to_string())
+        } else {
+            Err(format!("invalid ({value})"))
+        }
+    }
+
+    pub fn command_id(value: u32) -> Described {
+        CommandId::try_from(value)
+            .map(|command_id| command_id.name())
+            .map_err(|_| format!("unknown ({value:#010x})"))
+    }
+
+    pub fn command_status(value: u32) -> Described {
+        CommandStatus::try_from(value)
+            .map(|status| format!("{status:?} ({value:#010x})"))
+            .map_err(|_| format!("unknown ({value:#010x})"))
+    }
+
+    pub fn sequence_number(value: u32) -> Described {
+        match value {
+            0 | u32::MAX => Err(format!("reserved ({value})")),
+            _ => Ok(value.to_string()),
+        }
+    }
+
+    pub fn seconds(value: u32) -> Described {
+        Ok(format!("{value} s"))
+    }
+}
+
+/// Octets as space-separated lower-case hex
+fn hex_row(octets: &[u8]) -> String {
+    octets.iter().map(|b| format!("{b:02x}")).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Encodable;
+    use crate::datatypes::{
+        BroadcastSm, CommandStatus, DestinationAddress, EsmClass, MessageId, NumericPlanIndicator,
+        PriorityFlag, ScheduleDeliveryTime, ServiceType, ShortMessage, SourceAddr, SubmitMulti,
+        SubmitMultiResponse, SubmitSm, Tlv, TypeOfNumber, UnsuccessSmeAddress, ValidityPeriod,
+    };
+
+    fn submit_sm() -> Vec<u8> {
+        let mut submit = SubmitSm::builder()
+            .sequence_number(7)
+            .source_addr("1234")
+            .destination_addr("5678")
+            .short_message("Hello")
+            .build()
+            .unwrap();
+        submit.user_message_reference =
+            Some(Tlv::new(tags::USER_MESSAGE_REFERENCE, vec![0, 42]).unwrap());
+        submit.to_bytes().to_vec()
+    }
+
+    /// Rewrite command_length to match a cut-down PDU
+    fn with_length(pdu: &[u8]) -> Vec<u8> {
+        let length = (pdu.len() as u32).to_be_bytes();
+        let mut pdu = pdu.to_vec();
+        pdu[..4].copy_from_slice(&length);
+        pdu
+    }
+
+    #[test]
+    fn test_well_formed_pdu_is_laid_out_field_by_field() {
+        let pdu = submit_sm();
+        let inspection = inspect(&pdu);
+
+        assert!(inspection.is_clean(), "{inspection}");
+        assert!(matches!(inspection.frame, Some(Frame::SubmitSm(_))));
+        assert_eq!(inspection.command_id, Some(CommandId::SubmitSm));
+        assert_eq!(inspection.field("command_id").unwrap().value, "submit_sm");
+        assert_eq!(inspection.field("source_addr").unwrap().value, "\"1234\"");
+        assert_eq!(inspection.field("short_message").unwrap().value, "\"Hello\"");
+
+        let tlv = inspection.field("user_message_reference").unwrap();
+        assert_eq!(tlv.offset, pdu.len() - 6);
+        assert_eq!(tlv.bytes, [0x02, 0x04, 0x00, 0x02, 0x00, 0x2A]);
+        assert_eq!(tlv.value, "42");
+
+        // The fields account for every octet, in order
+        let mut offset = 0;
+        for field in &inspection.fields {
+            assert_eq!(field.offset, offset, "{}", field.name);
+            offset += field.bytes.len();
+        }
+        assert_eq!(offset, pdu.len());
+    }
+
+    #[test]
+    fn test_truncated_pdu_reports_where_decoding_failed() {
+        let pdu = submit_sm();
+        // Header, service_type, source address, destination ton/npi and two address octets
+        let inspection = inspect(&pdu[..28]);
+
+        let failure = inspection.failure.as_ref().unwrap();
+        assert_eq!((failure.offset, failure.field.as_str()), (26, "destination_addr"));
+        assert!(inspection.frame.is_none());
+        assert_eq!(inspection.warnings[0].field, "command_length");
+        assert_eq!(inspection.fields.last().unwrap().name, "unparsed");
+
+        let text = inspection.to_string();
+        assert!(text.contains("error at 0x001a in destination_addr"), "{text}");
+
+        let inspection = inspect(&pdu[..10]);
+        let failure = inspection.failure.unwrap();
+        assert_eq!((failure.offset, failure.field.as_str()), (8, "command_status"));
+    }
+
+    #[test]
+    fn test_invalid_field_is_located() {
+        let mut pdu = submit_sm();
+        pdu[17] = 9; // source_addr_ton
+
+        let inspection = inspect(&pdu);
+        let issue = inspection.failure.as_ref().or(inspection.warnings.first()).unwrap();
+        assert_eq!(issue.offset, 17, "{inspection}");
+        assert_eq!(inspection.field("source_addr_ton").unwrap().value, "invalid (9)");
+
+        // An unknown command_id stops the walk at the header
+        let mut pdu = submit_sm();
+        pdu[7] = 0x77;
+        let failure = inspect(&pdu).failure.unwrap();
+        assert_eq!((failure.offset, failure.field.as_str()), (4, "command_id"));
+    }
+
+    #[test]
+    fn test_bind_response_names_sc_interface_version() {
+        let pdu = parse_hex(
+            "0x0000001a 0x80000002 00000000 00000001 \
+             53:4d:53:43:00 0210 0001 34",
+        )
+        .unwrap();
+        let inspection = inspect(&pdu);
+
+        assert!(inspection.is_clean(), "{inspection}");
+        assert_eq!(inspection.field("system_id").unwrap().value, "\"SMSC\"");
+        assert_eq!(inspection.field("sc_interface_version").unwrap().value, "SmppV34 (52)");
+    }
+
+    #[test]
+    fn test_hex_parsing() {
+        assert_eq!(parse_hex("00 0a\n0B-ff"), Ok(vec![0x00, 0x0A, 0x0B, 0xFF]));
+        assert_eq!(parse_hex("0x10x2"), Err(HexError::InvalidDigit { position: 4, found: 'x' }));
+        assert_eq!(parse_hex("abc"), Err(HexError::OddLength));
+    }
+
+    #[test]
+    fn test_malformed_pdus_never_panic() {
+        let destinations = vec![
+            DestinationAddress::sme_address(
+                TypeOfNumber::Unknown,
+                NumericPlanIndicator::Unknown,
+                "1",
+            )
+            .unwrap(),
+            DestinationAddress::distribution_list("friends").unwrap(),
+        ];
+        let submit_multi = SubmitMulti::new(
+            3,
+            ServiceType::default(),
+            TypeOfNumber::Unknown,
+            NumericPlanIndicator::Unknown,
+            SourceAddr::new("1234", TypeOfNumber::Unknown).unwrap(),
+            destinations,
+            EsmClass::default(),
+            0,
+            PriorityFlag::Level0,
+            ScheduleDeliveryTime::default(),
+            ValidityPeriod::default(),
+            0,
+            0,
+            crate::datatypes::DataCoding::default(),
+            0,
+            ShortMessage::new(b"Hi").unwrap(),
+        )
+        .unwrap();
+        let unsuccess = vec![UnsuccessSmeAddress::new(
+            TypeOfNumber::International,
+            NumericPlanIndicator::Isdn,
+            "1111",
+            CommandStatus::InvalidDestinationAddress,
+        )];
+        let response =
+            SubmitMultiResponse::partial_success(3, MessageId::new(b"M1").unwrap(), unsuccess)
+                .unwrap();
+        let broadcast = BroadcastSm::builder()
+            .sequence_number(9)
+            .broadcast_area_identifier(vec![0x00, 0x12])
+            .build()
+            .unwrap();
+
+        let pdus = [
+            submit_sm(),
+            submit_multi.to_bytes().to_vec(),
+            response.to_bytes().to_vec(),
+            broadcast.to_bytes().to_vec(),
+        ];
+        for pdu in pdus {
+            assert!(inspect(&pdu).is_clean(), "{}", inspect(&pdu));
+            for cut in 0..pdu.len() {
+                let inspection = inspect(&pdu[..cut]);
+                assert!(inspection.failure.is_some(), "{inspection}");
+                if cut >= PduHeader::SIZE {
+                    let inspection = inspect(&with_length(&pdu[..cut]));
+                    let covered: usize = inspection.fields.iter().map(|f| f.bytes.len()).sum();
+                    assert_eq!(covered, cut, "{inspection}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+// ABOUTME: The wire layout of each PDU's mandatory parameters, as the registry's decoders read it
+// ABOUTME: Walked by the inspector to annotate PDUs and by the canonical form to read and write
+
+use crate::datatypes::CommandId;
+
+/// How a string field is laid out, mirroring the decoders
+#[derive(Clone, Copy)]
+pub(crate) enum Text {
+    /// Always this many octets, the value ending at the first NUL
+    Fixed(usize),
+    /// A C-Octet String of at most this many octets including the NUL
+    COctet(usize),
+    /// A C-Octet String followed by any NUL padding up to this width
+    Padded(usize),
+    /// At most this many octets, ending early after a NUL
+    Bounded(usize),
+    /// A C-Octet String with no limit of its own
+    Terminated,
+}
+
+/// What a one-octet field holds
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Octet {
+    Number,
+    Ton,
+    Npi,
+    Priority,
+    Version,
+    State,
+    EsmClass,
+    RegisteredDelivery,
+    DataCoding,
+}
+
+pub(crate) const SOURCE: [&str; 3] = ["source_addr_ton", "source_addr_npi", "source_addr"];
+pub(crate) const DEST: [&str; 3] = ["dest_addr_ton", "dest_addr_npi", "destination_addr"];
+pub(crate) const ESME: [&str; 3] = ["esme_addr_ton", "esme_addr_npi", "esme_addr"];
+
+/// A walk through a PDU's mandatory parameters
+///
+/// Counts and lengths that follow from another field (sm_length,
+/// number_of_dests and the like) are left to the method for the field they
+/// belong to.
+pub(crate) trait Layout {
+    type Error;
+
+    fn octet(&mut self, name: &str, kind: Octet) -> Result<(), Self::Error>;
+    fn u16(&mut self, name: &str) -> Result<(), Self::Error>;
+    /// A four-octet number; broadcast_frequency_interval is the only one
+    fn u32(&mut self, name: &str) -> Result<(), Self::Error>;
+    fn status(&mut self, name: &str) -> Result<(), Self::Error>;
+    fn text(&mut self, name: &str, layout: Text) -> Result<(), Self::Error>;
+    /// A text field that a response may leave out altogether
+    fn optional_text(&mut self, name: &str, layout: Text) -> Result<(), Self::Error>;
+    /// sm_length and short_message
+    fn short_message(&mut self) -> Result<(), Self::Error>;
+    /// broadcast_area_identifier and its length
+    fn area(&mut self) -> Result<(), Self::Error>;
+    /// submit_multi's dest_address list and its number_of_dests
+    fn dest_addresses(&mut self) -> Result<(), Self::Error>;
+    /// submit_multi_resp's unsuccess_sme list and its no_unsuccess
+    fn unsuccess_smes(&mut self) -> Result<(), Self::Error>;
+
+    fn address(&mut self, names: [&str; 3], layout: Text) -> Result<(), Self::Error> {
+        self.octet(names[0], Octet::Ton)?;
+        self.octet(names[1], Octet::Npi)?;
+        self.text(names[2], layout)
+    }
+}
+
+/// The mandatory parameters of each command
+pub(crate) fn body<L: Layout>(layout: &mut L, command_id: CommandId) -> Result<(), L::Error> {
+    use CommandId::*;
+
+    match command_id {
+        BindTransmitter | BindReceiver | BindTransceiver => {
+            layout.text("system_id", Text::Fixed(16))?;
+            layout.text("password", Text::Fixed(9))?;
+            layout.text("system_type", Text::Fixed(13))?;
+            layout.octet("interface_version", Octet::Version)?;
+            layout.octet("addr_ton", Octet::Ton)?;
+            layout.octet("addr_npi", Octet::Npi)?;
+            layout.text("address_range", Text::Fixed(41))
+        }
+        BindTransmitterResp | BindReceiverResp | BindTransceiverResp => {
+            layout.optional_text("system_id", Text::Padded(16))
+        }
+        Outbind => {
+            layout.text("system_id", Text::Bounded(17))?;
+            layout.text("password", Text::Bounded(10))
+        }
+        GenericNack | Unbind | UnbindResp | EnquireLink | EnquireLinkResp | CancelSmResp
+        | ReplaceSmResp | CancelBroadcastSmResp => Ok(()),
+        SubmitSm | DeliverSm => {
+            layout.text("service_type", Text::COctet(6))?;
+            layout.address(SOURCE, Text::COctet(21))?;
+            layout.address(DEST, Text::COctet(21))?;
+            layout.octet("esm_class", Octet::EsmClass)?;
+            layout.octet("protocol_id", Octet::Number)?;
+            layout.octet("priority_flag", Octet::Priority)?;
+            layout.text("schedule_delivery_time", Text::COctet(17))?;
+            layout.text("validity_period", Text::COctet(17))?;
+            layout.octet("registered_delivery", Octet::RegisteredDelivery)?;
+            layout.octet("replace_if_present_flag", Octet::Number)?;
+            layout.octet("data_coding", Octet::DataCoding)?;
+            layout.octet("sm_default_msg_id", Octet::Number)?;
+            layout.short_message()
+        }
+        SubmitSmResp => layout.optional_text("message_id", Text::COctet(65)),
+        DeliverSmResp => layout.optional_text("message_id", Text::Padded(65)),
+        DataSm => {
+            layout.text("service_type", Text::Fixed(6))?;
+            layout.address(SOURCE, Text::Fixed(21))?;
+            layout.address(DEST, Text::Fixed(21))?;
+            layout.octet("esm_class", Octet::EsmClass)?;
+            layout.octet("registered_delivery", Octet::RegisteredDelivery)?;
+            layout.octet("data_coding", Octet::DataCoding)
+        }
+        DataSmResp | BroadcastSmResp => layout.text("message_id", Text::Fixed(65)),
+        QuerySm | QueryBroadcastSm => {
+            layout.text("message_id", Text::Fixed(65))?;
+            layout.address(SOURCE, Text::Fixed(21))
+        }
+        QuerySmResp => {
+            layout.text("message_id", Text::Fixed(65))?;
+            layout.text("final_date", Text::Fixed(17))?;
+            layout.octet("message_state", Octet::State)?;
+            layout.octet("error_code", Octet::Number)
+        }
+        QueryBroadcastSmResp => {
+            layout.text("message_id", Text::Fixed(65))?;
+            layout.octet("message_state", Octet::State)?;
+            layout.text("final_date", Text::Fixed(17))
+        }
+        ReplaceSm => {
+            layout.text("message_id", Text::Fixed(65))?;
+            layout.address(SOURCE, Text::Fixed(21))?;
+            layout.text("schedule_delivery_time", Text::Fixed(17))?;
+            layout.text("validity_period", Text::Fixed(17))?;
+            layout.octet("registered_delivery", Octet::RegisteredDelivery)?;
+            layout.octet("sm_default_msg_id", Octet::Number)?;
+            layout.short_message()
+        }
+        CancelSm => {
+            layout.text("service_type", Text::Fixed(6))?;
+            layout.text("message_id", Text::Fixed(65))?;
+            layout.address(SOURCE, Text::Fixed(21))?;
+            layout.address(DEST, Text::Fixed(21))
+        }
+        CancelBroadcastSm => {
+            layout.text("service_type", Text::Fixed(6))?;
+            layout.text("message_id", Text::Fixed(65))?;
+            layout.address(SOURCE, Text::Fixed(21))
+        }
+        SubmitMulti => {
+            layout.text("service_type", Text::Fixed(6))?;
+            layout.address(SOURCE, Text::Fixed(21))?;
+            layout.dest_addresses()?;
+            layout.octet("esm_class", Octet::EsmClass)?;
+            layout.octet("protocol_id", Octet::Number)?;
+            layout.octet("priority_flag", Octet::Priority)?;
+            layout.text("schedule_delivery_time", Text::Fixed(17))?;
+            layout.text("validity_period", Text::Fixed(17))?;
+            layout.octet("registered_delivery", Octet::RegisteredDelivery)?;
+            layout.octet("replace_if_present_flag", Octet::Number)?;
+            layout.octet("data_coding", Octet::DataCoding)?;
+            layout.octet("sm_default_msg_id", Octet::Number)?;
+            layout.short_message()
+        }
+        SubmitMultiResp => {
+            layout.text("message_id", Text::Fixed(65))?;
+            layout.unsuccess_smes()
+        }
+        AlertNotification => {
+            layout.address(SOURCE, Text::Fixed(21))?;
+            layout.address(ESME, Text::Fixed(21))
+        }
+        BroadcastSm => {
+            layout.text("service_type", Text::Fixed(6))?;
+            layout.address(SOURCE, Text::Fixed(21))?;
+            layout.text("message_id", Text::Fixed(65))?;
+            layout.octet("priority_flag", Octet::Priority)?;
+            layout.text("schedule_delivery_time", Text::Fixed(17))?;
+            layout.text("validity_period", Text::Fixed(17))?;
+            layout.octet("data_coding", Octet::DataCoding)?;
+            layout.area()?;
+            layout.octet("broadcast_content_type", Octet::Number)?;
+            layout.u16("broadcast_rep_num")?;
+            layout.u32("broadcast_frequency_interval")
+        }
+    }
+}
@@ -1,9 +1,21 @@
+#[cfg(feature = "serde")]
+pub mod canonical;
 pub mod client;
 pub mod codec;
 pub mod connection;
 pub mod datatypes;
 pub mod frame;
+pub mod inspect;
+mod layout;
 pub mod macros;
+pub mod metrics;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+pub mod pcap;
+pub mod recording;
+pub mod redaction;
+#[cfg(any(test, feature = "simulator"))]
+pub mod simulator;
 
 #[cfg(test)]
 mod tests;
@@ -11,7 +23,10 @@ mod tests;
 // Re-export frame types (which now come from codec)
 
 // Re-export codec types for direct access
-pub use codec::{CodecError, Decodable, Encodable, Frame, PduHeader, PduRegistry};
+pub use codec::{
+    CodecError, Decodable, DecodePolicy, DecodeWarning, DecodedFrame, Encodable, Frame, PduHeader,
+    PduRegistry,
+};
 
 // Re-export the main client API for easy access
 pub use client::{
@@ -0,0 +1,346 @@
+// ABOUTME: Pluggable metrics for SMPP sessions: PDU counts, response latencies and session health
+// ABOUTME: Events go to a MetricsRecorder; the `metrics` feature adds an adapter for that facade
+
+//! Session Metrics
+//!
+//! Connections, clients, pools and flow control report what happens on a
+//! session as [`MetricEvent`]s to a [`MetricsRecorder`], each with the
+//! [`SessionLabels`] of the session it happened on:
+//!
+//! * **PDUs** sent and received, by command_id and command_status
+//! * **Response latency** of each request, by command_id
+//! * **Window occupancy** - requests using or waiting for a pooled session
+//! * **Bind state** changes, **reconnects** of pooled sessions and
+//!   **keep-alive failures**
+//! * **Flow-control rate** whenever a [`FlowControlManager`] adjusts it
+//!
+//! Implement [`MetricsRecorder`] (closures taking the labels and the event
+//! do) to feed another system, or enable the `metrics` feature and use
+//! `MetricsFacade` to publish through the [`metrics`](https://docs.rs/metrics)
+//! crate to whichever exporter the application installs.
+//!
+//! ```rust,no_run
+//! use smpp::client::{BindCredentials, DefaultClient, SmppClient, SmppConnection};
+//! use smpp::metrics::{MetricEvent, SessionLabels, SessionMetrics};
+//! use std::sync::Arc;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let recorder = Arc::new(|labels: &SessionLabels, event: &MetricEvent| {
+//!     println!("{} ({}): {event:?}", labels.session, labels.system_id);
+//! });
+//! let mut client = DefaultClient::connect("localhost:2775").await?;
+//! client.set_metrics(SessionMetrics::new(recorder, "primary"));
+//! client.bind(&BindCredentials::transmitter("system_id", "password")).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`FlowControlManager`]: crate::client::FlowControlManager
+
+use crate::client::BindType;
+use crate::datatypes::{CommandId, CommandStatus};
+use crate::recording::Direction;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Identifies the session a metric is about
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SessionLabels {
+    /// A name for the session chosen by the application, or by the pool
+    pub session: String,
+    /// The system_id the session binds with, empty until it is known
+    pub system_id: String,
+}
+
+/// The state of an SMPP session (Section 2.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindState {
+    /// Connected, not yet bound
+    Open,
+    Bound(BindType),
+    /// Unbound, the connection not yet closed
+    Unbound,
+    Closed,
+}
+
+impl BindState {
+    /// e.g. `bound_trx`, in the specification's terms
+    pub fn name(self) -> &'static str {
+        match self {
+            BindState::Open => "open",
+            BindState::Bound(BindType::Transmitter) => "bound_tx",
+            BindState::Bound(BindType::Receiver) => "bound_rx",
+            BindState::Bound(BindType::Transceiver) => "bound_trx",
+            BindState::Unbound => "unbound",
+            BindState::Closed => "closed",
+        }
+    }
+
+    /// Every state, e.g. to reset gauges that are set per state
+    pub const ALL: [BindState; 6] = [
+        BindState::Open,
+        BindState::Bound(BindType::Transmitter),
+        BindState::Bound(BindType::Receiver),
+        BindState::Bound(BindType::Transceiver),
+        BindState::Unbound,
+        BindState::Closed,
+    ];
+}
+
+/// Something that happened on a session
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricEvent {
+    /// A PDU was written to or read from the connection
+    ///
+    /// The header fields are given as they were on the wire, since the PDU
+    /// may use a command_id or command_status this crate does not know; see
+    /// [`command_label`] and [`status_label`].
+    Pdu {
+        direction: Direction,
+        command_id: u32,
+        command_status: u32,
+    },
+    /// The response to a request arrived, `latency` after the request was sent
+    ResponseLatency {
+        command_id: CommandId,
+        latency: Duration,
+    },
+    /// Requests using or waiting for the session
+    Window { in_flight: usize },
+    /// The session moved to a new state
+    BindState(BindState),
+    /// A pooled session was bound again after being removed
+    Reconnect,
+    /// An enquire_link went unanswered or failed
+    KeepAliveFailure,
+    /// Flow control set a new rate limit, in messages per second
+    FlowControlRate(f64),
+}
+
+/// The name of a command_id, e.g. `submit_sm`, or its value in hex when unknown
+pub fn command_label(command_id: u32) -> String {
+    match CommandId::try_from(command_id) {
+        Ok(command_id) => command_id.name(),
+        Err(_) => format!("{command_id:#010x}"),
+    }
+}
+
+/// The name of a command_status, e.g. `Ok`, or its value in hex when unknown
+pub fn status_label(command_status: u32) -> String {
+    match CommandStatus::try_from(command_status) {
+        Ok(status) => format!("{status:?}"),
+        Err(_) => format!("{command_status:#010x}"),
+    }
+}
+
+/// Receives the metrics of one or more sessions
+///
+/// Called on the task driving the session, so it should not block. Closures
+/// taking a `&SessionLabels` and a `&MetricEvent` implement this trait.
+pub trait MetricsRecorder: Send + Sync {
+    fn record(&self, labels: &SessionLabels, event: &MetricEvent);
+}
+
+impl<F> MetricsRecorder for F
+where
+    F: Fn(&SessionLabels, &MetricEvent) + Send + Sync,
+{
+    fn record(&self, labels: &SessionLabels, event: &MetricEvent) {
+        self(labels, event)
+    }
+}
+
+/// A recorder together with the labels of the session it records
+///
+/// Cheap to clone; the clones share the recorder.
+#[derive(Clone)]
+pub struct SessionMetrics {
+    recorder: Arc<dyn MetricsRecorder>,
+    labels: SessionLabels,
+}
+
+impl SessionMetrics {
+    /// Record the metrics of the session named `session` to `recorder`
+    pub fn new(recorder: Arc<dyn MetricsRecorder>, session: impl Into<String>) -> Self {
+        Self {
+            recorder,
+            labels: SessionLabels {
+                session: session.into(),
+                system_id: String::new(),
+            },
+        }
+    }
+
+    /// The same recorder and system_id, for the session named `session`
+    pub fn for_session(&self, session: impl Into<String>) -> Self {
+        Self {
+            recorder: Arc::clone(&self.recorder),
+            labels: SessionLabels {
+                session: session.into(),
+                system_id: self.labels.system_id.clone(),
+            },
+        }
+    }
+
+    pub fn with_system_id(mut self, system_id: impl Into<String>) -> Self {
+        self.set_system_id(system_id);
+        self
+    }
+
+    /// Label everything recorded from now on with `system_id`
+    pub fn set_system_id(&mut self, system_id: impl Into<String>) {
+        self.labels.system_id = system_id.into();
+    }
+
+    pub fn labels(&self) -> &SessionLabels {
+        &self.labels
+    }
+
+    pub fn record(&self, event: MetricEvent) {
+        self.recorder.record(&self.labels, &event);
+    }
+
+    /// Record a PDU from its encoded header; shorter octets are ignored
+    pub(crate) fn pdu(&self, direction: Direction, pdu: &[u8]) {
+        let field = |at: usize| {
+            pdu.get(at..at + 4)
+                .map(|octets| u32::from_be_bytes(octets.try_into().unwrap()))
+        };
+        if let (Some(command_id), Some(command_status)) = (field(4), field(8)) {
+            self.record(MetricEvent::Pdu {
+                direction,
+                command_id,
+                command_status,
+            });
+        }
+    }
+}
+
+impl fmt::Debug for SessionMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionMetrics")
+            .field("labels", &self.labels)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Publishes session metrics through the `metrics` facade
+///
+/// Every metric carries `session` and `system_id` labels:
+///
+/// | metric                            | kind      | further labels                 |
+/// |-----------------------------------|-----------|--------------------------------|
+/// | `smpp_pdus_sent_total`            | counter   | `command_id`, `command_status` |
+/// | `smpp_pdus_received_total`        | counter   | `command_id`, `command_status` |
+/// | `smpp_response_latency_seconds`   | histogram | `command_id`                   |
+/// | `smpp_window_in_flight`           | gauge     |                                |
+/// | `smpp_bind_state`                 | gauge     | `state`                        |
+/// | `smpp_reconnects_total`           | counter   |                                |
+/// | `smpp_keep_alive_failures_total`  | counter   |                                |
+/// | `smpp_flow_control_rate`          | gauge     |                                |
+///
+/// `smpp_bind_state` is 1 for the current state and 0 for the others, and
+/// `smpp_flow_control_rate` is in messages per second.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MetricsFacade;
+
+#[cfg(feature = "metrics")]
+impl MetricsRecorder for MetricsFacade {
+    fn record(&self, labels: &SessionLabels, event: &MetricEvent) {
+        let mut tags = vec![
+            ("session", labels.session.clone()),
+            ("system_id", labels.system_id.clone()),
+        ];
+        match *event {
+            MetricEvent::Pdu {
+                direction,
+                command_id,
+                command_status,
+            } => {
+                tags.push(("command_id", command_label(command_id)));
+                tags.push(("command_status", status_label(command_status)));
+                match direction {
+                    Direction::Outbound => metrics::counter!("smpp_pdus_sent_total", &tags),
+                    Direction::Inbound => metrics::counter!("smpp_pdus_received_total", &tags),
+                }
+                .increment(1);
+            }
+            MetricEvent::ResponseLatency {
+                command_id,
+                latency,
+            } => {
+                tags.push(("command_id", command_id.name()));
+                metrics::histogram!("smpp_response_latency_seconds", &tags)
+                    .record(latency.as_secs_f64());
+            }
+            MetricEvent::Window { in_flight } => {
+                metrics::gauge!("smpp_window_in_flight", &tags).set(in_flight as f64);
+            }
+            MetricEvent::BindState(current) => {
+                for state in BindState::ALL {
+                    let mut tags = tags.clone();
+                    tags.push(("state", state.name().to_string()));
+                    let value = if state == current { 1.0 } else { 0.0 };
+                    metrics::gauge!("smpp_bind_state", &tags).set(value);
+                }
+            }
+            MetricEvent::Reconnect => {
+                metrics::counter!("smpp_reconnects_total", &tags).increment(1);
+            }
+            MetricEvent::KeepAliveFailure => {
+                metrics::counter!("smpp_keep_alive_failures_total", &tags).increment(1);
+            }
+            MetricEvent::FlowControlRate(rate) => {
+                metrics::gauge!("smpp_flow_control_rate", &tags).set(rate);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_pdus_are_recorded_from_their_headers() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorder = {
+            let events = Arc::clone(&events);
+            move |labels: &SessionLabels, event: &MetricEvent| {
+                events.lock().unwrap().push((labels.clone(), event.clone()));
+            }
+        };
+        let metrics = SessionMetrics::new(Arc::new(recorder), "primary").with_system_id("esme");
+
+        let header: Vec<u8> = [16u32, 0x8000_0004, 0x58, 2]
+            .iter()
+            .flat_map(|field| field.to_be_bytes())
+            .collect();
+        metrics.pdu(Direction::Inbound, &header);
+        metrics.pdu(Direction::Outbound, &header[..10]);
+
+        let labels = SessionLabels {
+            session: "primary".to_string(),
+            system_id: "esme".to_string(),
+        };
+        let event = MetricEvent::Pdu {
+            direction: Direction::Inbound,
+            command_id: 0x8000_0004,
+            command_status: 0x58,
+        };
+        assert_eq!(*events.lock().unwrap(), vec![(labels, event)]);
+    }
+
+    #[test]
+    fn test_labels_name_known_values_and_show_others_in_hex() {
+        assert_eq!(command_label(0x8000_0004), "submit_sm_resp");
+        assert_eq!(command_label(0x0001_0200), "0x00010200");
+        assert_eq!(status_label(0), "Ok");
+        assert_eq!(status_label(0x58), "ThrottlingError");
+        assert_eq!(status_label(0x0400), "0x00000400");
+        assert_eq!(BindState::Bound(BindType::Transceiver).name(), "bound_trx");
+    }
+}
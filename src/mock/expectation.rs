@@ -0,0 +1,478 @@
+// ABOUTME: Expectations for the mock SMSC: which PDU comes next, what it must contain, the reply
+// ABOUTME: Builds default responses for requests so scripts only state what they care about
+
+use crate::Frame;
+use crate::datatypes::{
+    BindReceiverResponse, BindTransceiverResponse, BindTransmitterResponse, CommandId,
+    CancelSmResponse, CommandStatus, DataSmResponse, DeliverSmResponse, EnquireLinkResponse,
+    MessageId, MessageState, QuerySmResponse, ReplaceSmResponse, SubmitMultiResponse,
+    SubmitSmResponse, SystemId, UnbindResponse,
+};
+use std::fmt;
+use std::time::Duration;
+
+/// system_id the mock returns in bind responses
+const MOCK_SYSTEM_ID: &str = "MOCK-SMSC";
+
+type Check = Box<dyn Fn(&Frame) -> bool + Send>;
+type ReplyFn = Box<dyn FnOnce(&Frame) -> Frame + Send>;
+
+/// How the mock answers a PDU that met an expectation
+enum Reply {
+    /// The default response, with this status and message_id
+    Respond {
+        status: CommandStatus,
+        message_id: String,
+    },
+    /// A response built by the test
+    With(ReplyFn),
+    /// No response
+    Nothing,
+}
+
+/// One PDU a [`MockSmsc`](crate::mock::MockSmsc) expects to receive
+///
+/// An expectation names the PDU's command, any conditions on its contents,
+/// and the reply. Requests are answered with `ESME_ROK` and an empty
+/// message_id unless told otherwise; responses and alert_notification get
+/// no reply.
+///
+/// # Example
+///
+/// ```rust
+/// use smpp::datatypes::CommandStatus;
+/// use smpp::mock::Expectation;
+/// use std::time::Duration;
+///
+/// let bind = Expectation::bind_transmitter()
+///     .with_system_id("test")
+///     .reply_status(CommandStatus::Ok);
+/// let submit = Expectation::submit_sm()
+///     .with_destination_prefix("44")
+///     .reply_message_id("msg-1")
+///     .reply_after(Duration::from_millis(200));
+/// assert_eq!(submit.to_string(), "submit_sm where destination starts with \"44\"");
+/// ```
+pub struct Expectation {
+    command_id: CommandId,
+    conditions: Vec<(String, Check)>,
+    reply: Reply,
+    delay: Duration,
+}
+
+impl Expectation {
+    /// Expect a PDU with `command_id`, whatever it contains
+    pub fn new(command_id: CommandId) -> Self {
+        let reply = if is_request(command_id) {
+            Reply::Respond {
+                status: CommandStatus::Ok,
+                message_id: String::new(),
+            }
+        } else {
+            Reply::Nothing
+        };
+        Self {
+            command_id,
+            conditions: Vec::new(),
+            reply,
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Expect a bind_transmitter
+    pub fn bind_transmitter() -> Self {
+        Self::new(CommandId::BindTransmitter)
+    }
+
+    /// Expect a bind_receiver
+    pub fn bind_receiver() -> Self {
+        Self::new(CommandId::BindReceiver)
+    }
+
+    /// Expect a bind_transceiver
+    pub fn bind_transceiver() -> Self {
+        Self::new(CommandId::BindTransceiver)
+    }
+
+    /// Expect a submit_sm
+    pub fn submit_sm() -> Self {
+        Self::new(CommandId::SubmitSm)
+    }
+
+    /// Expect a submit_multi
+    pub fn submit_multi() -> Self {
+        Self::new(CommandId::SubmitMulti)
+    }
+
+    /// Expect a data_sm
+    pub fn data_sm() -> Self {
+        Self::new(CommandId::DataSm)
+    }
+
+    /// Expect a query_sm
+    pub fn query_sm() -> Self {
+        Self::new(CommandId::QuerySm)
+    }
+
+    /// Expect a cancel_sm
+    pub fn cancel_sm() -> Self {
+        Self::new(CommandId::CancelSm)
+    }
+
+    /// Expect a replace_sm
+    pub fn replace_sm() -> Self {
+        Self::new(CommandId::ReplaceSm)
+    }
+
+    /// Expect an enquire_link
+    pub fn enquire_link() -> Self {
+        Self::new(CommandId::EnquireLink)
+    }
+
+    /// Expect an unbind
+    pub fn unbind() -> Self {
+        Self::new(CommandId::Unbind)
+    }
+
+    /// Expect an unbind_resp, e.g. after the mock sent an unbind
+    pub fn unbind_resp() -> Self {
+        Self::new(CommandId::UnbindResp)
+    }
+
+    /// Expect a deliver_sm_resp, e.g. after the mock sent a deliver_sm
+    pub fn deliver_sm_resp() -> Self {
+        Self::new(CommandId::DeliverSmResp)
+    }
+
+    /// The command the expectation is for
+    pub fn command_id(&self) -> CommandId {
+        self.command_id
+    }
+
+    /// Require the PDU to satisfy `check`, described as `description` in failures
+    pub fn matching(
+        mut self,
+        description: impl Into<String>,
+        check: impl Fn(&Frame) -> bool + Send + 'static,
+    ) -> Self {
+        self.conditions.push((description.into(), Box::new(check)));
+        self
+    }
+
+    /// Require a bind with this system_id
+    pub fn with_system_id(self, system_id: impl Into<String>) -> Self {
+        let system_id = system_id.into();
+        let description = format!("system_id is {system_id:?}");
+        self.matching(description, move |frame| {
+            bind_credentials(frame).is_some_and(|(bound, _)| bound == system_id)
+        })
+    }
+
+    /// Require a bind with this password
+    pub fn with_password(self, password: impl Into<String>) -> Self {
+        let password = password.into();
+        let description = format!("password is {password:?}");
+        self.matching(description, move |frame| {
+            bind_credentials(frame).is_some_and(|(_, given)| given == password)
+        })
+    }
+
+    /// Require a submit_sm or data_sm whose destination starts with `prefix`
+    pub fn with_destination_prefix(self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        let description = format!("destination starts with {prefix:?}");
+        self.matching(description, move |frame| {
+            let destination = match frame {
+                Frame::SubmitSm(submit) => submit.destination_addr.to_string(),
+                Frame::DataSm(data) => data.destination_addr.to_string(),
+                _ => return false,
+            };
+            destination.starts_with(&prefix)
+        })
+    }
+
+    /// Require a submit_sm whose short_message is `text`
+    pub fn with_text(self, text: impl Into<String>) -> Self {
+        let text = text.into();
+        let description = format!("text is {text:?}");
+        self.matching(description, move |frame| {
+            matches!(frame, Frame::SubmitSm(submit) if submit.short_message.to_string() == text)
+        })
+    }
+
+    /// Require a response with this command_status
+    pub fn with_status(self, status: CommandStatus) -> Self {
+        let description = format!("status is {status:?}");
+        self.matching(description, move |frame| response_status(frame) == Some(status))
+    }
+
+    /// Answer with the default response carrying `status`
+    pub fn reply_status(mut self, status: CommandStatus) -> Self {
+        match &mut self.reply {
+            Reply::Respond { status: current, .. } => *current = status,
+            reply => {
+                *reply = Reply::Respond {
+                    status,
+                    message_id: String::new(),
+                }
+            }
+        }
+        self
+    }
+
+    /// Answer a submission or query with this message_id
+    pub fn reply_message_id(mut self, message_id: impl Into<String>) -> Self {
+        match &mut self.reply {
+            Reply::Respond {
+                message_id: current,
+                ..
+            } => *current = message_id.into(),
+            reply => {
+                *reply = Reply::Respond {
+                    status: CommandStatus::Ok,
+                    message_id: message_id.into(),
+                }
+            }
+        }
+        self
+    }
+
+    /// Answer with the frame `reply` builds from the received PDU
+    pub fn reply_with(mut self, reply: impl FnOnce(&Frame) -> Frame + Send + 'static) -> Self {
+        self.reply = Reply::With(Box::new(reply));
+        self
+    }
+
+    /// Send no reply, e.g. to let the client time out
+    pub fn no_reply(mut self) -> Self {
+        self.reply = Reply::Nothing;
+        self
+    }
+
+    /// Wait `delay` before replying
+    pub fn reply_after(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Why `frame` does not meet the expectation, or `None` if it does
+    pub(crate) fn mismatch(&self, frame: &Frame) -> Option<String> {
+        if frame.command_id() != self.command_id {
+            return Some(format!("received {}", describe(frame)));
+        }
+        let failed: Vec<&str> = self
+            .conditions
+            .iter()
+            .filter(|(_, check)| !check(frame))
+            .map(|(description, _)| description.as_str())
+            .collect();
+        if failed.is_empty() {
+            None
+        } else {
+            Some(format!("received {} where not {}", describe(frame), failed.join(", ")))
+        }
+    }
+
+    /// The reply to a PDU that met the expectation, and how long to wait before sending it
+    pub(crate) fn into_reply(self, frame: &Frame) -> (Option<Frame>, Duration) {
+        let reply = match self.reply {
+            Reply::Respond { status, message_id } => default_response(frame, status, &message_id),
+            Reply::With(reply) => Some(reply(frame)),
+            Reply::Nothing => None,
+        };
+        (reply, self.delay)
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.command_id.name())?;
+        for (i, (description, _)) in self.conditions.iter().enumerate() {
+            let joiner = if i == 0 { " where" } else { "," };
+            write!(f, "{joiner} {description}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Expectation").field(&self.to_string()).finish()
+    }
+}
+
+/// A PDU's command and sequence number, for failure messages
+pub(crate) fn describe(frame: &Frame) -> String {
+    format!(
+        "{} (sequence {})",
+        frame.command_id().name(),
+        frame.sequence_number()
+    )
+}
+
+/// Whether a command expects a response
+fn is_request(command_id: CommandId) -> bool {
+    (command_id as u32) & 0x8000_0000 == 0 && command_id != CommandId::AlertNotification
+}
+
+/// The system_id and password of a bind
+fn bind_credentials(frame: &Frame) -> Option<(String, String)> {
+    let (system_id, password) = match frame {
+        Frame::BindTransmitter(bind) => (&bind.system_id, &bind.password),
+        Frame::BindReceiver(bind) => (&bind.system_id, &bind.password),
+        Frame::BindTransceiver(bind) => (&bind.system_id, &bind.password),
+        _ => return None,
+    };
+    let password = password.as_ref().and_then(|p| p.as_str().ok()).unwrap_or_default();
+    Some((system_id.to_string(), password.to_string()))
+}
+
+/// The command_status of a response PDU
+fn response_status(frame: &Frame) -> Option<CommandStatus> {
+    match frame {
+        Frame::DeliverSmResp(resp) => Some(resp.command_status),
+        Frame::DataSmResp(resp) => Some(resp.command_status),
+        Frame::SubmitSmResp(resp) => Some(resp.command_status),
+        Frame::UnbindResp(resp) => Some(resp.command_status),
+        Frame::EnquireLinkResp(resp) => Some(resp.command_status),
+        Frame::GenericNack(nack) => Some(nack.command_status),
+        _ => None,
+    }
+}
+
+/// The response an SMSC would send to a request
+fn default_response(frame: &Frame, status: CommandStatus, message_id: &str) -> Option<Frame> {
+    let sequence_number = frame.sequence_number();
+    let system_id = SystemId::from(MOCK_SYSTEM_ID);
+    let message_id = MessageId::from(message_id);
+    let response = match frame {
+        Frame::BindTransmitter(_) => Frame::BindTransmitterResp(BindTransmitterResponse {
+            command_status: status,
+            sequence_number,
+            system_id,
+            sc_interface_version: None,
+        }),
+        Frame::BindReceiver(_) => Frame::BindReceiverResp(BindReceiverResponse {
+            command_status: status,
+            sequence_number,
+            system_id,
+            sc_interface_version: None,
+        }),
+        Frame::BindTransceiver(_) => Frame::BindTransceiverResp(BindTransceiverResponse {
+            command_status: status,
+            sequence_number,
+            system_id,
+            sc_interface_version: None,
+        }),
+        Frame::SubmitSm(_) => Frame::SubmitSmResp(SubmitSmResponse {
+            command_status: status,
+            sequence_number,
+            message_id,
+        }),
+        Frame::SubmitMulti(_) => Frame::SubmitMultiResp(SubmitMultiResponse {
+            command_status: status,
+            sequence_number,
+            message_id,
+            no_unsuccess: 0,
+            unsuccess_sme: Vec::new(),
+        }),
+        Frame::DataSm(_) => Frame::DataSmResp(DataSmResponse {
+            command_status: status,
+            sequence_number,
+            message_id,
+            optional_parameters: Vec::new(),
+        }),
+        Frame::DeliverSm(_) => Frame::DeliverSmResp(DeliverSmResponse {
+            command_status: status,
+            sequence_number,
+            message_id,
+        }),
+        Frame::QuerySm(_) => Frame::QuerySmResp(QuerySmResponse {
+            command_status: status,
+            sequence_number,
+            message_id,
+            final_date: None,
+            message_state: MessageState::Enroute,
+            error_code: 0,
+        }),
+        Frame::CancelSm(_) => Frame::CancelSmResp(CancelSmResponse {
+            command_status: status,
+            sequence_number,
+        }),
+        Frame::ReplaceSm(_) => Frame::ReplaceSmResp(ReplaceSmResponse {
+            command_status: status,
+            sequence_number,
+        }),
+        Frame::EnquireLink(_) => Frame::EnquireLinkResp(EnquireLinkResponse {
+            command_status: status,
+            sequence_number,
+        }),
+        Frame::Unbind(_) => Frame::UnbindResp(UnbindResponse {
+            command_status: status,
+            sequence_number,
+        }),
+        _ => return None,
+    };
+    Some(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datatypes::SubmitSm;
+
+    fn submit(destination: &str, sequence_number: u32) -> Frame {
+        let mut submit = SubmitSm::builder()
+            .source_addr("1234")
+            .destination_addr(destination)
+            .short_message("Hello")
+            .build()
+            .unwrap();
+        submit.sequence_number = sequence_number;
+        Frame::SubmitSm(Box::new(submit))
+    }
+
+    #[test]
+    fn test_mismatch_names_failed_conditions() {
+        let expectation = Expectation::submit_sm()
+            .with_destination_prefix("44")
+            .with_text("Hello");
+        assert_eq!(expectation.mismatch(&submit("447700900123", 2)), None);
+        assert_eq!(
+            expectation.mismatch(&submit("33612345678", 3)).unwrap(),
+            "received submit_sm (sequence 3) where not destination starts with \"44\""
+        );
+
+        let unbind = Frame::Unbind(crate::datatypes::Unbind {
+            command_status: CommandStatus::Ok,
+            sequence_number: 4,
+        });
+        assert_eq!(expectation.mismatch(&unbind).unwrap(), "received unbind (sequence 4)");
+    }
+
+    #[test]
+    fn test_default_reply_carries_status_and_message_id() {
+        let (reply, delay) = Expectation::submit_sm()
+            .reply_message_id("abc")
+            .reply_after(Duration::from_millis(200))
+            .into_reply(&submit("447700900123", 7));
+        assert_eq!(delay, Duration::from_millis(200));
+        match reply {
+            Some(Frame::SubmitSmResp(resp)) => {
+                assert_eq!(resp.sequence_number, 7);
+                assert_eq!(resp.command_status, CommandStatus::Ok);
+                assert_eq!(resp.message_id.to_string(), "abc");
+            }
+            other => panic!("Expected submit_sm_resp, got {other:?}"),
+        }
+
+        let (reply, _) = Expectation::deliver_sm_resp().into_reply(&Frame::DeliverSmResp(
+            DeliverSmResponse {
+                command_status: CommandStatus::Ok,
+                sequence_number: 1,
+                message_id: MessageId::default(),
+            },
+        ));
+        assert!(reply.is_none());
+    }
+}
@@ -0,0 +1,463 @@
+// ABOUTME: Scriptable in-process mock SMSC for unit tests of SMPP clients
+// ABOUTME: Plays a script of expectations and sends over one connection, reporting unmet steps
+
+//! Mock SMSC
+//!
+//! Where the simulator behaves like an SMSC, the mock plays a script: it
+//! accepts one connection and expects the client's PDUs in order, asserting
+//! on their contents and answering each as told. Between expectations it can
+//! send PDUs of its own, pause, or hang up. It is available with the `mock`
+//! feature, meant for dev-dependencies only.
+//!
+//! Anything that goes wrong is recorded rather than panicking on the mock's
+//! task: a PDU that does not meet the current expectation ends the script
+//! and closes the connection. When the [`MockSmsc`] is dropped it panics
+//! with the failures and the steps that were never reached, failing the test.
+//!
+//! enquire_link is answered automatically unless the script expects it, so
+//! keep-alive timing does not make tests flaky.
+//!
+//! ```rust,no_run
+//! use smpp::Frame;
+//! use smpp::client::{BindCredentials, DefaultClient, SmsMessage};
+//! use smpp::client::{SmppClient, SmppConnection, SmppTransmitter};
+//! use smpp::datatypes::{CommandStatus, Unbind};
+//! use smpp::mock::{Expectation, MockSmsc};
+//! use std::time::Duration;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let mock = MockSmsc::builder()
+//!     .expect(Expectation::bind_transmitter().with_system_id("X").reply_status(CommandStatus::Ok))
+//!     .expect(
+//!         Expectation::submit_sm()
+//!             .with_destination_prefix("44")
+//!             .reply_message_id("Z")
+//!             .reply_after(Duration::from_millis(200)),
+//!     )
+//!     .send(Frame::Unbind(Unbind { command_status: CommandStatus::Ok, sequence_number: 1 }))
+//!     .expect(Expectation::unbind_resp())
+//!     .start()
+//!     .await?;
+//!
+//! let mut client = DefaultClient::connect(mock.local_addr()).await?;
+//! client.bind(&BindCredentials::transmitter("X", "secret")).await?;
+//! let message_id = client.send_sms(&SmsMessage::new("447700900123", "1234", "Hi")).await?;
+//! assert_eq!(message_id, "Z");
+//! // ... answer the unbind, then check every step was met
+//! mock.verify().await;
+//! # Ok(())
+//! # }
+//! ```
+
+mod expectation;
+
+pub use expectation::Expectation;
+
+use crate::Frame;
+use crate::codec::PduRegistry;
+use crate::connection::Connection;
+use crate::datatypes::{CommandId, CommandStatus, EnquireLinkResponse, InterfaceVersion};
+use expectation::describe;
+use std::collections::VecDeque;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// How long [`MockSmsc::verify`] waits for the script to finish
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One step of a mock's script
+enum Step {
+    Expect(Expectation),
+    Send(Frame),
+    Pause(Duration),
+    Disconnect,
+}
+
+impl Step {
+    fn description(&self) -> String {
+        match self {
+            Step::Expect(expectation) => format!("expect {expectation}"),
+            Step::Send(frame) => format!("send {}", describe(frame)),
+            Step::Pause(duration) => format!("pause for {duration:?}"),
+            Step::Disconnect => "disconnect".to_string(),
+        }
+    }
+}
+
+/// Builds the script a [`MockSmsc`] plays
+pub struct MockSmscBuilder {
+    steps: Vec<Step>,
+    auto_enquire_link: bool,
+}
+
+impl Default for MockSmscBuilder {
+    fn default() -> Self {
+        Self {
+            steps: Vec::new(),
+            auto_enquire_link: true,
+        }
+    }
+}
+
+impl MockSmscBuilder {
+    /// Expect the client's next PDU to meet `expectation`
+    pub fn expect(mut self, expectation: Expectation) -> Self {
+        self.steps.push(Step::Expect(expectation));
+        self
+    }
+
+    /// Send `frame` to the client as is
+    pub fn send(mut self, frame: Frame) -> Self {
+        self.steps.push(Step::Send(frame));
+        self
+    }
+
+    /// Wait before the next step
+    pub fn pause(mut self, duration: Duration) -> Self {
+        self.steps.push(Step::Pause(duration));
+        self
+    }
+
+    /// Close the connection, ending the script
+    pub fn disconnect(mut self) -> Self {
+        self.steps.push(Step::Disconnect);
+        self
+    }
+
+    /// Whether to answer enquire_links the script does not expect (default: true)
+    pub fn with_auto_enquire_link(mut self, enabled: bool) -> Self {
+        self.auto_enquire_link = enabled;
+        self
+    }
+
+    /// Listen on a free local port and play the script to the first client
+    pub async fn start(self) -> io::Result<MockSmsc> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let progress = Arc::new(Mutex::new(Progress {
+            pending: self.steps.iter().map(Step::description).collect(),
+            failures: Vec::new(),
+        }));
+        let (done_tx, done) = watch::channel(false);
+        let script = Script {
+            progress: progress.clone(),
+            auto_enquire_link: self.auto_enquire_link,
+            done: done_tx,
+        };
+        let task = tokio::spawn(script.run(listener, self.steps));
+        Ok(MockSmsc {
+            addr,
+            progress,
+            done,
+            task,
+            verified: false,
+        })
+    }
+}
+
+/// What has happened to a script so far
+struct Progress {
+    /// Steps not yet completed, in order
+    pending: VecDeque<String>,
+    /// What went wrong
+    failures: Vec<String>,
+}
+
+/// A scripted fake SMSC serving one connection
+///
+/// Create one with [`MockSmsc::builder`]. Dropping it stops the script and,
+/// unless the thread is already panicking, panics if any step failed or was
+/// not reached.
+pub struct MockSmsc {
+    addr: SocketAddr,
+    progress: Arc<Mutex<Progress>>,
+    done: watch::Receiver<bool>,
+    task: JoinHandle<()>,
+    verified: bool,
+}
+
+impl MockSmsc {
+    /// Start writing a script
+    pub fn builder() -> MockSmscBuilder {
+        MockSmscBuilder::default()
+    }
+
+    /// The address to connect the client to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Wait up to `timeout` for the script to finish, returning whether it did
+    ///
+    /// A script finishes when its last step completes or a step fails.
+    pub async fn wait(&self, timeout: Duration) -> bool {
+        let mut done = self.done.clone();
+        tokio::time::timeout(timeout, done.wait_for(|done| *done))
+            .await
+            .is_ok_and(|result| result.is_ok())
+    }
+
+    /// Failed steps and steps not reached yet, as messages
+    pub fn problems(&self) -> Vec<String> {
+        let progress = self.progress.lock().unwrap();
+        progress
+            .failures
+            .iter()
+            .cloned()
+            .chain(progress.pending.iter().map(|step| format!("not reached: {step}")))
+            .collect()
+    }
+
+    /// Wait for the script to finish, then panic if anything went wrong
+    pub async fn verify(mut self) {
+        self.wait(VERIFY_TIMEOUT).await;
+        self.verified = true;
+        assert_report(&self.problems());
+    }
+}
+
+impl Drop for MockSmsc {
+    fn drop(&mut self) {
+        self.task.abort();
+        if !self.verified && !std::thread::panicking() {
+            assert_report(&self.problems());
+        }
+    }
+}
+
+/// Panic listing `problems`, if there are any
+fn assert_report(problems: &[String]) {
+    if !problems.is_empty() {
+        panic!("mock SMSC script not satisfied:\n  - {}", problems.join("\n  - "));
+    }
+}
+
+/// The task playing a script
+struct Script {
+    progress: Arc<Mutex<Progress>>,
+    auto_enquire_link: bool,
+    done: watch::Sender<bool>,
+}
+
+impl Script {
+    async fn run(self, listener: TcpListener, steps: Vec<Step>) {
+        let socket = match listener.accept().await {
+            Ok((socket, _)) => socket,
+            Err(e) => return self.fail(format!("accepting the client failed: {e}")),
+        };
+        // Decode everything; the script decides what is allowed
+        let mut connection =
+            Connection::with_registry(socket, PduRegistry::for_version(InterfaceVersion::SmppV50));
+
+        for step in steps {
+            let result = match step {
+                Step::Expect(expectation) => self.expect(&mut connection, expectation).await,
+                Step::Send(frame) => connection
+                    .write_frame(&frame)
+                    .await
+                    .map_err(|e| format!("sending {} failed: {e}", describe(&frame))),
+                Step::Pause(duration) => {
+                    tokio::time::sleep(duration).await;
+                    Ok(())
+                }
+                Step::Disconnect => {
+                    self.complete();
+                    self.done.send_replace(true);
+                    return;
+                }
+            };
+            match result {
+                Ok(()) => self.complete(),
+                Err(failure) => return self.fail(failure),
+            }
+        }
+        self.done.send_replace(true);
+
+        // The script is over; anything but keep-alive from here on is unexpected
+        loop {
+            match connection.read_frame().await {
+                Ok(Some(frame)) if self.is_auto_enquire_link(&frame, None) => {
+                    if self.answer_enquire_link(&mut connection, &frame).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(Some(frame)) => self.record(format!(
+                    "unexpected {} after the script ended",
+                    describe(&frame)
+                )),
+                Ok(None) | Err(_) => return,
+            }
+        }
+    }
+
+    /// Read until the next PDU the script cares about and check it against `expectation`
+    async fn expect(
+        &self,
+        connection: &mut Connection,
+        expectation: Expectation,
+    ) -> Result<(), String> {
+        let frame = loop {
+            match connection.read_frame().await {
+                Ok(Some(frame)) if self.is_auto_enquire_link(&frame, Some(&expectation)) => {
+                    self.answer_enquire_link(connection, &frame)
+                        .await
+                        .map_err(|e| format!("answering enquire_link failed: {e}"))?;
+                }
+                Ok(Some(frame)) => break frame,
+                Ok(None) => {
+                    return Err(format!("expected {expectation}, but the client disconnected"));
+                }
+                Err(e) => return Err(format!("expected {expectation}, but reading failed: {e}")),
+            }
+        };
+
+        if let Some(mismatch) = expectation.mismatch(&frame) {
+            return Err(format!("expected {expectation}, {mismatch}"));
+        }
+        let (reply, delay) = expectation.into_reply(&frame);
+        if let Some(reply) = reply {
+            tokio::time::sleep(delay).await;
+            connection
+                .write_frame(&reply)
+                .await
+                .map_err(|e| format!("replying to {} failed: {e}", describe(&frame)))?;
+        }
+        Ok(())
+    }
+
+    /// Whether `frame` is an enquire_link the mock answers by itself
+    fn is_auto_enquire_link(&self, frame: &Frame, expecting: Option<&Expectation>) -> bool {
+        self.auto_enquire_link
+            && matches!(frame, Frame::EnquireLink(_))
+            && expecting.is_none_or(|e| e.command_id() != CommandId::EnquireLink)
+    }
+
+    async fn answer_enquire_link(
+        &self,
+        connection: &mut Connection,
+        frame: &Frame,
+    ) -> io::Result<()> {
+        let response = EnquireLinkResponse {
+            command_status: CommandStatus::Ok,
+            sequence_number: frame.sequence_number(),
+        };
+        connection.write_frame(&Frame::EnquireLinkResp(response)).await
+    }
+
+    /// Mark the current step completed
+    fn complete(&self) {
+        self.progress.lock().unwrap().pending.pop_front();
+    }
+
+    /// Record a problem without ending the script
+    fn record(&self, failure: String) {
+        self.progress.lock().unwrap().failures.push(failure);
+    }
+
+    /// Fail the current step, ending the script
+    fn fail(&self, failure: String) {
+        let mut progress = self.progress.lock().unwrap();
+        progress.pending.pop_front();
+        progress.failures.push(failure);
+        drop(progress);
+        self.done.send_replace(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datatypes::{SubmitSm, Unbind};
+    use tokio::net::TcpStream;
+
+    async fn client(mock: &MockSmsc) -> Connection {
+        let socket = TcpStream::connect(mock.local_addr()).await.unwrap();
+        Connection::new(socket)
+    }
+
+    fn submit(destination: &str) -> Frame {
+        let mut submit = SubmitSm::builder()
+            .source_addr("1234")
+            .destination_addr(destination)
+            .short_message("Hello")
+            .build()
+            .unwrap();
+        submit.sequence_number = 2;
+        Frame::SubmitSm(Box::new(submit))
+    }
+
+    #[tokio::test]
+    async fn test_script_answers_and_sends_in_order() {
+        let mock = MockSmsc::builder()
+            .expect(Expectation::submit_sm().reply_message_id("Z"))
+            .send(Frame::Unbind(Unbind {
+                command_status: CommandStatus::Ok,
+                sequence_number: 9,
+            }))
+            .start()
+            .await
+            .unwrap();
+        let mut connection = client(&mock).await;
+
+        connection.write_frame(&submit("447700900123")).await.unwrap();
+        // An unscripted enquire_link is answered in between
+        let ping = Frame::EnquireLink(crate::datatypes::EnquireLink {
+            command_status: CommandStatus::Ok,
+            sequence_number: 3,
+        });
+        connection.write_frame(&ping).await.unwrap();
+
+        match connection.read_frame().await.unwrap() {
+            Some(Frame::SubmitSmResp(resp)) => assert_eq!(resp.message_id.to_string(), "Z"),
+            other => panic!("Expected submit_sm_resp, got {other:?}"),
+        }
+        assert!(matches!(connection.read_frame().await.unwrap(), Some(Frame::Unbind(_))));
+        assert!(matches!(
+            connection.read_frame().await.unwrap(),
+            Some(Frame::EnquireLinkResp(_))
+        ));
+        mock.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_mismatch_ends_script_and_is_reported() {
+        let mock = MockSmsc::builder()
+            .expect(Expectation::submit_sm().with_destination_prefix("44"))
+            .expect(Expectation::unbind())
+            .start()
+            .await
+            .unwrap();
+        let mut connection = client(&mock).await;
+
+        connection.write_frame(&submit("33612345678")).await.unwrap();
+        assert!(connection.read_frame().await.unwrap().is_none());
+        assert!(mock.wait(Duration::from_secs(1)).await);
+        assert_eq!(
+            mock.problems(),
+            vec![
+                "expected submit_sm where destination starts with \"44\", received submit_sm \
+                 (sequence 2) where not destination starts with \"44\""
+                    .to_string(),
+                "not reached: expect unbind".to_string(),
+            ]
+        );
+        let mut mock = mock;
+        mock.verified = true;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "not reached: expect bind_transmitter")]
+    async fn test_drop_panics_on_unmet_expectations() {
+        let mock = MockSmsc::builder()
+            .expect(Expectation::bind_transmitter())
+            .start()
+            .await
+            .unwrap();
+        drop(mock);
+    }
+}
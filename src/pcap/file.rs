@@ -0,0 +1,273 @@
+// ABOUTME: Reads the packets of pcap and pcapng capture files, in either byte order
+// ABOUTME: Timestamps are normalised to time since the Unix epoch whatever the file's resolution
+
+use std::time::Duration;
+use thiserror::Error;
+
+/// Errors reading a capture file
+#[derive(Debug, Error)]
+pub enum CaptureError {
+    #[error("not a pcap or pcapng capture")]
+    UnknownFormat,
+
+    #[error("malformed capture at offset {offset}: {reason}")]
+    Malformed { offset: usize, reason: String },
+}
+
+/// A captured link-layer frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    /// When the packet was captured, since the Unix epoch
+    pub timestamp: Duration,
+    /// The capture's link-layer header type (LINKTYPE_* value)
+    pub link_type: u32,
+    /// The captured octets, possibly cut short by the snapshot length
+    pub data: Vec<u8>,
+}
+
+/// Read every packet of a pcap or pcapng capture
+///
+/// A record cut short at the end of the file, as left by a capture that was
+/// interrupted, ends the capture rather than failing it.
+pub fn read_packets(capture: &[u8]) -> Result<Vec<Packet>, CaptureError> {
+    let magic = capture.get(..4).ok_or(CaptureError::UnknownFormat)?;
+    match magic {
+        [0x0A, 0x0D, 0x0D, 0x0A] => read_pcapng(capture),
+        [0xD4, 0xC3, 0xB2, 0xA1] => read_pcap(capture, false, 1_000_000),
+        [0xA1, 0xB2, 0xC3, 0xD4] => read_pcap(capture, true, 1_000_000),
+        [0x4D, 0x3C, 0xB2, 0xA1] => read_pcap(capture, false, 1_000_000_000),
+        [0xA1, 0xB2, 0x3C, 0x4D] => read_pcap(capture, true, 1_000_000_000),
+        _ => Err(CaptureError::UnknownFormat),
+    }
+}
+
+/// Integers in the byte order the capture was written in
+#[derive(Clone, Copy)]
+struct Order {
+    big_endian: bool,
+}
+
+impl Order {
+    fn u16(self, data: &[u8], at: usize) -> Option<u16> {
+        let octets = data.get(at..at + 2)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(octets)
+        } else {
+            u16::from_le_bytes(octets)
+        })
+    }
+
+    fn u32(self, data: &[u8], at: usize) -> Option<u32> {
+        let octets = data.get(at..at + 4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(octets)
+        } else {
+            u32::from_le_bytes(octets)
+        })
+    }
+}
+
+/// A timestamp of `ticks` at `per_second` ticks per second
+fn timestamp(ticks: u64, per_second: u64) -> Duration {
+    let nanos = u128::from(ticks % per_second) * 1_000_000_000 / u128::from(per_second);
+    Duration::new(ticks / per_second, nanos as u32)
+}
+
+fn read_pcap(
+    capture: &[u8],
+    big_endian: bool,
+    per_second: u64,
+) -> Result<Vec<Packet>, CaptureError> {
+    let order = Order { big_endian };
+    let link_type = order.u32(capture, 20).ok_or(CaptureError::Malformed {
+        offset: 0,
+        reason: "file header shorter than 24 octets".to_string(),
+    })?;
+    // The upper bits may carry FCS information
+    let link_type = link_type & 0x0FFF_FFFF;
+
+    let mut packets = Vec::new();
+    let mut at = 24;
+    while let (Some(seconds), Some(fraction), Some(length)) = (
+        order.u32(capture, at),
+        order.u32(capture, at + 4),
+        order.u32(capture, at + 8),
+    ) {
+        let start = at + 16;
+        let Some(data) = capture.get(start..start + length as usize) else {
+            break;
+        };
+        let ticks = u64::from(seconds) * per_second + u64::from(fraction);
+        packets.push(Packet {
+            timestamp: timestamp(ticks, per_second),
+            link_type,
+            data: data.to_vec(),
+        });
+        at = start + length as usize;
+    }
+    Ok(packets)
+}
+
+/// An interface described by a pcapng Interface Description Block
+struct Interface {
+    link_type: u32,
+    per_second: u64,
+    snap_length: u32,
+}
+
+fn read_pcapng(capture: &[u8]) -> Result<Vec<Packet>, CaptureError> {
+    let mut packets = Vec::new();
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut order = Order { big_endian: false };
+    let mut last_timestamp = Duration::ZERO;
+    let mut at = 0;
+
+    while at + 12 <= capture.len() {
+        let malformed = |reason: &str| CaptureError::Malformed {
+            offset: at,
+            reason: reason.to_string(),
+        };
+        let block_type = order.u32(capture, at).unwrap_or_default();
+
+        // Each section sets the byte order of the blocks that follow
+        if block_type == 0x0A0D_0D0A {
+            order = match capture[at + 8..at + 12] {
+                [0x4D, 0x3C, 0x2B, 0x1A] => Order { big_endian: false },
+                [0x1A, 0x2B, 0x3C, 0x4D] => Order { big_endian: true },
+                _ => return Err(malformed("unknown byte-order magic")),
+            };
+            interfaces.clear();
+        }
+
+        let length = order.u32(capture, at + 4).unwrap_or_default() as usize;
+        if length < 12 || !length.is_multiple_of(4) {
+            return Err(malformed("invalid block length"));
+        }
+        let Some(block) = capture.get(at..at + length) else {
+            break;
+        };
+        let body_end = length - 4;
+
+        match block_type {
+            // Interface Description Block
+            1 => {
+                let mut interface = Interface {
+                    link_type: u32::from(order.u16(block, 8).unwrap_or_default()),
+                    per_second: 1_000_000,
+                    snap_length: order.u32(block, 12).unwrap_or_default(),
+                };
+                let mut option = 16;
+                while let (Some(code), Some(len)) =
+                    (order.u16(block, option), order.u16(block, option + 2))
+                {
+                    let value = option + 4;
+                    if code == 0 || value + usize::from(len) > body_end {
+                        break;
+                    }
+                    // if_tsresol: a power of ten, or of two when the high bit is set
+                    if code == 9 && len >= 1 {
+                        let resolution = u32::from(block[value] & 0x7F);
+                        let base: u64 = if block[value] & 0x80 == 0 { 10 } else { 2 };
+                        interface.per_second = base
+                            .checked_pow(resolution)
+                            .ok_or_else(|| malformed("unsupported timestamp resolution"))?;
+                    }
+                    option = value + usize::from(len).next_multiple_of(4);
+                }
+                interfaces.push(interface);
+            }
+            // Enhanced Packet Block, and the obsolete Packet Block it replaced
+            2 | 6 => {
+                let id = match block_type {
+                    6 => order.u32(block, 8),
+                    _ => order.u16(block, 8).map(u32::from),
+                };
+                let interface = id
+                    .and_then(|id| interfaces.get(id as usize))
+                    .ok_or_else(|| malformed("packet for an undescribed interface"))?;
+                let (Some(high), Some(low), Some(captured)) = (
+                    order.u32(block, 12),
+                    order.u32(block, 16),
+                    order.u32(block, 20),
+                ) else {
+                    return Err(malformed("packet block too short"));
+                };
+                let data = block
+                    .get(28..28 + captured as usize)
+                    .filter(|_| 28 + captured as usize <= body_end)
+                    .ok_or_else(|| malformed("packet data overruns its block"))?;
+                let ticks = (u64::from(high) << 32) | u64::from(low);
+                last_timestamp = timestamp(ticks, interface.per_second);
+                packets.push(Packet {
+                    timestamp: last_timestamp,
+                    link_type: interface.link_type,
+                    data: data.to_vec(),
+                });
+            }
+            // Simple Packet Block: no timestamp, and always the first interface
+            3 => {
+                let interface = interfaces
+                    .first()
+                    .ok_or_else(|| malformed("packet for an undescribed interface"))?;
+                let original = order.u32(block, 8).unwrap_or_default();
+                let mut captured = (original as usize).min(body_end.saturating_sub(12));
+                if interface.snap_length > 0 {
+                    captured = captured.min(interface.snap_length as usize);
+                }
+                packets.push(Packet {
+                    timestamp: last_timestamp,
+                    link_type: interface.link_type,
+                    data: block[12..12 + captured].to_vec(),
+                });
+            }
+            // Name resolution, statistics and other blocks carry no packets
+            _ => {}
+        }
+        at += length;
+    }
+    Ok(packets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pcap_records_in_both_byte_orders() {
+        let mut little = vec![0xD4, 0xC3, 0xB2, 0xA1, 2, 0, 4, 0];
+        little.extend_from_slice(&[0; 12]);
+        little.extend_from_slice(&1u32.to_le_bytes());
+        for value in [10u32, 500_000, 3, 3] {
+            little.extend_from_slice(&value.to_le_bytes());
+        }
+        little.extend_from_slice(&[1, 2, 3]);
+
+        let mut big = vec![0xA1, 0xB2, 0x3C, 0x4D, 0, 2, 0, 4];
+        big.extend_from_slice(&[0; 12]);
+        big.extend_from_slice(&101u32.to_be_bytes());
+        for value in [10u32, 500_000_000, 3, 3] {
+            big.extend_from_slice(&value.to_be_bytes());
+        }
+        big.extend_from_slice(&[1, 2, 3]);
+        // A record cut short by an interrupted capture is dropped
+        big.extend_from_slice(&[0, 0, 0]);
+
+        for (capture, link_type) in [(little, 1), (big, 101)] {
+            let packets = read_packets(&capture).unwrap();
+            assert_eq!(packets.len(), 1);
+            assert_eq!(packets[0].timestamp, Duration::from_millis(10_500));
+            assert_eq!(packets[0].link_type, link_type);
+            assert_eq!(packets[0].data, [1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn test_unknown_formats_are_rejected() {
+        assert!(matches!(read_packets(b"GIF89a"), Err(CaptureError::UnknownFormat)));
+        assert!(matches!(read_packets(b""), Err(CaptureError::UnknownFormat)));
+
+        let mut bad_block = vec![0x0A, 0x0D, 0x0D, 0x0A, 13, 0, 0, 0, 0x4D, 0x3C, 0x2B, 0x1A];
+        bad_block.extend_from_slice(&[0; 4]);
+        assert!(matches!(read_packets(&bad_block), Err(CaptureError::Malformed { .. })));
+    }
+}
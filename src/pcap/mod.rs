@@ -0,0 +1,93 @@
+// ABOUTME: Reads SMPP sessions out of tcpdump captures for offline troubleshooting
+// ABOUTME: pcap/pcapng parsing, TCP reassembly, PDU splitting and request/response pairing
+
+//! Capture Analysis
+//!
+//! Turns a pcap or pcapng capture (as written by tcpdump, tshark or
+//! Wireshark) into the SMPP sessions it contains, without any capture
+//! library. It is what `smpp pcap` (built with the `cli` feature) prints:
+//!
+//! * **Captures** - pcap with microsecond or nanosecond timestamps in either
+//!   byte order, and pcapng with per-interface timestamp resolution
+//! * **Links** - Ethernet (with VLAN tags), Linux cooked capture v1 and v2,
+//!   BSD loopback and raw IP, over IPv4 and IPv6
+//! * **Reassembly** - each TCP direction is put back in order; retransmitted
+//!   data is read once, and segments that arrive early wait for the gap
+//!   before them to fill
+//! * **Resynchronisation** - a capture that starts mid-connection, or loses
+//!   segments, is searched for the next valid PDU header and the skipped
+//!   octets reported as a [`Problem`]
+//! * **Timelines** - requests are paired with their responses (or a
+//!   generic_nack) by sequence_number, giving the latency of each exchange,
+//!   the error responses, and the requests left unanswered
+//!
+//! PDUs are decoded leniently as SMPP v5.0 so that every command is known;
+//! one whose body does not decode is still placed on the timeline from its
+//! header.
+//!
+//! ```rust,no_run
+//! # fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let capture = std::fs::read("smpp.pcap")?;
+//! for session in smpp::pcap::read_sessions(&capture, Some(2775))? {
+//!     println!("{session}");
+//!     for pdu in session.unanswered() {
+//!         println!("unanswered: {:?} #{}", pdu.header.command_id, pdu.header.sequence_number);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod file;
+mod session;
+mod tcp;
+
+pub use file::{CaptureError, Packet, read_packets};
+pub use session::{CapturedPdu, Direction, Exchange, Problem, Session, Summary, clock, millis};
+
+use crate::codec::{DecodePolicy, PduRegistry};
+use crate::datatypes::InterfaceVersion;
+use session::Connection;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+/// Read the SMPP sessions in a capture, ordered by when each began
+///
+/// Only TCP connections with `port` at one end are considered when it is
+/// given; otherwise every connection is, and those in which no SMPP PDU is
+/// found are left out.
+pub fn read_sessions(capture: &[u8], port: Option<u16>) -> Result<Vec<Session>, CaptureError> {
+    let mut connections: Vec<Connection> = Vec::new();
+    let mut by_endpoints: HashMap<(SocketAddr, SocketAddr), usize> = HashMap::new();
+
+    for packet in read_packets(capture)? {
+        let Some(segment) = tcp::segment(&packet) else {
+            continue;
+        };
+        if let Some(port) = port
+            && segment.source.port() != port
+            && segment.destination.port() != port
+        {
+            continue;
+        }
+        let endpoints = if segment.source < segment.destination {
+            (segment.source, segment.destination)
+        } else {
+            (segment.destination, segment.source)
+        };
+        let index = *by_endpoints.entry(endpoints).or_insert_with(|| {
+            connections.push(Connection::new(&segment));
+            connections.len() - 1
+        });
+        connections[index].push(&segment);
+    }
+
+    let registry = PduRegistry::for_version(InterfaceVersion::SmppV50)
+        .with_policy(DecodePolicy::Lenient);
+    let mut sessions: Vec<_> = connections
+        .into_iter()
+        .filter_map(|connection| connection.finish(&registry))
+        .collect();
+    sessions.sort_by_key(Session::start);
+    Ok(sessions)
+}
@@ -0,0 +1,666 @@
+// ABOUTME: Splits reassembled TCP streams into SMPP PDUs and pairs requests with responses
+// ABOUTME: Builds the per-connection timeline: latencies, error responses, unanswered requests
+
+use super::tcp::{Chunk, Segment, Stream};
+use crate::codec::{Frame, PduHeader, PduRegistry};
+use crate::datatypes::{CommandId, CommandStatus};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Which way a PDU travelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    EsmeToSmsc,
+    SmscToEsme,
+}
+
+impl Direction {
+    fn reverse(self) -> Self {
+        match self {
+            Direction::EsmeToSmsc => Direction::SmscToEsme,
+            Direction::SmscToEsme => Direction::EsmeToSmsc,
+        }
+    }
+}
+
+/// A PDU found in a captured session
+#[derive(Debug)]
+pub struct CapturedPdu {
+    /// When the segment completing the PDU was captured, since the Unix epoch
+    pub timestamp: Duration,
+    pub direction: Direction,
+    pub header: PduHeader,
+    /// The decoded PDU, or `None` when its body could not be decoded
+    pub frame: Option<Frame>,
+    /// The PDU as it was on the wire
+    pub bytes: Vec<u8>,
+    /// Index of the response to this request, or of the request this responds to
+    pub partner: Option<usize>,
+}
+
+impl CapturedPdu {
+    /// Whether the PDU is a request that should have been answered
+    pub fn expects_response(&self) -> bool {
+        needs_response(self.header.command_id)
+    }
+}
+
+/// A request and the response that answered it, as indices into [`Session::pdus`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exchange {
+    pub request: usize,
+    pub response: usize,
+    /// Time from capturing the request to capturing its response
+    pub latency: Duration,
+}
+
+/// Something in the capture that got in the way of reading the session
+///
+/// Lost segments, bytes that had to be skipped to find the next PDU header,
+/// PDUs whose bodies did not decode and reused sequence numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub timestamp: Duration,
+    pub direction: Direction,
+    pub message: String,
+}
+
+/// Counts and latencies over a session
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub pdus: usize,
+    pub exchanges: usize,
+    /// Responses with a command_status other than `Ok`, generic_nack included
+    pub error_responses: usize,
+    pub unanswered: usize,
+    pub unmatched: usize,
+    pub problems: usize,
+    pub min_latency: Option<Duration>,
+    pub avg_latency: Option<Duration>,
+    pub max_latency: Option<Duration>,
+}
+
+/// One SMPP connection found in a capture
+#[derive(Debug)]
+pub struct Session {
+    pub esme: SocketAddr,
+    pub smsc: SocketAddr,
+    /// The system_id the ESME bound with, when the bind was captured
+    pub system_id: Option<String>,
+    /// The bind command the ESME used, when the bind was captured
+    pub bind: Option<CommandId>,
+    /// Every PDU, in the order the capture completed them
+    pub pdus: Vec<CapturedPdu>,
+    pub exchanges: Vec<Exchange>,
+    pub problems: Vec<Problem>,
+}
+
+impl Session {
+    /// When the session's first PDU was captured
+    pub fn start(&self) -> Duration {
+        self.pdus.first().map_or(Duration::ZERO, |pdu| pdu.timestamp)
+    }
+
+    /// Requests that no response in the capture answered
+    pub fn unanswered(&self) -> impl Iterator<Item = &CapturedPdu> {
+        self.pdus
+            .iter()
+            .filter(|pdu| pdu.expects_response() && pdu.partner.is_none())
+    }
+
+    /// Responses to requests the capture does not contain
+    pub fn unmatched(&self) -> impl Iterator<Item = &CapturedPdu> {
+        self.pdus
+            .iter()
+            .filter(|pdu| pdu.header.command_id.is_response() && pdu.partner.is_none())
+    }
+
+    pub fn summary(&self) -> Summary {
+        let latencies: Vec<_> = self.exchanges.iter().map(|exchange| exchange.latency).collect();
+        let total: Duration = latencies.iter().sum();
+        Summary {
+            pdus: self.pdus.len(),
+            exchanges: self.exchanges.len(),
+            error_responses: self
+                .pdus
+                .iter()
+                .filter(|pdu| {
+                    pdu.header.command_id.is_response()
+                        && pdu.header.command_status != CommandStatus::Ok
+                })
+                .count(),
+            unanswered: self.unanswered().count(),
+            unmatched: self.unmatched().count(),
+            problems: self.problems.len(),
+            min_latency: latencies.iter().min().copied(),
+            avg_latency: (!latencies.is_empty()).then(|| total / latencies.len() as u32),
+            max_latency: latencies.iter().max().copied(),
+        }
+    }
+}
+
+/// Time of day (UTC) to the microsecond, as tcpdump prints it
+pub fn clock(timestamp: Duration) -> String {
+    let seconds = timestamp.as_secs() % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}.{:06}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        timestamp.subsec_micros()
+    )
+}
+
+/// Milliseconds to a tenth, for showing latencies
+pub fn millis(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// The timeline: one line per PDU, problems where they occurred, then the summary
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "session {} (ESME", self.esme)?;
+        if let Some(system_id) = &self.system_id {
+            write!(f, " {system_id:?}")?;
+        }
+        if let Some(bind) = self.bind {
+            write!(f, ", {}", bind.name())?;
+        }
+        writeln!(f, ") <-> {} (SMSC)", self.smsc)?;
+
+        let mut problems = self.problems.iter().peekable();
+        for pdu in &self.pdus {
+            while let Some(problem) = problems.next_if(|p| p.timestamp <= pdu.timestamp) {
+                writeln!(f, "{}  !  {}", clock(problem.timestamp), problem.message)?;
+            }
+            let arrow = match pdu.direction {
+                Direction::EsmeToSmsc => '>',
+                Direction::SmscToEsme => '<',
+            };
+            let header = &pdu.header;
+            write!(
+                f,
+                "{}  {arrow}  {} #{}",
+                clock(pdu.timestamp),
+                header.command_id.name(),
+                header.sequence_number
+            )?;
+            if header.command_id.is_response() {
+                write!(f, " {:?}", header.command_status)?;
+                match pdu.partner {
+                    Some(request) => write!(
+                        f,
+                        "  {}",
+                        millis(pdu.timestamp.saturating_sub(self.pdus[request].timestamp))
+                    )?,
+                    None => write!(f, "  unmatched")?,
+                }
+            } else if pdu.expects_response() && pdu.partner.is_none() {
+                write!(f, "  unanswered")?;
+            }
+            writeln!(f)?;
+        }
+        for problem in problems {
+            writeln!(f, "{}  !  {}", clock(problem.timestamp), problem.message)?;
+        }
+
+        let summary = self.summary();
+        write!(f, "{} PDUs, {} exchanges", summary.pdus, summary.exchanges)?;
+        if let (Some(min), Some(avg), Some(max)) =
+            (summary.min_latency, summary.avg_latency, summary.max_latency)
+        {
+            let (min, avg, max) = (millis(min), millis(avg), millis(max));
+            write!(f, " (latency min {min} / avg {avg} / max {max})")?;
+        }
+        write!(
+            f,
+            ", {} error responses, {} unanswered, {} unmatched, {} problems",
+            summary.error_responses, summary.unanswered, summary.unmatched, summary.problems
+        )
+    }
+}
+
+/// Something found while splitting a stream
+enum Piece {
+    Pdu(Duration, PduHeader, Vec<u8>),
+    Problem(Duration, String),
+}
+
+/// Cuts one direction's bytes into PDUs at their command_length
+///
+/// After lost data, an invalid header, or when the capture began after the
+/// connection did, the splitter resynchronises: it scans forward an octet at
+/// a time for something that decodes as a PDU header.
+struct Splitter {
+    buffer: Vec<u8>,
+    /// Stream offset of the first buffered octet
+    offset: u64,
+    resync: bool,
+    /// Octets passed over while resynchronising
+    skipped: u64,
+    last: Duration,
+}
+
+impl Splitter {
+    fn new(offset: u64, resync: bool) -> Self {
+        Splitter {
+            buffer: Vec::new(),
+            offset,
+            resync,
+            skipped: 0,
+            last: Duration::ZERO,
+        }
+    }
+
+    fn push(&mut self, chunk: Chunk, pieces: &mut Vec<Piece>) {
+        self.last = chunk.timestamp;
+        if chunk.missing > 0 {
+            let mut message = format!(
+                "{} octets missing from the capture at stream offset {}",
+                chunk.missing,
+                chunk.offset - chunk.missing
+            );
+            if !self.buffer.is_empty() && !self.resync {
+                message.push_str(&format!(
+                    "; dropped {} octets of an incomplete PDU",
+                    self.buffer.len()
+                ));
+            }
+            pieces.push(Piece::Problem(chunk.timestamp, message));
+            self.skipped += if self.resync { self.buffer.len() as u64 } else { 0 };
+            self.buffer.clear();
+            self.offset = chunk.offset;
+            self.resync = true;
+        }
+        self.buffer.extend_from_slice(&chunk.data);
+
+        let mut at = 0;
+        while self.buffer.len() - at >= PduHeader::SIZE {
+            let header = PduHeader::decode(&mut Cursor::new(&self.buffer[at..]));
+            if self.resync {
+                if header.is_err() {
+                    at += 1;
+                    self.skipped += 1;
+                    continue;
+                }
+                if self.skipped > 0 {
+                    pieces.push(Piece::Problem(
+                        chunk.timestamp,
+                        format!(
+                            "skipped {} octets to the next PDU header at stream offset {}",
+                            self.skipped,
+                            self.offset + at as u64
+                        ),
+                    ));
+                }
+                self.resync = false;
+                self.skipped = 0;
+            }
+            let header = match header {
+                Ok(header) => header,
+                Err(error) => {
+                    pieces.push(Piece::Problem(
+                        chunk.timestamp,
+                        format!(
+                            "invalid PDU header at stream offset {}: {error}",
+                            self.offset + at as u64
+                        ),
+                    ));
+                    self.resync = true;
+                    continue;
+                }
+            };
+            let length = header.command_length as usize;
+            if self.buffer.len() - at < length {
+                break;
+            }
+            pieces.push(Piece::Pdu(
+                chunk.timestamp,
+                header,
+                self.buffer[at..at + length].to_vec(),
+            ));
+            at += length;
+        }
+        self.buffer.drain(..at);
+        self.offset += at as u64;
+    }
+
+    fn finish(self, pieces: &mut Vec<Piece>) {
+        let left = self.buffer.len() as u64;
+        let message = if self.resync && self.skipped + left > 0 {
+            format!("{} octets at the end of the stream held no PDU header", self.skipped + left)
+        } else if !self.resync && left > 0 {
+            format!("the stream ends {left} octets into an incomplete PDU")
+        } else {
+            return;
+        };
+        pieces.push(Piece::Problem(self.last, message));
+    }
+}
+
+/// One direction of a connection
+#[derive(Default)]
+struct Half {
+    stream: Stream,
+    splitter: Option<Splitter>,
+    syn: bool,
+}
+
+impl Half {
+    fn push(&mut self, chunk: Chunk, pieces: &mut Vec<Piece>) {
+        // A stream whose start was not captured may begin mid-PDU
+        let resync = !self.syn;
+        self.splitter
+            .get_or_insert_with(|| Splitter::new(chunk.offset, resync))
+            .push(chunk, pieces);
+    }
+}
+
+/// A TCP connection as seen so far
+pub(crate) struct Connection {
+    first_source: SocketAddr,
+    first_destination: SocketAddr,
+    /// The side that sent the opening SYN
+    opener: Option<SocketAddr>,
+    halves: HashMap<SocketAddr, Half>,
+    pieces: Vec<(SocketAddr, Piece)>,
+}
+
+impl Connection {
+    pub fn new(segment: &Segment) -> Self {
+        Connection {
+            first_source: segment.source,
+            first_destination: segment.destination,
+            opener: None,
+            halves: HashMap::new(),
+            pieces: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, segment: &Segment) {
+        // Only a SYN without ACK opens a connection; the SYN-ACK answers it
+        if segment.syn && self.opener.is_none() && !self.halves.contains_key(&segment.source) {
+            self.opener = Some(segment.source);
+        }
+        let half = self.halves.entry(segment.source).or_default();
+        half.syn |= segment.syn;
+        if let Some(chunk) = half.stream.push(segment) {
+            let mut pieces = Vec::new();
+            half.push(chunk, &mut pieces);
+            self.pieces.extend(pieces.into_iter().map(|piece| (segment.source, piece)));
+        }
+    }
+
+    /// Finish reading the connection, `None` if it carried no SMPP
+    pub fn finish(mut self, registry: &PduRegistry) -> Option<Session> {
+        let mut sources: Vec<_> = self.halves.keys().copied().collect();
+        sources.sort();
+        for source in sources {
+            let mut half = self.halves.remove(&source).unwrap_or_default();
+            let mut pieces = Vec::new();
+            while let Some(chunk) = half.stream.skip_gap() {
+                half.push(chunk, &mut pieces);
+            }
+            if let Some(splitter) = half.splitter {
+                splitter.finish(&mut pieces);
+            }
+            self.pieces.extend(pieces.into_iter().map(|piece| (source, piece)));
+        }
+        if !self.pieces.iter().any(|(_, piece)| matches!(piece, Piece::Pdu(..))) {
+            return None;
+        }
+        // Data held back by a gap was only split now, but belongs where it was captured
+        self.pieces.sort_by_key(|(_, piece)| match piece {
+            Piece::Pdu(timestamp, ..) | Piece::Problem(timestamp, _) => *timestamp,
+        });
+
+        let esme = self.esme();
+        let smsc = if esme == self.first_source {
+            self.first_destination
+        } else {
+            self.first_source
+        };
+        let direction = |source| {
+            if source == esme {
+                Direction::EsmeToSmsc
+            } else {
+                Direction::SmscToEsme
+            }
+        };
+
+        let mut session = Session {
+            esme,
+            smsc,
+            system_id: None,
+            bind: None,
+            pdus: Vec::new(),
+            exchanges: Vec::new(),
+            problems: Vec::new(),
+        };
+        let mut pending: HashMap<(Direction, u32), usize> = HashMap::new();
+        for (source, piece) in self.pieces {
+            let direction = direction(source);
+            let (timestamp, header, bytes) = match piece {
+                Piece::Pdu(timestamp, header, bytes) => (timestamp, header, bytes),
+                Piece::Problem(timestamp, message) => {
+                    session.problems.push(Problem {
+                        timestamp,
+                        direction,
+                        message,
+                    });
+                    continue;
+                }
+            };
+
+            let mut body = Cursor::new(&bytes[PduHeader::SIZE..]);
+            let frame = match registry.decode_frame(header.clone(), &mut body) {
+                Ok(decoded) => Some(decoded.frame),
+                Err(error) => {
+                    session.problems.push(Problem {
+                        timestamp,
+                        direction,
+                        message: format!(
+                            "cannot decode {} #{}: {error}",
+                            header.command_id.name(),
+                            header.sequence_number
+                        ),
+                    });
+                    None
+                }
+            };
+            if is_bind(header.command_id) && session.bind.is_none() {
+                session.bind = Some(header.command_id);
+                session.system_id = first_c_string(&bytes[PduHeader::SIZE..]);
+            }
+
+            let index = session.pdus.len();
+            let key = |direction| (direction, header.sequence_number);
+            let mut partner = None;
+            if header.command_id.is_response() {
+                let request = pending.get(&key(direction.reverse())).copied();
+                if let Some(request) = request.filter(|&request| {
+                    answers(session.pdus[request].header.command_id, header.command_id)
+                }) {
+                    pending.remove(&key(direction.reverse()));
+                    session.pdus[request].partner = Some(index);
+                    session.exchanges.push(Exchange {
+                        request,
+                        response: index,
+                        latency: timestamp.saturating_sub(session.pdus[request].timestamp),
+                    });
+                    partner = Some(request);
+                }
+            } else if needs_response(header.command_id)
+                && pending.insert(key(direction), index).is_some()
+            {
+                session.problems.push(Problem {
+                    timestamp,
+                    direction,
+                    message: format!(
+                        "sequence_number {} reused while a request with it is unanswered",
+                        header.sequence_number
+                    ),
+                });
+            }
+            session.pdus.push(CapturedPdu {
+                timestamp,
+                direction,
+                header,
+                frame,
+                bytes,
+                partner,
+            });
+        }
+        Some(session)
+    }
+
+    /// The ESME's side of the connection
+    ///
+    /// The side that bound, or else that opened the connection, or else the
+    /// side not on the well-known SMPP port.
+    fn esme(&self) -> SocketAddr {
+        let other = |addr| {
+            if addr == self.first_source {
+                self.first_destination
+            } else {
+                self.first_source
+            }
+        };
+        for (source, piece) in &self.pieces {
+            if let Piece::Pdu(_, header, _) = piece {
+                if is_bind(header.command_id) {
+                    return *source;
+                }
+                if header.command_id == CommandId::Outbind {
+                    return other(*source);
+                }
+            }
+        }
+        if let Some(opener) = self.opener {
+            return opener;
+        }
+        if matches!(self.first_source.port(), 2775 | 2776) {
+            return self.first_destination;
+        }
+        self.first_source
+    }
+}
+
+fn is_bind(command_id: CommandId) -> bool {
+    matches!(
+        command_id,
+        CommandId::BindTransmitter | CommandId::BindReceiver | CommandId::BindTransceiver
+    )
+}
+
+/// Every request but alert_notification and outbind has a response
+fn needs_response(command_id: CommandId) -> bool {
+    !command_id.is_response()
+        && !matches!(command_id, CommandId::AlertNotification | CommandId::Outbind)
+}
+
+/// Whether `response` can answer `request`
+fn answers(request: CommandId, response: CommandId) -> bool {
+    response == CommandId::GenericNack || response as u32 == request as u32 | 0x8000_0000
+}
+
+/// The leading C-Octet String of a body, which for a bind is its system_id
+///
+/// Read directly so that it is found even when the bind's body does not decode.
+fn first_c_string(body: &[u8]) -> Option<String> {
+    let end = body.iter().position(|&octet| octet == 0)?;
+    std::str::from_utf8(&body[..end]).ok().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(offset: u64, missing: u64, data: &[u8]) -> Chunk {
+        Chunk {
+            timestamp: Duration::from_millis(offset),
+            offset,
+            missing,
+            data: data.to_vec(),
+        }
+    }
+
+    fn enquire_link(sequence_number: u32) -> Vec<u8> {
+        let mut pdu = vec![0, 0, 0, 16, 0, 0, 0, 0x15, 0, 0, 0, 0];
+        pdu.extend_from_slice(&sequence_number.to_be_bytes());
+        pdu
+    }
+
+    fn sequence_numbers(pieces: &[Piece]) -> Vec<u32> {
+        pieces
+            .iter()
+            .filter_map(|piece| match piece {
+                Piece::Pdu(_, header, _) => Some(header.sequence_number),
+                Piece::Problem(..) => None,
+            })
+            .collect()
+    }
+
+    fn problems(pieces: &[Piece]) -> Vec<&str> {
+        pieces
+            .iter()
+            .filter_map(|piece| match piece {
+                Piece::Problem(_, message) => Some(message.as_str()),
+                Piece::Pdu(..) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_splitter_waits_for_whole_pdus() {
+        let stream = [enquire_link(1), enquire_link(2)].concat();
+        let mut splitter = Splitter::new(0, false);
+        let mut pieces = Vec::new();
+        splitter.push(chunk(0, 0, &stream[..20]), &mut pieces);
+        assert_eq!(sequence_numbers(&pieces), [1]);
+        splitter.push(chunk(20, 0, &stream[20..]), &mut pieces);
+        assert_eq!(sequence_numbers(&pieces), [1, 2]);
+        splitter.finish(&mut pieces);
+        assert!(problems(&pieces).is_empty());
+    }
+
+    #[test]
+    fn test_splitter_resynchronises_after_lost_data() {
+        let mut splitter = Splitter::new(0, false);
+        let mut pieces = Vec::new();
+        splitter.push(chunk(0, 0, &enquire_link(1)[..10]), &mut pieces);
+
+        let after_gap = [&[0xAA; 5][..], &enquire_link(2)].concat();
+        splitter.push(chunk(40, 24, &after_gap), &mut pieces);
+        assert_eq!(sequence_numbers(&pieces), [2]);
+        assert_eq!(
+            problems(&pieces),
+            [
+                "24 octets missing from the capture at stream offset 16; \
+                 dropped 10 octets of an incomplete PDU",
+                "skipped 5 octets to the next PDU header at stream offset 45",
+            ]
+        );
+
+        splitter.push(chunk(61, 0, b"GET / HTTP/1.1\r\n\r\n"), &mut pieces);
+        splitter.finish(&mut pieces);
+        assert_eq!(
+            problems(&pieces)[2..],
+            [
+                "invalid PDU header at stream offset 61: Invalid command_id: 0x2f204854",
+                "18 octets at the end of the stream held no PDU header",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_responses_answer_their_request_or_generic_nack() {
+        assert!(answers(CommandId::SubmitSm, CommandId::SubmitSmResp));
+        assert!(answers(CommandId::SubmitSm, CommandId::GenericNack));
+        assert!(!answers(CommandId::SubmitSm, CommandId::DeliverSmResp));
+        assert_eq!(clock(Duration::new(1_700_000_000, 1_500_000)), "22:13:20.001500");
+    }
+}
@@ -0,0 +1,246 @@
+// ABOUTME: Extracts TCP segments from captured frames and puts each direction's bytes back in order
+// ABOUTME: Handles Ethernet, VLAN, Linux cooked and raw IP links over IPv4 and IPv6
+
+use super::Packet;
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LOOP: u32 = 108;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+/// A TCP segment carried by a captured packet
+#[derive(Debug)]
+pub(crate) struct Segment {
+    pub timestamp: Duration,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub sequence: u32,
+    pub syn: bool,
+    pub payload: Vec<u8>,
+}
+
+/// The TCP segment in a packet, if it carries one
+///
+/// IP fragments are skipped; SMPP peers do not send segments large enough
+/// to be fragmented in practice.
+pub(crate) fn segment(packet: &Packet) -> Option<Segment> {
+    let data = &packet.data[..];
+    let ip = match packet.link_type {
+        LINKTYPE_ETHERNET => {
+            let mut at = 12;
+            let mut ether_type = u16::from_be_bytes([*data.get(at)?, *data.get(at + 1)?]);
+            // 802.1Q and 802.1ad tags
+            while matches!(ether_type, 0x8100 | 0x88A8) {
+                at += 4;
+                ether_type = u16::from_be_bytes([*data.get(at)?, *data.get(at + 1)?]);
+            }
+            data.get(at + 2..)?
+        }
+        LINKTYPE_LINUX_SLL => data.get(16..)?,
+        LINKTYPE_LINUX_SLL2 => data.get(20..)?,
+        LINKTYPE_NULL | LINKTYPE_LOOP => data.get(4..)?,
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => data,
+        _ => return None,
+    };
+
+    let (source, destination, tcp) = match ip.first()? >> 4 {
+        4 => ipv4(ip)?,
+        6 => ipv6(ip)?,
+        _ => return None,
+    };
+
+    let offset = usize::from(tcp.get(12)? >> 4) * 4;
+    let flags = *tcp.get(13)?;
+    Some(Segment {
+        timestamp: packet.timestamp,
+        source: SocketAddr::new(source, u16::from_be_bytes([tcp[0], tcp[1]])),
+        destination: SocketAddr::new(destination, u16::from_be_bytes([tcp[2], tcp[3]])),
+        sequence: u32::from_be_bytes([tcp[4], tcp[5], tcp[6], tcp[7]]),
+        syn: flags & 0x02 != 0,
+        payload: tcp.get(offset..)?.to_vec(),
+    })
+}
+
+fn ipv4(ip: &[u8]) -> Option<(IpAddr, IpAddr, &[u8])> {
+    let header = usize::from(ip.first()? & 0x0F) * 4;
+    let total = usize::from(u16::from_be_bytes([*ip.get(2)?, *ip.get(3)?]));
+    let fragment = u16::from_be_bytes([*ip.get(6)?, *ip.get(7)?]);
+    // More fragments, or a fragment offset
+    if fragment & 0x3FFF != 0 || *ip.get(9)? != 6 {
+        return None;
+    }
+    let source: [u8; 4] = ip.get(12..16)?.try_into().ok()?;
+    let destination: [u8; 4] = ip.get(16..20)?.try_into().ok()?;
+    // The total length excludes any Ethernet padding
+    let tcp = ip.get(header..total.min(ip.len()))?;
+    Some((Ipv4Addr::from(source).into(), Ipv4Addr::from(destination).into(), tcp))
+}
+
+fn ipv6(ip: &[u8]) -> Option<(IpAddr, IpAddr, &[u8])> {
+    let payload = usize::from(u16::from_be_bytes([*ip.get(4)?, *ip.get(5)?]));
+    let source: [u8; 16] = ip.get(8..24)?.try_into().ok()?;
+    let destination: [u8; 16] = ip.get(24..40)?.try_into().ok()?;
+    let end = (40 + payload).min(ip.len());
+
+    let mut next = *ip.get(6)?;
+    let mut at = 40;
+    loop {
+        match next {
+            6 => break,
+            // Hop-by-hop, routing and destination options
+            0 | 43 | 60 => {
+                next = *ip.get(at)?;
+                at += (usize::from(*ip.get(at + 1)?) + 1) * 8;
+            }
+            // Fragments, and anything that is not TCP
+            _ => return None,
+        }
+    }
+    let tcp = ip.get(at..end)?;
+    Some((Ipv6Addr::from(source).into(), Ipv6Addr::from(destination).into(), tcp))
+}
+
+/// One direction of a TCP connection, reassembled in sequence order
+///
+/// Retransmitted and overlapping data is delivered once, and segments that
+/// arrive early are held until the data before them does. A capture that
+/// starts mid-connection starts the stream at the first segment seen.
+#[derive(Debug, Default)]
+pub(crate) struct Stream {
+    /// Sequence number of the stream's first octet
+    base: Option<u32>,
+    /// Octets delivered so far
+    delivered: u64,
+    /// Segments waiting for the data before them, by stream offset
+    early: BTreeMap<u64, (Duration, Vec<u8>)>,
+}
+
+/// Data delivered by a [`Stream`]
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Chunk {
+    /// When the segment that completed this data was captured
+    pub timestamp: Duration,
+    /// Stream offset of the first octet
+    pub offset: u64,
+    /// Octets missing from the stream just before this data
+    pub missing: u64,
+    pub data: Vec<u8>,
+}
+
+impl Stream {
+    /// Add a segment, returning the data it makes available in order
+    pub fn push(&mut self, segment: &Segment) -> Option<Chunk> {
+        if segment.syn {
+            self.base.get_or_insert(segment.sequence.wrapping_add(1));
+        }
+        if segment.payload.is_empty() {
+            return None;
+        }
+        let base = *self.base.get_or_insert(segment.sequence);
+
+        // Sequence numbers behind the stream's start wrap to huge offsets
+        let offset = u64::from(segment.sequence.wrapping_sub(base));
+        if offset >= 1 << 31 {
+            return None;
+        }
+        let end = offset + segment.payload.len() as u64;
+        if end <= self.delivered {
+            return None;
+        }
+        let kept = self.early.entry(offset).or_insert((segment.timestamp, Vec::new()));
+        if segment.payload.len() > kept.1.len() {
+            *kept = (segment.timestamp, segment.payload.clone());
+        }
+        self.drain(segment.timestamp, false)
+    }
+
+    /// Deliver data held after a gap that was never filled
+    ///
+    /// Called once the capture is over, when the missing segments can no
+    /// longer arrive.
+    pub fn skip_gap(&mut self) -> Option<Chunk> {
+        let timestamp = self.early.values().next()?.0;
+        self.drain(timestamp, true)
+    }
+
+    fn drain(&mut self, timestamp: Duration, skip_gap: bool) -> Option<Chunk> {
+        let start = self.delivered;
+        let mut missing = 0;
+        let mut data = Vec::new();
+        while let Some(entry) = self.early.first_entry() {
+            let offset = *entry.key();
+            if offset > self.delivered {
+                if !(skip_gap && data.is_empty()) {
+                    break;
+                }
+                missing = offset - self.delivered;
+                self.delivered = offset;
+            }
+            let (_, payload) = entry.remove();
+            let overlap = (self.delivered - offset) as usize;
+            if overlap < payload.len() {
+                data.extend_from_slice(&payload[overlap..]);
+                self.delivered = offset + payload.len() as u64;
+            }
+        }
+        (!data.is_empty()).then(|| Chunk {
+            timestamp,
+            offset: start + missing,
+            missing,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(sequence: u32, payload: &[u8]) -> Segment {
+        Segment {
+            timestamp: Duration::from_secs(u64::from(sequence)),
+            source: "10.0.0.1:1".parse().unwrap(),
+            destination: "10.0.0.2:2".parse().unwrap(),
+            sequence,
+            syn: false,
+            payload: payload.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_stream_reorders_and_drops_retransmissions() {
+        let mut stream = Stream::default();
+        let syn = Segment {
+            syn: true,
+            ..segment(u32::MAX, b"")
+        };
+        assert_eq!(stream.push(&syn), None);
+
+        // Sequence numbers wrap past zero
+        assert_eq!(stream.push(&segment(0, b"abc")).unwrap().data, b"abc");
+        assert_eq!(stream.push(&segment(6, b"ghi")), None);
+        let chunk = stream.push(&segment(3, b"def")).unwrap();
+        assert_eq!((chunk.offset, chunk.data.as_slice()), (3, &b"defghi"[..]));
+        assert_eq!(stream.push(&segment(0, b"abc")), None);
+        // An overlapping retransmission delivers only the new octets
+        assert_eq!(stream.push(&segment(7, b"hijk")).unwrap().data, b"jk");
+    }
+
+    #[test]
+    fn test_stream_skips_gaps_that_never_fill() {
+        let mut stream = Stream::default();
+        stream.push(&segment(100, b"ab")).unwrap();
+        assert_eq!(stream.push(&segment(110, b"xyz")), None);
+
+        let chunk = stream.skip_gap().unwrap();
+        assert_eq!((chunk.offset, chunk.missing, chunk.data.as_slice()), (10, 8, &b"xyz"[..]));
+        assert_eq!(stream.skip_gap(), None);
+    }
+}
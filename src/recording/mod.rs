@@ -0,0 +1,461 @@
+// ABOUTME: Records the PDUs of a session with timestamps and direction to a compact file
+// ABOUTME: Recordings replay as either peer, to reproduce incidents and build regression tests
+
+//! Session Recording and Replay
+//!
+//! A [`Recorder`] attached to a [`Connection`](crate::connection::Connection)
+//! (or a [`DefaultClient`](crate::client::DefaultClient)) writes down every
+//! PDU exactly as it crossed the wire, with when it did and which way. The
+//! resulting [`Recording`] can be played back by a [`Replayer`]:
+//!
+//! * **As the ESME** - connect to an SMSC and send the recorded client's
+//!   PDUs, checking that the SMSC answers as it did
+//! * **As the SMSC** - accept a client and answer it as the recorded SMSC
+//!   did, checking that the client sends what it sent
+//! * **Timing** - the original gaps between PDUs, a multiple of their speed,
+//!   or as fast as the peer keeps up (see [`Timing`])
+//!
+//! Replay is deterministic: PDUs are sent in their recorded order and byte
+//! for byte, except that responses take the sequence_number of the live
+//! request they answer. Where the live peer does something else, the
+//! [`ReplayReport`] says so.
+//!
+//! ## File Format
+//!
+//! All integers are big-endian, as in SMPP itself:
+//!
+//! ```text
+//! header  "SMPPREC" 0x01 | start: u64, microseconds since the Unix epoch
+//! event   direction: u8 (0 outbound, 1 inbound)
+//!         | offset: u64, nanoseconds since the start | the PDU's octets
+//! ```
+//!
+//! ```rust,no_run
+//! use smpp::client::{BindCredentials, DefaultClient, SmppClient, SmppConnection};
+//! use smpp::recording::{Recorder, Recording, Replayer, Timing};
+//! use tokio::net::TcpListener;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//! // Record a session with a real SMSC...
+//! let mut client = DefaultClient::connect("smsc.example.com:2775").await?;
+//! client.set_recorder(Recorder::create("session.smpprec")?);
+//! client.bind(&BindCredentials::transmitter("system_id", "password")).await?;
+//! client.unbind().await?;
+//! client.disconnect().await?;
+//!
+//! // ...and later stand in for it, ten times faster
+//! let recording = Recording::load("session.smpprec")?;
+//! let listener = TcpListener::bind("127.0.0.1:2775").await?;
+//! let report = Replayer::new(recording)
+//!     .with_timing(Timing::Accelerated(10.0))
+//!     .as_smsc(&listener)
+//!     .await?;
+//! assert!(report.is_faithful(), "{report}");
+//! # Ok(())
+//! # }
+//! ```
+
+mod replay;
+
+pub use replay::{Divergence, ReplayReport, Replayer, Role, Timing};
+
+use crate::codec::{MAX_PDU_SIZE, PduHeader};
+use crate::datatypes::CommandId;
+use bytes::Bytes;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use thiserror::Error;
+
+const MAGIC: &[u8; 7] = b"SMPPREC";
+const FORMAT_VERSION: u8 = 1;
+const HEADER_SIZE: usize = 16;
+const EVENT_HEADER_SIZE: usize = 9;
+
+/// Which way a recorded PDU went, seen from the recording connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Written by the connection
+    Outbound,
+    /// Read by the connection
+    Inbound,
+}
+
+/// Errors reading a recording
+#[derive(Debug, Error)]
+pub enum RecordingError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("not a session recording")]
+    NotARecording,
+
+    #[error("unsupported recording format version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("malformed recording at offset {offset}: {reason}")]
+    Malformed { offset: usize, reason: String },
+}
+
+/// A PDU in a recording
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// Time since the recording started
+    pub offset: Duration,
+    pub direction: Direction,
+    /// The PDU as it was on the wire
+    pub pdu: Bytes,
+}
+
+impl Event {
+    /// The command_id, if it is one this crate knows
+    pub fn command_id(&self) -> Option<CommandId> {
+        CommandId::try_from(self.header_field(4)).ok()
+    }
+
+    pub fn command_status(&self) -> u32 {
+        self.header_field(8)
+    }
+
+    pub fn sequence_number(&self) -> u32 {
+        self.header_field(12)
+    }
+
+    fn header_field(&self, at: usize) -> u32 {
+        self.pdu
+            .get(at..at + 4)
+            .map_or(0, |field| u32::from_be_bytes(field.try_into().expect("four octets")))
+    }
+}
+
+/// The PDUs of a recorded session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    /// When recording started, since the Unix epoch
+    pub start: Duration,
+    /// Every PDU, in the order the connection read or wrote them
+    pub events: Vec<Event>,
+}
+
+impl Recording {
+    /// Read a recording from its file format
+    ///
+    /// An event cut short at the end, as left by a process that stopped
+    /// mid-write, ends the recording rather than failing it.
+    pub fn parse(data: &[u8]) -> Result<Self, RecordingError> {
+        if data.len() < HEADER_SIZE || &data[..7] != MAGIC {
+            return Err(RecordingError::NotARecording);
+        }
+        if data[7] != FORMAT_VERSION {
+            return Err(RecordingError::UnsupportedVersion(data[7]));
+        }
+        let micros = u64::from_be_bytes(data[8..16].try_into().expect("eight octets"));
+
+        let mut events = Vec::new();
+        let mut at = HEADER_SIZE;
+        while let Some(event) = data.get(at..at + EVENT_HEADER_SIZE + 4) {
+            let direction = match event[0] {
+                0 => Direction::Outbound,
+                1 => Direction::Inbound,
+                other => {
+                    return Err(RecordingError::Malformed {
+                        offset: at,
+                        reason: format!("unknown direction {other}"),
+                    });
+                }
+            };
+            let nanos = u64::from_be_bytes(event[1..9].try_into().expect("eight octets"));
+            let length = u32::from_be_bytes(event[9..13].try_into().expect("four octets"));
+            if !(PduHeader::SIZE as u32..=MAX_PDU_SIZE).contains(&length) {
+                return Err(RecordingError::Malformed {
+                    offset: at,
+                    reason: format!("invalid command_length {length}"),
+                });
+            }
+            let start = at + EVENT_HEADER_SIZE;
+            let Some(pdu) = data.get(start..start + length as usize) else {
+                break;
+            };
+            events.push(Event {
+                offset: Duration::from_nanos(nanos),
+                direction,
+                pdu: Bytes::copy_from_slice(pdu),
+            });
+            at = start + length as usize;
+        }
+
+        Ok(Recording {
+            start: Duration::from_micros(micros),
+            events,
+        })
+    }
+
+    /// Read a recording file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// The recording in its file format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = header(self.start);
+        for event in &self.events {
+            encode_event(&mut data, event.offset, event.direction, &event.pdu);
+        }
+        data
+    }
+
+    /// Write the recording to a file
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+
+    /// The role the recording connection played
+    ///
+    /// The side that sent a bind is the ESME. Without one, the side sending
+    /// outbind, deliver_sm or alert_notification is the SMSC, and the side
+    /// submitting messages is the ESME; failing all that, the recording is
+    /// taken to have been made by a client.
+    pub fn recorded_as(&self) -> Role {
+        let sender = |commands: &[CommandId]| {
+            self.events
+                .iter()
+                .find(|event| event.command_id().is_some_and(|id| commands.contains(&id)))
+                .map(|event| event.direction)
+        };
+        let binds = [
+            CommandId::BindTransmitter,
+            CommandId::BindReceiver,
+            CommandId::BindTransceiver,
+        ];
+        let smsc_requests = [
+            CommandId::Outbind,
+            CommandId::DeliverSm,
+            CommandId::AlertNotification,
+        ];
+        let esme_requests = [
+            CommandId::SubmitSm,
+            CommandId::SubmitMulti,
+            CommandId::QuerySm,
+            CommandId::CancelSm,
+            CommandId::ReplaceSm,
+        ];
+        let esme = sender(&binds)
+            .or_else(|| sender(&smsc_requests).map(opposite))
+            .or_else(|| sender(&esme_requests))
+            .unwrap_or(Direction::Outbound);
+        match esme {
+            Direction::Outbound => Role::Esme,
+            Direction::Inbound => Role::Smsc,
+        }
+    }
+}
+
+fn opposite(direction: Direction) -> Direction {
+    match direction {
+        Direction::Outbound => Direction::Inbound,
+        Direction::Inbound => Direction::Outbound,
+    }
+}
+
+fn header(start: Duration) -> Vec<u8> {
+    let mut data = Vec::with_capacity(HEADER_SIZE);
+    data.extend_from_slice(MAGIC);
+    data.push(FORMAT_VERSION);
+    data.extend_from_slice(&(start.as_micros() as u64).to_be_bytes());
+    data
+}
+
+fn encode_event(data: &mut Vec<u8>, offset: Duration, direction: Direction, pdu: &[u8]) {
+    data.push(match direction {
+        Direction::Outbound => 0,
+        Direction::Inbound => 1,
+    });
+    data.extend_from_slice(&(offset.as_nanos() as u64).to_be_bytes());
+    data.extend_from_slice(pdu);
+}
+
+/// Writes every PDU a connection reads or writes to a recording
+///
+/// Clones share the same recording, so one recorder can follow a client
+/// from binding through to its [`InboundSession`](crate::client::InboundSession).
+/// File recordings are buffered; the file is complete once
+/// [`flush`](Self::flush) has been called or the last clone dropped.
+///
+/// Recording never interrupts a session: if writing fails, the failure is
+/// logged and recording stops.
+#[derive(Clone)]
+pub struct Recorder {
+    inner: Arc<Mutex<Sink>>,
+    memory: Option<Arc<Mutex<Vec<u8>>>>,
+}
+
+struct Sink {
+    writer: Box<dyn Write + Send>,
+    start: Instant,
+    failed: bool,
+}
+
+/// A `Write` into memory shared with the recorder that owns it
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.lock().expect("recording buffer poisoned").extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Recorder {
+    /// Record to `writer`, which receives the file format
+    pub fn new(writer: impl Write + Send + 'static) -> io::Result<Self> {
+        Self::with_writer(Box::new(writer), None)
+    }
+
+    /// Record to a new file at `path`, replacing any file already there
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+
+    /// Record in memory, to be read back with [`recording`](Self::recording)
+    pub fn in_memory() -> Self {
+        let memory = Arc::new(Mutex::new(Vec::new()));
+        let writer = Box::new(SharedBuffer(memory.clone()));
+        Self::with_writer(writer, Some(memory)).expect("writing to memory cannot fail")
+    }
+
+    fn with_writer(
+        mut writer: Box<dyn Write + Send>,
+        memory: Option<Arc<Mutex<Vec<u8>>>>,
+    ) -> io::Result<Self> {
+        let start = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        writer.write_all(&header(start))?;
+        Ok(Recorder {
+            inner: Arc::new(Mutex::new(Sink {
+                writer,
+                start: Instant::now(),
+                failed: false,
+            })),
+            memory,
+        })
+    }
+
+    /// Record one PDU
+    pub fn record(&self, direction: Direction, pdu: &[u8]) {
+        let mut sink = self.inner.lock().expect("recorder poisoned");
+        if sink.failed {
+            return;
+        }
+        let mut event = Vec::with_capacity(EVENT_HEADER_SIZE + pdu.len());
+        encode_event(&mut event, sink.start.elapsed(), direction, pdu);
+        if let Err(e) = sink.writer.write_all(&event) {
+            tracing::warn!("Recording failed, no further PDUs will be recorded: {e}");
+            sink.failed = true;
+        }
+    }
+
+    /// Write out anything buffered
+    pub fn flush(&self) -> io::Result<()> {
+        self.inner.lock().expect("recorder poisoned").writer.flush()
+    }
+
+    /// What has been recorded so far, for a recorder made with [`in_memory`](Self::in_memory)
+    pub fn recording(&self) -> Option<Recording> {
+        let memory = self.memory.as_ref()?.lock().expect("recording buffer poisoned");
+        Recording::parse(&memory).ok()
+    }
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("in_memory", &self.memory.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pdu(command_id: u32, sequence_number: u32) -> Bytes {
+        let mut pdu = vec![0, 0, 0, 16];
+        pdu.extend_from_slice(&command_id.to_be_bytes());
+        pdu.extend_from_slice(&[0; 4]);
+        pdu.extend_from_slice(&sequence_number.to_be_bytes());
+        Bytes::from(pdu)
+    }
+
+    fn event(millis: u64, direction: Direction, pdu: Bytes) -> Event {
+        Event {
+            offset: Duration::from_millis(millis),
+            direction,
+            pdu,
+        }
+    }
+
+    #[test]
+    fn test_recordings_round_trip_through_the_file_format() {
+        let recording = Recording {
+            start: Duration::from_micros(1_700_000_000_123_456),
+            events: vec![
+                event(0, Direction::Outbound, pdu(0x15, 1)),
+                event(7, Direction::Inbound, pdu(0x8000_0015, 1)),
+            ],
+        };
+        let data = recording.to_bytes();
+        assert_eq!(data.len(), HEADER_SIZE + 2 * (EVENT_HEADER_SIZE + 16));
+        assert_eq!(Recording::parse(&data).unwrap(), recording);
+
+        // A final event cut short is dropped
+        let cut = Recording::parse(&data[..data.len() - 3]).unwrap();
+        assert_eq!(cut.events, recording.events[..1]);
+
+        assert!(matches!(Recording::parse(b"SMPPREC"), Err(RecordingError::NotARecording)));
+        let mut future = data.clone();
+        future[7] = 2;
+        assert!(matches!(Recording::parse(&future), Err(RecordingError::UnsupportedVersion(2))));
+        let mut bad = data;
+        bad[HEADER_SIZE] = 9;
+        assert!(matches!(Recording::parse(&bad), Err(RecordingError::Malformed { .. })));
+    }
+
+    #[test]
+    fn test_in_memory_recorder() {
+        let recorder = Recorder::in_memory();
+        recorder.clone().record(Direction::Outbound, &pdu(0x04, 2));
+        recorder.record(Direction::Inbound, &pdu(0x8000_0004, 2));
+
+        let recording = recorder.recording().unwrap();
+        assert_eq!(recording.events.len(), 2);
+        assert!(recording.events[0].offset <= recording.events[1].offset);
+        assert_eq!(recording.events[1].command_id(), Some(CommandId::SubmitSmResp));
+        assert_eq!(recording.events[1].sequence_number(), 2);
+        assert!(Recorder::new(Vec::new()).unwrap().recording().is_none());
+    }
+
+    #[test]
+    fn test_the_recording_side_is_found_from_the_traffic() {
+        let recording = |events| Recording {
+            start: Duration::ZERO,
+            events,
+        };
+        let bind = recording(vec![event(0, Direction::Inbound, pdu(0x09, 1))]);
+        assert_eq!(bind.recorded_as(), Role::Smsc);
+        let deliver = recording(vec![
+            event(0, Direction::Outbound, pdu(0x15, 1)),
+            event(1, Direction::Inbound, pdu(0x05, 2)),
+        ]);
+        assert_eq!(deliver.recorded_as(), Role::Esme);
+        let keep_alive = recording(vec![event(0, Direction::Inbound, pdu(0x15, 1))]);
+        assert_eq!(keep_alive.recorded_as(), Role::Esme);
+    }
+}
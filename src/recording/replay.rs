@@ -0,0 +1,269 @@
+// ABOUTME: Plays one side of a recorded session against a live peer, in order and on time
+// ABOUTME: Reports every point where the live peer does not do what the recorded one did
+
+use super::{Direction, Event, Recording};
+use crate::codec::PduRegistry;
+use crate::connection::Connection;
+use crate::datatypes::{CommandStatus, InterfaceVersion};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::time::Instant;
+
+/// The peer a replay stands in for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Esme,
+    Smsc,
+}
+
+/// How the gaps between recorded PDUs are reproduced
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Timing {
+    /// As they were recorded
+    Original,
+    /// Divided by the factor given: `Accelerated(10.0)` is ten times faster
+    Accelerated(f64),
+    /// Not at all: each PDU is sent as soon as the one before it allows
+    AsFastAsPossible,
+}
+
+impl Timing {
+    fn scale(self, gap: Duration) -> Duration {
+        match self {
+            Timing::Original => gap,
+            Timing::Accelerated(factor) if factor.is_finite() && factor > 0.0 => {
+                gap.div_f64(factor)
+            }
+            Timing::Accelerated(_) | Timing::AsFastAsPossible => Duration::ZERO,
+        }
+    }
+}
+
+/// A point where the live peer departed from the recording
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Index of the recorded event, in [`Recording::events`]
+    pub event: usize,
+    pub message: String,
+}
+
+/// The outcome of a replay
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// PDUs sent to the live peer
+    pub sent: usize,
+    /// PDUs received from the live peer
+    pub received: usize,
+    pub divergences: Vec<Divergence>,
+}
+
+impl ReplayReport {
+    /// Whether the live peer did everything the recorded one did
+    pub fn is_faithful(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sent {} PDUs, received {}, {} divergences",
+            self.sent,
+            self.received,
+            self.divergences.len()
+        )?;
+        for divergence in &self.divergences {
+            write!(f, "\n  event {}: {}", divergence.event, divergence.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays back one side of a [`Recording`]
+///
+/// The replayer's own PDUs are sent when they fall due, measured from the
+/// last PDU received from the live peer so that the recorded response times
+/// are kept whatever the live peer's speed. The peer's recorded PDUs are
+/// waited for in turn, up to the peer timeout each (five seconds by default),
+/// and compared by command_id and, for responses, command_status.
+#[derive(Debug, Clone)]
+pub struct Replayer {
+    recording: Recording,
+    timing: Timing,
+    peer_timeout: Duration,
+}
+
+impl Replayer {
+    pub fn new(recording: Recording) -> Self {
+        Replayer {
+            recording,
+            timing: Timing::Original,
+            peer_timeout: Duration::from_secs(5),
+        }
+    }
+
+    pub fn with_timing(mut self, timing: Timing) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    /// How long to wait for each PDU the live peer should send
+    pub fn with_peer_timeout(mut self, timeout: Duration) -> Self {
+        self.peer_timeout = timeout;
+        self
+    }
+
+    /// Connect to an SMSC and play the recorded ESME
+    pub async fn as_esme(&self, addr: impl ToSocketAddrs) -> crate::Result<ReplayReport> {
+        let mut connection = connection(TcpStream::connect(addr).await?);
+        self.play(Role::Esme, &mut connection).await
+    }
+
+    /// Accept one client on `listener` and play the recorded SMSC
+    pub async fn as_smsc(&self, listener: &TcpListener) -> crate::Result<ReplayReport> {
+        let (socket, _) = listener.accept().await?;
+        self.play(Role::Smsc, &mut connection(socket)).await
+    }
+
+    /// Play `role` over a connection that is already open
+    ///
+    /// Replay stops early, with a divergence, if the peer disconnects.
+    pub async fn play(
+        &self,
+        role: Role,
+        connection: &mut Connection,
+    ) -> crate::Result<ReplayReport> {
+        let ours = if self.recording.recorded_as() == role {
+            Direction::Outbound
+        } else {
+            Direction::Inbound
+        };
+
+        let mut report = ReplayReport::default();
+        // Recorded sequence_numbers of the peer's requests, to the live ones
+        let mut sequence_numbers = HashMap::new();
+        // The last PDU received, live and as recorded, which the timing follows
+        let mut anchor = (Instant::now(), Duration::ZERO);
+
+        for (index, event) in self.recording.events.iter().enumerate() {
+            if event.direction == ours {
+                let gap = event.offset.saturating_sub(anchor.1);
+                tokio::time::sleep_until(anchor.0 + self.timing.scale(gap)).await;
+
+                let mut pdu = event.pdu.to_vec();
+                if is_response(event)
+                    && let Some(live) = sequence_numbers.get(&event.sequence_number())
+                {
+                    pdu[12..16].copy_from_slice(&u32::to_be_bytes(*live));
+                }
+                connection.write_pdu(&pdu).await?;
+                report.sent += 1;
+                continue;
+            }
+
+            let divergence = |message| Divergence {
+                event: index,
+                message,
+            };
+            let pdu = match tokio::time::timeout(self.peer_timeout, connection.read_pdu()).await {
+                Ok(Ok(Some(pdu))) => pdu,
+                Ok(Ok(None)) => {
+                    let message =
+                        format!("the peer disconnected instead of sending {}", describe(event));
+                    report.divergences.push(divergence(message));
+                    return Ok(report);
+                }
+                Ok(Err(e)) => return Err(e),
+                Err(_) => {
+                    let message = format!(
+                        "expected {} within {:?}, but nothing arrived",
+                        describe(event),
+                        self.peer_timeout
+                    );
+                    report.divergences.push(divergence(message));
+                    continue;
+                }
+            };
+            report.received += 1;
+            anchor = (Instant::now(), event.offset);
+
+            let live = Event {
+                offset: event.offset,
+                direction: event.direction,
+                pdu,
+            };
+            let matches = live.header_field(4) == event.header_field(4)
+                && (!is_response(event) || live.command_status() == event.command_status());
+            if !matches {
+                let message = format!("expected {}, received {}", describe(event), describe(&live));
+                report.divergences.push(divergence(message));
+            }
+            if !is_response(&live) {
+                sequence_numbers.insert(event.sequence_number(), live.sequence_number());
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// A connection that passes every PDU through, known command or not
+fn connection(socket: TcpStream) -> Connection {
+    Connection::with_registry(socket, PduRegistry::for_version(InterfaceVersion::SmppV50))
+}
+
+fn is_response(event: &Event) -> bool {
+    event.pdu.get(4).is_some_and(|octet| octet & 0x80 != 0)
+}
+
+/// e.g. `submit_sm_resp #2 (InvalidDestinationAddress)`
+fn describe(event: &Event) -> String {
+    let command = match event.command_id() {
+        Some(command_id) => command_id.name(),
+        None => format!("command {:#010x}", event.header_field(4)),
+    };
+    let mut text = format!("{command} #{}", event.sequence_number());
+    if is_response(event) || event.command_status() != 0 {
+        match CommandStatus::try_from(event.command_status()) {
+            Ok(status) => text.push_str(&format!(" ({status:?})")),
+            Err(_) => text.push_str(&format!(" (status {:#010x})", event.command_status())),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    #[test]
+    fn test_timing_scales_gaps() {
+        let gap = Duration::from_millis(500);
+        assert_eq!(Timing::Original.scale(gap), gap);
+        assert_eq!(Timing::Accelerated(10.0).scale(gap), Duration::from_millis(50));
+        assert_eq!(Timing::AsFastAsPossible.scale(gap), Duration::ZERO);
+        assert_eq!(Timing::Accelerated(0.0).scale(gap), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_events_are_described_by_command_and_status() {
+        let event = |header: [u32; 4]| Event {
+            offset: Duration::ZERO,
+            direction: Direction::Inbound,
+            pdu: header.iter().flat_map(|field| field.to_be_bytes()).collect::<Bytes>(),
+        };
+        assert_eq!(describe(&event([16, 0x15, 0, 3])), "enquire_link #3");
+        assert_eq!(
+            describe(&event([16, 0x8000_0004, 0x0B, 2])),
+            "submit_sm_resp #2 (InvalidDestinationAddress)"
+        );
+        assert_eq!(
+            describe(&event([16, 0x8000_0000, 3, 2])),
+            "generic_nack #2 (InvalidCommandId)"
+        );
+        assert_eq!(describe(&event([16, 0x0001_0200, 0, 9])), "command 0x00010200 #9");
+    }
+}
@@ -0,0 +1,188 @@
+// ABOUTME: One-line PDU descriptions for trace logs, with passwords and message content redacted
+// ABOUTME: The redaction policy says which of them are hidden; both are by default
+
+//! PDU Redaction
+//!
+//! Connections log every PDU they read and write at trace level, field by
+//! field (see [`inspect`](crate::inspect)), as a [`RedactedPdu`]. Its
+//! [`RedactionPolicy`] decides whether passwords and message content are
+//! written out or hidden, message content leaving only its length:
+//!
+//! ```text
+//! bind_transmitter #1 Ok system_id="esme" password=<redacted> ...
+//! ```
+
+use crate::datatypes::InterfaceVersion;
+use crate::inspect::inspect_as;
+use std::fmt;
+
+/// Which fields of a PDU are left out of logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RedactionPolicy {
+    /// The password of bind PDUs and outbind
+    pub passwords: bool,
+    /// short_message and the message_payload TLV
+    pub message_content: bool,
+}
+
+impl Default for RedactionPolicy {
+    fn default() -> Self {
+        Self {
+            passwords: true,
+            message_content: true,
+        }
+    }
+}
+
+impl RedactionPolicy {
+    /// Log every field as it is, e.g. while debugging against a test SMSC
+    pub fn none() -> Self {
+        Self {
+            passwords: false,
+            message_content: false,
+        }
+    }
+
+    pub fn with_passwords(mut self, redact: bool) -> Self {
+        self.passwords = redact;
+        self
+    }
+
+    pub fn with_message_content(mut self, redact: bool) -> Self {
+        self.message_content = redact;
+        self
+    }
+
+    /// Whether the field named `field` is redacted
+    pub fn redacts(&self, field: &str) -> bool {
+        match field {
+            "password" => self.passwords,
+            "short_message" | "message_payload" => self.message_content,
+            _ => false,
+        }
+    }
+}
+
+/// Displays an encoded PDU on one line, redacted by a [`RedactionPolicy`]
+///
+/// The PDU is only decoded when it is displayed, so a `RedactedPdu` given to
+/// a disabled log event costs nothing.
+#[derive(Debug, Clone, Copy)]
+pub struct RedactedPdu<'a> {
+    pdu: &'a [u8],
+    version: InterfaceVersion,
+    policy: RedactionPolicy,
+}
+
+impl<'a> RedactedPdu<'a> {
+    pub fn new(pdu: &'a [u8], version: InterfaceVersion, policy: RedactionPolicy) -> Self {
+        Self {
+            pdu,
+            version,
+            policy,
+        }
+    }
+}
+
+impl fmt::Display for RedactedPdu<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inspection = inspect_as(self.pdu, self.version);
+        let value = |name: &str| {
+            let field = inspection.field(name)?;
+            // The value may carry a numeric code after the name, e.g. `Ok (0x00000000)`
+            Some(field.value.split(" (").next().unwrap_or_default().to_string())
+        };
+        write!(
+            f,
+            "{} #{} {}",
+            value("command_id").unwrap_or_else(|| "unknown command".to_string()),
+            value("sequence_number").unwrap_or_default(),
+            value("command_status").unwrap_or_default()
+        )?;
+
+        let header = ["command_length", "command_id", "command_status", "sequence_number"];
+        for field in &inspection.fields {
+            if header.contains(&field.name.as_str()) {
+                continue;
+            }
+            if !self.policy.redacts(&field.name) {
+                write!(f, " {}={}", field.name, field.value)?;
+            } else if field.name == "password" {
+                // Not even its length
+                write!(f, " password=<redacted>")?;
+            } else {
+                write!(f, " {}=<redacted, {} octets>", field.name, field.bytes.len())?;
+            }
+        }
+        if let Some(failure) = &inspection.failure {
+            write!(f, " (undecodable at {}: {})", failure.field, failure.message)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Encodable;
+    use crate::datatypes::{BindTransmitter, SubmitSm};
+
+    fn bind() -> Vec<u8> {
+        BindTransmitter::builder()
+            .sequence_number(1)
+            .system_id("esme")
+            .password("secret")
+            .build()
+            .unwrap()
+            .to_bytes()
+            .to_vec()
+    }
+
+    fn submit() -> Vec<u8> {
+        SubmitSm::builder()
+            .sequence_number(2)
+            .source_addr("1234")
+            .destination_addr("5678")
+            .short_message("Meet at noon")
+            .build()
+            .unwrap()
+            .to_bytes()
+            .to_vec()
+    }
+
+    fn show(pdu: &[u8], policy: RedactionPolicy) -> String {
+        RedactedPdu::new(pdu, InterfaceVersion::SmppV34, policy).to_string()
+    }
+
+    #[test]
+    fn test_passwords_and_content_are_redacted_by_default() {
+        let bind = show(&bind(), RedactionPolicy::default());
+        assert!(bind.starts_with("bind_transmitter #1 Ok system_id=\"esme\""), "{bind}");
+        assert!(bind.contains("password=<redacted>"), "{bind}");
+        assert!(!bind.contains("secret"));
+
+        let submit = show(&submit(), RedactionPolicy::default());
+        assert!(submit.starts_with("submit_sm #2 Ok"), "{submit}");
+        assert!(submit.contains("destination_addr=\"5678\""), "{submit}");
+        assert!(submit.contains("short_message=<redacted, 12 octets>"), "{submit}");
+        assert!(!submit.contains("noon"));
+    }
+
+    #[test]
+    fn test_policy_can_reveal_either_field() {
+        let passwords_only = RedactionPolicy::default().with_message_content(false);
+        assert!(show(&submit(), passwords_only).contains("short_message=\"Meet at noon\""));
+        assert!(!show(&bind(), passwords_only).contains("secret"));
+
+        assert!(show(&bind(), RedactionPolicy::none()).contains("password=\"secret\""));
+    }
+
+    #[test]
+    fn test_undecodable_pdus_are_described_as_far_as_they_go() {
+        let bind = bind();
+        let truncated = show(&bind[..20], RedactionPolicy::default());
+        assert!(truncated.starts_with("bind_transmitter #1 Ok"), "{truncated}");
+        assert!(truncated.contains("undecodable at"), "{truncated}");
+        assert!(!truncated.contains("secret"));
+    }
+}
@@ -0,0 +1,288 @@
+// ABOUTME: Configuration of the SMSC simulator: accounts, versions, receipts, MO and failures
+// ABOUTME: Builder-style settings shared by the library simulator and the smsc-simulator binary
+
+use crate::datatypes::{CommandStatus, InterfaceVersion};
+use std::time::Duration;
+
+/// Credentials the simulator accepts in a bind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    /// The bind's system_id
+    pub system_id: String,
+    /// The bind's password
+    pub password: String,
+}
+
+impl Account {
+    /// Create an account
+    pub fn new(system_id: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            system_id: system_id.into(),
+            password: password.into(),
+        }
+    }
+}
+
+/// A mobile originated message the simulator delivers as deliver_sm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoMessage {
+    /// The subscriber sending the message
+    pub source_addr: String,
+    /// The address the message is sent to, typically a short code
+    pub destination_addr: String,
+    /// The message text
+    pub text: String,
+}
+
+impl MoMessage {
+    /// Create an MO message
+    pub fn new(
+        source_addr: impl Into<String>,
+        destination_addr: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        Self {
+            source_addr: source_addr.into(),
+            destination_addr: destination_addr.into(),
+            text: text.into(),
+        }
+    }
+}
+
+/// An MO message delivered to every receiver session some time after it binds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptedMo {
+    /// How long after the bind to deliver the message
+    pub delay: Duration,
+    /// The message
+    pub message: MoMessage,
+}
+
+/// Faults injected into responses to message submissions
+///
+/// Each mode applies to every Nth submit_sm, submit_multi or data_sm on a
+/// session, counting from 1. When several apply to the same submission
+/// the first in the order disconnect, drop, throttle, queue full, slow wins.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FailureModes {
+    /// Close the connection without answering the Nth submission
+    pub disconnect_after: Option<u32>,
+    /// Never answer every Nth submission
+    pub drop_every: Option<u32>,
+    /// Answer every Nth submission with ESME_RTHROTTLED
+    pub throttle_every: Option<u32>,
+    /// Answer every Nth submission with ESME_RMSGQFUL
+    pub queue_full_every: Option<u32>,
+    /// Delay the answer to every Nth submission by `slow_delay`
+    pub slow_every: Option<u32>,
+    /// How long slow answers are delayed (default: none)
+    pub slow_delay: Duration,
+}
+
+/// How the simulator answers one message submission
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Fault {
+    Disconnect,
+    Drop,
+    Throttle,
+    QueueFull,
+    Slow(Duration),
+}
+
+impl FailureModes {
+    /// No injected faults
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Close the connection on the Nth submission
+    pub fn with_disconnect_after(mut self, n: u32) -> Self {
+        self.disconnect_after = Some(n);
+        self
+    }
+
+    /// Leave every Nth submission unanswered
+    pub fn with_drop_every(mut self, n: u32) -> Self {
+        self.drop_every = Some(n);
+        self
+    }
+
+    /// Throttle every Nth submission
+    pub fn with_throttle_every(mut self, n: u32) -> Self {
+        self.throttle_every = Some(n);
+        self
+    }
+
+    /// Reject every Nth submission with a full message queue
+    pub fn with_queue_full_every(mut self, n: u32) -> Self {
+        self.queue_full_every = Some(n);
+        self
+    }
+
+    /// Delay the answer to every Nth submission
+    pub fn with_slow_every(mut self, n: u32, delay: Duration) -> Self {
+        self.slow_every = Some(n);
+        self.slow_delay = delay;
+        self
+    }
+
+    /// The fault injected into submission number `n` of a session
+    pub(crate) fn fault_for(&self, n: u32) -> Option<Fault> {
+        let every = |period: Option<u32>| period.is_some_and(|p| p > 0 && n.is_multiple_of(p));
+        if self.disconnect_after == Some(n) {
+            Some(Fault::Disconnect)
+        } else if every(self.drop_every) {
+            Some(Fault::Drop)
+        } else if every(self.throttle_every) {
+            Some(Fault::Throttle)
+        } else if every(self.queue_full_every) {
+            Some(Fault::QueueFull)
+        } else if every(self.slow_every) {
+            Some(Fault::Slow(self.slow_delay))
+        } else {
+            None
+        }
+    }
+}
+
+/// Configuration of a [`Simulator`](crate::simulator::Simulator)
+///
+/// # Example
+///
+/// ```rust
+/// use smpp::datatypes::InterfaceVersion;
+/// use smpp::simulator::{Account, FailureModes, SimulatorConfig};
+/// use std::time::Duration;
+///
+/// let config = SimulatorConfig::new()
+///     .with_account(Account::new("test", "secret"))
+///     .with_versions(vec![InterfaceVersion::SmppV34])
+///     .with_receipt_delay(Some(Duration::from_millis(100)))
+///     .with_failures(FailureModes::none().with_throttle_every(10));
+///
+/// assert_eq!(config.accounts.len(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatorConfig {
+    /// system_id returned in bind responses (default: "SMSC-SIM")
+    pub system_id: String,
+
+    /// Accepted credentials; empty accepts any bind (default: empty)
+    pub accounts: Vec<Account>,
+
+    /// Accepted interface versions (default: v3.3, v3.4 and v5.0)
+    pub versions: Vec<InterfaceVersion>,
+
+    /// Delay before a requested delivery receipt is sent; `None` sends none (default: 500ms)
+    pub receipt_delay: Option<Duration>,
+
+    /// MO messages delivered to each receiver session after it binds
+    pub scripted_mo: Vec<ScriptedMo>,
+
+    /// Faults injected into submission responses (default: none)
+    pub failures: FailureModes,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            system_id: "SMSC-SIM".to_string(),
+            accounts: Vec::new(),
+            versions: vec![
+                InterfaceVersion::SmppV33,
+                InterfaceVersion::SmppV34,
+                InterfaceVersion::SmppV50,
+            ],
+            receipt_delay: Some(Duration::from_millis(500)),
+            scripted_mo: Vec::new(),
+            failures: FailureModes::default(),
+        }
+    }
+}
+
+impl SimulatorConfig {
+    /// Create a configuration with the defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the system_id returned in bind responses
+    pub fn with_system_id(mut self, system_id: impl Into<String>) -> Self {
+        self.system_id = system_id.into();
+        self
+    }
+
+    /// Accept binds with `account`; once any account is added, others are refused
+    pub fn with_account(mut self, account: Account) -> Self {
+        self.accounts.push(account);
+        self
+    }
+
+    /// Set the accepted interface versions
+    pub fn with_versions(mut self, versions: Vec<InterfaceVersion>) -> Self {
+        self.versions = versions;
+        self
+    }
+
+    /// Set the delay before delivery receipts, or `None` to send none
+    pub fn with_receipt_delay(mut self, delay: Option<Duration>) -> Self {
+        self.receipt_delay = delay;
+        self
+    }
+
+    /// Deliver `message` to each receiver session `delay` after it binds
+    pub fn with_scripted_mo(mut self, delay: Duration, message: MoMessage) -> Self {
+        self.scripted_mo.push(ScriptedMo { delay, message });
+        self
+    }
+
+    /// Set the faults injected into submission responses
+    pub fn with_failures(mut self, failures: FailureModes) -> Self {
+        self.failures = failures;
+        self
+    }
+
+    /// Whether a bind with these credentials is accepted, as the status to answer it with
+    pub(crate) fn authenticate(&self, system_id: &str, password: &str) -> CommandStatus {
+        if self.accounts.is_empty() {
+            return CommandStatus::Ok;
+        }
+        match self.accounts.iter().find(|account| account.system_id == system_id) {
+            Some(account) if account.password == password => CommandStatus::Ok,
+            Some(_) => CommandStatus::InvalidPassword,
+            None => CommandStatus::InvalidSystemId,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fault_precedence_and_periods() {
+        let failures = FailureModes::none()
+            .with_disconnect_after(6)
+            .with_drop_every(3)
+            .with_throttle_every(2)
+            .with_slow_every(5, Duration::from_millis(50));
+
+        assert_eq!(failures.fault_for(1), None);
+        assert_eq!(failures.fault_for(2), Some(Fault::Throttle));
+        assert_eq!(failures.fault_for(3), Some(Fault::Drop));
+        assert_eq!(failures.fault_for(5), Some(Fault::Slow(Duration::from_millis(50))));
+        assert_eq!(failures.fault_for(6), Some(Fault::Disconnect));
+        assert_eq!(FailureModes::none().with_queue_full_every(0).fault_for(4), None);
+    }
+
+    #[test]
+    fn test_authentication() {
+        let open = SimulatorConfig::new();
+        assert_eq!(open.authenticate("anyone", ""), CommandStatus::Ok);
+
+        let config = SimulatorConfig::new().with_account(Account::new("test", "secret"));
+        assert_eq!(config.authenticate("test", "secret"), CommandStatus::Ok);
+        assert_eq!(config.authenticate("test", "wrong"), CommandStatus::InvalidPassword);
+        assert_eq!(config.authenticate("other", "secret"), CommandStatus::InvalidSystemId);
+    }
+}
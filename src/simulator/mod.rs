@@ -0,0 +1,328 @@
+// ABOUTME: In-process SMSC simulator for exercising SMPP clients without a real SMSC
+// ABOUTME: Accepts binds, answers submissions, sends receipts and MO messages, injects faults
+
+//! SMSC Simulator
+//!
+//! A small SMSC that speaks enough SMPP to run clients against in tests and
+//! CI, available with the `simulator` feature. It is what the
+//! `smsc-simulator` binary (built with the `cli` and `simulator` features)
+//! runs, and can be started in-process:
+//!
+//! * **Binds** - transmitter, receiver and transceiver, with optional
+//!   credential checks and interface version negotiation
+//! * **Submissions** - submit_sm, submit_multi and data_sm answered with
+//!   generated message ids; query_sm reports their state
+//! * **Delivery receipts** - sent as deliver_sm after a configurable delay
+//!   when the submission requested one
+//! * **MO messages** - scripted per bind, or injected through a
+//!   [`SimulatorHandle`]
+//! * **Failure modes** - throttling, `ESME_RMSGQFUL`, dropped and slow
+//!   responses, and abrupt disconnects (see [`FailureModes`])
+//!
+//! ```rust,no_run
+//! use smpp::client::{BindCredentials, DefaultClient, SmsMessage};
+//! use smpp::client::{SmppClient, SmppConnection, SmppTransmitter};
+//! use smpp::simulator::{Simulator, SimulatorConfig};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let simulator = Simulator::bind("127.0.0.1:0", SimulatorConfig::default()).await?;
+//! let addr = simulator.local_addr()?;
+//! let handle = simulator.handle();
+//! tokio::spawn(simulator.run());
+//!
+//! let mut client = DefaultClient::connect(addr).await?;
+//! client.bind(&BindCredentials::transmitter("test", "test")).await?;
+//! let message_id = client.send_sms(&SmsMessage::new("447700900123", "1234", "Hello")).await?;
+//! assert_eq!(handle.stats().submissions, 1);
+//! # Ok(())
+//! # }
+//! ```
+
+mod config;
+mod session;
+
+pub use config::{Account, FailureModes, MoMessage, ScriptedMo, SimulatorConfig};
+
+use crate::datatypes::{DeliverSm, MessageState};
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+/// Counters describing what the simulator has done
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimulatorStats {
+    /// Successful binds
+    pub binds: u64,
+    /// Message submissions received, whatever they were answered with
+    pub submissions: u64,
+    /// Delivery receipts sent
+    pub receipts: u64,
+    /// MO messages sent
+    pub mo_messages: u64,
+    /// Sessions currently bound
+    pub active_sessions: u64,
+}
+
+/// An SMSC simulator listening for connections
+pub struct Simulator {
+    listener: TcpListener,
+    state: Arc<State>,
+}
+
+impl Simulator {
+    /// Listen on `addr`; port 0 picks a free port
+    pub async fn bind<A: ToSocketAddrs>(addr: A, config: SimulatorConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        Ok(Self {
+            listener,
+            state: Arc::new(State::new(config)),
+        })
+    }
+
+    /// The address the simulator listens on
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// A handle for inspecting and driving the simulator while it runs
+    pub fn handle(&self) -> SimulatorHandle {
+        SimulatorHandle {
+            state: self.state.clone(),
+        }
+    }
+
+    /// Accept connections until accepting fails, serving each in its own task
+    pub async fn run(self) -> io::Result<()> {
+        info!("SMSC simulator listening on {}", self.listener.local_addr()?);
+        loop {
+            let (socket, peer) = self.listener.accept().await?;
+            let id = self.state.next_session.fetch_add(1, Ordering::Relaxed);
+            debug!(session = id, %peer, "Accepted connection");
+            tokio::spawn(session::run(socket, self.state.clone(), id));
+        }
+    }
+}
+
+/// Inspects and drives a running [`Simulator`]
+#[derive(Clone)]
+pub struct SimulatorHandle {
+    state: Arc<State>,
+}
+
+impl SimulatorHandle {
+    /// Deliver an MO message to every bound receiver or transceiver
+    ///
+    /// Returns how many sessions it was sent to.
+    pub fn inject_mo(&self, message: &MoMessage) -> usize {
+        self.state.broadcast(None, message)
+    }
+
+    /// Deliver an MO message to the receivers bound with `system_id`
+    pub fn inject_mo_to(&self, system_id: &str, message: &MoMessage) -> usize {
+        self.state.broadcast(Some(system_id), message)
+    }
+
+    /// The state of a message the simulator accepted
+    pub fn message_state(&self, message_id: &str) -> Option<MessageState> {
+        self.state
+            .messages
+            .lock()
+            .unwrap()
+            .get(message_id)
+            .map(|record| record.state.clone())
+    }
+
+    /// A snapshot of the simulator's counters
+    pub fn stats(&self) -> SimulatorStats {
+        SimulatorStats {
+            binds: self.state.binds.load(Ordering::Relaxed),
+            submissions: self.state.submissions.load(Ordering::Relaxed),
+            receipts: self.state.receipts.load(Ordering::Relaxed),
+            mo_messages: self.state.mo_messages.load(Ordering::Relaxed),
+            active_sessions: self.state.sessions.lock().unwrap().len() as u64,
+        }
+    }
+}
+
+/// PDUs handed to a session from outside its own read loop
+pub(crate) enum Outbound {
+    /// A response to write as is
+    Response(crate::Frame),
+    /// A deliver_sm to send with the session's next sequence number
+    Deliver(Box<DeliverSm>),
+}
+
+/// A bound session that can be sent PDUs
+struct SessionEntry {
+    system_id: String,
+    receives: bool,
+    outbound: mpsc::UnboundedSender<Outbound>,
+}
+
+/// What the simulator knows about a message it accepted
+struct MessageRecord {
+    state: MessageState,
+    final_date: Option<String>,
+}
+
+/// State shared by all sessions of a simulator
+pub(crate) struct State {
+    config: SimulatorConfig,
+    next_session: AtomicU64,
+    next_message: AtomicU64,
+    sessions: Mutex<HashMap<u64, SessionEntry>>,
+    messages: Mutex<HashMap<String, MessageRecord>>,
+    binds: AtomicU64,
+    submissions: AtomicU64,
+    receipts: AtomicU64,
+    mo_messages: AtomicU64,
+}
+
+impl State {
+    fn new(config: SimulatorConfig) -> Self {
+        Self {
+            config,
+            next_session: AtomicU64::new(1),
+            next_message: AtomicU64::new(1),
+            sessions: Mutex::new(HashMap::new()),
+            messages: Mutex::new(HashMap::new()),
+            binds: AtomicU64::new(0),
+            submissions: AtomicU64::new(0),
+            receipts: AtomicU64::new(0),
+            mo_messages: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn config(&self) -> &SimulatorConfig {
+        &self.config
+    }
+
+    /// Add a bound session
+    pub(crate) fn register(
+        &self,
+        id: u64,
+        system_id: &str,
+        receives: bool,
+        outbound: mpsc::UnboundedSender<Outbound>,
+    ) {
+        self.binds.fetch_add(1, Ordering::Relaxed);
+        let entry = SessionEntry {
+            system_id: system_id.to_string(),
+            receives,
+            outbound,
+        };
+        self.sessions.lock().unwrap().insert(id, entry);
+    }
+
+    /// Remove a session that unbound or disconnected
+    pub(crate) fn unregister(&self, id: u64) {
+        self.sessions.lock().unwrap().remove(&id);
+    }
+
+    /// Count a message submission
+    pub(crate) fn count_submission(&self) {
+        self.submissions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Allocate a message id and record the message as en route
+    ///
+    /// Ids are 8 hex digits so that they are valid in every SMPP version.
+    pub(crate) fn accept_message(&self) -> String {
+        let n = self.next_message.fetch_add(1, Ordering::Relaxed);
+        let message_id = format!("{:08x}", n & 0xFFFF_FFFF);
+        self.messages.lock().unwrap().insert(
+            message_id.clone(),
+            MessageRecord {
+                state: MessageState::Enroute,
+                final_date: None,
+            },
+        );
+        message_id
+    }
+
+    /// The state and final date of an accepted message
+    pub(crate) fn lookup(&self, message_id: &str) -> Option<(MessageState, Option<String>)> {
+        self.messages
+            .lock()
+            .unwrap()
+            .get(message_id)
+            .map(|record| (record.state.clone(), record.final_date.clone()))
+    }
+
+    /// Send a delivery receipt, preferring the session that submitted the message
+    ///
+    /// Falls back to any receiver bound with the same system_id. The message
+    /// is marked delivered whether or not a receiver was bound.
+    pub(crate) fn deliver_receipt(
+        &self,
+        message_id: &str,
+        done_date: &str,
+        submitter: u64,
+        system_id: &str,
+        receipt: DeliverSm,
+    ) {
+        if let Some(record) = self.messages.lock().unwrap().get_mut(message_id) {
+            record.state = MessageState::Delivered;
+            record.final_date = Some(done_date.to_string());
+        }
+
+        let sessions = self.sessions.lock().unwrap();
+        let target = sessions
+            .get(&submitter)
+            .filter(|entry| entry.receives)
+            .or_else(|| {
+                sessions
+                    .values()
+                    .find(|entry| entry.receives && entry.system_id == system_id)
+            });
+        match target {
+            Some(entry) => {
+                if entry.outbound.send(Outbound::Deliver(Box::new(receipt))).is_ok() {
+                    self.receipts.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            None => debug!(message_id, "No receiver bound for delivery receipt"),
+        }
+    }
+
+    /// Send an MO message to the session `id`
+    pub(crate) fn deliver_mo(&self, id: u64, message: &MoMessage) -> bool {
+        let Some(pdu) = session::mo_pdu(message) else {
+            return false;
+        };
+        let sessions = self.sessions.lock().unwrap();
+        let sent = sessions
+            .get(&id)
+            .is_some_and(|entry| entry.outbound.send(Outbound::Deliver(Box::new(pdu))).is_ok());
+        if sent {
+            self.mo_messages.fetch_add(1, Ordering::Relaxed);
+        }
+        sent
+    }
+
+    /// Send an MO message to every receiver, or those bound with `system_id`
+    fn broadcast(&self, system_id: Option<&str>, message: &MoMessage) -> usize {
+        let Some(pdu) = session::mo_pdu(message) else {
+            return 0;
+        };
+        let sessions = self.sessions.lock().unwrap();
+        let sent = sessions
+            .values()
+            .filter(|entry| entry.receives)
+            .filter(|entry| system_id.is_none_or(|system_id| entry.system_id == system_id))
+            .filter(|entry| {
+                entry
+                    .outbound
+                    .send(Outbound::Deliver(Box::new(pdu.clone())))
+                    .is_ok()
+            })
+            .count();
+        self.mo_messages.fetch_add(sent as u64, Ordering::Relaxed);
+        sent
+    }
+}
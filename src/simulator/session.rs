@@ -0,0 +1,504 @@
+// ABOUTME: One simulated SMSC session: bind negotiation, submissions and outbound deliveries
+// ABOUTME: Reads PDUs from a client connection and writes responses, receipts and MO messages
+
+use super::config::Fault;
+use super::{MoMessage, Outbound, State};
+use crate::Frame;
+use crate::client::sequence::next_after;
+use crate::codec::PduRegistry;
+use crate::connection::Connection;
+use crate::datatypes::{
+    BindReceiverResponse, BindTransceiverResponse, BindTransmitterResponse, CommandId,
+    CommandStatus, DataSmResponse, DeliverSm, EnquireLinkResponse, EsmClass, GenericNack,
+    InterfaceVersion, MessageId, MessageState, Password, QuerySmResponse, SmppDateTime,
+    SubmitMultiResponse, SubmitSmResponse, SystemId, Tlv, UnbindResponse, tags,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// esm_class of a deliver_sm carrying an SMSC delivery receipt
+const ESM_CLASS_DELIVERY_RECEIPT: u8 = 0x04;
+
+/// How many characters of the original message a receipt quotes
+const RECEIPT_TEXT_LEN: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BindKind {
+    Transmitter,
+    Receiver,
+    Transceiver,
+}
+
+impl BindKind {
+    fn transmits(self) -> bool {
+        self != BindKind::Receiver
+    }
+
+    fn receives(self) -> bool {
+        self != BindKind::Transmitter
+    }
+}
+
+/// The bind a session was accepted with
+struct Bound {
+    system_id: String,
+    kind: BindKind,
+    version: InterfaceVersion,
+}
+
+/// What a submission needs to produce its delivery receipt
+struct ReceiptRequest {
+    source_addr: String,
+    destination_addr: String,
+    text: String,
+}
+
+/// Serve one client connection until it unbinds or disconnects
+pub(crate) async fn run(socket: TcpStream, state: Arc<State>, id: u64) {
+    let (outbound, outbound_rx) = mpsc::unbounded_channel();
+    let mut session = Session {
+        id,
+        state,
+        // Decode everything; the bind decides what is allowed
+        connection: Connection::with_registry(
+            socket,
+            PduRegistry::for_version(InterfaceVersion::SmppV50),
+        ),
+        outbound,
+        outbound_rx,
+        bound: None,
+        submissions: 0,
+        sequence: 0,
+    };
+    session.serve().await;
+    session.state.unregister(id);
+    debug!(session = id, "Session ended");
+}
+
+struct Session {
+    id: u64,
+    state: Arc<State>,
+    connection: Connection,
+    outbound: mpsc::UnboundedSender<Outbound>,
+    outbound_rx: mpsc::UnboundedReceiver<Outbound>,
+    bound: Option<Bound>,
+    /// Message submissions received, for the failure modes
+    submissions: u32,
+    /// Last sequence number used for a PDU sent by the simulator
+    sequence: u32,
+}
+
+impl Session {
+    async fn serve(&mut self) {
+        loop {
+            let keep_going = tokio::select! {
+                frame = self.connection.read_frame() => match frame {
+                    Ok(Some(frame)) => self.frame(frame).await,
+                    Ok(None) => false,
+                    Err(e) => {
+                        warn!(session = self.id, "Dropping connection: {e}");
+                        false
+                    }
+                },
+                Some(outbound) = self.outbound_rx.recv() => self.send(outbound).await,
+            };
+            if !keep_going {
+                return;
+            }
+        }
+    }
+
+    /// Handle a PDU from the client, returning whether the session continues
+    async fn frame(&mut self, frame: Frame) -> bool {
+        let sequence_number = frame.sequence_number();
+        match frame {
+            Frame::BindTransmitter(bind) => {
+                self.bind(
+                    sequence_number,
+                    BindKind::Transmitter,
+                    &bind.system_id,
+                    bind.password.as_ref(),
+                    bind.interface_version,
+                )
+                .await
+            }
+            Frame::BindReceiver(bind) => {
+                self.bind(
+                    sequence_number,
+                    BindKind::Receiver,
+                    &bind.system_id,
+                    bind.password.as_ref(),
+                    bind.interface_version,
+                )
+                .await
+            }
+            Frame::BindTransceiver(bind) => {
+                self.bind(
+                    sequence_number,
+                    BindKind::Transceiver,
+                    &bind.system_id,
+                    bind.password.as_ref(),
+                    bind.interface_version,
+                )
+                .await
+            }
+            Frame::EnquireLink(_) => {
+                self.write(&Frame::EnquireLinkResp(EnquireLinkResponse {
+                    command_status: CommandStatus::Ok,
+                    sequence_number,
+                }))
+                .await
+            }
+            Frame::Unbind(_) => {
+                self.write(&Frame::UnbindResp(UnbindResponse {
+                    command_status: CommandStatus::Ok,
+                    sequence_number,
+                }))
+                .await;
+                info!(session = self.id, "Client unbound");
+                false
+            }
+            frame if frame.is_response() => {
+                // deliver_sm_resp and the like need no action
+                let command_id = frame.command_id();
+                debug!(session = self.id, sequence_number, "Received {command_id:?}");
+                true
+            }
+            _ if !self.bound.as_ref().is_some_and(|bound| bound.kind.transmits()) => {
+                let nack = GenericNack::new(CommandStatus::IncorrectBindStatus, sequence_number);
+                self.write(&Frame::GenericNack(nack)).await
+            }
+            Frame::SubmitSm(submit) => {
+                let receipt = requests_receipt(submit.registered_delivery).then(|| ReceiptRequest {
+                    source_addr: submit.source_addr.to_string(),
+                    destination_addr: submit.destination_addr.to_string(),
+                    text: String::from_utf8_lossy(submit.short_message.as_bytes()).into_owned(),
+                });
+                self.submission(CommandId::SubmitSm, sequence_number, receipt).await
+            }
+            Frame::DataSm(data) => {
+                let payload = data.message_payload().map(|payload| &payload[..]);
+                let receipt = requests_receipt(data.registered_delivery).then(|| ReceiptRequest {
+                    source_addr: data.source_addr.to_string(),
+                    destination_addr: data.destination_addr.to_string(),
+                    text: String::from_utf8_lossy(payload.unwrap_or_default()).into_owned(),
+                });
+                self.submission(CommandId::DataSm, sequence_number, receipt).await
+            }
+            Frame::SubmitMulti(_) => {
+                self.submission(CommandId::SubmitMulti, sequence_number, None).await
+            }
+            Frame::QuerySm(query) => {
+                let message_id = query.message_id.as_str().unwrap_or_default().to_string();
+                let response = match self.state.lookup(&message_id) {
+                    Some((message_state, final_date)) => QuerySmResponse {
+                        command_status: CommandStatus::Ok,
+                        sequence_number,
+                        message_id: query.message_id,
+                        final_date,
+                        message_state,
+                        error_code: 0,
+                    },
+                    None => QuerySmResponse {
+                        command_status: CommandStatus::InvalidMessageId,
+                        sequence_number,
+                        message_id: query.message_id,
+                        final_date: None,
+                        message_state: MessageState::Unknown,
+                        error_code: 0,
+                    },
+                };
+                self.write(&Frame::QuerySmResp(response)).await
+            }
+            frame => {
+                debug!(session = self.id, "Rejecting unsupported {:?}", frame.command_id());
+                let nack = GenericNack::invalid_command_id(sequence_number);
+                self.write(&Frame::GenericNack(nack)).await
+            }
+        }
+    }
+
+    /// Answer a bind, negotiating the highest configured version not above the client's
+    async fn bind(
+        &mut self,
+        sequence_number: u32,
+        kind: BindKind,
+        system_id: &SystemId,
+        password: Option<&Password>,
+        requested: InterfaceVersion,
+    ) -> bool {
+        let config = self.state.config();
+        let system_id = system_id.as_str().unwrap_or_default().to_string();
+        let password = password.and_then(|p| p.as_str().ok()).unwrap_or_default();
+        let version = config.versions.iter().copied().filter(|v| *v <= requested).max();
+
+        let status = match version {
+            _ if self.bound.is_some() => CommandStatus::AlreadyBoundState,
+            None => CommandStatus::BindFailed,
+            Some(_) => config.authenticate(&system_id, password),
+        };
+
+        // Clients below v3.4 cannot parse the TLV
+        let sc_interface_version = version
+            .filter(|v| *v >= InterfaceVersion::SmppV34 && requested >= InterfaceVersion::SmppV34)
+            .and_then(|v| Tlv::new(tags::SC_INTERFACE_VERSION, vec![v as u8]).ok());
+        let smsc_id = SystemId::from(config.system_id.as_str());
+        let response = match kind {
+            BindKind::Transmitter => Frame::BindTransmitterResp(BindTransmitterResponse {
+                command_status: status,
+                sequence_number,
+                system_id: smsc_id,
+                sc_interface_version,
+            }),
+            BindKind::Receiver => Frame::BindReceiverResp(BindReceiverResponse {
+                command_status: status,
+                sequence_number,
+                system_id: smsc_id,
+                sc_interface_version,
+            }),
+            BindKind::Transceiver => Frame::BindTransceiverResp(BindTransceiverResponse {
+                command_status: status,
+                sequence_number,
+                system_id: smsc_id,
+                sc_interface_version,
+            }),
+        };
+        if !self.write(&response).await {
+            return false;
+        }
+
+        let (CommandStatus::Ok, Some(version)) = (status, version) else {
+            info!(session = self.id, %system_id, "Refused bind: {status:?}");
+            return true;
+        };
+        info!(session = self.id, %system_id, ?kind, ?version, "Bound");
+        self.state.register(self.id, &system_id, kind.receives(), self.outbound.clone());
+        if kind.receives() {
+            for scripted in &self.state.config().scripted_mo {
+                let (state, id, delay, message) =
+                    (self.state.clone(), self.id, scripted.delay, scripted.message.clone());
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    state.deliver_mo(id, &message);
+                });
+            }
+        }
+        self.bound = Some(Bound {
+            system_id,
+            kind,
+            version,
+        });
+        true
+    }
+
+    /// Answer a submit_sm, data_sm or submit_multi, applying the failure modes
+    async fn submission(
+        &mut self,
+        command_id: CommandId,
+        sequence_number: u32,
+        receipt: Option<ReceiptRequest>,
+    ) -> bool {
+        self.state.count_submission();
+        self.submissions += 1;
+
+        let fault = self.state.config().failures.fault_for(self.submissions);
+        let delay = match fault {
+            Some(Fault::Disconnect) => {
+                warn!(session = self.id, "Disconnecting on submission {}", self.submissions);
+                return false;
+            }
+            Some(Fault::Drop) => {
+                debug!(session = self.id, sequence_number, "Dropping response to {command_id:?}");
+                return true;
+            }
+            Some(Fault::Throttle) => {
+                let status = CommandStatus::ThrottlingError;
+                let response = submission_response(command_id, sequence_number, status, "");
+                return self.write(&response).await;
+            }
+            Some(Fault::QueueFull) => {
+                let status = CommandStatus::MessageQueueFull;
+                let response = submission_response(command_id, sequence_number, status, "");
+                return self.write(&response).await;
+            }
+            Some(Fault::Slow(delay)) => Some(delay),
+            None => None,
+        };
+
+        let message_id = self.state.accept_message();
+        let response =
+            submission_response(command_id, sequence_number, CommandStatus::Ok, &message_id);
+        if let (Some(request), Some(receipt_delay)) = (receipt, self.state.config().receipt_delay) {
+            self.schedule_receipt(message_id, request, delay.unwrap_or_default() + receipt_delay);
+        }
+
+        match delay {
+            Some(delay) => {
+                let outbound = self.outbound.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    let _ = outbound.send(Outbound::Response(response));
+                });
+                true
+            }
+            None => self.write(&response).await,
+        }
+    }
+
+    /// Send a delivery receipt for `message_id` after `delay`
+    fn schedule_receipt(&self, message_id: String, request: ReceiptRequest, delay: Duration) {
+        let Some(bound) = &self.bound else {
+            return;
+        };
+        let (state, submitter, system_id) = (self.state.clone(), self.id, bound.system_id.clone());
+        let submit_date = SmppDateTime::now_utc();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let done_date = SmppDateTime::now_utc();
+            match receipt_pdu(&message_id, &request, &submit_date, &done_date) {
+                Some(receipt) => {
+                    let done = done_date.as_str().unwrap_or_default();
+                    state.deliver_receipt(&message_id, done, submitter, &system_id, receipt);
+                }
+                None => warn!(message_id, "Could not build delivery receipt"),
+            }
+        });
+    }
+
+    /// Write a PDU queued from outside the read loop
+    async fn send(&mut self, outbound: Outbound) -> bool {
+        match outbound {
+            Outbound::Response(frame) => self.write(&frame).await,
+            Outbound::Deliver(mut pdu) => {
+                self.sequence = next_after(self.sequence);
+                pdu.sequence_number = self.sequence;
+                if self.bound.as_ref().is_some_and(|b| b.version < InterfaceVersion::SmppV34) {
+                    pdu.receipted_message_id = None;
+                    pdu.message_state = None;
+                }
+                self.write(&Frame::DeliverSm(pdu)).await
+            }
+        }
+    }
+
+    /// Write a PDU, returning whether the connection is still usable
+    async fn write(&mut self, frame: &Frame) -> bool {
+        match self.connection.write_frame(frame).await {
+            Ok(()) => true,
+            Err(e) => {
+                warn!(session = self.id, "Write failed: {e}");
+                false
+            }
+        }
+    }
+}
+
+/// The response to a message submission
+fn submission_response(
+    command_id: CommandId,
+    sequence_number: u32,
+    status: CommandStatus,
+    message_id: &str,
+) -> Frame {
+    let message_id = MessageId::from(message_id);
+    match command_id {
+        CommandId::DataSm => Frame::DataSmResp(DataSmResponse {
+            command_status: status,
+            sequence_number,
+            message_id,
+            optional_parameters: Vec::new(),
+        }),
+        CommandId::SubmitMulti => Frame::SubmitMultiResp(SubmitMultiResponse {
+            command_status: status,
+            sequence_number,
+            message_id,
+            no_unsuccess: 0,
+            unsuccess_sme: Vec::new(),
+        }),
+        _ => Frame::SubmitSmResp(SubmitSmResponse {
+            command_status: status,
+            sequence_number,
+            message_id,
+        }),
+    }
+}
+
+/// A deliver_sm reporting `message_id` delivered, in the SMPP v3.4 Appendix B format
+fn receipt_pdu(
+    message_id: &str,
+    request: &ReceiptRequest,
+    submit_date: &SmppDateTime,
+    done_date: &SmppDateTime,
+) -> Option<DeliverSm> {
+    // Receipt dates are YYMMDDhhmm
+    let date = |date: &SmppDateTime| -> String {
+        date.as_str().unwrap_or_default().chars().take(10).collect()
+    };
+    let text: String = request.text.chars().take(RECEIPT_TEXT_LEN).collect();
+    let receipt = format!(
+        "id:{message_id} sub:001 dlvrd:001 submit date:{} done date:{} stat:DELIVRD err:000 \
+         text:{text}",
+        date(submit_date),
+        date(done_date),
+    );
+
+    let mut receipted_message_id = message_id.as_bytes().to_vec();
+    receipted_message_id.push(0);
+    DeliverSm::builder()
+        .source_addr(&request.destination_addr)
+        .destination_addr(&request.source_addr)
+        .esm_class(EsmClass::from_byte(ESM_CLASS_DELIVERY_RECEIPT).ok()?)
+        .short_message(&receipt)
+        .receipted_message_id(Tlv::new(tags::RECEIPTED_MESSAGE_ID, receipted_message_id).ok()?)
+        .message_state(Tlv::new(tags::MESSAGE_STATE, vec![MessageState::Delivered as u8]).ok()?)
+        .build()
+        .ok()
+}
+
+/// Whether registered_delivery asks for an SMSC delivery receipt
+fn requests_receipt(registered_delivery: u8) -> bool {
+    registered_delivery & 0x03 != 0
+}
+
+/// A deliver_sm carrying an MO message
+pub(crate) fn mo_pdu(message: &MoMessage) -> Option<DeliverSm> {
+    match DeliverSm::builder()
+        .source_addr(&message.source_addr)
+        .destination_addr(&message.destination_addr)
+        .short_message(&message.text)
+        .build()
+    {
+        Ok(pdu) => Some(pdu),
+        Err(e) => {
+            warn!("Cannot deliver MO message {message:?}: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::DeliveryReceipt;
+
+    #[test]
+    fn test_receipt_pdu_parses_as_delivery_receipt() {
+        let request = ReceiptRequest {
+            source_addr: "1234".to_string(),
+            destination_addr: "447700900123".to_string(),
+            text: "A message longer than twenty characters".to_string(),
+        };
+        let date = SmppDateTime::now_utc();
+        let pdu = receipt_pdu("0000002a", &request, &date, &date).unwrap();
+
+        assert_eq!(pdu.source_addr.as_str().unwrap(), "447700900123");
+        assert_eq!(pdu.esm_class.to_byte(), ESM_CLASS_DELIVERY_RECEIPT);
+        let receipt = DeliveryReceipt::from_deliver_sm(&pdu).unwrap();
+        assert_eq!(receipt.message_id, "0000002a");
+        assert_eq!(receipt.state, MessageState::Delivered);
+        assert_eq!(receipt.text.as_deref(), Some("A message longer tha"));
+    }
+}
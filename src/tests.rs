@@ -124,6 +124,7 @@ mod integration_tests {
             its_reply_type: None,
             its_session_info: None,
             ussd_service_op: None,
+            network_routing: NetworkRouting::default(),
         };
 
         let bytes = crate::codec::Encodable::to_bytes(&submit_sm);
@@ -191,6 +192,7 @@ mod integration_tests {
             its_reply_type: None,
             its_session_info: None,
             ussd_service_op: None,
+            network_routing: NetworkRouting::default(),
         };
 
         // Validate should return an error for length mismatch
@@ -364,6 +366,7 @@ mod integration_tests {
             its_reply_type: None,
             its_session_info: None,
             ussd_service_op: None,
+            network_routing: NetworkRouting::default(),
         }
     }
 
@@ -449,8 +452,8 @@ mod integration_tests {
 
     #[test]
     fn test_interface_version_invalid_values() {
-        // Test that invalid byte values are rejected
-        let invalid_values = [0x00, 0x01, 0x32, 0x35, 0x49, 0x51, 0xFF];
+        // Test that invalid byte values are rejected (0x00-0x32 denote v3.3)
+        let invalid_values = [0x35, 0x49, 0x51, 0xFF];
         
         for invalid in invalid_values {
             let result = InterfaceVersion::try_from(invalid);
@@ -532,7 +535,7 @@ mod integration_tests {
 
         let bytes = tlv.to_bytes();
         let expected = vec![
-            0x14, 0x2C, // tag (CONGESTION_STATE = 0x142C)
+            0x04, 0x28, // tag (CONGESTION_STATE = 0x0428)
             0x00, 0x01, // length
             0x00,       // value (0%)
         ];
@@ -557,7 +560,7 @@ mod integration_tests {
             let bytes = tlv.to_bytes();
             
             // Verify tag and length
-            assert_eq!(&bytes[0..2], &[0x14, 0x2C]); // CONGESTION_STATE tag
+            assert_eq!(&bytes[0..2], &[0x04, 0x28]); // CONGESTION_STATE tag
             assert_eq!(&bytes[2..4], &[0x00, 0x01]); // length = 1
             assert_eq!(bytes[4], value); // congestion value
         }
@@ -570,7 +573,7 @@ mod integration_tests {
 
         // Test decoding congestion state value of 75%
         let data = vec![
-            0x14, 0x2C, // tag
+            0x04, 0x28, // tag
             0x00, 0x01, // length
             0x4B,       // value (75)
         ];
@@ -666,7 +669,7 @@ mod integration_tests {
 
         let bytes = tlv.to_bytes();
         let mut expected = vec![
-            0x06, 0x00, // tag (BILLING_IDENTIFICATION = 0x0600)
+            0x06, 0x0B, // tag (BILLING_IDENTIFICATION = 0x060B)
             0x00, 0x0A, // length (10 bytes)
         ];
         expected.extend_from_slice(billing_id);
@@ -688,7 +691,7 @@ mod integration_tests {
 
         let bytes = tlv.to_bytes();
         let mut expected = vec![
-            0x06, 0x0E, // tag (SOURCE_NETWORK_ID = 0x060E)
+            0x06, 0x0D, // tag (SOURCE_NETWORK_ID = 0x060D)
             0x00, 0x06, // length (6 bytes)
         ];
         expected.extend_from_slice(network_id);
@@ -710,7 +713,7 @@ mod integration_tests {
 
         let bytes = tlv.to_bytes();
         let mut expected = vec![
-            0x06, 0x0F, // tag (DEST_NETWORK_ID = 0x060F)
+            0x06, 0x0E, // tag (DEST_NETWORK_ID = 0x060E)
             0x00, 0x06, // length (6 bytes)
         ];
         expected.extend_from_slice(network_id);
@@ -732,7 +735,7 @@ mod integration_tests {
 
         let bytes = tlv.to_bytes();
         let mut expected = vec![
-            0x06, 0x0C, // tag (SOURCE_NODE_ID = 0x060C)
+            0x06, 0x0F, // tag (SOURCE_NODE_ID = 0x060F)
             0x00, 0x08, // length (8 bytes)
         ];
         expected.extend_from_slice(&node_id);
@@ -754,7 +757,7 @@ mod integration_tests {
 
         let bytes = tlv.to_bytes();
         let mut expected = vec![
-            0x06, 0x0D, // tag (DEST_NODE_ID = 0x060D)
+            0x06, 0x10, // tag (DEST_NODE_ID = 0x0610)
             0x00, 0x08, // length (8 bytes)
         ];
         expected.extend_from_slice(&node_id);
@@ -2551,4 +2554,1590 @@ mod integration_tests {
         assert_eq!(large_tlv.value.len(), 255);
         assert_eq!(large_tlv.length, 255);
     }
+
+    #[test]
+    fn test_v33_registry_restrictions() {
+        use crate::codec::PduRegistry;
+        use crate::datatypes::tags;
+
+        let v33_registry = PduRegistry::for_version(InterfaceVersion::SmppV33);
+
+        // No optional parameters and no v3.4-only PDUs
+        assert!(!v33_registry.supports_tlv(tags::USER_MESSAGE_REFERENCE));
+        assert!(!v33_registry.supports_tlv(tags::MESSAGE_PAYLOAD));
+        assert!(!v33_registry.is_registered(CommandId::DataSm));
+        assert!(!v33_registry.is_registered(CommandId::AlertNotification));
+        assert!(!v33_registry.supports_feature("data_sm"));
+        assert!(v33_registry.supports_feature("submit_sm"));
+
+        // Upgrading restores the v3.4 capabilities
+        let mut registry = PduRegistry::for_version(InterfaceVersion::SmppV33);
+        registry.upgrade_to_version(InterfaceVersion::SmppV34);
+        assert!(registry.is_registered(CommandId::DataSm));
+        assert!(registry.supports_tlv(tags::MESSAGE_PAYLOAD));
+    }
+
+    #[test]
+    fn test_v33_rejects_optional_parameters() {
+        use crate::codec::{CodecError, DecodePolicy, PduHeader, PduRegistry};
+        use bytes::Bytes;
+
+        let submit_sm = SubmitSm::builder()
+            .source_addr("1234")
+            .destination_addr("5678")
+            .short_message("Hi")
+            .user_message_reference(Tlv {
+                tag: tags::USER_MESSAGE_REFERENCE,
+                length: 2,
+                value: Bytes::from_static(&[0x00, 0x01]),
+            })
+            .build()
+            .unwrap();
+        let bytes = submit_sm.to_bytes();
+
+        let v33_registry = PduRegistry::for_version(InterfaceVersion::SmppV33);
+        assert!(v33_registry.check_outbound(&bytes).is_err());
+        assert!(PduRegistry::new().check_outbound(&bytes).is_ok());
+
+        let mut cursor = Cursor::new(bytes.as_ref());
+        let header = PduHeader::decode(&mut cursor).unwrap();
+        assert!(matches!(
+            v33_registry.decode_frame(header.clone(), &mut cursor),
+            Err(CodecError::FieldValidation { field: "optional_parameters", .. })
+        ));
+
+        // Lenient mode drops the TLVs and keeps the message
+        let lenient = PduRegistry::for_version(InterfaceVersion::SmppV33)
+            .with_policy(DecodePolicy::Lenient);
+        let mut cursor = Cursor::new(bytes.as_ref());
+        cursor.set_position(16);
+        let decoded = lenient.decode_frame(header, &mut cursor).unwrap();
+        assert_eq!(decoded.warnings.len(), 1);
+        match decoded.frame {
+            Frame::SubmitSm(pdu) => assert!(pdu.user_message_reference.is_none()),
+            other => panic!("expected submit_sm, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_v33_outbound_command_and_message_id() {
+        use crate::codec::PduRegistry;
+
+        let v33_registry = PduRegistry::for_version(InterfaceVersion::SmppV33);
+
+        let data_sm = DataSm::new(
+            1,
+            ServiceType::default(),
+            TypeOfNumber::Unknown,
+            NumericPlanIndicator::Unknown,
+            SourceAddr::new("1234", TypeOfNumber::Unknown).unwrap(),
+            TypeOfNumber::Unknown,
+            NumericPlanIndicator::Unknown,
+            DestinationAddr::new("5678", TypeOfNumber::Unknown).unwrap(),
+            EsmClass::default(),
+            0,
+            DataCoding::default(),
+        )
+        .unwrap();
+        assert!(v33_registry.check_outbound(&data_sm.to_bytes()).is_err());
+
+        let response = |message_id: &str| SubmitSmResponse {
+            command_status: CommandStatus::Ok,
+            sequence_number: 1,
+            message_id: MessageId::from(message_id),
+        };
+        assert!(v33_registry.check_outbound(&response("0A1B2C3D").to_bytes()).is_ok());
+        assert!(v33_registry.check_outbound(&response("msg-000000001").to_bytes()).is_err());
+        assert!(PduRegistry::new().check_outbound(&response("msg-000000001").to_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_submit_sm_resp_error_without_body() {
+        use crate::codec::{PduHeader, PduRegistry};
+
+        // v3.4 SMSCs omit the message_id when command_status is non-zero
+        let data = vec![
+            0x00, 0x00, 0x00, 0x10, // command_length = 16
+            0x80, 0x00, 0x00, 0x04, // submit_sm_resp
+            0x00, 0x00, 0x00, 0x58, // ESME_RTHROTTLED
+            0x00, 0x00, 0x00, 0x07, // sequence_number
+        ];
+        let mut cursor = Cursor::new(data.as_slice());
+        let header = PduHeader::decode(&mut cursor).unwrap();
+        let frame = PduRegistry::new().decode_pdu(header, &mut cursor).unwrap();
+
+        match frame {
+            Frame::SubmitSmResp(resp) => {
+                assert_eq!(resp.command_status, CommandStatus::ThrottlingError);
+                assert!(resp.message_id.is_empty());
+            }
+            other => panic!("expected submit_sm_resp, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_detect_version_from_variable_length_bind() {
+        use crate::codec::PduRegistry;
+
+        // bind_transmitter with unpadded C-Octet Strings, as sent by most ESMEs
+        let mut data = vec![
+            0x00, 0x00, 0x00, 0x1D, // command_length = 29
+            0x00, 0x00, 0x00, 0x02, // bind_transmitter
+            0x00, 0x00, 0x00, 0x00, // command_status
+            0x00, 0x00, 0x00, 0x01, // sequence_number
+            0x45, 0x53, 0x4D, 0x45, 0x00, // system_id "ESME"
+            0x70, 0x77, 0x00, // password "pw"
+            0x00, // system_type ""
+            0x50, // interface_version
+            0x01, 0x01, // addr_ton, addr_npi
+            0x00, // address_range ""
+        ];
+        assert_eq!(
+            PduRegistry::detect_version_from_bind(&data),
+            Some(InterfaceVersion::SmppV50)
+        );
+
+        // Octets of a following PDU are not part of this one
+        data.extend_from_slice(&[0xFF, 0xFF]);
+        assert_eq!(
+            PduRegistry::detect_version_from_bind(&data),
+            Some(InterfaceVersion::SmppV50)
+        );
+
+        // An incomplete PDU has no version
+        assert_eq!(PduRegistry::detect_version_from_bind(&data[..28]), None);
+    }
+
+    #[test]
+    fn test_registry_negotiation() {
+        use crate::codec::PduRegistry;
+        use crate::datatypes::tags;
+
+        // SMSC reports a lower version: the session downgrades
+        let mut registry = PduRegistry::for_version(InterfaceVersion::SmppV50);
+        let version = registry.negotiate(InterfaceVersion::SmppV50, Some(InterfaceVersion::SmppV34));
+        assert_eq!(version, InterfaceVersion::SmppV34);
+        assert_eq!(registry.version(), InterfaceVersion::SmppV34);
+        assert!(!registry.supports_feature("broadcast_sm"));
+        assert!(!registry.supports_tlv(tags::CONGESTION_STATE));
+        assert!(!registry.registered_commands().contains(&CommandId::BroadcastSm));
+
+        // SMSC supports more than requested: the requested version wins
+        let mut registry = PduRegistry::for_version(InterfaceVersion::SmppV34);
+        let version = registry.negotiate(InterfaceVersion::SmppV34, Some(InterfaceVersion::SmppV50));
+        assert_eq!(version, InterfaceVersion::SmppV34);
+
+        // No sc_interface_version TLV: assume at most v3.4
+        let mut registry = PduRegistry::for_version(InterfaceVersion::SmppV50);
+        let version = registry.negotiate(InterfaceVersion::SmppV50, None);
+        assert_eq!(version, InterfaceVersion::SmppV34);
+
+        // A v3.3 SMSC removes data_sm and bind_transceiver
+        let mut registry = PduRegistry::for_version(InterfaceVersion::SmppV34);
+        let version = registry.negotiate(InterfaceVersion::SmppV34, Some(InterfaceVersion::SmppV33));
+        assert_eq!(version, InterfaceVersion::SmppV33);
+        assert!(!registry.registered_commands().contains(&CommandId::DataSm));
+        assert!(!registry.registered_commands().contains(&CommandId::BindTransceiverResp));
+        assert!(registry.registered_commands().contains(&CommandId::BindTransmitterResp));
+    }
+
+    #[test]
+    fn test_v50_tlv_on_v34_session() {
+        use crate::codec::{DecodePolicy, PduHeader, PduRegistry};
+        use crate::datatypes::tags;
+
+        let mut data_sm = DataSm::new(
+            1,
+            ServiceType::default(),
+            TypeOfNumber::Unknown,
+            NumericPlanIndicator::Unknown,
+            SourceAddr::new("1234", TypeOfNumber::Unknown).unwrap(),
+            TypeOfNumber::Unknown,
+            NumericPlanIndicator::Unknown,
+            DestinationAddr::new("5678", TypeOfNumber::Unknown).unwrap(),
+            EsmClass::default(),
+            0,
+            DataCoding::default(),
+        )
+        .unwrap();
+        data_sm.add_tlv(Tlv {
+            tag: tags::CONGESTION_STATE,
+            length: 1,
+            value: vec![50].into(),
+        });
+        let bytes = data_sm.to_bytes();
+
+        // Outbound: refused on a v3.4 session, allowed on v5.0
+        let v34_registry = PduRegistry::for_version(InterfaceVersion::SmppV34);
+        assert!(v34_registry.check_outbound(&bytes).is_err());
+        assert!(PduRegistry::for_version(InterfaceVersion::SmppV50)
+            .check_outbound(&bytes)
+            .is_ok());
+
+        // Inbound: rejected when strict
+        let mut cursor = Cursor::new(bytes.as_ref());
+        let header = PduHeader::decode(&mut cursor).unwrap();
+        assert!(v34_registry.decode_pdu(header.clone(), &mut cursor).is_err());
+
+        // Inbound: dropped with a warning when lenient
+        let lenient = PduRegistry::for_version(InterfaceVersion::SmppV34)
+            .with_policy(DecodePolicy::Lenient);
+        let mut cursor = Cursor::new(bytes.as_ref());
+        let header = PduHeader::decode(&mut cursor).unwrap();
+        let decoded = lenient.decode_frame(header, &mut cursor).unwrap();
+        assert_eq!(decoded.warnings.len(), 1);
+        match decoded.frame {
+            Frame::DataSm(pdu) => assert!(pdu.optional_parameters.is_empty()),
+            other => panic!("Expected DataSm frame, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_network_routing_roundtrip_and_version_gating() {
+        use crate::codec::{PduHeader, PduRegistry};
+
+        let routing = NetworkRouting {
+            billing_identification: Some(BillingIdentification::internal(&b"ACCT-42"[..]).unwrap()),
+            source_network_id: Some("NET-A".to_string()),
+            dest_node_id: Some(NodeId::new("123456").unwrap()),
+            dest_addr_np_resolution: Some(NpResolution::NumberPorted),
+            dest_addr_np_country: Some(44),
+            source_network_type: Some(NetworkType::Gsm),
+            ..Default::default()
+        };
+        let submit_sm = SubmitSm::builder()
+            .source_addr("1234")
+            .destination_addr("5678")
+            .short_message("Hi")
+            .network_routing(routing.clone())
+            .build()
+            .unwrap();
+        let bytes = submit_sm.to_bytes();
+        assert_eq!(bytes.len(), submit_sm.encoded_size());
+
+        // Billing and routing TLVs need a v5.0 session
+        assert!(PduRegistry::for_version(InterfaceVersion::SmppV34)
+            .check_outbound(&bytes)
+            .is_err());
+        let v50_registry = PduRegistry::for_version(InterfaceVersion::SmppV50);
+        assert!(v50_registry.check_outbound(&bytes).is_ok());
+
+        let mut cursor = Cursor::new(bytes.as_ref());
+        let header = PduHeader::decode(&mut cursor).unwrap();
+        match v50_registry.decode_pdu(header, &mut cursor).unwrap() {
+            Frame::SubmitSm(pdu) => {
+                assert_eq!(pdu.network_routing, routing);
+                assert_eq!(*pdu, submit_sm);
+            }
+            other => panic!("Expected SubmitSm frame, got {other:?}"),
+        }
+
+        // The v3.4 network types alone are fine on a v3.4 session
+        let v34_only = SubmitSm::builder()
+            .source_addr("1234")
+            .destination_addr("5678")
+            .short_message("Hi")
+            .network_routing(NetworkRouting {
+                dest_network_type: Some(NetworkType::Is95),
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        assert!(PduRegistry::for_version(InterfaceVersion::SmppV34)
+            .check_outbound(&v34_only.to_bytes())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_deliver_sm_frame_roundtrip() {
+        use crate::codec::{PduHeader, PduRegistry};
+
+        let deliver_sm = DeliverSm::builder()
+            .sequence_number(77)
+            .source_addr("447700900123")
+            .destination_addr("12345")
+            .short_message("Reply text")
+            .network_routing(NetworkRouting {
+                dest_network_id: Some("HOME".to_string()),
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let bytes = deliver_sm.to_bytes();
+        assert_eq!(bytes.len(), deliver_sm.encoded_size());
+
+        let registry = PduRegistry::for_version(InterfaceVersion::SmppV50);
+        let mut cursor = Cursor::new(bytes.as_ref());
+        let header = PduHeader::decode(&mut cursor).unwrap();
+        let frame = registry.decode_pdu(header, &mut cursor).unwrap();
+        assert_eq!(frame.command_id(), CommandId::DeliverSm);
+        assert_eq!(frame.sequence_number(), 77);
+        match frame {
+            Frame::DeliverSm(pdu) => assert_eq!(*pdu, deliver_sm),
+            other => panic!("Expected DeliverSm frame, got {other:?}"),
+        }
+
+        let response = DeliverSmResponse {
+            command_status: CommandStatus::Ok,
+            sequence_number: 77,
+            message_id: MessageId::default(),
+        };
+        let bytes = response.to_bytes();
+        let mut cursor = Cursor::new(bytes.as_ref());
+        let header = PduHeader::decode(&mut cursor).unwrap();
+        match registry.decode_pdu(header, &mut cursor).unwrap() {
+            Frame::DeliverSmResp(pdu) => assert_eq!(pdu, response),
+            other => panic!("Expected DeliverSmResp frame, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bind_times_out_when_smsc_is_silent() {
+        use crate::client::{
+            BindCredentials, DefaultClient, SmppClient, SmppError, TimeoutConfig,
+        };
+        use crate::mock::{Expectation, MockSmsc};
+        use std::time::Duration;
+
+        // Take the bind without ever answering it
+        let mock = MockSmsc::builder()
+            .expect(Expectation::bind_transmitter().no_reply())
+            .start()
+            .await
+            .unwrap();
+
+        let timeouts = TimeoutConfig::new().with_session_init(Duration::from_millis(100));
+        let mut client = DefaultClient::connect_with_timeouts(mock.local_addr(), timeouts)
+            .await
+            .unwrap();
+        match client.bind(&BindCredentials::transmitter("test", "pass")).await {
+            Err(err @ SmppError::Timeout { .. }) => {
+                assert_eq!(err.command_id(), Some(CommandId::BindTransmitter));
+                assert!(err.is_fatal_for_session());
+                let SmppError::Timeout { timeout, .. } = err else { unreachable!() };
+                assert_eq!(timeout, Duration::from_millis(100));
+            }
+            other => panic!("Expected bind timeout, got {other:?}"),
+        }
+        mock.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_late_response_to_timed_out_request_is_discarded() {
+        use crate::client::{
+            BindCredentials, DefaultClient, SmppClient, SmppError, SmppTransmitter, SmsMessage,
+            TimeoutConfig,
+        };
+        use crate::mock::{Expectation, MockSmsc};
+        use std::time::Duration;
+
+        // Answer the first submit_sm only after the client has given up on
+        // it and sent the second
+        let mock = MockSmsc::builder()
+            .expect(Expectation::bind_transmitter())
+            .expect(
+                Expectation::submit_sm()
+                    .reply_message_id("first")
+                    .reply_after(Duration::from_millis(300)),
+            )
+            .expect(Expectation::submit_sm().reply_message_id("second"))
+            .start()
+            .await
+            .unwrap();
+
+        let timeouts = TimeoutConfig::new()
+            .with_response_for(CommandId::SubmitSm, Duration::from_millis(200));
+        let mut client = DefaultClient::connect_with_timeouts(mock.local_addr(), timeouts)
+            .await
+            .unwrap();
+        client
+            .bind(&BindCredentials::transmitter("test", "pass"))
+            .await
+            .unwrap();
+
+        let message = SmsMessage::new("5678", "1234", "Hello");
+        match client.send_sms(&message).await {
+            Err(err @ SmppError::Timeout { .. }) => {
+                assert_eq!(err.command_id(), Some(CommandId::SubmitSm));
+                assert!(err.is_retryable());
+                assert!(!err.is_fatal_for_session());
+            }
+            other => panic!("Expected submit_sm timeout, got {other:?}"),
+        }
+
+        // The late response to the first submit must not answer the second
+        assert_eq!(client.send_sms(&message).await.unwrap(), "second");
+        mock.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_clients_share_sequence_generator() {
+        use crate::client::{
+            AtomicSequenceGenerator, DefaultClient, SequenceGenerator, SmppClient, SmppConnection,
+        };
+        use crate::simulator::{Simulator, SimulatorConfig};
+        use std::sync::Arc;
+
+        let simulator = Simulator::bind("127.0.0.1:0", SimulatorConfig::new()).await.unwrap();
+        let addr = simulator.local_addr().unwrap();
+        let server = tokio::spawn(simulator.run());
+
+        let shared: Arc<dyn SequenceGenerator> =
+            Arc::new(AtomicSequenceGenerator::starting_at(0x7FFF_FFFE));
+        let mut first = DefaultClient::connect(addr).await.unwrap();
+        let mut second = DefaultClient::connect(addr).await.unwrap();
+        first.set_sequence_generator(Arc::clone(&shared));
+        second.set_sequence_generator(Arc::clone(&shared));
+
+        assert_eq!(first.next_sequence_number(), 0x7FFF_FFFE);
+        assert_eq!(second.next_sequence_number(), 0x7FFF_FFFF);
+        // Wraps to 1 rather than entering the reserved range
+        assert_eq!(first.next_sequence_number(), 1);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_submit_is_numbered_by_the_clients_generator() {
+        use crate::client::{
+            BindCredentials, DefaultClient, SequenceGenerator, SmppClient, SmppConnection,
+            SmppTransmitter, WrappingSequenceGenerator,
+        };
+        use crate::mock::{Expectation, MockSmsc};
+        use std::sync::Arc;
+
+        let mock = MockSmsc::builder()
+            .expect(Expectation::bind_transmitter())
+            .expect(
+                Expectation::submit_sm()
+                    .matching("sequence_number is 3", |frame| frame.sequence_number() == 3)
+                    .reply_message_id("numbered"),
+            )
+            .start()
+            .await
+            .unwrap();
+
+        let shared = Arc::new(WrappingSequenceGenerator::new());
+        let mut client = DefaultClient::connect(mock.local_addr()).await.unwrap();
+        client.set_sequence_generator(shared.clone());
+        client
+            .bind(&BindCredentials::transmitter("test", "pass"))
+            .await
+            .unwrap();
+
+        // Another client sharing the generator has 2 in flight
+        assert_eq!(shared.next_sequence(), Some(2));
+        let mut submit = SubmitSm::builder()
+            .source_addr("1234")
+            .destination_addr("447700900123")
+            .short_message("Hello")
+            .build()
+            .unwrap();
+        submit.sequence_number = 2;
+        assert_eq!(client.submit_sm(&submit).await.unwrap(), "numbered");
+
+        // Only the number the client allocated is released
+        assert_eq!(shared.in_flight(), 1);
+        shared.release(2);
+        assert_eq!(shared.in_flight(), 0);
+        mock.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_throttled_submit_is_retried_with_new_sequence_number() {
+        use crate::client::{
+            BackoffPolicy, BindCredentials, DefaultClient, RetryPolicy, SmppClient,
+            SmppConnection, SmppError, SmppTransmitter, SmsMessage,
+        };
+        use crate::mock::{Expectation, MockSmsc};
+        use std::collections::HashSet;
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+
+        let seen = Arc::new(Mutex::new(HashSet::new()));
+        let fresh_submit = || {
+            let seen = Arc::clone(&seen);
+            Expectation::submit_sm().matching("a sequence number not used before", move |frame| {
+                seen.lock().unwrap().insert(frame.sequence_number())
+            })
+        };
+        // Throttle the first attempt, accept the second, then reject the
+        // next message permanently
+        let mock = MockSmsc::builder()
+            .expect(Expectation::bind_transmitter())
+            .expect(fresh_submit().reply_status(CommandStatus::ThrottlingError))
+            .expect(fresh_submit().reply_message_id("accepted"))
+            .expect(fresh_submit().reply_status(CommandStatus::InvalidDestinationAddress))
+            .start()
+            .await
+            .unwrap();
+
+        let mut client = DefaultClient::connect(mock.local_addr()).await.unwrap();
+        client
+            .bind(&BindCredentials::transmitter("test", "pass"))
+            .await
+            .unwrap();
+        client.set_retry_policy(
+            RetryPolicy::new(3)
+                .with_throttling(BackoffPolicy::new(Duration::from_millis(5), Duration::from_millis(50)))
+                .with_honour_suggested_delay(false),
+        );
+
+        let message = SmsMessage::new("5678", "1234", "Hello");
+        assert_eq!(client.send_sms(&message).await.unwrap(), "accepted");
+
+        // Permanent rejections are reported after a single attempt
+        match client.send_sms(&message).await {
+            Err(SmppError::Protocol { status, .. }) => {
+                assert_eq!(status, CommandStatus::InvalidDestinationAddress)
+            }
+            other => panic!("Expected protocol error, got {other:?}"),
+        }
+        client.disconnect().await.unwrap();
+        mock.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_pool_balances_submits_and_refills_discarded_sessions() {
+        use crate::client::{BindCredentials, PoolConfig, SmppClient, SmppPool, SmsMessage};
+        use crate::simulator::{Simulator, SimulatorConfig};
+
+        let simulator = Simulator::bind("127.0.0.1:0", SimulatorConfig::new()).await.unwrap();
+        let addr = simulator.local_addr().unwrap();
+        let handle = simulator.handle();
+        let server = tokio::spawn(simulator.run());
+
+        let config = PoolConfig::new(
+            vec![addr.to_string()],
+            BindCredentials::transmitter("test", "pass"),
+        )
+        .with_size(2)
+        .with_rate_limit(1000.0);
+        let pool = SmppPool::connect(config).await.unwrap();
+        assert_eq!(pool.bound_sessions(), 2);
+        assert_eq!(handle.stats().binds, 2);
+
+        // Round-robin alternates sessions, so each has sent two submits
+        // and allocated sequence numbers 1 (the bind) to 3
+        let message = SmsMessage::new("5678", "1234", "Hello");
+        for _ in 0..4 {
+            pool.send_sms(&message).await.unwrap();
+        }
+        assert_eq!(handle.stats().submissions, 4);
+        assert_eq!(pool.outstanding(), 0);
+        for _ in 0..2 {
+            assert_eq!(pool.session().await.unwrap().next_sequence_number(), 4);
+        }
+
+        pool.session().await.unwrap().discard();
+        assert_eq!(pool.bound_sessions(), 1);
+        assert_eq!(pool.maintain().await, 2);
+
+        // The replacement session is a new bind
+        assert_eq!(handle.stats().binds, 3);
+        for _ in 0..2 {
+            pool.send_sms(&message).await.unwrap();
+        }
+        assert_eq!(handle.stats().submissions, 6);
+
+        pool.close().await;
+        assert_eq!(pool.bound_sessions(), 0);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_spool_replays_unacknowledged_messages_after_restart() {
+        use crate::client::{
+            BindCredentials, DefaultClient, Recorded, SmppClient, SmppConnection, SmppError, Spool,
+        };
+        use crate::mock::{Expectation, MockSmsc};
+
+        let path = std::env::temp_dir().join(format!("smpp-spool-replay-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // A message spooled just before the process died, never sent
+        let submit = SubmitSm::builder()
+            .source_addr("1234")
+            .destination_addr("5678")
+            .short_message("Hello")
+            .build()
+            .unwrap();
+        {
+            let spool = Spool::open(&path).unwrap();
+            assert!(matches!(spool.record(&submit).unwrap(), Recorded::New(1)));
+        }
+
+        // Accept the replayed message, reject the next one permanently
+        let mock = MockSmsc::builder()
+            .expect(Expectation::bind_transmitter())
+            .expect(Expectation::submit_sm().reply_message_id("replayed"))
+            .expect(Expectation::submit_sm().reply_status(CommandStatus::InvalidDestinationAddress))
+            .start()
+            .await
+            .unwrap();
+
+        let spool = Spool::open(&path).unwrap();
+        assert_eq!(spool.pending().len(), 1);
+
+        let mut client = DefaultClient::connect(mock.local_addr()).await.unwrap();
+        client
+            .bind(&BindCredentials::transmitter("test", "pass"))
+            .await
+            .unwrap();
+        assert_eq!(spool.replay(&mut client).await.unwrap(), 1);
+        assert!(spool.pending().is_empty());
+
+        // Permanent rejections are settled rather than left for replay
+        assert!(matches!(
+            spool.submit(&mut client, &submit).await,
+            Err(SmppError::Protocol { .. })
+        ));
+        assert!(spool.pending().is_empty());
+        mock.verify().await;
+
+        drop(spool);
+        assert!(Spool::open(&path).unwrap().pending().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_inbound_session_streams_events_and_sends_chosen_responses() {
+        use crate::client::{
+            BindCredentials, DefaultClient, InboundConfig, InboundEvent, SmppClient, SmppConnection,
+        };
+        use crate::mock::{Expectation, MockSmsc};
+
+        let mut mo = DeliverSm::builder().short_message("Hello").build().unwrap();
+        mo.sequence_number = 1;
+        let mut receipt = DeliverSm::builder()
+            .short_message("id:42 sub:001 dlvrd:001 submit date:2401011200 done date:2401011201 stat:DELIVRD err:000 text:Hello")
+            .build()
+            .unwrap();
+        receipt.sequence_number = 2;
+        receipt.esm_class = EsmClass::from_byte(0x04).unwrap();
+        let data_sm = DataSm::new(
+            3,
+            ServiceType::default(),
+            TypeOfNumber::Unknown,
+            NumericPlanIndicator::Unknown,
+            SourceAddr::new("1234", TypeOfNumber::Unknown).unwrap(),
+            TypeOfNumber::Unknown,
+            NumericPlanIndicator::Unknown,
+            DestinationAddr::new("5678", TypeOfNumber::Unknown).unwrap(),
+            EsmClass::default(),
+            0,
+            DataCoding::default(),
+        )
+        .unwrap();
+        let alert = AlertNotification::new(
+            4,
+            TypeOfNumber::International,
+            NumericPlanIndicator::Isdn,
+            SourceAddr::new("447700900123", TypeOfNumber::International).unwrap(),
+            TypeOfNumber::Unknown,
+            NumericPlanIndicator::Unknown,
+            SourceAddr::new("test", TypeOfNumber::Unknown).unwrap(),
+        )
+        .unwrap()
+        .with_availability(MsAvailabilityStatus::Unavailable);
+
+        let answering = |expectation: Expectation, sequence_number: u32| {
+            expectation.matching(format!("sequence_number is {sequence_number}"), move |frame| {
+                frame.sequence_number() == sequence_number
+            })
+        };
+        // Responses go out as the consumer handles the events, in order
+        let mock = MockSmsc::builder()
+            .expect(Expectation::bind_receiver())
+            .send(Frame::DeliverSm(Box::new(mo)))
+            .send(Frame::DeliverSm(Box::new(receipt)))
+            .send(Frame::DataSm(Box::new(data_sm)))
+            .send(Frame::AlertNotification(alert))
+            .expect(answering(Expectation::deliver_sm_resp(), 1).with_status(CommandStatus::Ok))
+            .expect(answering(Expectation::deliver_sm_resp(), 2).with_status(CommandStatus::Ok))
+            .expect(
+                answering(Expectation::new(CommandId::DataSmResp), 3)
+                    .with_status(CommandStatus::MessageQueueFull),
+            )
+            .send(Frame::EnquireLink(EnquireLink::new(5)))
+            .expect(answering(Expectation::new(CommandId::EnquireLinkResp), 5))
+            .send(Frame::Unbind(Unbind {
+                command_status: CommandStatus::Ok,
+                sequence_number: 6,
+            }))
+            .expect(answering(Expectation::unbind_resp(), 6))
+            .start()
+            .await
+            .unwrap();
+
+        let mut client = DefaultClient::connect(mock.local_addr()).await.unwrap();
+        client
+            .bind(&BindCredentials::receiver("test", "pass"))
+            .await
+            .unwrap();
+        let mut session = client.into_inbound(InboundConfig::default()).unwrap();
+
+        match session.next().await.unwrap().unwrap() {
+            InboundEvent::Message { pdu, ack } => {
+                assert_eq!(pdu.short_message.as_bytes(), b"Hello");
+                ack.ack().unwrap();
+            }
+            other => panic!("Expected an MO message, got {other:?}"),
+        }
+        match session.next().await.unwrap().unwrap() {
+            InboundEvent::Receipt { receipt, ack, .. } => {
+                assert_eq!(receipt.message_id, "42");
+                assert_eq!(receipt.state, MessageState::Delivered);
+                ack.ack().unwrap();
+            }
+            other => panic!("Expected a delivery receipt, got {other:?}"),
+        }
+        match session.next().await.unwrap().unwrap() {
+            InboundEvent::Data { ack, .. } => ack.respond(CommandStatus::MessageQueueFull).unwrap(),
+            other => panic!("Expected a data_sm, got {other:?}"),
+        }
+        match session.next().await.unwrap().unwrap() {
+            InboundEvent::Alert { alert, pdu } => {
+                assert_eq!(pdu.sequence_number, 4);
+                assert_eq!(alert.subscriber, "447700900123");
+                assert_eq!(alert.availability, Some(MsAvailabilityStatus::Unavailable));
+            }
+            other => panic!("Expected an alert_notification, got {other:?}"),
+        }
+
+        // The SMSC's unbind ends the stream
+        assert!(session.next().await.is_none());
+        mock.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_inbound_session_stops_reading_while_consumer_lags() {
+        use crate::client::{
+            BindCredentials, DefaultClient, InboundConfig, SmppClient, SmppConnection,
+        };
+        use crate::mock::{Expectation, MockSmsc};
+        use std::time::Duration;
+
+        let mut script = MockSmsc::builder().expect(Expectation::bind_receiver());
+        for sequence_number in 1..=3 {
+            let mut mo = DeliverSm::builder().short_message("Hello").build().unwrap();
+            mo.sequence_number = sequence_number;
+            script = script.send(Frame::DeliverSm(Box::new(mo)));
+        }
+        for sequence_number in 1..=3 {
+            script = script.expect(Expectation::deliver_sm_resp().matching(
+                format!("sequence_number is {sequence_number}"),
+                move |frame| frame.sequence_number() == sequence_number,
+            ));
+        }
+        let mock = script.expect(Expectation::unbind()).start().await.unwrap();
+
+        let mut client = DefaultClient::connect(mock.local_addr()).await.unwrap();
+        client
+            .bind(&BindCredentials::receiver("test", "pass"))
+            .await
+            .unwrap();
+        let mut session = client
+            .into_inbound(InboundConfig::new().with_capacity(1).with_auto_ack(true))
+            .unwrap();
+
+        // One event is buffered and one waits for room: the third is not read
+        assert!(!mock.wait(Duration::from_millis(200)).await);
+        assert_eq!(
+            mock.problems(),
+            vec![
+                "not reached: expect deliver_sm_resp where sequence_number is 3",
+                "not reached: expect unbind",
+            ]
+        );
+
+        for _ in 0..3 {
+            let event = session.next().await.unwrap().unwrap();
+            assert!(matches!(event, crate::client::InboundEvent::Message { ack, .. } if !ack.is_pending()));
+        }
+
+        session.close().await.unwrap();
+        mock.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_alert_notification_during_request_goes_to_alert_handler() {
+        use crate::client::{
+            BindCredentials, DefaultClient, SmppClient, SmppConnection, SmppTransmitter,
+            SubscriberAlert,
+        };
+        use crate::mock::{Expectation, MockSmsc};
+        use std::sync::{Arc, Mutex};
+
+        let submit = SubmitSm::builder()
+            .source_addr("1234")
+            .destination_addr("447700900123")
+            .short_message("Hello")
+            .build()
+            .unwrap();
+
+        // The subscriber comes back just before the response goes out
+        let alert = AlertNotification::international_subscriber_available(
+            90,
+            "447700900123",
+            "447700900999",
+        )
+        .unwrap()
+        .with_availability(MsAvailabilityStatus::Available);
+        // The client numbers the bind 1 and the submit 2
+        let resp = SubmitSmResponse {
+            command_status: CommandStatus::Ok,
+            sequence_number: 2,
+            message_id: MessageId::from("after-alert"),
+        };
+        let mock = MockSmsc::builder()
+            .expect(Expectation::bind_transceiver())
+            .expect(Expectation::submit_sm().no_reply())
+            .send(Frame::AlertNotification(alert))
+            .send(Frame::SubmitSmResp(resp))
+            .start()
+            .await
+            .unwrap();
+
+        let mut client = DefaultClient::connect(mock.local_addr()).await.unwrap();
+        client
+            .bind(&BindCredentials::transceiver("test", "pass"))
+            .await
+            .unwrap();
+
+        let alerts = Arc::new(Mutex::new(Vec::new()));
+        let seen = alerts.clone();
+        client.set_alert_handler(move |alert: &SubscriberAlert| {
+            seen.lock().unwrap().push(alert.clone());
+        });
+
+        assert_eq!(client.submit_sm(&submit).await.unwrap(), "after-alert");
+
+        let alerts = alerts.lock().unwrap().clone();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].subscriber, "447700900123");
+        assert!(alerts[0].is_available());
+        mock.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_client_against_simulator_receives_receipt_and_mo() {
+        use crate::client::{
+            BindCredentials, DefaultClient, InboundConfig, InboundEvent, SmppClient, SmppConnection,
+            SmppTransmitter, SmsMessage,
+        };
+        use crate::simulator::{MoMessage, Simulator, SimulatorConfig};
+        use std::time::Duration;
+
+        let config = SimulatorConfig::new().with_receipt_delay(Some(Duration::from_millis(20)));
+        let simulator = Simulator::bind("127.0.0.1:0", config).await.unwrap();
+        let addr = simulator.local_addr().unwrap();
+        let handle = simulator.handle();
+        let server = tokio::spawn(simulator.run());
+
+        let mut client = DefaultClient::connect(addr).await.unwrap();
+        client
+            .bind(&BindCredentials::transceiver("test", "test"))
+            .await
+            .unwrap();
+        let message = SmsMessage::builder()
+            .to("447700900123")
+            .from("1234")
+            .text("Hello")
+            .with_delivery_receipt()
+            .build()
+            .unwrap();
+        let message_id = client.send_sms(&message).await.unwrap();
+        assert_eq!(handle.message_state(&message_id), Some(MessageState::Enroute));
+
+        let mut inbound = client.into_inbound(InboundConfig::new()).unwrap();
+        match inbound.next().await {
+            Some(Ok(InboundEvent::Receipt { receipt, ack, .. })) => {
+                assert_eq!(receipt.message_id, message_id);
+                assert_eq!(receipt.state, MessageState::Delivered);
+                ack.ack().unwrap();
+            }
+            other => panic!("Expected receipt, got {other:?}"),
+        }
+        assert_eq!(handle.message_state(&message_id), Some(MessageState::Delivered));
+
+        assert_eq!(handle.inject_mo(&MoMessage::new("447700900123", "1234", "Hi")), 1);
+        match inbound.next().await {
+            Some(Ok(InboundEvent::Message { pdu, ack })) => {
+                assert_eq!(pdu.short_message.as_str().unwrap(), "Hi");
+                ack.ack().unwrap();
+            }
+            other => panic!("Expected MO message, got {other:?}"),
+        }
+        inbound.close().await.unwrap();
+
+        let stats = handle.stats();
+        assert_eq!((stats.binds, stats.submissions), (1, 1));
+        assert_eq!((stats.receipts, stats.mo_messages), (1, 1));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_simulator_checks_credentials_and_injects_throttling() {
+        use crate::client::{
+            BindCredentials, DefaultClient, RetryPolicy, SmppClient, SmppConnection, SmppError,
+            SmppTransmitter, SmsMessage,
+        };
+        use crate::simulator::{Account, FailureModes, Simulator, SimulatorConfig};
+
+        let config = SimulatorConfig::new()
+            .with_account(Account::new("test", "secret"))
+            .with_failures(FailureModes::none().with_throttle_every(2));
+        let simulator = Simulator::bind("127.0.0.1:0", config).await.unwrap();
+        let addr = simulator.local_addr().unwrap();
+        let server = tokio::spawn(simulator.run());
+
+        let mut client = DefaultClient::connect(addr).await.unwrap();
+        match client.bind(&BindCredentials::transmitter("test", "wrong")).await {
+            Err(SmppError::Protocol { status, .. }) => {
+                assert_eq!(status, CommandStatus::InvalidPassword)
+            }
+            other => panic!("Expected bind failure, got {other:?}"),
+        }
+
+        let mut client = DefaultClient::connect(addr).await.unwrap();
+        client
+            .bind(&BindCredentials::transmitter("test", "secret"))
+            .await
+            .unwrap();
+        client.set_retry_policy(RetryPolicy::new(1));
+
+        let message = SmsMessage::new("447700900123", "1234", "Hello");
+        assert!(client.send_sms(&message).await.is_ok());
+        match client.send_sms(&message).await {
+            Err(SmppError::Protocol { status, .. }) => {
+                assert_eq!(status, CommandStatus::ThrottlingError)
+            }
+            other => panic!("Expected throttling, got {other:?}"),
+        }
+        assert!(client.send_sms(&message).await.is_ok());
+        client.disconnect().await.unwrap();
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_mock_smsc_scripts_bind_submit_and_smsc_unbind() {
+        use crate::client::{
+            BindCredentials, DefaultClient, InboundConfig, SmppClient, SmppConnection,
+            SmppTransmitter, SmsMessage,
+        };
+        use crate::mock::{Expectation, MockSmsc};
+        use std::time::{Duration, Instant};
+
+        let mock = MockSmsc::builder()
+            .expect(
+                Expectation::bind_transceiver()
+                    .with_system_id("X")
+                    .reply_status(CommandStatus::Ok),
+            )
+            .expect(
+                Expectation::submit_sm()
+                    .with_destination_prefix("44")
+                    .reply_message_id("Z")
+                    .reply_after(Duration::from_millis(200)),
+            )
+            .send(Frame::Unbind(Unbind {
+                command_status: CommandStatus::Ok,
+                sequence_number: 1,
+            }))
+            .expect(Expectation::unbind_resp().with_status(CommandStatus::Ok))
+            .start()
+            .await
+            .unwrap();
+
+        let mut client = DefaultClient::connect(mock.local_addr()).await.unwrap();
+        client
+            .bind(&BindCredentials::transceiver("X", "secret"))
+            .await
+            .unwrap();
+        let started = Instant::now();
+        let message = SmsMessage::new("447700900123", "1234", "Hello");
+        assert_eq!(client.send_sms(&message).await.unwrap(), "Z");
+        assert!(started.elapsed() >= Duration::from_millis(200));
+
+        // The inbound session answers the SMSC's unbind and ends
+        let mut inbound = client.into_inbound(InboundConfig::new()).unwrap();
+        assert!(inbound.next().await.is_none());
+        mock.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_client_queries_cancels_and_replaces_messages() {
+        use crate::client::{BindCredentials, DefaultClient, SmppClient, SmppConnection};
+        use crate::mock::{Expectation, MockSmsc};
+
+        let mock = MockSmsc::builder()
+            .expect(Expectation::bind_transmitter().reply_status(CommandStatus::Ok))
+            .expect(Expectation::query_sm().reply_message_id("msg-1"))
+            .expect(Expectation::cancel_sm().reply_status(CommandStatus::Ok))
+            .expect(Expectation::replace_sm().reply_status(CommandStatus::InvalidMessageId))
+            .expect(Expectation::unbind())
+            .start()
+            .await
+            .unwrap();
+
+        let mut client = DefaultClient::connect(mock.local_addr()).await.unwrap();
+        client
+            .bind(&BindCredentials::transmitter("ops", "secret"))
+            .await
+            .unwrap();
+        let source = SourceAddr::new("1234", TypeOfNumber::Unknown).unwrap();
+
+        let query = QuerySm::new(
+            0,
+            MessageId::from("msg-1"),
+            TypeOfNumber::Unknown,
+            NumericPlanIndicator::Unknown,
+            source,
+        )
+        .unwrap();
+        let state = client.query_sm(&query).await.unwrap();
+        assert_eq!(state.message_id, MessageId::from("msg-1"));
+        assert_eq!(state.message_state, MessageState::Enroute);
+
+        let cancel = CancelSm::new(
+            0,
+            ServiceType::default(),
+            MessageId::from("msg-1"),
+            TypeOfNumber::Unknown,
+            NumericPlanIndicator::Unknown,
+            source,
+            TypeOfNumber::Unknown,
+            NumericPlanIndicator::Unknown,
+            SourceAddr::new("447700900123", TypeOfNumber::Unknown).unwrap(),
+        )
+        .unwrap();
+        client.cancel_sm(&cancel).await.unwrap();
+
+        let replace = ReplaceSm::new(
+            0,
+            MessageId::from("msg-1"),
+            TypeOfNumber::Unknown,
+            NumericPlanIndicator::Unknown,
+            source,
+            ScheduleDeliveryTime::default(),
+            ValidityPeriod::default(),
+            0,
+            0,
+            ShortMessage::from("Changed"),
+        )
+        .unwrap();
+        let error = client.replace_sm(&replace).await.unwrap_err();
+        assert!(error.to_string().contains("InvalidMessageId"), "{error}");
+
+        client.unbind().await.unwrap();
+        mock.verify().await;
+    }
+
+    #[test]
+    fn test_inspect_explains_an_encoded_deliver_sm() {
+        let mut deliver = DeliverSm::builder().short_message("").build().unwrap();
+        deliver.sequence_number = 5;
+        deliver.data_coding = DataCoding::Ucs2;
+        let text: Vec<u8> = "Привет".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        deliver.message_payload = Some(Tlv::new(tags::MESSAGE_PAYLOAD, text).unwrap());
+        deliver.receipted_message_id =
+            Some(Tlv::new(tags::RECEIPTED_MESSAGE_ID, &b"42\0"[..]).unwrap());
+        deliver.message_state = Some(Tlv::new(tags::MESSAGE_STATE, vec![2]).unwrap());
+        let pdu = Frame::DeliverSm(Box::new(deliver)).to_bytes().unwrap();
+
+        let inspection = crate::inspect::inspect(&pdu);
+        assert!(inspection.is_clean(), "{inspection}");
+        assert!(matches!(inspection.frame, Some(Frame::DeliverSm(_))));
+        assert_eq!(inspection.field("message_payload").unwrap().value, "\"Привет\"");
+        assert_eq!(inspection.field("receipted_message_id").unwrap().value, "\"42\"");
+        assert_eq!(inspection.field("message_state").unwrap().value, "Delivered (2)");
+
+        // Cutting the PDU inside the payload is reported at that TLV
+        let payload = inspection.field("message_payload").unwrap().offset;
+        let mut cut = pdu[..payload + 6].to_vec();
+        cut[..4].copy_from_slice(&(payload as u32 + 6).to_be_bytes());
+        let failure = crate::inspect::inspect(&cut).failure.unwrap();
+        assert_eq!((failure.offset, failure.field.as_str()), (payload, "message_payload"));
+    }
+
+    fn fixture_sessions(name: &str) -> Vec<crate::pcap::Session> {
+        let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+        let capture = std::fs::read(path).unwrap();
+        crate::pcap::read_sessions(&capture, None).unwrap()
+    }
+
+    #[test]
+    fn test_pcap_timeline_of_a_complete_session() {
+        use crate::pcap::Direction;
+        use std::time::Duration;
+
+        // The capture also holds an HTTP connection, which is not SMPP
+        let sessions = fixture_sessions("smpp_session.pcap");
+        assert_eq!(sessions.len(), 1);
+        let session = &sessions[0];
+        assert_eq!(session.esme, "10.0.0.1:40000".parse().unwrap());
+        assert_eq!(session.system_id.as_deref(), Some("esme"));
+        assert_eq!(session.bind, Some(CommandId::BindTransceiver));
+        assert!(session.problems.is_empty(), "{session}");
+
+        // A submit_sm split over two segments, and one sent out of order and retransmitted
+        let commands: Vec<_> = session
+            .pdus
+            .iter()
+            .map(|pdu| (pdu.header.command_id, pdu.header.sequence_number))
+            .collect();
+        assert_eq!(
+            commands[..6],
+            [
+                (CommandId::BindTransceiver, 1),
+                (CommandId::BindTransceiverResp, 1),
+                (CommandId::SubmitSm, 2),
+                (CommandId::SubmitSmResp, 2),
+                (CommandId::SubmitSm, 3),
+                (CommandId::SubmitSmResp, 3),
+            ]
+        );
+        assert!(session.pdus.iter().all(|pdu| pdu.frame.is_some()));
+
+        let latencies: Vec<_> = session
+            .exchanges
+            .iter()
+            .map(|exchange| exchange.latency.as_millis())
+            .collect();
+        assert_eq!(latencies, [10, 29, 49, 5, 2]);
+        let deliver = &session.pdus[session.exchanges[3].request];
+        assert_eq!(deliver.direction, Direction::SmscToEsme);
+
+        let unanswered: Vec<_> = session.unanswered().collect();
+        assert_eq!(unanswered.len(), 1);
+        assert_eq!(unanswered[0].header.command_id, CommandId::EnquireLink);
+        assert_eq!(unanswered[0].header.sequence_number, 4);
+
+        let summary = session.summary();
+        assert_eq!((summary.exchanges, summary.error_responses, summary.unmatched), (5, 1, 0));
+        assert_eq!(summary.max_latency, Some(Duration::from_millis(49)));
+        let timeline = session.to_string();
+        assert!(timeline.contains("<  submit_sm_resp #3 InvalidDestinationAddress  49.0 ms"));
+        assert!(timeline.contains(">  enquire_link #4  unanswered"));
+    }
+
+    #[test]
+    fn test_pcap_timeline_recovers_from_a_midstream_start_and_lost_data() {
+        let sessions = fixture_sessions("smpp_midstream.pcapng");
+        assert_eq!(sessions.len(), 1);
+        let session = &sessions[0];
+        // No bind was captured; the ESME is the side away from port 2775
+        assert_eq!(session.esme, "[2001:db8::1]:50000".parse().unwrap());
+        assert_eq!(session.system_id, None);
+
+        let problems: Vec<_> = session.problems.iter().map(|p| p.message.as_str()).collect();
+        assert_eq!(
+            problems,
+            [
+                "skipped 10 octets to the next PDU header at stream offset 10",
+                "30 octets missing from the capture at stream offset 114",
+                "skipped 26 octets to the next PDU header at stream offset 170",
+            ]
+        );
+
+        // Two submit_sm in one segment, the second rejected with generic_nack
+        let nack = session
+            .pdus
+            .iter()
+            .find(|pdu| pdu.header.command_id == CommandId::GenericNack)
+            .unwrap();
+        let request = &session.pdus[nack.partner.unwrap()];
+        assert_eq!(request.header.command_id, CommandId::SubmitSm);
+        assert_eq!(request.header.sequence_number, 12);
+        assert_eq!(nack.header.command_status, CommandStatus::InvalidCommandId);
+
+        let unmatched: Vec<_> = session.unmatched().collect();
+        assert_eq!(unmatched.len(), 1);
+        assert_eq!(unmatched[0].header.sequence_number, 99);
+
+        // The enquire_link after the lost data is still paired with its response
+        let last = session.exchanges.last().unwrap();
+        assert_eq!(session.pdus[last.request].header.sequence_number, 14);
+        assert_eq!(last.latency.as_millis(), 5);
+        assert_eq!(session.unanswered().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_recorded_session_replays_as_either_peer() {
+        use crate::client::{
+            BindCredentials, DefaultClient, SmppClient, SmppConnection, SmppTransmitter,
+            SmsMessage,
+        };
+        use crate::mock::{Expectation, MockSmsc};
+        use crate::recording::{Direction, Recorder, Recording, Replayer, Role, Timing};
+        use crate::simulator::{Account, Simulator, SimulatorConfig};
+        use std::time::Duration;
+        use tokio::net::TcpListener;
+
+        async fn session(addr: std::net::SocketAddr, recorder: Option<Recorder>) -> String {
+            let mut client = DefaultClient::connect(addr).await.unwrap();
+            if let Some(recorder) = recorder {
+                client.set_recorder(recorder);
+            }
+            client
+                .bind(&BindCredentials::transmitter("test", "test"))
+                .await
+                .unwrap();
+            let message = SmsMessage::new("447700900123", "1234", "Hello");
+            let message_id = client.send_sms(&message).await.unwrap();
+            client.unbind().await.unwrap();
+            client.disconnect().await.unwrap();
+            message_id
+        }
+
+        let mock = MockSmsc::builder()
+            .expect(Expectation::bind_transmitter().with_system_id("test"))
+            .expect(Expectation::submit_sm().reply_message_id("msg-1"))
+            .expect(Expectation::unbind())
+            .start()
+            .await
+            .unwrap();
+        let recorder = Recorder::in_memory();
+        assert_eq!(session(mock.local_addr(), Some(recorder.clone())).await, "msg-1");
+        mock.verify().await;
+        let recording = Recording::parse(&recorder.recording().unwrap().to_bytes()).unwrap();
+        assert_eq!(recording.recorded_as(), Role::Esme);
+        let events: Vec<_> = recording
+            .events
+            .iter()
+            .map(|event| (event.direction, event.command_id().unwrap()))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                (Direction::Outbound, CommandId::BindTransmitter),
+                (Direction::Inbound, CommandId::BindTransmitterResp),
+                (Direction::Outbound, CommandId::SubmitSm),
+                (Direction::Inbound, CommandId::SubmitSmResp),
+                (Direction::Outbound, CommandId::Unbind),
+                (Direction::Inbound, CommandId::UnbindResp),
+            ]
+        );
+
+        // Standing in for the SMSC, the replay answers with the recorded message_id
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let replay_addr = listener.local_addr().unwrap();
+        let replayer = Replayer::new(recording.clone()).with_timing(Timing::AsFastAsPossible);
+        let smsc = tokio::spawn(async move { replayer.as_smsc(&listener).await.unwrap() });
+        assert_eq!(session(replay_addr, None).await, "msg-1");
+        let report = smsc.await.unwrap();
+        assert!(report.is_faithful(), "{report}");
+        assert_eq!((report.sent, report.received), (3, 3));
+
+        // Standing in for the client, it replays against a different SMSC
+        let simulator = Simulator::bind("127.0.0.1:0", SimulatorConfig::new()).await.unwrap();
+        let addr = simulator.local_addr().unwrap();
+        tokio::spawn(simulator.run());
+        let report = Replayer::new(recording.clone())
+            .with_timing(Timing::Accelerated(100.0))
+            .as_esme(addr)
+            .await
+            .unwrap();
+        assert!(report.is_faithful(), "{report}");
+
+        // An SMSC that answers differently is reported
+        let config = SimulatorConfig::new().with_account(Account::new("test", "secret"));
+        let strict = Simulator::bind("127.0.0.1:0", config).await.unwrap();
+        let strict_addr = strict.local_addr().unwrap();
+        tokio::spawn(strict.run());
+        let report = Replayer::new(recording)
+            .with_timing(Timing::AsFastAsPossible)
+            .with_peer_timeout(Duration::from_millis(500))
+            .as_esme(strict_addr)
+            .await
+            .unwrap();
+        assert_eq!(report.divergences[0].event, 1);
+        assert_eq!(
+            report.divergences[0].message,
+            "expected bind_transmitter_resp #1 (Ok), received bind_transmitter_resp #1 \
+             (InvalidPassword)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sessions_report_metrics_labelled_by_session_and_system_id() {
+        use crate::client::{
+            BindCredentials, BindType, DefaultClient, PoolConfig, SmppClient, SmppConnection,
+            SmppPool, SmppTransmitter, SmsMessage,
+        };
+        use crate::metrics::{
+            BindState, MetricEvent, SessionLabels, SessionMetrics, command_label, status_label,
+        };
+        use crate::recording::Direction;
+        use crate::simulator::{Simulator, SimulatorConfig};
+        use std::sync::{Arc, Mutex};
+
+        let simulator = Simulator::bind("127.0.0.1:0", SimulatorConfig::new()).await.unwrap();
+        let addr = simulator.local_addr().unwrap();
+        tokio::spawn(simulator.run());
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorder = {
+            let events = Arc::clone(&events);
+            move |labels: &SessionLabels, event: &MetricEvent| {
+                events.lock().unwrap().push((labels.clone(), event.clone()));
+            }
+        };
+        let metrics = SessionMetrics::new(Arc::new(recorder), "primary");
+
+        let mut client = DefaultClient::connect(addr).await.unwrap();
+        client.set_metrics(metrics.clone());
+        client
+            .bind(&BindCredentials::transmitter("test", "test"))
+            .await
+            .unwrap();
+        let message = SmsMessage::new("447700900123", "1234", "Hello");
+        client.send_sms(&message).await.unwrap();
+        client.unbind().await.unwrap();
+        client.disconnect().await.unwrap();
+
+        let recorded: Vec<_> = std::mem::take(&mut *events.lock().unwrap());
+        assert!(recorded.iter().all(|(labels, _)| labels.session == "primary"));
+        assert!(recorded.iter().all(|(labels, _)| labels.system_id == "test"));
+
+        let pdus: Vec<_> = recorded
+            .iter()
+            .filter_map(|(_, event)| match event {
+                MetricEvent::Pdu {
+                    direction,
+                    command_id,
+                    command_status,
+                } => Some((*direction, command_label(*command_id), status_label(*command_status))),
+                _ => None,
+            })
+            .collect();
+        let pdu = |direction, command: &str| (direction, command.to_string(), "Ok".to_string());
+        assert_eq!(
+            pdus,
+            [
+                pdu(Direction::Outbound, "bind_transmitter"),
+                pdu(Direction::Inbound, "bind_transmitter_resp"),
+                pdu(Direction::Outbound, "submit_sm"),
+                pdu(Direction::Inbound, "submit_sm_resp"),
+                pdu(Direction::Outbound, "unbind"),
+                pdu(Direction::Inbound, "unbind_resp"),
+            ]
+        );
+
+        let latencies: Vec<_> = recorded
+            .iter()
+            .filter_map(|(_, event)| match event {
+                MetricEvent::ResponseLatency { command_id, .. } => Some(*command_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            latencies,
+            [CommandId::BindTransmitter, CommandId::SubmitSm, CommandId::Unbind]
+        );
+
+        let states: Vec<_> = recorded
+            .iter()
+            .filter_map(|(_, event)| match event {
+                MetricEvent::BindState(state) => Some(*state),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            states,
+            [
+                BindState::Bound(BindType::Transmitter),
+                BindState::Unbound,
+                BindState::Closed
+            ]
+        );
+
+        // A pool labels each session and counts replacing a discarded one as a reconnect
+        let credentials = BindCredentials::transmitter("test", "test");
+        let config = PoolConfig::new(vec![addr.to_string()], credentials).with_metrics(metrics);
+        let pool = SmppPool::connect(config).await.unwrap();
+        pool.send_sms(&message).await.unwrap();
+        pool.session().await.unwrap().discard();
+        assert_eq!(pool.maintain().await, 1);
+        pool.close().await;
+
+        let recorded: Vec<_> = std::mem::take(&mut *events.lock().unwrap());
+        assert!(recorded.iter().all(|(labels, _)| labels.session == "primary/0"));
+        assert!(recorded.iter().all(|(labels, _)| labels.system_id == "test"));
+        let count = |wanted: &MetricEvent| {
+            recorded
+                .iter()
+                .filter(|(_, event)| event == wanted)
+                .count()
+        };
+        assert_eq!(count(&MetricEvent::Reconnect), 1);
+        assert_eq!(count(&MetricEvent::Window { in_flight: 1 }), 2);
+        assert_eq!(count(&MetricEvent::Window { in_flight: 0 }), 2);
+        // Once when discarded, once when the pool closes
+        assert_eq!(count(&MetricEvent::BindState(BindState::Closed)), 2);
+    }
+
+    #[tokio::test]
+    async fn test_session_and_exchange_spans_log_redacted_pdus() {
+        use crate::client::{
+            BindCredentials, DefaultClient, SmppClient, SmppConnection, SmppTransmitter,
+            SmsMessage,
+        };
+        use crate::simulator::{Simulator, SimulatorConfig};
+        use std::sync::{Arc, Mutex};
+        use tracing_subscriber::fmt::format::FmtSpan;
+
+        /// Collects everything the subscriber writes
+        #[derive(Clone, Default)]
+        struct Capture(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Capture {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let capture = capture.clone();
+                move || capture.clone()
+            })
+            .with_max_level(tracing::Level::TRACE)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let simulator = Simulator::bind("127.0.0.1:0", SimulatorConfig::new()).await.unwrap();
+        let addr = simulator.local_addr().unwrap();
+        tokio::spawn(simulator.run());
+
+        let mut client = DefaultClient::connect(addr).await.unwrap();
+        client
+            .bind(&BindCredentials::transmitter("test", "test"))
+            .await
+            .unwrap();
+        let message = SmsMessage::new("447700900123", "1234", "Top secret plans");
+        client.send_sms(&message).await.unwrap();
+        client.unbind().await.unwrap();
+        drop(client);
+
+        let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+        let session = format!(
+            "smpp_session{{peer={addr} system_id=\"test\" bind_type=Transmitter version=SmppV34}}"
+        );
+        let submit = output
+            .lines()
+            .find(|line| line.contains("pdu=submit_sm #"))
+            .unwrap_or_else(|| panic!("no submit_sm logged in:\n{output}"));
+        assert!(submit.contains(&session), "{submit}");
+        let exchange = "smpp_exchange{command_id=submit_sm sequence_number=2}";
+        assert!(submit.contains(exchange), "{submit}");
+        assert!(submit.contains("short_message=<redacted, 16 octets>"), "{submit}");
+
+        // The exchange span closes with the response's status and the latency
+        let closed = output
+            .lines()
+            .find(|line| line.contains("command_id=submit_sm") && line.contains("close"))
+            .unwrap_or_else(|| panic!("submit_sm exchange not closed in:\n{output}"));
+        assert!(closed.contains("command_status=Ok latency="), "{closed}");
+
+        let bind = output
+            .lines()
+            .find(|line| line.contains("pdu=bind_transmitter #"))
+            .unwrap();
+        assert!(bind.contains("password=<redacted>"), "{bind}");
+        assert!(!output.contains("Top secret"), "{output}");
+        assert!(!output.contains("password=\"test\""), "{output}");
+    }
+
+    #[tokio::test]
+    async fn test_credentials_from_variables_bind_without_revealing_the_password() {
+        use crate::client::{
+            BindCredentials, BindType, CredentialError, DefaultClient, SmppClient, SmppConnection,
+        };
+        use crate::simulator::{Account, Simulator, SimulatorConfig};
+        use std::collections::HashMap;
+
+        // Look variables up in a map, as changing the process environment races other tests
+        let lookup = |vars: &HashMap<String, String>, name: &str| {
+            vars.get(name)
+                .cloned()
+                .ok_or_else(|| CredentialError::MissingVariable(name.to_string()))
+        };
+        let path = std::env::temp_dir().join(format!("smpp-password-{}", std::process::id()));
+        std::fs::write(&path, "s3cret\n").unwrap();
+        let mut vars = HashMap::from([
+            ("SMPP_SYSTEM_ID".to_string(), "esme".to_string()),
+            ("SMPP_PASSWORD_FILE".to_string(), path.display().to_string()),
+        ]);
+        let credentials =
+            BindCredentials::from_vars("SMPP", BindType::Transceiver, |name| lookup(&vars, name));
+        std::fs::remove_file(&path).unwrap();
+        let credentials = credentials.unwrap();
+        assert_eq!(credentials.system_id, "esme");
+        assert!(!format!("{credentials:?}").contains("s3cret"));
+
+        vars.remove("SMPP_SYSTEM_ID");
+        assert!(matches!(
+            BindCredentials::from_vars("SMPP", BindType::Transceiver, |name| lookup(&vars, name)),
+            Err(CredentialError::MissingVariable(name)) if name == "SMPP_SYSTEM_ID"
+        ));
+
+        let config = SimulatorConfig::new().with_account(Account::new("esme", "s3cret"));
+        let simulator = Simulator::bind("127.0.0.1:0", config).await.unwrap();
+        let addr = simulator.local_addr().unwrap();
+        tokio::spawn(simulator.run());
+
+        let mut client = DefaultClient::connect(addr).await.unwrap();
+        client.bind(&credentials).await.unwrap();
+
+        let mut client = DefaultClient::connect(addr).await.unwrap();
+        let wrong = credentials.with_password("wrong");
+        assert!(client.bind(&wrong).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bind_rejects_credentials_too_long_for_the_pdu() {
+        use crate::client::{
+            BindCredentials, DefaultClient, SmppClient, SmppConnection, SmppError,
+        };
+        use crate::mock::{Expectation, MockSmsc};
+
+        // Nothing reaches the SMSC until the unbind, which gets the first sequence number
+        let mock = MockSmsc::builder()
+            .expect(Expectation::unbind().matching("sequence_number is 1", |frame| {
+                frame.sequence_number() == 1
+            }))
+            .start()
+            .await
+            .unwrap();
+
+        let mut client = DefaultClient::connect(mock.local_addr()).await.unwrap();
+        let too_long = [
+            (BindCredentials::transceiver("esme", "123456789"), "password"),
+            (BindCredentials::transceiver("a".repeat(16), "secret"), "system_id"),
+            (
+                BindCredentials::transceiver("esme", "secret").with_system_type("a".repeat(13)),
+                "system_type",
+            ),
+        ];
+        for (credentials, field) in too_long {
+            match client.bind(&credentials).await {
+                Err(SmppError::InvalidData(reason)) => {
+                    assert!(reason.starts_with(&format!("Invalid {field}: ")), "{reason}");
+                    assert!(!reason.contains("123456789"), "{reason}");
+                }
+                other => panic!("Expected {field} to be rejected, got {other:?}"),
+            }
+        }
+        client.unbind().await.unwrap();
+        mock.verify().await;
+    }
 }